@@ -0,0 +1,52 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use songbird::{
+    constants::*,
+    driver::Bitrate,
+    input::{cached::Compressed, Input},
+};
+use std::io::{Seek, SeekFrom};
+
+const SOURCE_SECS: usize = 120;
+const TARGET_SECS: usize = 90;
+
+fn secs_to_pos(secs: usize) -> u64 {
+    (secs * AUDIO_FRAME_RATE * STEREO_FRAME_BYTE_SIZE) as u64
+}
+
+pub fn seek_compressed(c: &mut Criterion) {
+    let floats = utils::make_sine(SOURCE_SECS * AUDIO_FRAME_RATE * STEREO_FRAME_SIZE, true);
+    let src = Compressed::new(
+        Input::float_pcm(true, floats.into()),
+        Bitrate::BitsPerSecond(128_000),
+    )
+    .expect("Failed to compress source.");
+
+    let target = secs_to_pos(TARGET_SECS);
+
+    c.bench_function("Seek compressed source (from start)", |b| {
+        b.iter_batched_ref(
+            || Input::from(src.new_handle()),
+            |input| {
+                input.seek(black_box(SeekFrom::Start(target))).unwrap();
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("Seek compressed source (indexed)", |b| {
+        b.iter_batched_ref(
+            || {
+                let mut input = Input::from(src.new_handle());
+                input.seek(SeekFrom::Start(secs_to_pos(SOURCE_SECS))).unwrap();
+                input
+            },
+            |input| {
+                input.seek(black_box(SeekFrom::Start(target))).unwrap();
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, seek_compressed);
+criterion_main!(benches);
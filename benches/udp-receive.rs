@@ -0,0 +1,168 @@
+use criterion::{
+    black_box,
+    criterion_group,
+    criterion_main,
+    BatchSize,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+};
+use songbird::driver::bench_internals::recv_batch::RecvBatch;
+use std::{
+    net::UdpSocket as StdUdpSocket,
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::{
+    net::UdpSocket,
+    runtime::{Builder, Runtime},
+};
+
+// Simulates a busy channel, where many users speak at once: each burst holds
+// five ticks of audio from 50 speakers, which the receive task must drain.
+const SPEAKERS: usize = 50;
+const BURST: usize = SPEAKERS * 5;
+const PACKET_LEN: usize = 120;
+
+fn receive_burst(c: &mut Criterion) {
+    let rt = Builder::new_current_thread().enable_all().build().unwrap();
+
+    let (rx, tx) = rt.block_on(async {
+        let rx = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket2::SockRef::from(&rx)
+            .set_recv_buffer_size(1 << 20)
+            .unwrap();
+        let tx = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        tx.connect(rx.local_addr().unwrap()).await.unwrap();
+        (rx, tx)
+    });
+
+    let mut group = c.benchmark_group("Receive Burst");
+    group.throughput(Throughput::Elements(BURST as u64));
+
+    for batch_size in [1, 8, 32] {
+        let mut batch = RecvBatch::new(&rx, batch_size);
+
+        group.bench_with_input(
+            BenchmarkId::new("Batch Size", batch_size),
+            &batch_size,
+            |b, _| {
+                b.iter_batched(
+                    || {
+                        rt.block_on(async {
+                            for _ in 0..BURST {
+                                tx.send(&[0u8; PACKET_LEN]).await.unwrap();
+                            }
+                        })
+                    },
+                    |_| {
+                        rt.block_on(async {
+                            let mut received = 0;
+                            while received < BURST {
+                                received += batch.recv(&rx).await.unwrap();
+                            }
+                        })
+                    },
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+
+        assert!(batch.socket_dropped().unwrap_or_default() == 0);
+    }
+
+    group.finish();
+}
+
+// Packet loss is measured where every speaker's packet for a tick arrives at once,
+// and each packet costs the receive task some time to handle (as decryption and
+// decoding would). Timings here are set by the tick rate: the loss printed after each
+// case is the result of interest.
+const PACKET_COST: Duration = Duration::from_micros(1);
+const TICK: Duration = Duration::from_millis(20);
+
+/// Sends one burst from another thread, a tick at a time, while `batch` reads it,
+/// returning how many packets were lost.
+fn lose_burst(rt: &Runtime, rx: &UdpSocket, tx: &StdUdpSocket, batch: &mut RecvBatch) -> usize {
+    let tx = tx.try_clone().unwrap();
+    let sender = thread::spawn(move || {
+        for _ in 0..BURST / SPEAKERS {
+            for _ in 0..SPEAKERS {
+                tx.send(&[0u8; PACKET_LEN]).unwrap();
+            }
+            thread::sleep(TICK);
+        }
+    });
+
+    let received = rt.block_on(async {
+        let mut received = 0;
+        while let Ok(count) = tokio::time::timeout(TICK * 2, batch.recv(rx)).await {
+            let count = count.unwrap();
+            for i in 0..count {
+                black_box(batch.packet(i));
+                let start = Instant::now();
+                while start.elapsed() < PACKET_COST {}
+            }
+            received += count;
+        }
+        received
+    });
+
+    sender.join().unwrap();
+
+    BURST - received
+}
+
+fn burst_loss(c: &mut Criterion) {
+    let rt = Builder::new_current_thread().enable_all().build().unwrap();
+
+    let mut group = c.benchmark_group("Burst Loss");
+    group.sample_size(10);
+
+    for (recv_buffer, batch_size) in [(8, 1), (8, 32), (256, 1), (256, 32)] {
+        let rx = rt.block_on(async {
+            let rx = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            socket2::SockRef::from(&rx)
+                .set_recv_buffer_size(recv_buffer * 1024)
+                .unwrap();
+            rx
+        });
+        let tx = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+        tx.connect(rx.local_addr().unwrap()).unwrap();
+
+        let mut batch = RecvBatch::new(&rx, batch_size);
+        let mut sent = 0;
+        let mut lost = 0;
+
+        group.bench_with_input(
+            BenchmarkId::new(format!("{} KiB Buffer, Batch Size", recv_buffer), batch_size),
+            &batch_size,
+            |b, _| {
+                b.iter_custom(|iters| {
+                    let start = Instant::now();
+                    for _ in 0..iters {
+                        lost += lose_burst(&rt, &rx, &tx, &mut batch);
+                        sent += BURST;
+                    }
+                    start.elapsed()
+                })
+            },
+        );
+
+        println!(
+            "{} KiB buffer, batch size {}: lost {} of {} packets ({:.2}%), socket reports {:?} \
+             dropped.",
+            recv_buffer,
+            batch_size,
+            lost,
+            sent,
+            100.0 * lost as f64 / sent.max(1) as f64,
+            batch.socket_dropped(),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, receive_burst, burst_loss);
+criterion_main!(benches);
@@ -0,0 +1,235 @@
+//! Shared playback of one source across several voice connections.
+
+use crate::{constants::*, driver::Driver, input::Input};
+use parking_lot::Mutex;
+use std::{collections::VecDeque, mem, sync::Arc};
+use tokio::runtime::Handle;
+use uuid::Uuid;
+
+/// A single audio source, decoded once and then played by any number of
+/// [`Driver`]s.
+///
+/// This suits bots which play the same stream (e.g., a radio station) into many
+/// channels at once: each extra driver costs a copy of every frame, rather than
+/// a full decode of the source.
+///
+/// While a driver is subscribed, each broadcast frame is mixed alongside that
+/// driver's own tracks. It is scaled by [`Driver::set_broadcast_volume`], is ducked
+/// beneath ducking tracks like any other, and is included in the driver's
+/// [master gain]. A driver subscribes to at most one broadcast at a time, keeps its
+/// subscription if its tasks are restarted, and may join or leave at any point: new
+/// subscribers start from the live position rather than the start of the source.
+///
+/// Frames are only produced while at least one subscribed driver is connected
+/// and requesting audio.
+///
+/// [`Driver`]: Driver
+/// [`Driver::set_broadcast_volume`]: Driver::set_broadcast_volume
+/// [master gain]: Driver::set_master_gain
+#[derive(Clone, Debug)]
+pub struct Broadcast {
+    id: Uuid,
+    shared: Arc<Shared>,
+}
+
+impl Broadcast {
+    /// Creates a broadcast which will play `input`.
+    ///
+    /// If called from within a Tokio runtime, lazy and restartable inputs are
+    /// prepared using that runtime.
+    pub fn new(mut input: Input) -> Self {
+        if let Ok(handle) = Handle::try_current() {
+            input.prep_with_handle(handle);
+        }
+
+        Self {
+            id: Uuid::new_v4(),
+            shared: Arc::new(Shared {
+                input: Mutex::new(input),
+                ring: Mutex::new(Ring {
+                    frames: VecDeque::with_capacity(BROADCAST_BACKLOG),
+                    first: 0,
+                    finished: false,
+                }),
+            }),
+        }
+    }
+
+    /// Subscribes `driver` to this broadcast, replacing any other broadcast it
+    /// was playing.
+    pub fn add_driver(&self, driver: &mut Driver) {
+        driver.set_broadcast(Some(self.clone()));
+    }
+
+    /// Unsubscribes `driver` from this broadcast.
+    ///
+    /// This has no effect if `driver` is playing a different broadcast.
+    pub fn remove_driver(&self, driver: &mut Driver) {
+        if driver.broadcast().map(|b| b.id) == Some(self.id) {
+            driver.set_broadcast(None);
+        }
+    }
+
+    /// Returns whether this broadcast's source has no more audio to play.
+    pub fn is_finished(&self) -> bool {
+        self.shared.ring.lock().finished
+    }
+
+    pub(crate) fn subscribe(&self) -> Subscriber {
+        let cursor = {
+            let ring = self.shared.ring.lock();
+            ring.first + ring.frames.len() as u64
+        };
+
+        Subscriber {
+            shared: self.shared.clone(),
+            cursor,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    /// Source of all frames, locked only by the driver decoding the next one.
+    input: Mutex<Input>,
+    ring: Mutex<Ring>,
+}
+
+#[derive(Debug)]
+struct Ring {
+    /// Recently decoded frames of interleaved stereo audio, oldest first.
+    frames: VecDeque<Arc<[f32]>>,
+    /// Index of the oldest frame in `frames`.
+    first: u64,
+    finished: bool,
+}
+
+impl Ring {
+    fn get(&self, index: u64) -> Option<Arc<[f32]>> {
+        index
+            .checked_sub(self.first)
+            .and_then(|i| self.frames.get(i as usize))
+            .cloned()
+    }
+
+    /// Returns the frame at `cursor`, and moves `cursor` past it.
+    fn take(&self, cursor: &mut u64) -> Option<Arc<[f32]>> {
+        // Lagging drivers skip ahead to the oldest frame still held.
+        *cursor = (*cursor).max(self.first);
+
+        let frame = self.get(*cursor)?;
+        *cursor += 1;
+
+        Some(frame)
+    }
+
+    /// Returns `Some` if the frame at `cursor` needs no decoding: either it is
+    /// already held, or the source has ended.
+    fn take_settled(&self, cursor: &mut u64) -> Option<Option<Arc<[f32]>>> {
+        match self.take(cursor) {
+            Some(frame) => Some(Some(frame)),
+            None if self.finished => Some(None),
+            None => None,
+        }
+    }
+
+    fn push(&mut self, frame: Option<Arc<[f32]>>) {
+        match frame {
+            Some(frame) => {
+                if self.frames.len() >= BROADCAST_BACKLOG {
+                    self.frames.pop_front();
+                    self.first += 1;
+                }
+                self.frames.push_back(frame);
+            },
+            None => self.finished = true,
+        }
+    }
+}
+
+/// One driver's position within a [`Broadcast`].
+#[derive(Debug)]
+pub struct Subscriber {
+    shared: Arc<Shared>,
+    cursor: u64,
+}
+
+impl Subscriber {
+    /// Returns the next frame for this driver, decoding it if no other
+    /// driver has done so yet.
+    ///
+    /// If another driver is busy decoding that frame, this returns `None` rather
+    /// than waiting, and the frame is picked up on this driver's next tick.
+    pub(crate) fn next_frame(&mut self) -> Option<Arc<[f32]>> {
+        if let Some(frame) = self.shared.ring.lock().take_settled(&mut self.cursor) {
+            return frame;
+        }
+
+        let mut input = self.shared.input.try_lock()?;
+
+        // The frame may have been pushed while the input was being locked.
+        if let Some(frame) = self.shared.ring.lock().take_settled(&mut self.cursor) {
+            return frame;
+        }
+
+        let mut buffer = [0f32; STEREO_FRAME_SIZE];
+        let len = input.mix(&mut buffer, 1.0) / mem::size_of::<f32>();
+        let frame = (len != 0).then(|| Arc::from(&buffer[..len]));
+
+        let mut ring = self.shared.ring.lock();
+        ring.push(frame);
+        drop(input);
+
+        ring.take(&mut self.cursor)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::make_sine;
+
+    #[test]
+    fn subscribers_share_decoded_frames() {
+        let input = Input::float_pcm(true, make_sine(3 * MONO_FRAME_SIZE, true).into());
+        let broadcast = Broadcast::new(input);
+
+        let mut early = broadcast.subscribe();
+        let mut also_early = broadcast.subscribe();
+
+        let first = early.next_frame().unwrap();
+        assert_eq!(also_early.next_frame(), Some(first));
+
+        // Late joiners start from the live position.
+        let mut late = broadcast.subscribe();
+        let second = early.next_frame().unwrap();
+        assert_eq!(late.next_frame(), Some(second.clone()));
+        assert_eq!(also_early.next_frame(), Some(second));
+
+        assert!(early.next_frame().is_some());
+        assert!(early.next_frame().is_none());
+        assert!(broadcast.is_finished());
+
+        // Frames already decoded remain available to those behind.
+        assert!(late.next_frame().is_some());
+        assert!(late.next_frame().is_none());
+    }
+
+    #[test]
+    fn busy_decoder_does_not_block_other_subscribers() {
+        let input = Input::float_pcm(true, make_sine(3 * MONO_FRAME_SIZE, true).into());
+        let broadcast = Broadcast::new(input);
+        let mut subscriber = broadcast.subscribe();
+
+        // Another driver is partway through decoding the next frame.
+        let decoding = broadcast.shared.input.lock();
+        assert!(subscriber.next_frame().is_none());
+        assert_eq!(subscriber.cursor, 0);
+        drop(decoding);
+
+        // The frame is picked up on the following tick instead.
+        assert_eq!(subscriber.next_frame().unwrap().len(), STEREO_FRAME_SIZE);
+        assert_eq!(subscriber.cursor, 1);
+    }
+}
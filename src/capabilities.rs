@@ -0,0 +1,138 @@
+//! Reports on the optional features available to songbird at runtime.
+
+use crate::{driver::CryptoMode, input::YOUTUBE_DL_COMMAND};
+use parking_lot::{const_mutex, Mutex};
+use tokio::process::Command;
+
+/// Optional crate features which may be reported by [`Capabilities::features`].
+const FEATURES: &[(&str, bool)] = &[
+    ("driver", cfg!(feature = "driver")),
+    ("gateway", cfg!(feature = "gateway")),
+    ("serenity", cfg!(feature = "serenity")),
+    ("twilight", cfg!(feature = "twilight")),
+    ("rustls", cfg!(feature = "rustls")),
+    ("native", cfg!(feature = "native")),
+    ("builtin-queue", cfg!(feature = "builtin-queue")),
+    ("http", cfg!(feature = "http")),
+    ("youtube-dlc", cfg!(feature = "youtube-dlc")),
+    ("yt-dlp", cfg!(feature = "yt-dlp")),
+    ("zlib-simd", cfg!(feature = "zlib-simd")),
+    ("zlib-stock", cfg!(feature = "zlib-stock")),
+    ("internals", cfg!(feature = "internals")),
+];
+
+/// Versions of the external programs used by songbird's sources.
+#[derive(Clone, Debug)]
+struct Tools {
+    ffmpeg: Option<String>,
+    ffprobe: Option<String>,
+    youtube_dl: Option<String>,
+}
+
+static TOOLS: Mutex<Option<Tools>> = const_mutex(None);
+
+/// Features compiled into this build of songbird, and external programs
+/// available to it, as returned by [`capabilities`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// Names of the optional crate features enabled in this build.
+    pub features: Vec<&'static str>,
+    /// Encryption modes which the driver can negotiate with Discord.
+    pub crypto_modes: Vec<CryptoMode>,
+    /// Version reported by `ffmpeg`, or `None` if it could not be run.
+    ///
+    /// `ffmpeg` is required by [`ffmpeg`], [`ytdl`], and HTTP sources.
+    ///
+    /// [`ffmpeg`]: crate::input::ffmpeg
+    /// [`ytdl`]: crate::input::ytdl
+    pub ffmpeg: Option<String>,
+    /// Version reported by `ffprobe`, or `None` if it could not be run.
+    ///
+    /// `ffprobe` is used to read metadata for [`ffmpeg`] sources.
+    ///
+    /// [`ffmpeg`]: crate::input::ffmpeg
+    pub ffprobe: Option<String>,
+    /// Version reported by the `youtube-dl` compatible program selected by this
+    /// build's features, or `None` if it could not be run.
+    pub youtube_dl: Option<String>,
+}
+
+impl Capabilities {
+    /// Returns whether the named crate feature is enabled in this build.
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// Returns whether every external program used by songbird's sources
+    /// could be run.
+    pub fn has_all_tools(&self) -> bool {
+        self.ffmpeg.is_some() && self.ffprobe.is_some() && self.youtube_dl.is_some()
+    }
+}
+
+/// Reports which optional features are compiled into this build, and which
+/// external programs (`ffmpeg`, `ffprobe`, `youtube-dl`) can be run.
+///
+/// This allows bots to check their environment at startup, and fail early with
+/// a clear message rather than when a source is first played. External programs
+/// are probed for their version on the first call, and the results are cached
+/// for the lifetime of the process.
+pub async fn capabilities() -> Capabilities {
+    let cached = TOOLS.lock().clone();
+    let tools = match cached {
+        Some(tools) => tools,
+        None => {
+            let tools = Tools {
+                ffmpeg: version("ffmpeg", "-version").await,
+                ffprobe: version("ffprobe", "-version").await,
+                youtube_dl: version(YOUTUBE_DL_COMMAND, "--version").await,
+            };
+            TOOLS.lock().get_or_insert(tools).clone()
+        },
+    };
+
+    Capabilities {
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect(),
+        crypto_modes: vec![CryptoMode::Normal, CryptoMode::Suffix, CryptoMode::Lite],
+        ffmpeg: tools.ffmpeg,
+        ffprobe: tools.ffprobe,
+        youtube_dl: tools.youtube_dl,
+    }
+}
+
+/// Runs `program` with `flag`, returning the first line it prints if it succeeds.
+async fn version(program: &str, flag: &str) -> Option<String> {
+    let out = Command::new(program).arg(flag).output().await.ok()?;
+
+    if !out.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn capabilities_report_build_and_cache_probes() {
+        let caps = capabilities().await;
+        assert!(caps.has_feature("driver"));
+        assert!(!caps.has_feature("not-a-feature"));
+        assert!(caps.crypto_modes.contains(&CryptoMode::Normal));
+
+        let again = capabilities().await;
+        assert_eq!(caps.ffmpeg, again.ffmpeg);
+        assert_eq!(caps.youtube_dl, again.youtube_dl);
+        assert!(TOOLS.lock().is_some());
+    }
+}
@@ -1,5 +1,23 @@
 #[cfg(feature = "driver-core")]
-use super::driver::{retry::Retry, CryptoMode, DecodeMode};
+use super::driver::{
+    retry::Retry,
+    Bandwidth,
+    CryptoMode,
+    DecodeMode,
+    GatewayVersion,
+    IpPreference,
+    OpusApplication,
+    ReceiveFilter,
+    ReconnectResume,
+    TxRedundancy,
+    UdpSocketOptions,
+    VbrMode,
+    VoiceTickMode,
+};
+#[cfg(feature = "driver-core")]
+use tokio::runtime::Handle;
+#[cfg(feature = "driver-core")]
+use tracing::Span;
 
 use std::time::Duration;
 
@@ -34,6 +52,52 @@ pub struct Config {
     /// [`DecodeMode::Pass`]: DecodeMode::Pass
     /// [user speaking events]: crate::events::CoreEvent::SpeakingUpdate
     pub decode_mode: DecodeMode,
+    #[cfg(feature = "driver-core")]
+    /// Version of Discord's voice gateway protocol to connect with.
+    ///
+    /// Defaults to [`GatewayVersion::V8`], the highest version supported by songbird.
+    ///
+    /// Changes to this field will not immediately apply if the
+    /// driver is actively connected, but will apply to subsequent
+    /// sessions.
+    ///
+    /// [`GatewayVersion::V8`]: GatewayVersion::V8
+    pub gateway_version: GatewayVersion,
+    #[cfg(feature = "driver-core")]
+    /// Application mode used by the driver's Opus encoder.
+    ///
+    /// [`OpusApplication::Audio`] best suits music, while [`OpusApplication::Voip`]
+    /// favours speech intelligibility. [`OpusApplication::LowDelay`] reduces encoder
+    /// latency by a few milliseconds, at the cost of quality. This mode sets the
+    /// encoder's defaults for signal type and in-band FEC, which songbird does not
+    /// otherwise change.
+    ///
+    /// Defaults to [`OpusApplication::Audio`].
+    ///
+    /// Changing this field in a running driver will rebuild its encoder.
+    ///
+    /// [`OpusApplication::Audio`]: OpusApplication::Audio
+    /// [`OpusApplication::Voip`]: OpusApplication::Voip
+    /// [`OpusApplication::LowDelay`]: OpusApplication::LowDelay
+    pub opus_application: OpusApplication,
+    #[cfg(feature = "driver-core")]
+    /// Bitrate control used by the driver's Opus encoder.
+    ///
+    /// Defaults to [`VbrMode::ConstrainedVbr`].
+    ///
+    /// Changing this field in a running driver will rebuild its encoder.
+    ///
+    /// [`VbrMode::ConstrainedVbr`]: VbrMode::ConstrainedVbr
+    pub opus_vbr: VbrMode,
+    #[cfg(feature = "driver-core")]
+    /// Upper limit on the audio bandwidth coded by the driver's Opus encoder.
+    ///
+    /// Defaults to [`Bandwidth::Full`].
+    ///
+    /// Changing this field in a running driver will rebuild its encoder.
+    ///
+    /// [`Bandwidth::Full`]: Bandwidth::Full
+    pub opus_max_bandwidth: Bandwidth,
     #[cfg(feature = "gateway-core")]
     /// Configures the amount of time to wait for Discord to reply with connection information
     /// if [`Call::join`]/[`join_gateway`] are used.
@@ -47,6 +111,36 @@ pub struct Config {
     /// [`Call::join`]: crate::Call::join
     /// [`join_gateway`]: crate::Call::join_gateway
     pub gateway_timeout: Option<Duration>,
+    #[cfg(all(feature = "driver-core", feature = "gateway-core"))]
+    /// Interval at which [`Songbird`] checks that each call's driver tasks are
+    /// still running.
+    ///
+    /// If a call's mixer has exited unexpectedly (leaving the call silent), it is
+    /// restarted and the driver reconnects, after which [`CoreEvent::DriverRecreated`]
+    /// fires so that lost tracks can be replayed. If the driver's core task has
+    /// exited, the whole driver is recreated with its global event handlers, fires
+    /// the same event, and rejoins the call's channel. A failed event processor is
+    /// restarted without losing any tracks.
+    ///
+    /// This applies to calls created by a [`Songbird`] after this is set.
+    ///
+    /// Defaults to `None` (disabled).
+    ///
+    /// [`Songbird`]: crate::Songbird
+    /// [`CoreEvent::DriverRecreated`]: crate::events::CoreEvent::DriverRecreated
+    pub watchdog: Option<Duration>,
+    #[cfg(all(feature = "driver-core", feature = "gateway-core"))]
+    /// Backoff between consecutive restarts of a failing call by the [`watchdog`].
+    ///
+    /// The watchdog stops supervising a call once this retry limit is reached
+    /// without the call recovering, so that a persistent fault does not cause
+    /// endless restarts.
+    ///
+    /// Defaults to [`Retry::default`].
+    ///
+    /// [`watchdog`]: Self::watchdog
+    /// [`Retry::default`]: Retry::default
+    pub watchdog_retry: Retry,
     #[cfg(feature = "driver-core")]
     /// Number of concurrently active tracks to allocate memory for.
     ///
@@ -61,6 +155,287 @@ pub struct Config {
     /// the capacity of the track store.
     pub preallocated_tracks: usize,
     #[cfg(feature = "driver-core")]
+    /// Selects which users' received packets are decrypted, decoded, and passed
+    /// on to receive events.
+    ///
+    /// Filtering out unneeded users can save a significant amount of CPU time
+    /// in busy channels when using [`DecodeMode::Decode`].
+    ///
+    /// Defaults to [`ReceiveFilter::All`].
+    ///
+    /// [`DecodeMode::Decode`]: DecodeMode::Decode
+    /// [`ReceiveFilter::All`]: ReceiveFilter::All
+    pub receive_filter: ReceiveFilter,
+    #[cfg(feature = "driver-core")]
+    /// Length of time a received voice stream must send no packets before
+    /// [`CoreEvent::UserInactive`] fires.
+    ///
+    /// [`CoreEvent::UserActive`] fires once that stream sends another packet.
+    /// Discord clients stop sending packets shortly after a user stops speaking,
+    /// so this is a measure of how long a user has been silent.
+    ///
+    /// Defaults to `None`, disabling these events.
+    ///
+    /// [`CoreEvent::UserInactive`]: crate::events::CoreEvent::UserInactive
+    /// [`CoreEvent::UserActive`]: crate::events::CoreEvent::UserActive
+    pub user_inactivity: Option<Duration>,
+    #[cfg(feature = "driver-core")]
+    /// Interval between [`CoreEvent::Latency`] updates.
+    ///
+    /// Latency is measured over UDP, by sending an IP discovery request to the voice
+    /// server once per interval and timing its response. Updates fire once per
+    /// answered request.
+    ///
+    /// Defaults to `None`, disabling these events.
+    ///
+    /// [`CoreEvent::Latency`]: crate::events::CoreEvent::Latency
+    pub latency_interval: Option<Duration>,
+    #[cfg(feature = "driver-core")]
+    /// Number of recent RTP sequence numbers remembered for each received
+    /// voice stream, used to drop replayed packets.
+    ///
+    /// Packets which repeat a remembered sequence number, or which are older than
+    /// every remembered packet, are discarded before any events fire. This follows
+    /// the sliding window approach of [RFC 3711], protecting recordings from
+    /// duplicated or maliciously replayed audio. Windows are capped at 128 packets,
+    /// and the number of dropped packets is reported by [`Driver::replay_dropped`].
+    ///
+    /// Only packets which decrypt successfully are checked against, or move, this
+    /// window. Replay protection therefore has no effect under [`DecodeMode::Pass`].
+    ///
+    /// Defaults to `None`, disabling replay protection.
+    ///
+    /// [RFC 3711]: https://tools.ietf.org/html/rfc3711#section-3.3.2
+    /// [`Driver::replay_dropped`]: crate::driver::Driver::replay_dropped
+    /// [`DecodeMode::Pass`]: DecodeMode::Pass
+    pub replay_window: Option<u16>,
+    #[cfg(feature = "driver-core")]
+    /// Length of decoded audio remembered for each received voice stream,
+    /// for playback by [`Driver::replay_user`].
+    ///
+    /// Audio is only kept when using [`DecodeMode::Decode`]. Each second held costs
+    /// around 190kB of memory per speaking user.
+    ///
+    /// Defaults to `None`, keeping no audio.
+    ///
+    /// [`Driver::replay_user`]: crate::driver::Driver::replay_user
+    /// [`DecodeMode::Decode`]: DecodeMode::Decode
+    pub receive_history: Option<Duration>,
+    #[cfg(feature = "driver-core")]
+    /// Whether [`Driver::replay_user`] plays whatever audio it holds when
+    /// less than the requested length is available.
+    ///
+    /// If `false`, such calls instead fail with [`ReplayError::Insufficient`].
+    ///
+    /// Defaults to `true`.
+    ///
+    /// [`Driver::replay_user`]: crate::driver::Driver::replay_user
+    /// [`ReplayError::Insufficient`]: crate::driver::ReplayError::Insufficient
+    pub replay_partial: bool,
+    #[cfg(feature = "driver-core")]
+    /// **Experimental.** Packet-level redundancy applied to transmitted audio.
+    ///
+    /// Redundant packets can help receivers on very lossy links, at the cost of
+    /// extra bandwidth. Only [`TxRedundancy::Duplicate`] is compatible with
+    /// Discord clients.
+    ///
+    /// Defaults to [`TxRedundancy::None`].
+    ///
+    /// [`TxRedundancy::Duplicate`]: TxRedundancy::Duplicate
+    /// [`TxRedundancy::None`]: TxRedundancy::None
+    pub tx_redundancy: TxRedundancy,
+    #[cfg(feature = "driver-core")]
+    /// Fraction by which the bitrate of sent audio may fall below the encoder's
+    /// target before [`CoreEvent::BitrateShortfall`] fires.
+    ///
+    /// The rate of Opus data leaving the UDP socket is measured over each
+    /// [`BITRATE_WINDOW`] of continuous transmission. For instance, `0.25` reports
+    /// any window where under 75% of the target bitrate reached the network. This
+    /// catches quality loss caused by a constrained connection, rather than by
+    /// encoder settings. Variable bitrate encoding may legitimately undershoot
+    /// on quiet or simple audio, so small values are prone to false alarms.
+    ///
+    /// While set, the socket's send buffer is kept small (unless
+    /// [`UdpSocketOptions::send_buffer_size`] is set), so that sends wait on a
+    /// congested uplink rather than queueing seconds of audio. Packets which the OS
+    /// drops count as unsent. Losses past this host's own link cannot be seen.
+    ///
+    /// Defaults to `None`, disabling these checks.
+    ///
+    /// [`CoreEvent::BitrateShortfall`]: crate::events::CoreEvent::BitrateShortfall
+    /// [`BITRATE_WINDOW`]: crate::constants::BITRATE_WINDOW
+    /// [`UdpSocketOptions::send_buffer_size`]: UdpSocketOptions::send_buffer_size
+    pub bitrate_shortfall: Option<f32>,
+    #[cfg(feature = "driver-core")]
+    /// Length of time to keep sending silent frames once all audio has stopped,
+    /// before the driver signals that it has stopped speaking.
+    ///
+    /// Short gaps in playback (*e.g.*, while the next track in a queue loads) then
+    /// keep packets flowing and leave the speaking indicator untouched, which helps
+    /// hosts whose NAT mappings or speaking state misbehave during pauses in traffic.
+    /// Silence is only sent for at most this long, and is always followed by the
+    /// usual five trailing silent frames.
+    ///
+    /// Defaults to `None`.
+    pub gap_silence: Option<Duration>,
+    #[cfg(feature = "driver-core")]
+    /// Number of silent Opus frames sent ahead of audio whenever the driver starts
+    /// transmitting, either for the first time or after a gap in playback.
+    ///
+    /// Receiving clients use these frames to flush their decoders before new
+    /// speech arrives. They are sent in one burst alongside the first frame of audio,
+    /// so on the wire this costs a few small extra packets rather than added latency,
+    /// though audio will reach listeners that many frames later in stream time.
+    /// Setting this to `0` sends audio immediately; higher values may help
+    /// listeners on unreliable connections. Independently of this setting,
+    /// five silent frames are always sent when transmission stops.
+    ///
+    /// Defaults to `0`.
+    pub lead_in_silence: usize,
+    #[cfg(feature = "driver-core")]
+    /// Maximum time the mixer should spend reading and mixing a single track's frame.
+    ///
+    /// Tracks which exceed this for [`TRACK_BUDGET_STRIKES`] frames in a row are
+    /// paused, and fire [`TrackEvent::OverBudget`], so that one expensive source cannot
+    /// delay packet generation for every other track (or call) sharing a host.
+    /// Individual overruns are logged at the `debug` level.
+    ///
+    /// Defaults to `None`.
+    ///
+    /// [`TRACK_BUDGET_STRIKES`]: crate::constants::TRACK_BUDGET_STRIKES
+    /// [`TrackEvent::OverBudget`]: crate::events::TrackEvent::OverBudget
+    pub track_budget: Option<Duration>,
+    #[cfg(feature = "driver-core")]
+    /// Maximum time a track may spend preparing its source before producing
+    /// any audio.
+    ///
+    /// Tracks whose source is still starting up after this much playback time, such
+    /// as a lazy [`Restartable`] whose process has hung, are ended and fire
+    /// [`TrackEvent::Error`], so that one bad source cannot wedge a queue.
+    /// [`TrackHandle::timed_out`] then reports the cause, and any pending
+    /// source creation is cancelled.
+    ///
+    /// This only covers sources which report that they are still starting up.
+    /// Reads from a child process's pipe, such as `ffmpeg` or `youtube-dl` output,
+    /// block the mixer until data arrives, so a process which hangs after producing
+    /// its pipe is not caught. Wrapping such sources in a [`Restartable`] covers a
+    /// process which hangs while the source is being created.
+    ///
+    /// Defaults to `None`.
+    ///
+    /// [`Restartable`]: crate::input::restartable::Restartable
+    /// [`TrackEvent::Error`]: crate::events::TrackEvent::Error
+    /// [`TrackHandle::timed_out`]: crate::tracks::TrackHandle::timed_out
+    pub input_timeout: Option<Duration>,
+    #[cfg(feature = "driver-core")]
+    /// Window used to absorb bursts of skip and seek commands.
+    ///
+    /// Rapid seeks on a track are rate-limited: at most one is carried out per
+    /// window, and any others made in the meantime are merged into the latest,
+    /// which is applied as soon as the window allows. The driver's built-in
+    /// [`TrackQueue`] also debounces skips by this window, as in
+    /// [`TrackQueue::set_skip_debounce`]. This protects the host from command spam,
+    /// such as many users pressing "skip" at once, which would otherwise restart
+    /// (and possibly cancel) source creation for every track skipped past.
+    ///
+    /// Defaults to `None`.
+    ///
+    /// [`TrackQueue`]: crate::tracks::TrackQueue
+    /// [`TrackQueue::set_skip_debounce`]: crate::tracks::TrackQueue::set_skip_debounce
+    pub command_debounce: Option<Duration>,
+    #[cfg(feature = "driver-core")]
+    /// Maximum time to keep sending audio while the voice websocket is down and
+    /// being resumed.
+    ///
+    /// A websocket outage does not always affect the UDP session carrying audio, so
+    /// the mixer continues to send over it while the driver resumes the websocket in
+    /// the background. If the session has *also* died, these packets are wasted until
+    /// the driver notices and reconnects. Once this limit passes, playback is held
+    /// (tracks do not advance) until the websocket is resumed or a new connection is
+    /// made. `Some(Duration::default())` holds playback as soon as the websocket drops.
+    ///
+    /// Defaults to `None`, which keeps sending for the whole outage.
+    pub ws_outage_playback: Option<Duration>,
+    #[cfg(feature = "driver-core")]
+    /// Whether playing tracks are returned to their positions at the time a connection
+    /// was lost, once the driver has reconnected.
+    ///
+    /// Each track rewound in this way fires [`TrackEvent::Resumed`].
+    ///
+    /// Defaults to [`ReconnectResume::Disabled`].
+    ///
+    /// [`TrackEvent::Resumed`]: crate::events::TrackEvent::Resumed
+    /// [`ReconnectResume::Disabled`]: ReconnectResume::Disabled
+    pub reconnect_resume: ReconnectResume,
+    #[cfg(feature = "driver-core")]
+    /// Whether the mixer paces packets against absolute tick times.
+    ///
+    /// When enabled, each packet is scheduled exactly 20ms after the *intended* send
+    /// time of the last, so that late wakeups (*e.g.*, on a loaded host) are corrected
+    /// for by sleeping less on the next tick. This holds the long-term rate at exactly
+    /// 50 packets per second. When disabled, the mixer sleeps for 20ms after each send,
+    /// so any scheduling delay accumulates as drift.
+    ///
+    /// Defaults to `true`.
+    pub drift_compensation: bool,
+    #[cfg(feature = "driver-core")]
+    /// Socket options, such as DSCP marking, buffer sizes, and batched reads, applied
+    /// to the voice UDP socket whenever a connection is made.
+    ///
+    /// Defaults to [`UdpSocketOptions::default`], which leaves every option unchanged.
+    ///
+    /// [`UdpSocketOptions::default`]: UdpSocketOptions::default
+    pub udp_socket_options: UdpSocketOptions,
+    #[cfg(feature = "driver-core")]
+    /// Address families used to reach the voice server's websocket gateway, and
+    /// the order in which they are tried.
+    ///
+    /// If connecting over the preferred family fails, the driver falls back to the
+    /// other family (unless restricted to one family). The family which was used is
+    /// reported by [`CoreEvent::DriverConnect`]. The UDP socket always matches the
+    /// family of the address given by the voice server.
+    ///
+    /// Defaults to [`IpPreference::PreferV4`].
+    ///
+    /// [`CoreEvent::DriverConnect`]: crate::events::CoreEvent::DriverConnect
+    /// [`IpPreference::PreferV4`]: IpPreference::PreferV4
+    pub ip_preference: IpPreference,
+    #[cfg(feature = "driver-core")]
+    /// Tokio runtime on which the driver spawns its tasks.
+    ///
+    /// This covers the driver's core, event-handling, websocket and UDP tasks, and any
+    /// work offloaded by sources (*e.g.*, [`Restartable`] inputs). The mixer always runs
+    /// on its own dedicated thread, but uses this runtime for any async work. Setting this
+    /// allows audio work to be isolated from the rest of a bot, such as on a runtime
+    /// whose worker threads are pinned to dedicated cores.
+    ///
+    /// This is applied when a driver is created: changing it via [`Driver::set_config`]
+    /// does not move a running driver. Using [`Songbird::set_config`] places every
+    /// subsequently created call on the chosen runtime. Tasks are shut down in the
+    /// usual way when a driver leaves or is dropped, regardless of where they run.
+    ///
+    /// Defaults to `None`, which uses the runtime active when the driver is created.
+    ///
+    /// [`Restartable`]: crate::input::restartable::Restartable
+    /// [`Driver::set_config`]: crate::driver::Driver::set_config
+    /// [`Songbird::set_config`]: crate::Songbird::set_config
+    pub spawn_on: Option<Handle>,
+    #[cfg(feature = "driver-core")]
+    /// Span under which every driver task runs, and which is the parent of all
+    /// spans and logs those tasks produce.
+    ///
+    /// Attaching fields such as a guild ID to this span allows all logs for one call
+    /// to be correlated in deployments with many concurrent calls. As with
+    /// [`spawn_on`], this is applied when a driver is created.
+    ///
+    /// Defaults to `None`, where driver tasks run outside of any span. Each call
+    /// created by [`Songbird`] without a span set is given its own `call` span,
+    /// recording its `guild_id` and the `channel_id` it last joined.
+    ///
+    /// [`spawn_on`]: Config::spawn_on
+    /// [`Songbird`]: crate::Songbird
+    pub tracing_span: Option<Span>,
+    #[cfg(feature = "driver-core")]
     /// Connection retry logic for the [`Driver`].
     ///
     /// This controls how many times the [`Driver`] should retry any connections,
@@ -69,11 +444,69 @@ pub struct Config {
     /// [`Driver`]: crate::driver::Driver
     pub driver_retry: Retry,
     #[cfg(feature = "driver-core")]
+    /// The maximum total time to spend retrying a single connection attempt.
+    ///
+    /// Once the next retry would begin after this window has elapsed, the driver
+    /// gives up and fires a [`DriverDisconnect`] event with reason
+    /// [`DisconnectReason::ReconnectExhausted`]. Both this window and the
+    /// [`retry_limit`] of [`driver_retry`] are respected: retries stop when
+    /// either is reached.
+    ///
+    /// Defaults to `None`, placing no limit on the time spent retrying.
+    ///
+    /// [`DriverDisconnect`]: crate::events::CoreEvent::DriverDisconnect
+    /// [`DisconnectReason::ReconnectExhausted`]: crate::events::context_data::DisconnectReason::ReconnectExhausted
+    /// [`retry_limit`]: Retry::retry_limit
+    /// [`driver_retry`]: Config::driver_retry
+    pub reconnect_window: Option<Duration>,
+    #[cfg(feature = "driver-core")]
     /// Configures the maximum amount of time to wait for an attempted voice
     /// connection to Discord.
     ///
     /// Defaults to 10 seconds. If set to `None`, connections will never time out.
     pub driver_timeout: Option<Duration>,
+    #[cfg(feature = "driver-core")]
+    /// Configures whether received voice packets are batched together and
+    /// delivered once per 20ms tick via [`CoreEvent::VoiceTick`].
+    ///
+    /// This is typically more efficient than handling each [`VoicePacket`] individually
+    /// for bots which process all speakers together (*e.g.*, when recording).
+    ///
+    /// Defaults to [`VoiceTickMode::Disabled`].
+    ///
+    /// [`CoreEvent::VoiceTick`]: crate::events::CoreEvent::VoiceTick
+    /// [`VoicePacket`]: crate::events::CoreEvent::VoicePacket
+    /// [`VoiceTickMode::Disabled`]: VoiceTickMode::Disabled
+    pub voice_tick: VoiceTickMode,
+    #[cfg(feature = "driver-core")]
+    /// Configures whether decoded audio from all other speakers is mixed into a
+    /// single stream, delivered once per 20ms tick via [`CoreEvent::MixedAudio`].
+    ///
+    /// Each speaker's audio is placed by RTP timestamp, relative to the first packet
+    /// received from them. Lost packets, and those arriving after their tick has
+    /// been mixed, are heard as silence.
+    ///
+    /// This requires [`DecodeMode::Decode`].
+    ///
+    /// Defaults to `false`.
+    ///
+    /// [`CoreEvent::MixedAudio`]: crate::events::CoreEvent::MixedAudio
+    /// [`DecodeMode::Decode`]: DecodeMode::Decode
+    pub mixed_audio: bool,
+    #[cfg(feature = "driver-core")]
+    /// Maximum size of decoded audio held for all received streams in a call, in bytes.
+    ///
+    /// This covers audio kept for [`receive_history`] and [`mixed_audio`]. Once
+    /// exceeded, the buffered audio of the least recently active stream is dropped
+    /// (leaving its decoder intact) and [`CoreEvent::DecodedAudioCapped`] fires,
+    /// until the total fits within this limit.
+    ///
+    /// Defaults to `None` (unlimited).
+    ///
+    /// [`receive_history`]: Self::receive_history
+    /// [`mixed_audio`]: Self::mixed_audio
+    /// [`CoreEvent::DecodedAudioCapped`]: crate::events::CoreEvent::DecodedAudioCapped
+    pub max_decoded_audio: Option<usize>,
 }
 
 impl Default for Config {
@@ -83,14 +516,74 @@ impl Default for Config {
             crypto_mode: CryptoMode::Normal,
             #[cfg(feature = "driver-core")]
             decode_mode: DecodeMode::Decrypt,
+            #[cfg(feature = "driver-core")]
+            gateway_version: GatewayVersion::V8,
+            #[cfg(feature = "driver-core")]
+            opus_application: OpusApplication::Audio,
+            #[cfg(feature = "driver-core")]
+            opus_vbr: VbrMode::ConstrainedVbr,
+            #[cfg(feature = "driver-core")]
+            opus_max_bandwidth: Bandwidth::Full,
             #[cfg(feature = "gateway-core")]
             gateway_timeout: Some(Duration::from_secs(10)),
+            #[cfg(all(feature = "driver-core", feature = "gateway-core"))]
+            watchdog: None,
+            #[cfg(all(feature = "driver-core", feature = "gateway-core"))]
+            watchdog_retry: Default::default(),
             #[cfg(feature = "driver-core")]
             preallocated_tracks: 1,
             #[cfg(feature = "driver-core")]
+            receive_filter: ReceiveFilter::All,
+            #[cfg(feature = "driver-core")]
+            user_inactivity: None,
+            #[cfg(feature = "driver-core")]
+            latency_interval: None,
+            #[cfg(feature = "driver-core")]
+            replay_window: None,
+            #[cfg(feature = "driver-core")]
+            receive_history: None,
+            #[cfg(feature = "driver-core")]
+            replay_partial: true,
+            #[cfg(feature = "driver-core")]
+            tx_redundancy: TxRedundancy::None,
+            #[cfg(feature = "driver-core")]
+            bitrate_shortfall: None,
+            #[cfg(feature = "driver-core")]
+            gap_silence: None,
+            #[cfg(feature = "driver-core")]
+            lead_in_silence: 0,
+            #[cfg(feature = "driver-core")]
+            track_budget: None,
+            #[cfg(feature = "driver-core")]
+            input_timeout: None,
+            #[cfg(feature = "driver-core")]
+            command_debounce: None,
+            #[cfg(feature = "driver-core")]
+            ws_outage_playback: None,
+            #[cfg(feature = "driver-core")]
+            reconnect_resume: ReconnectResume::Disabled,
+            #[cfg(feature = "driver-core")]
+            drift_compensation: true,
+            #[cfg(feature = "driver-core")]
+            udp_socket_options: UdpSocketOptions::default(),
+            #[cfg(feature = "driver-core")]
+            ip_preference: IpPreference::PreferV4,
+            #[cfg(feature = "driver-core")]
+            spawn_on: None,
+            #[cfg(feature = "driver-core")]
+            tracing_span: None,
+            #[cfg(feature = "driver-core")]
             driver_retry: Default::default(),
             #[cfg(feature = "driver-core")]
+            reconnect_window: None,
+            #[cfg(feature = "driver-core")]
             driver_timeout: Some(Duration::from_secs(10)),
+            #[cfg(feature = "driver-core")]
+            voice_tick: VoiceTickMode::Disabled,
+            #[cfg(feature = "driver-core")]
+            mixed_audio: false,
+            #[cfg(feature = "driver-core")]
+            max_decoded_audio: None,
         }
     }
 }
@@ -109,12 +602,156 @@ impl Config {
         self
     }
 
+    /// Sets this `Config`'s voice gateway protocol version.
+    pub fn gateway_version(mut self, gateway_version: GatewayVersion) -> Self {
+        self.gateway_version = gateway_version;
+        self
+    }
+
+    /// Sets this `Config`'s Opus encoder application mode.
+    pub fn opus_application(mut self, opus_application: OpusApplication) -> Self {
+        self.opus_application = opus_application;
+        self
+    }
+
+    /// Sets this `Config`'s Opus encoder bitrate control.
+    pub fn opus_vbr(mut self, opus_vbr: VbrMode) -> Self {
+        self.opus_vbr = opus_vbr;
+        self
+    }
+
+    /// Sets this `Config`'s Opus encoder bandwidth limit.
+    pub fn opus_max_bandwidth(mut self, opus_max_bandwidth: Bandwidth) -> Self {
+        self.opus_max_bandwidth = opus_max_bandwidth;
+        self
+    }
+
     /// Sets this `Config`'s number of tracks to preallocate.
     pub fn preallocated_tracks(mut self, preallocated_tracks: usize) -> Self {
         self.preallocated_tracks = preallocated_tracks;
         self
     }
 
+    /// Sets this `Config`'s filter for received voice packets.
+    pub fn receive_filter(mut self, receive_filter: ReceiveFilter) -> Self {
+        self.receive_filter = receive_filter;
+        self
+    }
+
+    /// Sets this `Config`'s inactivity threshold for received voice streams.
+    pub fn user_inactivity(mut self, user_inactivity: Option<Duration>) -> Self {
+        self.user_inactivity = user_inactivity;
+        self
+    }
+
+    /// Sets this `Config`'s interval between latency updates.
+    pub fn latency_interval(mut self, latency_interval: Option<Duration>) -> Self {
+        self.latency_interval = latency_interval;
+        self
+    }
+
+    /// Sets this `Config`'s replay protection window for received packets.
+    pub fn replay_window(mut self, replay_window: Option<u16>) -> Self {
+        self.replay_window = replay_window;
+        self
+    }
+
+    /// Sets this `Config`'s length of received audio kept for replay.
+    pub fn receive_history(mut self, receive_history: Option<Duration>) -> Self {
+        self.receive_history = receive_history;
+        self
+    }
+
+    /// Sets whether this `Config` allows replays shorter than requested.
+    pub fn replay_partial(mut self, replay_partial: bool) -> Self {
+        self.replay_partial = replay_partial;
+        self
+    }
+
+    /// Sets this `Config`'s redundancy mode for transmitted audio.
+    pub fn tx_redundancy(mut self, tx_redundancy: TxRedundancy) -> Self {
+        self.tx_redundancy = tx_redundancy;
+        self
+    }
+
+    /// Sets this `Config`'s tolerated shortfall in sent bitrate.
+    pub fn bitrate_shortfall(mut self, bitrate_shortfall: Option<f32>) -> Self {
+        self.bitrate_shortfall = bitrate_shortfall;
+        self
+    }
+
+    /// Sets this `Config`'s length of silence to send over gaps in playback.
+    pub fn gap_silence(mut self, gap_silence: Option<Duration>) -> Self {
+        self.gap_silence = gap_silence;
+        self
+    }
+
+    /// Sets this `Config`'s number of silent frames sent before audio.
+    pub fn lead_in_silence(mut self, lead_in_silence: usize) -> Self {
+        self.lead_in_silence = lead_in_silence;
+        self
+    }
+
+    /// Sets this `Config`'s per-track processing budget.
+    pub fn track_budget(mut self, track_budget: Option<Duration>) -> Self {
+        self.track_budget = track_budget;
+        self
+    }
+
+    /// Sets this `Config`'s time limit for tracks to begin producing audio.
+    pub fn input_timeout(mut self, input_timeout: Option<Duration>) -> Self {
+        self.input_timeout = input_timeout;
+        self
+    }
+
+    /// Sets this `Config`'s window for absorbing bursts of skips and seeks.
+    pub fn command_debounce(mut self, command_debounce: Option<Duration>) -> Self {
+        self.command_debounce = command_debounce;
+        self
+    }
+
+    /// Sets this `Config`'s limit on playback during websocket outages.
+    pub fn ws_outage_playback(mut self, ws_outage_playback: Option<Duration>) -> Self {
+        self.ws_outage_playback = ws_outage_playback;
+        self
+    }
+
+    /// Sets how this `Config` resumes playing tracks after a reconnect.
+    pub fn reconnect_resume(mut self, reconnect_resume: ReconnectResume) -> Self {
+        self.reconnect_resume = reconnect_resume;
+        self
+    }
+
+    /// Sets whether this `Config` paces packets against absolute tick times.
+    pub fn drift_compensation(mut self, drift_compensation: bool) -> Self {
+        self.drift_compensation = drift_compensation;
+        self
+    }
+
+    /// Sets the options applied to this `Config`'s voice UDP socket.
+    pub fn udp_socket_options(mut self, udp_socket_options: UdpSocketOptions) -> Self {
+        self.udp_socket_options = udp_socket_options;
+        self
+    }
+
+    /// Sets this `Config`'s preferred address families for the voice gateway.
+    pub fn ip_preference(mut self, ip_preference: IpPreference) -> Self {
+        self.ip_preference = ip_preference;
+        self
+    }
+
+    /// Sets this `Config`'s runtime for spawning driver tasks.
+    pub fn spawn_on(mut self, spawn_on: Option<Handle>) -> Self {
+        self.spawn_on = spawn_on;
+        self
+    }
+
+    /// Sets the span which encloses this `Config`'s driver tasks.
+    pub fn tracing_span(mut self, tracing_span: Option<Span>) -> Self {
+        self.tracing_span = tracing_span;
+        self
+    }
+
     /// Sets this `Config`'s timeout for establishing a voice connection.
     pub fn driver_timeout(mut self, driver_timeout: Option<Duration>) -> Self {
         self.driver_timeout = driver_timeout;
@@ -127,10 +764,35 @@ impl Config {
         self
     }
 
+    /// Sets this `Config`'s maximum time spent retrying a voice connection.
+    pub fn reconnect_window(mut self, reconnect_window: Option<Duration>) -> Self {
+        self.reconnect_window = reconnect_window;
+        self
+    }
+
+    /// Sets this `Config`'s batching behaviour for received voice packets.
+    pub fn voice_tick(mut self, voice_tick: VoiceTickMode) -> Self {
+        self.voice_tick = voice_tick;
+        self
+    }
+
+    /// Sets whether this `Config` mixes all received audio into one stream.
+    pub fn mixed_audio(mut self, mixed_audio: bool) -> Self {
+        self.mixed_audio = mixed_audio;
+        self
+    }
+
+    /// Sets this `Config`'s limit on decoded audio held for received streams.
+    pub fn max_decoded_audio(mut self, max_decoded_audio: Option<usize>) -> Self {
+        self.max_decoded_audio = max_decoded_audio;
+        self
+    }
+
     /// This is used to prevent changes which would invalidate the current session.
     pub(crate) fn make_safe(&mut self, previous: &Config, connected: bool) {
         if connected {
             self.crypto_mode = previous.crypto_mode;
+            self.gateway_version = previous.gateway_version;
         }
     }
 }
@@ -143,3 +805,18 @@ impl Config {
         self
     }
 }
+
+#[cfg(all(feature = "driver-core", feature = "gateway-core"))]
+impl Config {
+    /// Sets this `Config`'s interval for checking that call tasks are running.
+    pub fn watchdog(mut self, watchdog: Option<Duration>) -> Self {
+        self.watchdog = watchdog;
+        self
+    }
+
+    /// Sets this `Config`'s backoff between restarts of a failing call.
+    pub fn watchdog_retry(mut self, watchdog_retry: Retry) -> Self {
+        self.watchdog_retry = watchdog_retry;
+        self
+    }
+}
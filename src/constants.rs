@@ -7,7 +7,11 @@ use discortp::rtp::RtpType;
 use std::time::Duration;
 
 #[cfg(feature = "driver-core")]
-/// The voice gateway version used by the library.
+/// The voice gateway version natively encoded by the voice gateway model.
+///
+/// The version negotiated by the driver is set via [`Config::gateway_version`].
+///
+/// [`Config::gateway_version`]: crate::Config::gateway_version
 pub const VOICE_GATEWAY_VERSION: u8 = crate::model::constants::GATEWAY_VERSION;
 
 #[cfg(feature = "driver-core")]
@@ -47,6 +51,13 @@ pub const FRAME_LEN_MS: usize = 1000 / AUDIO_FRAME_RATE;
 /// Maximum number of audio frames/packets to be sent per second to be buffered.
 pub const CHILD_BUFFER_LEN: usize = AUDIO_FRAME_RATE / 2;
 
+/// Maximum time to wait for a child process to exit, and for its stderr output to
+/// be drained, once its audio output has ended.
+///
+/// Processes typically exit within milliseconds of closing their output, so this
+/// only holds up the end of a track whose process hangs around.
+pub const CHILD_EXIT_TIMEOUT: Duration = Duration::from_millis(250);
+
 /// Maximum packet size for a voice packet.
 ///
 /// Set a safe amount below the Ethernet MTU to avoid fragmentation/rejection.
@@ -64,6 +75,75 @@ pub const UDP_KEEPALIVE_GAP_MS: u64 = 5_000;
 /// irrespective of outgoing UDP traffic.
 pub const UDP_KEEPALIVE_GAP: Duration = Duration::from_millis(UDP_KEEPALIVE_GAP_MS);
 
+/// Maximum time to wait for Discord to answer a UDP IP discovery request.
+pub const IP_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum time to wait for a TCP connection to each of the voice server's addresses,
+/// before the next is tried.
+///
+/// The final address is given as long as the driver's own timeout allows, so that
+/// a black-holed route (such as broken IPv6) does not prevent any fallback.
+pub const TCP_CONNECT_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Maximum time to wait for the voice server to acknowledge a websocket close
+/// frame when leaving a call.
+pub const WS_CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Maximum time [`Driver::try_play`] waits for a source to produce audio, unless
+/// [`Config::input_timeout`] is set.
+///
+/// [`Driver::try_play`]: crate::driver::Driver::try_play
+/// [`Config::input_timeout`]: crate::Config::input_timeout
+pub const TRY_PLAY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum amount of audio held for each speaker ahead of [`CoreEvent::MixedAudio`].
+///
+/// Audio is placed by RTP timestamp, so packets which arrive early or in bursts are
+/// mixed in over later ticks. A packet which would land beyond this limit (or
+/// this far behind) restarts its stream's timing instead.
+///
+/// [`CoreEvent::MixedAudio`]: crate::events::CoreEvent::MixedAudio
+pub const MIXED_AUDIO_BACKLOG: Duration = Duration::from_millis(100);
+
+/// Minimum time between reports of decoded audio dropped to respect
+/// [`Config::max_decoded_audio`].
+///
+/// [`Config::max_decoded_audio`]: crate::Config::max_decoded_audio
+pub const DECODED_AUDIO_CAP_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interval at which received voice streams are checked for inactivity.
+pub const USER_ACTIVITY_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Length of the window over which the bitrate of sent audio is measured, for
+/// [`Config::bitrate_shortfall`].
+///
+/// [`Config::bitrate_shortfall`]: crate::Config::bitrate_shortfall
+pub const BITRATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Number of consecutive frames in which a track may exceed [`Config::track_budget`]
+/// before it is paused.
+///
+/// [`Config::track_budget`]: crate::Config::track_budget
+pub const TRACK_BUDGET_STRIKES: usize = 10;
+
+/// Maximum number of bands in a track's equalizer.
+///
+/// Bands past this limit are ignored, bounding the per-frame cost of equalization.
+pub const MAX_EQ_BANDS: usize = 8;
+
+/// Largest pitch shift which may be applied to a track, in semitones (two octaves).
+///
+/// Requested shifts are clamped to lie within this distance of the original pitch.
+pub const MAX_PITCH_SEMITONES: f32 = 24.0;
+
+/// Number of consecutive frames which may fail to encode before the Opus
+/// encoder is rebuilt.
+pub const ENCODER_REBUILD_STRIKES: usize = 3;
+
+/// Maximum number of bytes searched for the start of valid audio, when a
+/// stream begins with corrupt or unrelated data.
+pub const RESYNC_WINDOW: usize = 64 * 1024;
+
 /// Opus silent frame, used to signal speech start and end (and prevent audio glitching).
 pub const SILENT_FRAME: [u8; 3] = [0xf8, 0xff, 0xfe];
 
@@ -73,3 +153,16 @@ pub const RTP_VERSION: u8 = 2;
 #[cfg(feature = "driver-core")]
 /// Profile type used by Discord's Opus audio traffic.
 pub const RTP_PROFILE_TYPE: RtpType = RtpType::Dynamic(120);
+
+#[cfg(feature = "driver-core")]
+/// Profile type used for RFC 2198 redundant audio, when enabled.
+///
+/// This is not understood by Discord.
+pub const RTP_RED_PROFILE_TYPE: RtpType = RtpType::Dynamic(121);
+
+/// Number of decoded frames a [`Broadcast`] keeps for drivers which fall behind.
+///
+/// Drivers lagging further than this skip ahead to the oldest frame held.
+///
+/// [`Broadcast`]: crate::Broadcast
+pub const BROADCAST_BACKLOG: usize = 50;
@@ -0,0 +1,47 @@
+use audiopus::{coder::Encoder as OpusEncoder, Bandwidth as OpusBandwidth, Result};
+
+/// Upper limit on the audio bandwidth coded by the driver's Opus encoder.
+///
+/// Lower limits discard high frequencies, letting the encoder spend its bitrate
+/// on the remaining audio. This suits voice-only bots, particularly alongside a
+/// low bitrate.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Bandwidth {
+    /// A 4kHz bandwidth.
+    Narrow,
+    /// A 6kHz bandwidth.
+    ///
+    /// Opus codes frames at [`Wide`] when it cannot use this bandwidth, such as
+    /// for music or at higher bitrates.
+    ///
+    /// [`Wide`]: Bandwidth::Wide
+    Medium,
+    /// An 8kHz bandwidth.
+    Wide,
+    /// A 12kHz bandwidth.
+    SuperWide,
+    /// A 20kHz bandwidth.
+    ///
+    /// This is the Opus encoder's default.
+    Full,
+}
+
+impl Bandwidth {
+    /// Applies this limit to `encoder`.
+    pub(crate) fn apply(self, encoder: &mut OpusEncoder) -> Result<()> {
+        encoder.set_max_bandwidth(self.into())
+    }
+}
+
+impl From<Bandwidth> for OpusBandwidth {
+    fn from(val: Bandwidth) -> Self {
+        match val {
+            Bandwidth::Narrow => OpusBandwidth::Narrowband,
+            Bandwidth::Medium => OpusBandwidth::Mediumband,
+            Bandwidth::Wide => OpusBandwidth::Wideband,
+            Bandwidth::SuperWide => OpusBandwidth::Superwideband,
+            Bandwidth::Full => OpusBandwidth::Fullband,
+        }
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::{
     driver::tasks::{error::Recipient, message::*},
+    model::CloseCode as VoiceCloseCode,
     ws::Error as WsError,
 };
 use flume::SendError;
@@ -17,12 +18,19 @@ pub enum Error {
     /// The driver hung up an internal signaller, either due to another connection attempt
     /// or a crash.
     AttemptDiscarded,
+    /// The voice gateway closed the connection while identifying or resuming,
+    /// typically due to an invalid or expired session or token.
+    ///
+    /// The close code sent by Discord is included, if known.
+    AuthRejected(Option<VoiceCloseCode>),
     /// An error occurred during [en/de]cryption of voice packets or key generation.
     Crypto(CryptoError),
     /// Server did not return the expected crypto mode during negotiation.
     CryptoModeInvalid,
     /// Selected crypto mode was not offered by server.
     CryptoModeUnavailable,
+    /// The voice server's hostname could not be resolved.
+    Dns(IoError),
     /// An indicator that an endpoint URL was invalid.
     EndpointUrl,
     /// Discord hello/ready handshake was violated.
@@ -31,6 +39,8 @@ pub enum Error {
     IllegalDiscoveryResponse,
     /// Could not parse Discord's view of our IP.
     IllegalIp,
+    /// Discord did not respond to IP discovery in time.
+    IpDiscoveryTimedOut,
     /// Miscellaneous I/O error.
     Io(IoError),
     /// JSON (de)serialization error.
@@ -39,10 +49,28 @@ pub enum Error {
     InterconnectFailure(Recipient),
     /// Error communicating with gateway server over WebSocket.
     Ws(WsError),
+    /// The TCP, TLS, or WebSocket handshake with the voice gateway failed.
+    WsHandshake(WsError),
     /// Connection attempt timed out.
     TimedOut,
 }
 
+impl Error {
+    /// Indicates whether this failure is likely to be transient, such that
+    /// reattempting the same connection may succeed.
+    ///
+    /// Failures where Discord has rejected the session, or where the
+    /// driver and Discord disagree on protocol, will not be fixed by retrying.
+    pub fn should_retry(&self) -> bool {
+        use Error::*;
+
+        matches!(
+            self,
+            Dns(_) | Io(_) | IpDiscoveryTimedOut | TimedOut | Ws(_) | WsHandshake(_)
+        )
+    }
+}
+
 impl From<CryptoError> for Error {
     fn from(e: CryptoError) -> Self {
         Error::Crypto(e)
@@ -97,17 +125,22 @@ impl fmt::Display for Error {
         use Error::*;
         match self {
             AttemptDiscarded => write!(f, "connection attempt was aborted/discarded"),
+            AuthRejected(Some(code)) => write!(f, "gateway rejected session ({:?})", code),
+            AuthRejected(None) => write!(f, "gateway rejected session"),
             Crypto(e) => e.fmt(f),
             CryptoModeInvalid => write!(f, "server changed negotiated encryption mode"),
             CryptoModeUnavailable => write!(f, "server did not offer chosen encryption mode"),
+            Dns(e) => write!(f, "could not resolve voice server ({})", e),
             EndpointUrl => write!(f, "endpoint URL received from gateway was invalid"),
             ExpectedHandshake => write!(f, "voice initialisation protocol was violated"),
             IllegalDiscoveryResponse => write!(f, "IP discovery/NAT punching response was invalid"),
             IllegalIp => write!(f, "IP discovery/NAT punching response had bad IP value"),
+            IpDiscoveryTimedOut => write!(f, "IP discovery/NAT punching timed out"),
             Io(e) => e.fmt(f),
             Json(e) => e.fmt(f),
             InterconnectFailure(e) => write!(f, "failed to contact other task ({:?})", e),
             Ws(e) => write!(f, "websocket issue ({:?}).", e),
+            WsHandshake(e) => write!(f, "websocket handshake failed ({:?}).", e),
             TimedOut => write!(f, "connection attempt timed out"),
         }
     }
@@ -117,17 +150,21 @@ impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
             Error::AttemptDiscarded => None,
+            Error::AuthRejected(_) => None,
             Error::Crypto(e) => e.source(),
             Error::CryptoModeInvalid => None,
             Error::CryptoModeUnavailable => None,
+            Error::Dns(e) => Some(e),
             Error::EndpointUrl => None,
             Error::ExpectedHandshake => None,
             Error::IllegalDiscoveryResponse => None,
             Error::IllegalIp => None,
-            Error::Io(e) => e.source(),
-            Error::Json(e) => e.source(),
+            Error::IpDiscoveryTimedOut => None,
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
             Error::InterconnectFailure(_) => None,
             Error::Ws(_) => None,
+            Error::WsHandshake(_) => None,
             Error::TimedOut => None,
         }
     }
@@ -2,22 +2,43 @@ pub mod error;
 
 use super::{
     tasks::{message::*, udp_rx, udp_tx, ws as ws_task},
-    Config, CryptoMode,
+    Config, CryptoMode, GatewayVersion, IpFamily, IpPreference,
 };
 use crate::{
     constants::*,
+    events::context_data::SessionDescription,
     model::{
-        payload::{Identify, Resume, SelectProtocol},
-        Event as GatewayEvent, ProtocolData,
+        payload::{
+            Identify,
+            Resume,
+            SelectProtocol,
+            SessionDescription as SessionDescriptionPayload,
+        },
+        CloseCode as VoiceCloseCode,
+        Event as GatewayEvent,
+        FromPrimitive,
+        ProtocolData,
     },
-    ws::{self, ReceiverExt, SenderExt, WsStream},
+    ws::{self, Error as WsError, ReceiverExt, SenderExt, WsStream},
     ConnectionInfo,
 };
+use async_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use discortp::discord::{IpDiscoveryPacket, IpDiscoveryType, MutableIpDiscoveryPacket};
 use error::{Error, Result};
 use flume::Sender;
-use std::{net::IpAddr, str::FromStr, sync::Arc};
-use tokio::{net::UdpSocket, spawn, time::timeout};
+use parking_lot::Mutex;
+use std::{
+    io::{Error as IoError, ErrorKind},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    net::{lookup_host, TcpStream, UdpSocket},
+    spawn,
+    time::timeout,
+};
 use tracing::{debug, info, instrument};
 use url::Url;
 use xsalsa20poly1305::{KeyInit, XSalsa20Poly1305 as Cipher};
@@ -31,7 +52,22 @@ use ws::create_native_tls_client;
 pub(crate) struct Connection {
     pub(crate) info: ConnectionInfo,
     pub(crate) ssrc: u32,
+    pub(crate) session: SessionDescription,
+    /// Address and encryption mode selected during the handshake, resent to
+    /// renegotiate encryption.
+    pub(crate) protocol: ProtocolData,
+    /// Whether a renegotiation has been requested, and its session description
+    /// has not yet arrived.
+    pub(crate) renegotiating: bool,
     pub(crate) ws: Sender<WsMessage>,
+    pub(crate) gateway_version: GatewayVersion,
+    /// Last sequence number received over the voice gateway, shared with the WS task.
+    pub(crate) last_seq: Arc<Mutex<Option<u64>>>,
+    pub(crate) ip_preference: IpPreference,
+    /// Address family of the current websocket connection.
+    pub(crate) ip_family: IpFamily,
+    /// Latest round-trip time to the voice server, shared with the WS task.
+    pub(crate) latency: Arc<Mutex<Duration>>,
 }
 
 impl Connection {
@@ -54,16 +90,25 @@ impl Connection {
         config: &Config,
         idx: usize,
     ) -> Result<Connection> {
-        let url = generate_url(&mut info.endpoint)?;
+        let gateway_version = config.gateway_version;
+        let url = generate_url(&mut info.endpoint, gateway_version)?;
+
+        let addrs = resolve(&url, config.ip_preference).await?;
+        let (tcp, ip_family) = connect_tcp(&addrs).await?;
 
         #[cfg(all(feature = "rustls-marker", not(feature = "native-marker")))]
-        let mut client = create_rustls_client(url).await?;
+        let mut client = create_rustls_client(url, tcp)
+            .await
+            .map_err(Error::WsHandshake)?;
 
         #[cfg(feature = "native-marker")]
-        let mut client = create_native_tls_client(url).await?;
+        let mut client = create_native_tls_client(url, tcp)
+            .await
+            .map_err(Error::WsHandshake)?;
 
         let mut hello = None;
         let mut ready = None;
+        let mut last_seq = None;
 
         client
             .send_json(&GatewayEvent::from(Identify {
@@ -75,10 +120,20 @@ impl Connection {
             .await?;
 
         loop {
-            let value = match client.recv_json().await? {
-                Some(value) => value,
+            let value = match client.recv_json().await.map_err(handshake_error)? {
+                Some((value, seq)) => {
+                    last_seq = seq.or(last_seq);
+                    value
+                },
                 None => continue,
             };
+            let value = match value {
+                Ok(value) => value,
+                Err(e) => {
+                    debug!("Unexpected JSON {:?}.", e);
+                    continue;
+                },
+            };
 
             match value {
                 GatewayEvent::Ready(r) => {
@@ -110,7 +165,12 @@ impl Connection {
             return Err(Error::CryptoModeUnavailable);
         }
 
-        let udp = UdpSocket::bind("0.0.0.0:0").await?;
+        let unspecified: IpAddr = match ready.ip {
+            IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+            IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+        };
+        let udp = UdpSocket::bind((unspecified, 0)).await?;
+        config.udp_socket_options.apply(&udp);
         udp.connect((ready.ip, ready.port)).await?;
 
         // Follow Discord's IP Discovery procedures, in case NAT tunnelling is needed.
@@ -125,10 +185,14 @@ impl Connection {
             view.set_ssrc(ready.ssrc);
         }
 
+        let discovery_start = Instant::now();
         udp.send(&bytes).await?;
 
-        let (len, _addr) = udp.recv_from(&mut bytes).await?;
-        {
+        let (len, _addr) = timeout(IP_DISCOVERY_TIMEOUT, udp.recv_from(&mut bytes))
+            .await
+            .map_err(|_| Error::IpDiscoveryTimedOut)??;
+        let latency = discovery_start.elapsed();
+        let protocol = {
             let view =
                 IpDiscoveryPacket::new(&bytes[..len]).ok_or(Error::IllegalDiscoveryResponse)?;
 
@@ -153,24 +217,30 @@ impl Connection {
                 Error::IllegalIp
             })?;
 
+            let protocol = ProtocolData {
+                address,
+                mode: config.crypto_mode.to_request_str().into(),
+                port: view.get_port(),
+            };
+
             client
                 .send_json(&GatewayEvent::from(SelectProtocol {
                     protocol: "udp".into(),
-                    data: ProtocolData {
-                        address,
-                        mode: config.crypto_mode.to_request_str().into(),
-                        port: view.get_port(),
-                    },
+                    data: protocol.clone(),
                 }))
                 .await?;
-        }
 
-        let cipher = init_cipher(&mut client, config.crypto_mode).await?;
+            protocol
+        };
+
+        let (cipher, session) = init_cipher(&mut client, config.crypto_mode, &mut last_seq).await?;
 
-        info!("Connected to: {}", info.endpoint);
+        info!("Connected to: {} over {:?}", info.endpoint, ip_family);
 
         info!("WS heartbeat duration {}ms.", hello.heartbeat_interval,);
 
+        info!("UDP round-trip time {:?}.", latency);
+
         let (ws_msg_tx, ws_msg_rx) = flume::unbounded();
         let (udp_sender_msg_tx, udp_sender_msg_rx) = flume::unbounded();
         let (udp_receiver_msg_tx, udp_receiver_msg_rx) = flume::unbounded();
@@ -186,7 +256,7 @@ impl Connection {
         let mix_conn = MixerConnection {
             cipher: cipher.clone(),
             crypto_state: config.crypto_mode.into(),
-            udp_rx: udp_receiver_msg_tx,
+            udp_rx: udp_receiver_msg_tx.clone(),
             udp_tx: udp_sender_msg_tx,
         };
 
@@ -198,6 +268,9 @@ impl Connection {
             .mixer
             .send(MixerMessage::SetConn(mix_conn, ready.ssrc))?;
 
+        let last_seq = Arc::new(Mutex::new(last_seq));
+        let latency = Arc::new(Mutex::new(latency));
+
         spawn(ws_task::runner(
             interconnect.clone(),
             ws_msg_rx,
@@ -206,6 +279,9 @@ impl Connection {
             hello.heartbeat_interval,
             idx,
             info.clone(),
+            gateway_version,
+            last_seq.clone(),
+            udp_receiver_msg_tx,
         ));
 
         spawn(udp_rx::runner(
@@ -214,13 +290,29 @@ impl Connection {
             cipher,
             config.clone(),
             udp_rx,
+            ssrc,
+            latency.clone(),
+        ));
+        spawn(udp_tx::runner(
+            interconnect.clone(),
+            udp_sender_msg_rx,
+            ssrc,
+            config.clone(),
+            udp_tx,
         ));
-        spawn(udp_tx::runner(udp_sender_msg_rx, ssrc, udp_tx));
 
         Ok(Connection {
             info,
             ssrc,
+            session,
+            protocol,
+            renegotiating: false,
             ws: ws_msg_tx,
+            gateway_version,
+            last_seq,
+            ip_preference: config.ip_preference,
+            ip_family,
+            latency,
         })
     }
 
@@ -235,33 +327,59 @@ impl Connection {
 
     #[instrument(skip(self))]
     pub async fn reconnect_inner(&mut self) -> Result<()> {
-        let url = generate_url(&mut self.info.endpoint)?;
+        let url = generate_url(&mut self.info.endpoint, self.gateway_version)?;
 
         // Thread may have died, we want to send to prompt a clean exit
         // (if at all possible) and then proceed as normal.
 
+        let addrs = resolve(&url, self.ip_preference).await?;
+        let (tcp, ip_family) = connect_tcp(&addrs).await?;
+
         #[cfg(all(feature = "rustls-marker", not(feature = "native-marker")))]
-        let mut client = create_rustls_client(url).await?;
+        let mut client = create_rustls_client(url, tcp)
+            .await
+            .map_err(Error::WsHandshake)?;
 
         #[cfg(feature = "native-marker")]
-        let mut client = create_native_tls_client(url).await?;
+        let mut client = create_native_tls_client(url, tcp)
+            .await
+            .map_err(Error::WsHandshake)?;
+
+        self.ip_family = ip_family;
+
+        let seq_ack = *self.last_seq.lock();
 
         client
-            .send_json(&GatewayEvent::from(Resume {
-                server_id: self.info.guild_id.into(),
-                session_id: self.info.session_id.clone(),
-                token: self.info.token.clone(),
-            }))
+            .send_json(&self.gateway_version.resume(
+                Resume {
+                    server_id: self.info.guild_id.into(),
+                    session_id: self.info.session_id.clone(),
+                    token: self.info.token.clone(),
+                },
+                seq_ack,
+            ))
             .await?;
 
         let mut hello = None;
         let mut resumed = None;
 
         loop {
-            let value = match client.recv_json().await? {
-                Some(value) => value,
+            let value = match client.recv_json().await.map_err(handshake_error)? {
+                Some((value, seq)) => {
+                    if let Some(seq) = seq {
+                        *self.last_seq.lock() = Some(seq);
+                    }
+                    value
+                },
                 None => continue,
             };
+            let value = match value {
+                Ok(value) => value,
+                Err(e) => {
+                    debug!("Unexpected JSON {:?}.", e);
+                    continue;
+                },
+            };
 
             match value {
                 GatewayEvent::Resumed => {
@@ -296,42 +414,136 @@ impl Connection {
     }
 }
 
+impl Connection {
+    /// Closes this connection's websocket, waiting until the voice server has
+    /// acknowledged the close or [`WS_CLOSE_TIMEOUT`] has passed.
+    ///
+    /// [`WS_CLOSE_TIMEOUT`]: crate::constants::WS_CLOSE_TIMEOUT
+    pub async fn close(&self) {
+        let (tx, rx) = flume::bounded(1);
+
+        if self.ws.send(WsMessage::Close(tx)).is_ok() {
+            let _ = timeout(WS_CLOSE_TIMEOUT, rx.recv_async()).await;
+        }
+    }
+}
+
 impl Drop for Connection {
     fn drop(&mut self) {
         info!("Disconnected");
     }
 }
 
-fn generate_url(endpoint: &mut String) -> Result<Url> {
+/// Checks that the voice server's hostname can be resolved, so that DNS failures
+/// can be told apart from other connection failures.
+async fn resolve(url: &Url, preference: IpPreference) -> Result<Vec<SocketAddr>> {
+    let host = url.host_str().ok_or(Error::EndpointUrl)?;
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let addrs = preference.order(lookup_host((host, port)).await.map_err(Error::Dns)?);
+
+    if addrs.is_empty() {
+        return Err(Error::Dns(IoError::new(
+            ErrorKind::NotFound,
+            "hostname resolved to no addresses of an allowed family",
+        )));
+    }
+
+    Ok(addrs)
+}
+
+/// Connects to the first reachable address in `addrs`, which are tried in order.
+///
+/// Each address but the last is abandoned after [`TCP_CONNECT_ATTEMPT_TIMEOUT`].
+async fn connect_tcp(addrs: &[SocketAddr]) -> Result<(TcpStream, IpFamily)> {
+    let mut last_err = None;
+
+    for (i, addr) in addrs.iter().enumerate() {
+        let attempt = if i + 1 < addrs.len() {
+            timeout(TCP_CONNECT_ATTEMPT_TIMEOUT, TcpStream::connect(addr))
+                .await
+                .unwrap_or_else(|_| Err(IoError::new(ErrorKind::TimedOut, "connection timed out")))
+        } else {
+            TcpStream::connect(addr).await
+        };
+
+        match attempt {
+            Ok(tcp) => {
+                let family = IpFamily::of(addr.ip());
+                info!("Reached voice gateway at {} ({:?}).", addr, family);
+                return Ok((tcp, family));
+            },
+            Err(e) => {
+                debug!("Failed to reach voice gateway at {}: {:?}", addr, e);
+                last_err = Some(e);
+            },
+        }
+    }
+
+    Err(last_err
+        .unwrap_or_else(|| IoError::new(ErrorKind::NotFound, "no addresses to connect to"))
+        .into())
+}
+
+/// Converts a WS closure during identify/resume into a session rejection, unless
+/// its close code shows that the voice server failed and the session may be resumed.
+fn handshake_error(e: WsError) -> Error {
+    let code = match &e {
+        WsError::WsClosed(Some(frame)) => match frame.code {
+            CloseCode::Library(l) => VoiceCloseCode::from_u16(l),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    match code {
+        Some(code) if !code.should_resume() => Error::AuthRejected(Some(code)),
+        _ => Error::Ws(e),
+    }
+}
+
+fn generate_url(endpoint: &mut String, version: GatewayVersion) -> Result<Url> {
     if endpoint.ends_with(":80") {
         let len = endpoint.len();
 
         endpoint.truncate(len - 3);
     }
 
-    Url::parse(&format!("wss://{}/?v={}", endpoint, VOICE_GATEWAY_VERSION))
+    // Test servers (see `driver::test::MockServer`) are reached over plain websockets.
+    #[cfg(feature = "internals")]
+    if endpoint.starts_with("ws://") {
+        return Url::parse(&format!("{}/?v={}", endpoint, version.number()))
+            .or(Err(Error::EndpointUrl));
+    }
+
+    Url::parse(&format!("wss://{}/?v={}", endpoint, version.number()))
         .or(Err(Error::EndpointUrl))
 }
 
 #[inline]
-async fn init_cipher(client: &mut WsStream, mode: CryptoMode) -> Result<Cipher> {
+async fn init_cipher(
+    client: &mut WsStream,
+    mode: CryptoMode,
+    last_seq: &mut Option<u64>,
+) -> Result<(Cipher, SessionDescription)> {
     loop {
         let value = match client.recv_json().await? {
-            Some(value) => value,
+            Some((value, seq)) => {
+                *last_seq = seq.or(*last_seq);
+                value
+            },
             None => continue,
         };
+        let value = match value {
+            Ok(value) => value,
+            Err(e) => {
+                debug!("Unexpected JSON {:?}.", e);
+                continue;
+            },
+        };
 
         match value {
-            GatewayEvent::SessionDescription(desc) => {
-                if desc.mode != mode.to_request_str() {
-                    return Err(Error::CryptoModeInvalid);
-                }
-
-                return match Cipher::new_from_slice(&desc.secret_key) {
-                    Ok(v) => Ok(v),
-                    Err(_) => Err(Error::Crypto(xsalsa20poly1305::Error)),
-                };
-            },
+            GatewayEvent::SessionDescription(desc) => return session_cipher(&desc, mode),
             other => {
                 debug!(
                     "Expected ready for key; got: op{}/v{:?}",
@@ -343,6 +555,22 @@ async fn init_cipher(client: &mut WsStream, mode: CryptoMode) -> Result<Cipher>
     }
 }
 
+/// Creates the cipher described by a session description, which must use `mode`.
+#[allow(clippy::result_large_err)]
+pub(crate) fn session_cipher(
+    desc: &SessionDescriptionPayload,
+    mode: CryptoMode,
+) -> Result<(Cipher, SessionDescription)> {
+    if desc.mode != mode.to_request_str() {
+        return Err(Error::CryptoModeInvalid);
+    }
+
+    match Cipher::new_from_slice(&desc.secret_key) {
+        Ok(v) => Ok((v, SessionDescription::new(mode, &desc.secret_key))),
+        Err(_) => Err(Error::Crypto(xsalsa20poly1305::Error)),
+    }
+}
+
 #[inline]
 fn has_valid_mode<T, It>(modes: It, mode: CryptoMode) -> bool
 where
@@ -351,3 +579,131 @@ where
 {
     modes.into_iter().any(|s| s == mode.to_request_str())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_tungstenite::tungstenite::protocol::CloseFrame;
+    use tokio::net::TcpListener;
+
+    #[cfg(feature = "internals")]
+    use crate::id::{GuildId, UserId};
+    #[cfg(feature = "internals")]
+    use async_tungstenite::tungstenite::Message;
+    #[cfg(feature = "internals")]
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn unreachable_family_falls_back_to_other() {
+        // Nothing listens on the IPv6 loopback, so connecting over it fails.
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let addrs = IpPreference::PreferV6.order(vec![
+            SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+            SocketAddr::from((Ipv6Addr::LOCALHOST, port)),
+        ]);
+        assert_eq!(IpFamily::of(addrs[0].ip()), IpFamily::V6);
+
+        let (_tcp, family) = connect_tcp(&addrs).await.unwrap();
+        assert_eq!(family, IpFamily::V4);
+
+        // Restricting to the failed family leaves nothing to fall back on.
+        let v6_only = IpPreference::V6Only.order(addrs);
+        assert!(connect_tcp(&v6_only).await.is_err());
+    }
+
+    fn closed_with(code: u16) -> WsError {
+        WsError::WsClosed(Some(CloseFrame {
+            code: CloseCode::Library(code),
+            reason: "".into(),
+        }))
+    }
+
+    #[test]
+    fn handshake_closures_are_judged_by_close_code() {
+        let rejected = handshake_error(closed_with(VoiceCloseCode::AuthenticationFailed as u16));
+        assert!(matches!(
+            rejected,
+            Error::AuthRejected(Some(VoiceCloseCode::AuthenticationFailed))
+        ));
+        assert!(!rejected.should_retry());
+
+        for e in [
+            closed_with(VoiceCloseCode::VoiceServerCrash as u16),
+            closed_with(1000),
+            WsError::WsClosed(None),
+        ] {
+            let e = handshake_error(e);
+            assert!(matches!(e, Error::Ws(_)));
+            assert!(e.should_retry());
+        }
+    }
+
+    /// Serves a voice gateway which closes every connection with `code` once
+    /// it has been sent an identify, counting each connection made.
+    #[cfg(feature = "internals")]
+    async fn closing_gateway(code: u16) -> (ConnectionInfo, Arc<AtomicUsize>) {
+        use async_tungstenite::tokio::accept_async;
+        use futures::{SinkExt, StreamExt};
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let info = ConnectionInfo {
+            channel_id: None,
+            endpoint: format!("ws://{}", listener.local_addr().unwrap()),
+            guild_id: GuildId(1),
+            session_id: "session".into(),
+            token: "token".into(),
+            user_id: UserId(2),
+        };
+        let connections = Arc::new(AtomicUsize::new(0));
+        let count = connections.clone();
+
+        spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                count.fetch_add(1, Ordering::SeqCst);
+                let mut ws = match accept_async(tcp).await {
+                    Ok(ws) => ws,
+                    Err(_) => continue,
+                };
+                let _ = ws.next().await;
+                let _ = ws
+                    .send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Library(code),
+                        reason: "".into(),
+                    })))
+                    .await;
+            }
+        });
+
+        (info, connections)
+    }
+
+    #[cfg(feature = "internals")]
+    #[tokio::test]
+    async fn only_transient_handshake_failures_are_retried() {
+        use crate::{driver::retry::*, Driver};
+
+        let retry = Retry {
+            strategy: Strategy::Every(Duration::from_millis(10)),
+            retry_limit: Some(2),
+            ..Default::default()
+        };
+        let config = Config::default().driver_retry(retry);
+
+        let (info, connections) =
+            closing_gateway(VoiceCloseCode::AuthenticationFailed as u16).await;
+        let mut driver = Driver::new(config.clone());
+        let e = driver.connect(info).await.unwrap_err();
+        assert!(matches!(
+            e,
+            Error::AuthRejected(Some(VoiceCloseCode::AuthenticationFailed))
+        ));
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        let (info, connections) = closing_gateway(VoiceCloseCode::VoiceServerCrash as u16).await;
+        let mut driver = Driver::new(config);
+        let e = driver.connect(info).await.unwrap_err();
+        assert!(matches!(e, Error::Ws(_)));
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+    }
+}
@@ -9,7 +9,7 @@ use xsalsa20poly1305::{
 };
 
 /// Variants of the XSalsa20Poly1305 encryption scheme.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 #[non_exhaustive]
 pub enum CryptoMode {
     /// The RTP header is used as the source of nonce bytes for the packet.
@@ -126,8 +126,9 @@ impl CryptoMode {
         let nonce_slice = if slice_to_use.len() == NONCE_SIZE {
             Nonce::from_slice(&slice_to_use[..NONCE_SIZE])
         } else {
-            let max_bytes_avail = slice_to_use.len();
-            nonce[..self.nonce_size().min(max_bytes_avail)].copy_from_slice(slice_to_use);
+            // Headers may be shorter (truncated) or longer (with CSRCs) than the nonce.
+            let len = self.nonce_size().min(slice_to_use.len());
+            nonce[..len].copy_from_slice(&slice_to_use[..len]);
             &nonce
         };
 
@@ -0,0 +1,68 @@
+use crate::model::{
+    payload::{Heartbeat, Resume},
+    Event as GatewayEvent,
+};
+use serde_json::{json, Value};
+
+/// Version of Discord's voice gateway protocol to negotiate within the driver.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum GatewayVersion {
+    /// Voice gateway v4.
+    ///
+    /// Heartbeats carry only a nonce, and resumed sessions will not
+    /// replay any messages missed during a disconnect.
+    V4,
+    /// Voice gateway v8.
+    ///
+    /// Messages from the server are sequenced, and heartbeats and resume attempts
+    /// acknowledge the last sequence number seen. This allows Discord to replay
+    /// any buffered messages missed while the driver was reconnecting.
+    V8,
+}
+
+impl GatewayVersion {
+    /// Returns the version number sent to Discord when connecting.
+    pub fn number(self) -> u8 {
+        use GatewayVersion::*;
+        match self {
+            V4 => 4,
+            V8 => 8,
+        }
+    }
+
+    /// Returns whether this version acknowledges sequenced server messages.
+    pub(crate) fn has_seq_ack(self) -> bool {
+        self != GatewayVersion::V4
+    }
+
+    /// Builds a heartbeat message, acknowledging `seq_ack` if supported.
+    pub(crate) fn heartbeat(self, nonce: u64, seq_ack: Option<u64>) -> Value {
+        if self.has_seq_ack() {
+            json!({
+                "op": 3,
+                "d": {
+                    "t": nonce,
+                    "seq_ack": seq_ack,
+                },
+            })
+        } else {
+            to_value(GatewayEvent::from(Heartbeat { nonce }))
+        }
+    }
+
+    /// Builds a resume message, acknowledging `seq_ack` if supported.
+    pub(crate) fn resume(self, resume: Resume, seq_ack: Option<u64>) -> Value {
+        let mut out = to_value(GatewayEvent::from(resume));
+
+        if self.has_seq_ack() {
+            out["d"]["seq_ack"] = json!(seq_ack);
+        }
+
+        out
+    }
+}
+
+fn to_value(evt: GatewayEvent) -> Value {
+    serde_json::to_value(evt).expect("Voice gateway events must serialize to valid JSON.")
+}
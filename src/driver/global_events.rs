@@ -0,0 +1,110 @@
+use crate::events::{Event, EventContext, EventData, EventHandle, EventHandler};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+
+/// A kept handler, alongside the event it currently waits on.
+type Entry = (EventHandle, Event, Arc<dyn EventHandler>);
+
+/// Global event handlers added to a [`Driver`], kept so that they can be
+/// restored if the driver's tasks must be recreated.
+///
+/// Each entry tracks the event its handler currently waits on, as handlers may
+/// change this (or cancel themselves) each time they fire.
+///
+/// [`Driver`]: super::Driver
+#[derive(Clone, Default)]
+pub(crate) struct GlobalEvents {
+    handlers: Arc<Mutex<Vec<Entry>>>,
+}
+
+impl GlobalEvents {
+    /// Keeps a copy of `action`, returning event data which shares it.
+    pub(crate) fn add<F: EventHandler + 'static>(&self, event: Event, action: F) -> EventData {
+        let handle = EventHandle::next();
+
+        self.handlers.lock().push((handle, event, Arc::new(action)));
+
+        self.data(handle)
+            .expect("Handler was stored immediately beforehand.")
+    }
+
+    pub(crate) fn remove(&self, handle: EventHandle) {
+        self.handlers.lock().retain(|(h, _, _)| *h != handle);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.handlers.lock().clear();
+    }
+
+    /// Creates fresh event data for every kept handler, for registration with
+    /// a new event processor.
+    pub(crate) fn restore(&self) -> Vec<EventData> {
+        let handles = self
+            .handlers
+            .lock()
+            .iter()
+            .map(|(handle, _, _)| *handle)
+            .collect::<Vec<_>>();
+
+        handles.into_iter().filter_map(|h| self.data(h)).collect()
+    }
+
+    fn data(&self, handle: EventHandle) -> Option<EventData> {
+        let handlers = self.handlers.lock();
+        let (_, event, action) = handlers.iter().find(|(h, _, _)| *h == handle)?;
+
+        Some(EventData {
+            handle,
+            event: *event,
+            fire_time: None,
+            action: Box::new(SharedHandler {
+                handle,
+                action: action.clone(),
+                store: self.clone(),
+            }),
+        })
+    }
+
+    /// Records the event a handler asked to wait on next.
+    fn update(&self, handle: EventHandle, event: Event) {
+        let mut handlers = self.handlers.lock();
+
+        if let Event::Cancel = event {
+            handlers.retain(|(h, _, _)| *h != handle);
+        } else if let Some(entry) = handlers.iter_mut().find(|(h, _, _)| *h == handle) {
+            entry.1 = event;
+        }
+    }
+}
+
+impl Debug for GlobalEvents {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("GlobalEvents")
+            .field("len", &self.handlers.lock().len())
+            .finish()
+    }
+}
+
+/// Runs a kept handler on behalf of the event processor.
+struct SharedHandler {
+    handle: EventHandle,
+    action: Arc<dyn EventHandler>,
+    store: GlobalEvents,
+}
+
+#[async_trait]
+impl EventHandler for SharedHandler {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        let next = self.action.act(ctx).await;
+
+        if let Some(event) = next {
+            self.store.update(self.handle, event);
+        }
+
+        next
+    }
+}
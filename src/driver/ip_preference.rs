@@ -0,0 +1,67 @@
+use std::net::{IpAddr, SocketAddr};
+
+/// Address families the driver may use when connecting to a voice server's
+/// websocket gateway, and the order in which they are tried.
+///
+/// Every address of an allowed family is tried in turn, so that a broken route
+/// over the preferred family (e.g., a misconfigured IPv6 network) falls back to
+/// the other family without failing the connection.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum IpPreference {
+    /// Only IPv4 addresses will be used.
+    V4Only,
+    /// Only IPv6 addresses will be used.
+    V6Only,
+    /// IPv4 addresses are tried first, followed by IPv6 addresses.
+    PreferV4,
+    /// IPv6 addresses are tried first, followed by IPv4 addresses.
+    PreferV6,
+}
+
+impl IpPreference {
+    /// Returns whether `family` may be used under this preference.
+    pub fn allows(self, family: IpFamily) -> bool {
+        !matches!(
+            (self, family),
+            (IpPreference::V4Only, IpFamily::V6) | (IpPreference::V6Only, IpFamily::V4)
+        )
+    }
+
+    /// Filters `addrs` down to the allowed families, placing the preferred
+    /// family first.
+    ///
+    /// The resolver's ordering is otherwise kept.
+    pub(crate) fn order(self, addrs: impl IntoIterator<Item = SocketAddr>) -> Vec<SocketAddr> {
+        let (v4, v6): (Vec<_>, Vec<_>) = addrs
+            .into_iter()
+            .partition(|addr| IpFamily::of(addr.ip()) == IpFamily::V4);
+
+        match self {
+            IpPreference::V4Only => v4,
+            IpPreference::V6Only => v6,
+            IpPreference::PreferV4 => v4.into_iter().chain(v6).collect(),
+            IpPreference::PreferV6 => v6.into_iter().chain(v4).collect(),
+        }
+    }
+}
+
+/// The address family of an established connection.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum IpFamily {
+    /// Internet Protocol version 4.
+    V4,
+    /// Internet Protocol version 6.
+    V6,
+}
+
+impl IpFamily {
+    /// Returns the family of the given address.
+    pub fn of(addr: IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(_) => IpFamily::V4,
+            IpAddr::V6(_) => IpFamily::V6,
+        }
+    }
+}
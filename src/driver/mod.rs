@@ -11,23 +11,62 @@
 #[cfg(feature = "internals")]
 pub mod bench_internals;
 
+mod bandwidth;
 pub(crate) mod connection;
 mod crypto;
 mod decode_mode;
+mod gateway_version;
+mod global_events;
+mod ip_preference;
+mod receive_filter;
+mod reconnect_resume;
+mod recorder;
+mod replay;
 pub mod retry;
 pub(crate) mod tasks;
+#[cfg(feature = "internals")]
+pub mod test;
+pub(crate) mod tx_redundancy;
+mod udp_socket_options;
+mod vbr_mode;
+mod voice_tick_mode;
 
+pub use bandwidth::Bandwidth;
 use connection::error::{Error, Result};
 pub use crypto::CryptoMode;
 pub(crate) use crypto::CryptoState;
 pub use decode_mode::DecodeMode;
+pub use gateway_version::GatewayVersion;
+use global_events::GlobalEvents;
+pub use ip_preference::{IpFamily, IpPreference};
+pub use receive_filter::{ReceiveFilter, ReceiveTarget};
+pub use reconnect_resume::ReconnectResume;
+pub use recorder::Recorder;
+pub use replay::{ReplayError, ReplayResult};
+pub use tx_redundancy::TxRedundancy;
+pub use udp_socket_options::UdpSocketOptions;
+pub use vbr_mode::VbrMode;
+pub use voice_tick_mode::VoiceTickMode;
 
 #[cfg(feature = "builtin-queue")]
 use crate::tracks::TrackQueue;
 use crate::{
-    events::EventData,
-    input::Input,
-    tracks::{self, Track, TrackHandle},
+    broadcast::Broadcast,
+    constants::TRY_PLAY_TIMEOUT,
+    events::{context_data::BitrateData, EventHandle, EventInfo},
+    id::UserId,
+    input::{
+        error::Result as InputResult,
+        tts::TtsBackend,
+        utils,
+        Codec,
+        Container,
+        Input,
+        Metadata,
+        Reader,
+    },
+    model::SpeakingState,
+    tracks::{self, PlayError, Track, TrackHandle},
     Config,
     ConnectionInfo,
     Event,
@@ -35,12 +74,19 @@ use crate::{
 };
 /// Opus encoder bitrate settings.
 pub use audiopus::{self as opus, Bitrate};
+/// Opus encoder application modes, tuning the encoder for speech, music, or latency.
+pub use audiopus::Application as OpusApplication;
 use core::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
 };
 use flume::{r#async::RecvFut, SendError, Sender};
+use std::{
+    collections::HashMap,
+    mem,
+    time::{Duration, Instant},
+};
 use tasks::message::CoreMessage;
 use tracing::instrument;
 
@@ -53,7 +99,13 @@ use tracing::instrument;
 pub struct Driver {
     config: Config,
     self_mute: bool,
+    receiving: bool,
+    master_gain: f32,
+    broadcast: Option<Broadcast>,
+    broadcast_volume: f32,
+    speaking_flags: SpeakingState,
     sender: Sender<CoreMessage>,
+    global_events: GlobalEvents,
     #[cfg(feature = "builtin-queue")]
     queue: TrackQueue,
 }
@@ -66,13 +118,24 @@ impl Driver {
     pub fn new(config: Config) -> Self {
         let sender = Self::start_inner(config.clone());
 
-        Driver {
+        let driver = Driver {
             config,
             self_mute: false,
+            receiving: true,
+            master_gain: 1.0,
+            broadcast: None,
+            broadcast_volume: 1.0,
+            speaking_flags: SpeakingState::empty(),
             sender,
+            global_events: Default::default(),
             #[cfg(feature = "builtin-queue")]
             queue: Default::default(),
-        }
+        };
+
+        #[cfg(feature = "builtin-queue")]
+        driver.queue.set_skip_debounce(driver.config.command_debounce);
+
+        driver
     }
 
     fn start_inner(config: Config) -> Sender<CoreMessage> {
@@ -86,7 +149,17 @@ impl Driver {
     fn restart_inner(&mut self) {
         self.sender = Self::start_inner(self.config.clone());
 
+        self.reapply_state();
+    }
+
+    /// Resends settings held by the mixer, after it has been replaced.
+    fn reapply_state(&mut self) {
         self.mute(self.self_mute);
+        self.set_receiving(self.receiving);
+        self.set_master_gain(self.master_gain);
+        self.set_broadcast_volume(self.broadcast_volume);
+        self.set_broadcast(self.broadcast.clone());
+        self.set_speaking_flags(self.speaking_flags);
     }
 
     /// Connects to a voice channel using the specified server.
@@ -136,6 +209,83 @@ impl Driver {
         self.self_mute
     }
 
+    /// Sets a gain applied to the sum of all playing tracks.
+    ///
+    /// This attenuates (or boosts) everything the driver sends uniformly, without
+    /// changing the volume of any individual track. Each frame of audio is built in
+    /// the following order:
+    ///
+    /// 1. each track's pitch shift, equalizer, and noise gate, if any, are applied,
+    /// 2. each track is scaled by its volume, including any active ducking,
+    /// 3. all tracks are summed,
+    /// 4. the sum is scaled by this master gain,
+    /// 5. a soft clipper limits any peaks outside `[-1.0, 1.0]`.
+    ///
+    /// Overlapping tracks therefore add together, and a master gain below `1.0` can
+    /// keep a loud mix out of the limiter. Any gain other than `1.0` disables Opus
+    /// passthrough. Defaults to `1.0`.
+    ///
+    /// If there is no live voice connection, then this only acts as a settings
+    /// update for future connections.
+    #[instrument(skip(self))]
+    pub fn set_master_gain(&mut self, gain: f32) {
+        self.master_gain = gain;
+        self.send(CoreMessage::SetMasterGain(gain));
+    }
+
+    /// Returns the gain applied to the sum of all playing tracks.
+    #[instrument(skip(self))]
+    pub fn master_gain(&self) -> f32 {
+        self.master_gain
+    }
+
+    /// Sets whether audio received from other users is processed.
+    ///
+    /// While paused, incoming voice packets are discarded before decryption and
+    /// decoding, and no [`VoicePacket`], [`SpeakingUpdate`], or [`VoiceTick`] events
+    /// fire, saving CPU while the call stays connected and keeps sending audio. The
+    /// mapping of SSRCs to users is retained, but per-stream decoder state is flushed
+    /// on pause so that decoding restarts cleanly from the first packet after resuming.
+    ///
+    /// If there is no live voice connection, then this only acts as a settings
+    /// update for future connections.
+    ///
+    /// [`VoicePacket`]: crate::events::CoreEvent::VoicePacket
+    /// [`SpeakingUpdate`]: crate::events::CoreEvent::SpeakingUpdate
+    /// [`VoiceTick`]: crate::events::CoreEvent::VoiceTick
+    #[instrument(skip(self))]
+    pub fn set_receiving(&mut self, receiving: bool) {
+        self.receiving = receiving;
+        self.send(CoreMessage::SetReceiving(receiving));
+    }
+
+    /// Returns whether the driver is processing received audio.
+    #[instrument(skip(self))]
+    pub fn is_receiving(&self) -> bool {
+        self.receiving
+    }
+
+    /// Sets additional flags to announce alongside audio, such as
+    /// [`SpeakingState::PRIORITY`] to duck other users as a priority speaker,
+    /// or [`SpeakingState::SOUNDSHARE`].
+    ///
+    /// These are sent in the `speaking` bitfield of Discord's Speaking payload
+    /// (`MICROPHONE = 1`, `SOUNDSHARE = 2`, `PRIORITY = 4`) whenever the driver
+    /// starts sending audio, including after a reconnect or a restart of its tasks.
+    /// Changes made while audio is playing are announced on the next packet.
+    /// [`SpeakingState::MICROPHONE`] is managed by the driver, and is ignored here.
+    /// Priority speaking also requires the bot to hold the *Priority Speaker*
+    /// permission.
+    ///
+    /// [`SpeakingState::PRIORITY`]: crate::model::SpeakingState::PRIORITY
+    /// [`SpeakingState::SOUNDSHARE`]: crate::model::SpeakingState::SOUNDSHARE
+    /// [`SpeakingState::MICROPHONE`]: crate::model::SpeakingState::MICROPHONE
+    #[instrument(skip(self))]
+    pub fn set_speaking_flags(&mut self, flags: SpeakingState) {
+        self.speaking_flags = flags;
+        self.send(CoreMessage::SetSpeakingFlags(flags));
+    }
+
     /// Plays audio from a source, returning a handle for further control.
     ///
     /// This can be a source created via [`ffmpeg`] or [`ytdl`].
@@ -150,6 +300,143 @@ impl Driver {
         handle
     }
 
+    /// Plays audio from a source, resolving once it has produced its first frame
+    /// of audio or has failed to start.
+    ///
+    /// Unlike [`play_source`], this lets callers report a source which could not
+    /// be played (*e.g.*, a dead link or a crashed `ffmpeg` process) as soon as that
+    /// is known. The wait is bounded by [`Config::input_timeout`] if set, or by
+    /// [`TRY_PLAY_TIMEOUT`] otherwise; a source which misses this deadline is stopped.
+    ///
+    /// Tracks only play while the driver is connected, so this should be called
+    /// once a connection has been made.
+    ///
+    /// [`play_source`]: Driver::play_source
+    /// [`Config::input_timeout`]: crate::Config::input_timeout
+    /// [`TRY_PLAY_TIMEOUT`]: crate::constants::TRY_PLAY_TIMEOUT
+    #[instrument(skip(self, source))]
+    pub fn try_play(
+        &mut self,
+        source: Input,
+    ) -> impl Future<Output = std::result::Result<TrackHandle, PlayError>> {
+        let (mut player, handle) = super::create_player(source);
+        let (tx, rx) = flume::bounded(1);
+        player.started = Some(tx);
+        self.send(CoreMessage::AddTrack(player));
+
+        let timeout = self.config.input_timeout.unwrap_or(TRY_PLAY_TIMEOUT);
+
+        async move {
+            match tokio::time::timeout(timeout, rx.recv_async()).await {
+                Ok(Ok(())) => Ok(handle),
+                Ok(Err(_)) => Err(match handle.failure() {
+                    Some(failure) => PlayError::Failed(failure),
+                    None if handle.timed_out() => PlayError::TimedOut,
+                    None => PlayError::Ended,
+                }),
+                Err(_) => {
+                    let _ = handle.stop();
+                    Err(PlayError::TimedOut)
+                },
+            }
+        }
+    }
+
+    /// Speaks `text` aloud, synthesized using `backend` with its default voice,
+    /// returning a handle for further control.
+    ///
+    /// Speech is played alongside any other tracks. To choose a voice, call
+    /// [`TtsBackend::synthesize`] directly and pass the result to [`play_source`].
+    ///
+    /// The returned future does not borrow the driver, which need not stay locked
+    /// while speech is synthesized.
+    ///
+    /// [`TtsBackend::synthesize`]: crate::input::tts::TtsBackend::synthesize
+    /// [`play_source`]: Driver::play_source
+    #[instrument(skip(self, backend))]
+    pub fn speak<'a, B>(
+        &mut self,
+        text: &'a str,
+        backend: &'a B,
+    ) -> impl Future<Output = InputResult<TrackHandle>> + 'a
+    where
+        B: TtsBackend + ?Sized,
+    {
+        let sender = self.sender.clone();
+
+        async move {
+            let source = backend.synthesize(text, &Default::default()).await?;
+
+            let (player, handle) = super::create_player(source);
+            let _ = sender.send(CoreMessage::AddTrack(player));
+
+            Ok(handle)
+        }
+    }
+
+    /// Plays back up to `duration` of the latest audio received from `user`,
+    /// returning a handle for further control.
+    ///
+    /// Received audio is only held if [`Config::receive_history`] is set and the
+    /// driver uses [`DecodeMode::Decode`]. If less than `duration` is held, whatever
+    /// is available is played unless [`Config::replay_partial`] is disabled.
+    ///
+    /// History is kept while receiving is paused by [`set_receiving`]. The returned
+    /// future does not borrow the driver, which need not stay locked while it runs.
+    ///
+    /// [`Config::receive_history`]: crate::Config::receive_history
+    /// [`Config::replay_partial`]: crate::Config::replay_partial
+    /// [`set_receiving`]: Driver::set_receiving
+    #[instrument(skip(self, user))]
+    pub fn replay_user(
+        &mut self,
+        user: impl Into<UserId>,
+        duration: Duration,
+    ) -> impl Future<Output = ReplayResult<TrackHandle>> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetUserHistory(user.into(), duration, tx));
+
+        let replay_partial = self.config.replay_partial;
+        let sender = self.sender.clone();
+
+        async move {
+            let samples = rx.recv_async().await.unwrap_or_default();
+            if samples.is_empty() {
+                return Err(ReplayError::NoAudio);
+            }
+
+            let available = utils::sample_count_to_timestamp(samples.len(), true);
+            if !replay_partial && samples.len() < utils::timestamp_to_sample_count(duration, true)
+            {
+                return Err(ReplayError::Insufficient {
+                    available,
+                    requested: duration,
+                });
+            }
+
+            let mut bytes = Vec::with_capacity(samples.len() * mem::size_of::<i16>());
+            for sample in samples {
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+
+            let metadata = Metadata {
+                duration: Some(available),
+                ..Default::default()
+            };
+
+            let (player, handle) = super::create_player(Input::new(
+                true,
+                Reader::from_memory(bytes),
+                Codec::Pcm,
+                Container::Raw,
+                Some(metadata),
+            ));
+            let _ = sender.send(CoreMessage::AddTrack(player));
+
+            Ok(handle)
+        }
+    }
+
     /// Plays audio from a source, returning a handle for further control.
     ///
     /// Unlike [`play_source`], this stops all other sources attached
@@ -195,6 +482,59 @@ impl Driver {
         self.send(CoreMessage::SetTrack(Some(track)));
     }
 
+    /// Sends a single pre-encoded 20ms Opus frame in the next outgoing packet.
+    ///
+    /// This frame *preempts* any mixed audio for that packet: playing tracks
+    /// still advance as normal, but their audio for the 20ms is dropped. Several
+    /// frames sent in a row are played back-to-back, one per packet. This suits
+    /// short, latency-sensitive sounds (e.g., a beep acknowledging a command),
+    /// which would otherwise need a full [`Input`] and [`Track`].
+    ///
+    /// Frames are discarded if the driver is not connected, is muted, or if they
+    /// are too large to fit in a voice packet.
+    ///
+    /// [`Input`]: crate::input::Input
+    /// [`Track`]: crate::tracks::Track
+    #[instrument(skip(self, frame))]
+    pub fn play_instant_opus(&mut self, frame: Vec<u8>) {
+        self.send(CoreMessage::PlayInstantOpus(frame));
+    }
+
+    pub(crate) fn set_broadcast(&mut self, broadcast: Option<Broadcast>) {
+        let subscriber = broadcast.as_ref().map(Broadcast::subscribe);
+        self.broadcast = broadcast;
+        self.send(CoreMessage::SetBroadcast(subscriber));
+    }
+
+    pub(crate) fn broadcast(&self) -> Option<&Broadcast> {
+        self.broadcast.as_ref()
+    }
+
+    /// Sets the volume of any [`Broadcast`] this driver is subscribed to.
+    ///
+    /// Broadcast audio is scaled by this volume and any active ducking, before it
+    /// is summed with this driver's tracks and scaled by the [master gain].
+    /// Defaults to `1.0`.
+    ///
+    /// If there is no live voice connection, then this only acts as a settings
+    /// update for future connections.
+    ///
+    /// [`Broadcast`]: crate::Broadcast
+    /// [master gain]: Driver::set_master_gain
+    #[instrument(skip(self))]
+    pub fn set_broadcast_volume(&mut self, volume: f32) {
+        self.broadcast_volume = volume;
+        self.send(CoreMessage::SetBroadcastVolume(volume));
+    }
+
+    /// Returns the volume of any [`Broadcast`] this driver is subscribed to.
+    ///
+    /// [`Broadcast`]: crate::Broadcast
+    #[instrument(skip(self))]
+    pub fn broadcast_volume(&self) -> f32 {
+        self.broadcast_volume
+    }
+
     /// Sets the bitrate for encoding Opus packets sent along
     /// the channel being managed.
     ///
@@ -213,13 +553,186 @@ impl Driver {
         self.send(CoreMessage::SetTrack(None))
     }
 
+    /// Returns the bitrate most recently requested for this driver, alongside
+    /// the bitrate actually applied by its Opus encoder.
+    ///
+    /// The encoder may not honour every request exactly. Differences after a change
+    /// are also reported via [`CoreEvent::BitrateMismatch`].
+    ///
+    /// [`CoreEvent::BitrateMismatch`]: crate::events::CoreEvent::BitrateMismatch
+    #[instrument(skip(self))]
+    pub fn bitrate(&mut self) -> impl Future<Output = Option<BitrateData>> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetBitrate(tx));
+
+        async move { rx.recv_async().await.ok() }
+    }
+
+    /// Returns the RTP SSRC used for this driver's outgoing audio, if connected.
+    ///
+    /// This may change whenever the driver reconnects: the current value is also
+    /// reported by [`CoreEvent::DriverConnect`] and [`CoreEvent::DriverReconnect`].
+    ///
+    /// This is mainly useful when bridging audio between calls. A bridge which uses
+    /// two bots in the same channel (one receiving, one playing relayed audio) must
+    /// stop the receiving driver from hearing the other's output, or audio will loop.
+    /// Relayed tracks can record which stream they came from in their
+    /// [`TrackHandle::typemap`].
+    ///
+    /// ```rust,no_run
+    /// use songbird::driver::{Driver, ReceiveFilter, ReceiveTarget};
+    ///
+    /// async fn exclude_relay(listener: &mut Driver, speaker: &mut Driver) {
+    ///     if let Some(ssrc) = speaker.ssrc().await {
+    ///         listener.set_receive_filter(ReceiveFilter::Deny(vec![ReceiveTarget::Ssrc(ssrc)]));
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`CoreEvent::DriverConnect`]: crate::events::CoreEvent::DriverConnect
+    /// [`CoreEvent::DriverReconnect`]: crate::events::CoreEvent::DriverReconnect
+    /// [`TrackHandle::typemap`]: crate::tracks::TrackHandle::typemap
+    #[instrument(skip(self))]
+    pub fn ssrc(&mut self) -> impl Future<Output = Option<u32>> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetSsrc(tx));
+
+        async move { rx.recv_async().await.ok().flatten() }
+    }
+
+    /// Returns the latest round-trip time to the voice server, if connected.
+    ///
+    /// This is first measured during IP discovery, and then refreshed by further
+    /// discovery requests while [`Config::latency_interval`] is set. Periodic updates
+    /// can also be received via [`CoreEvent::Latency`].
+    ///
+    /// [`Config::latency_interval`]: crate::Config::latency_interval
+    ///
+    /// [`CoreEvent::Latency`]: crate::events::CoreEvent::Latency
+    #[instrument(skip(self))]
+    pub fn latency(&mut self) -> impl Future<Output = Option<Duration>> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetLatency(tx));
+
+        async move { rx.recv_async().await.ok().flatten() }
+    }
+
+    /// Returns the number of other users known to be in the voice channel.
+    ///
+    /// Users are learned from the speaking and client connect events sent by the
+    /// voice gateway, and forgotten on [`CoreEvent::ClientDisconnect`]. Users who
+    /// have not yet sent audio may not be counted, so a count of zero is best
+    /// confirmed alongside [`CoreEvent::UserInactive`] before pausing or leaving.
+    ///
+    /// This is `None` if the driver is not connected, or if the voice gateway has
+    /// not yet announced any user since the driver (re)connected.
+    ///
+    /// [`CoreEvent::ClientDisconnect`]: crate::events::CoreEvent::ClientDisconnect
+    /// [`CoreEvent::UserInactive`]: crate::events::CoreEvent::UserInactive
+    #[instrument(skip(self))]
+    pub fn listener_count(&mut self) -> impl Future<Output = Option<usize>> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetListenerCount(tx));
+
+        async move { rx.recv_async().await.ok().flatten() }
+    }
+
+    /// Returns the time at which each received voice stream last sent a packet,
+    /// keyed by SSRC.
+    ///
+    /// This is empty if the driver is not connected. Streams are forgotten when
+    /// their user disconnects, or when they are excluded by [`Config::receive_filter`].
+    ///
+    /// [`Config::receive_filter`]: crate::Config::receive_filter
+    #[instrument(skip(self))]
+    pub fn user_activity(&mut self) -> impl Future<Output = HashMap<u32, Instant>> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetUserActivity(tx));
+
+        async move { rx.recv_async().await.unwrap_or_default() }
+    }
+
+    /// Returns the number of received packets dropped as replays during
+    /// the current connection.
+    ///
+    /// This is always zero unless [`Config::replay_window`] is set, or if
+    /// the driver is not connected.
+    ///
+    /// [`Config::replay_window`]: crate::Config::replay_window
+    #[instrument(skip(self))]
+    pub fn replay_dropped(&mut self) -> impl Future<Output = u64> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetReplayDropped(tx));
+
+        async move { rx.recv_async().await.unwrap_or_default() }
+    }
+
+    /// Returns the number of received packets dropped as malformed during
+    /// the current connection.
+    ///
+    /// This counts datagrams which are too large, are truncated, cannot be
+    /// parsed as RTP or RTCP, or hold undecodable audio. This is always zero if
+    /// the driver is not connected.
+    #[instrument(skip(self))]
+    pub fn malformed_dropped(&mut self) -> impl Future<Output = u64> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetMalformedDropped(tx));
+
+        async move { rx.recv_async().await.unwrap_or_default() }
+    }
+
+    /// Returns the number of received packets dropped by the OS during the current
+    /// connection, because the UDP socket's receive buffer was full.
+    ///
+    /// Frequent drops suggest raising [`UdpSocketOptions::recv_buffer_size`], or
+    /// reading several packets at once with [`UdpSocketOptions::recv_batch`].
+    /// This is only reported on Linux, and is `None` elsewhere or if the driver
+    /// is not connected.
+    ///
+    /// [`UdpSocketOptions::recv_buffer_size`]: UdpSocketOptions::recv_buffer_size
+    /// [`UdpSocketOptions::recv_batch`]: UdpSocketOptions::recv_batch
+    #[instrument(skip(self))]
+    pub fn socket_dropped(&mut self) -> impl Future<Output = Option<u64>> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetSocketDropped(tx));
+
+        async move { rx.recv_async().await.ok().flatten() }
+    }
+
+    /// Returns the number of outgoing frames which failed to encode, and were
+    /// replaced with silence.
+    ///
+    /// Failures should not occur in normal operation: persistent failures cause
+    /// the Opus encoder to be rebuilt.
+    #[instrument(skip(self))]
+    pub fn encode_errors(&mut self) -> impl Future<Output = u64> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::GetEncodeErrors(tx));
+
+        async move { rx.recv_async().await.unwrap_or_default() }
+    }
+
     /// Sets the configuration for this driver (and parent `Call`, if applicable).
     #[instrument(skip(self))]
     pub fn set_config(&mut self, config: Config) {
+        #[cfg(feature = "builtin-queue")]
+        self.queue.set_skip_debounce(config.command_debounce);
+
         self.config = config.clone();
         self.send(CoreMessage::SetConfig(config))
     }
 
+    /// Sets which users' received audio is decoded by this driver.
+    ///
+    /// This updates the driver's [`Config::receive_filter`].
+    ///
+    /// [`Config::receive_filter`]: crate::Config::receive_filter
+    #[instrument(skip(self))]
+    pub fn set_receive_filter(&mut self, filter: ReceiveFilter) {
+        self.config.receive_filter = filter;
+        self.send(CoreMessage::SetConfig(self.config.clone()))
+    }
+
     /// Returns a view of this driver's configuration.
     #[instrument(skip(self))]
     pub fn config(&self) -> &Config {
@@ -237,20 +750,149 @@ impl Driver {
     /// within the supplied function or closure. *Taking excess time could prevent
     /// timely sending of packets, causing audio glitches and delays*.
     ///
+    /// The returned [`EventHandle`] may be passed to [`remove_global_event`]
+    /// to remove this handler later.
+    ///
     /// [`Track`]: crate::tracks::Track
     /// [`TrackEvent`]: crate::events::TrackEvent
     /// [`EventContext`]: crate::events::EventContext
+    /// [`EventHandle`]: crate::events::EventHandle
+    /// [`remove_global_event`]: Driver::remove_global_event
     #[instrument(skip(self, action))]
-    pub fn add_global_event<F: EventHandler + 'static>(&mut self, event: Event, action: F) {
-        self.send(CoreMessage::AddEvent(EventData::new(event, action)));
+    pub fn add_global_event<F: EventHandler + 'static>(
+        &mut self,
+        event: Event,
+        action: F,
+    ) -> EventHandle {
+        let data = self.global_events.add(event, action);
+        let handle = data.handle();
+        self.send(CoreMessage::AddEvent(data));
+
+        handle
+    }
+
+    /// Removes a single global event handler, as returned by [`add_global_event`].
+    ///
+    /// Removal is ordered with respect to event dispatch: once processed, the
+    /// handler will not fire again. Removing a handler which has already been
+    /// removed or cancelled has no effect.
+    ///
+    /// [`add_global_event`]: Driver::add_global_event
+    #[instrument(skip(self))]
+    pub fn remove_global_event(&mut self, handle: EventHandle) {
+        self.global_events.remove(handle);
+        self.send(CoreMessage::RemoveGlobalEvent(handle));
     }
 
     /// Removes all global event handlers from an audio context.
     #[instrument(skip(self))]
     pub fn remove_all_global_events(&mut self) {
+        self.global_events.clear();
         self.send(CoreMessage::RemoveGlobalEvents);
     }
 
+    /// Request descriptions of all global events registered on this driver.
+    ///
+    /// This is intended for debugging and inspection, and does not expose
+    /// any event handlers.
+    #[instrument(skip(self))]
+    pub fn list_global_events(&mut self) -> impl Future<Output = Vec<EventInfo>> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(CoreMessage::ListGlobalEvents(tx));
+
+        async move { rx.recv_async().await.unwrap_or_default() }
+    }
+
+    /// Requests a fresh session key from the voice server, and switches to it
+    /// without leaving the call.
+    ///
+    /// This is a recovery tool for rare desync bugs, where the voice server stops
+    /// accepting (or the driver stops decrypting) packets because both sides no
+    /// longer agree on the encryption state, such as a nonce counter. Symptoms
+    /// include audio that appears to send normally but is never heard, while the
+    /// connection otherwise stays healthy. Tracks keep playing throughout, and
+    /// [`CoreEvent::CryptoRenegotiated`] fires once the new key is in use. Most
+    /// bots should never need to call this.
+    ///
+    /// This resends the protocol selection made when connecting, which the voice
+    /// gateway does not document as a mid-session operation: a server may ignore
+    /// it, or close the connection (which the driver then handles as it would any
+    /// other disconnect). Only a session description which arrives after this
+    /// call is put to use.
+    ///
+    /// This has no effect if the driver is not connected.
+    ///
+    /// [`CoreEvent::CryptoRenegotiated`]: crate::events::CoreEvent::CryptoRenegotiated
+    #[instrument(skip(self))]
+    pub fn renegotiate_crypto(&mut self) {
+        self.send(CoreMessage::RenegotiateCrypto);
+    }
+
+    /// Returns a handle for messaging the inner tasks directly.
+    pub(crate) fn core_sender(&self) -> Sender<CoreMessage> {
+        self.sender.clone()
+    }
+
+    /// Reports whether all of this driver's background tasks are still running.
+    ///
+    /// Returns `None` if the core task has itself exited.
+    pub(crate) fn check_tasks(&self) -> impl Future<Output = Option<bool>> {
+        let (tx, rx) = flume::bounded(1);
+        let sent = self.sender.send(CoreMessage::CheckTasks(tx)).is_ok();
+
+        async move {
+            if sent {
+                rx.recv_async().await.ok()
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Restarts any of this driver's background tasks which have exited.
+    ///
+    /// Tracks held by a failed mixer are lost, so the built-in queue is then cleared.
+    /// Returns `true` if the whole driver was recreated, losing its connection: its
+    /// global event handlers are restored, and [`CoreEvent::DriverRecreated`] fires
+    /// straight away.
+    ///
+    /// [`CoreEvent::DriverRecreated`]: crate::events::CoreEvent::DriverRecreated
+    pub(crate) fn restart_tasks(&mut self) -> bool {
+        #[cfg(feature = "builtin-queue")]
+        let queue = Some(self.queue.clone());
+        #[cfg(not(feature = "builtin-queue"))]
+        let queue = None;
+
+        if self.sender.send(CoreMessage::RestartTasks(queue)).is_ok() {
+            self.reapply_state();
+            return false;
+        }
+
+        self.restart_inner();
+
+        for data in self.global_events.restore() {
+            self.send(CoreMessage::AddEvent(data));
+        }
+
+        #[cfg(feature = "builtin-queue")]
+        self.queue.stop();
+
+        self.send(CoreMessage::SignalRecreated);
+
+        true
+    }
+
+    /// Drops the current connection after the watchdog has given up restarting
+    /// this driver's tasks, firing [`CoreEvent::DriverDisconnect`] with reason
+    /// [`DisconnectReason::TasksFailed`].
+    ///
+    /// [`CoreEvent::DriverDisconnect`]: crate::events::CoreEvent::DriverDisconnect
+    /// [`DisconnectReason::TasksFailed`]: crate::events::context_data::DisconnectReason::TasksFailed
+    pub(crate) fn signal_tasks_failed(&mut self) {
+        let handlers = self.global_events.restore();
+        self.send(CoreMessage::SignalTasksFailed(handlers));
+    }
+
     /// Sends a message to the inner tasks, restarting it if necessary.
     fn send(&mut self, status: CoreMessage) {
         // Restart thread if it errored.
@@ -295,6 +937,7 @@ impl Driver {
     /// [`Track`]: crate::tracks::Track
     pub fn enqueue(&mut self, mut track: Track) {
         self.queue.add_raw(&mut track);
+        self.queue.set_driver(self.core_sender());
         self.play(track);
     }
 }
@@ -0,0 +1,66 @@
+use crate::id::UserId;
+
+/// A user or RTP source identified by a [`ReceiveFilter`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ReceiveTarget {
+    /// All packets sent by the given user.
+    ///
+    /// Packets are only associated with a user once Discord has reported
+    /// the user's SSRC via a [`SpeakingStateUpdate`].
+    ///
+    /// [`SpeakingStateUpdate`]: crate::events::CoreEvent::SpeakingStateUpdate
+    User(UserId),
+    /// All packets with the given RTP synchronisation source.
+    Ssrc(u32),
+}
+
+impl From<UserId> for ReceiveTarget {
+    fn from(user: UserId) -> Self {
+        Self::User(user)
+    }
+}
+
+/// Selects which users' received packets are processed by the driver.
+///
+/// Packets rejected by a filter are dropped before any decoding takes place,
+/// and no decoder state is allocated for their sender. As a result, they will
+/// not trigger [`VoicePacket`], [`VoiceTick`], or [`SpeakingUpdate`] events.
+/// SSRC/user mappings are still tracked for all users, so that filters may
+/// change at any time.
+///
+/// [`VoicePacket`]: crate::events::CoreEvent::VoicePacket
+/// [`VoiceTick`]: crate::events::CoreEvent::VoiceTick
+/// [`SpeakingUpdate`]: crate::events::CoreEvent::SpeakingUpdate
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ReceiveFilter {
+    /// Packets from all sources are processed.
+    All,
+    /// Only packets from the listed sources are processed.
+    ///
+    /// Packets from an SSRC which is not yet known to belong to a
+    /// listed user are dropped.
+    Allow(Vec<ReceiveTarget>),
+    /// Packets from all sources except those listed are processed.
+    Deny(Vec<ReceiveTarget>),
+}
+
+impl ReceiveFilter {
+    /// Returns whether packets from `ssrc`, which may belong to `user`,
+    /// should be processed.
+    pub fn allows(&self, ssrc: u32, user: Option<UserId>) -> bool {
+        let listed = |targets: &[ReceiveTarget]| {
+            targets.iter().any(|t| match t {
+                ReceiveTarget::Ssrc(s) => *s == ssrc,
+                ReceiveTarget::User(u) => Some(*u) == user,
+            })
+        };
+
+        match self {
+            Self::All => true,
+            Self::Allow(targets) => listed(targets),
+            Self::Deny(targets) => !listed(targets),
+        }
+    }
+}
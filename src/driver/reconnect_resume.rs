@@ -0,0 +1,32 @@
+/// Handling of playing tracks when the driver reconnects after losing its connection.
+///
+/// Audio may continue to be mixed and sent while a connection is being resumed (see
+/// [`Config::ws_outage_playback`]), and is lost if the voice session turns out to have
+/// died. These modes return tracks to where they were when the connection was lost.
+///
+/// [`Config::ws_outage_playback`]: crate::Config::ws_outage_playback
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ReconnectResume {
+    /// Tracks carry on from wherever they were when the new connection is made.
+    Disabled,
+    /// Seekable tracks are returned to their positions when the connection was lost.
+    ///
+    /// Tracks which cannot seek (*e.g.*, live streams) carry on as usual.
+    Rewind,
+    /// Seekable tracks are returned to their positions when the connection was lost.
+    ///
+    /// Tracks which cannot seek are ended, reporting
+    /// [`TrackError::SeekUnsupported`] as their [`EndReason`].
+    ///
+    /// [`TrackError::SeekUnsupported`]: crate::tracks::TrackError::SeekUnsupported
+    /// [`EndReason`]: crate::tracks::EndReason
+    RewindOrStop,
+}
+
+impl ReconnectResume {
+    /// Returns whether track positions must be saved when the connection is lost.
+    pub fn is_enabled(self) -> bool {
+        self != ReconnectResume::Disabled
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    constants::SAMPLE_RATE_RAW,
+    events::{Event, EventContext, EventHandler},
+};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::{
+    fs::File,
+    io::{BufWriter, Result as IoResult, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::warn;
+
+const WAV_HEADER_LEN: u32 = 44;
+const CHANNELS: u16 = 2;
+const BYTES_PER_SAMPLE: u16 = 2;
+
+/// Records all received audio to WAV files, which can be split into chunks
+/// without stopping.
+///
+/// A `Recorder` writes each [`CoreEvent::MixedAudio`] frame it is given as
+/// 16-bit stereo PCM at 48kHz. As these frames are filled with silence while no-one
+/// is speaking, the time between and within recordings matches the call itself.
+/// This requires [`Config::mixed_audio`] and [`DecodeMode::Decode`].
+///
+/// Audio is written to numbered files alongside the path given to [`new`]: `call.wav`
+/// is recorded to `call-0000.wav`, then `call-0001.wav` after the first [`rotate`],
+/// and so on. Each file is limited to 4GiB (just over six hours), after which the
+/// recording rotates by itself.
+///
+/// ```rust,no_run
+/// # use songbird::{driver::Recorder, events::CoreEvent, Driver};
+/// # fn example(driver: &mut Driver) -> std::io::Result<()> {
+/// let recorder = Recorder::new("call.wav")?;
+/// driver.add_global_event(CoreEvent::MixedAudio.into(), recorder.clone());
+///
+/// // Later, such as once every hour:
+/// let finished = recorder.rotate()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`CoreEvent::MixedAudio`]: crate::events::CoreEvent::MixedAudio
+/// [`Config::mixed_audio`]: crate::Config::mixed_audio
+/// [`DecodeMode::Decode`]: super::DecodeMode::Decode
+/// [`new`]: Recorder::new
+/// [`rotate`]: Recorder::rotate
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    inner: Arc<Mutex<RecorderState>>,
+}
+
+#[derive(Debug)]
+struct RecorderState {
+    base: PathBuf,
+    index: u32,
+    file: Option<WavFile>,
+}
+
+impl Recorder {
+    /// Starts recording to the first numbered file alongside `path`.
+    ///
+    /// Existing files with the same names are overwritten.
+    pub fn new(path: impl Into<PathBuf>) -> IoResult<Self> {
+        let base = path.into();
+        let file = WavFile::create(chunk_path(&base, 0))?;
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(RecorderState {
+                base,
+                index: 0,
+                file: Some(file),
+            })),
+        })
+    }
+
+    /// Finishes the current file and carries on recording to the next, returning
+    /// the path of the finished file.
+    ///
+    /// The switch happens between two frames, so no audio is lost or repeated
+    /// across the boundary. If the next file cannot be created, the current file
+    /// is kept open and recording continues there.
+    pub fn rotate(&self) -> IoResult<PathBuf> {
+        self.inner.lock().rotate()
+    }
+
+    /// Finishes the current file and stops recording, returning the path of the
+    /// finished file.
+    ///
+    /// Returns `None` if recording had already stopped.
+    pub fn finish(&self) -> IoResult<Option<PathBuf>> {
+        self.inner.lock().file.take().map(WavFile::finish).transpose()
+    }
+
+    /// Returns the path of the file currently being recorded to, if any.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.inner.lock().file.as_ref().map(|file| file.path.clone())
+    }
+
+    fn write(&self, audio: &[i16]) -> IoResult<()> {
+        let mut state = self.inner.lock();
+
+        let frame_len = (audio.len() * BYTES_PER_SAMPLE as usize) as u64;
+        let full = match state.file.as_ref() {
+            Some(file) => u64::from(file.data_len) + frame_len > WavFile::MAX_DATA_LEN,
+            None => return Ok(()),
+        };
+        if full {
+            state.rotate()?;
+        }
+
+        match state.file.as_mut() {
+            Some(file) => file.write(audio),
+            None => Ok(()),
+        }
+    }
+}
+
+impl RecorderState {
+    fn rotate(&mut self) -> IoResult<PathBuf> {
+        let next = WavFile::create(chunk_path(&self.base, self.index + 1))?;
+        self.index += 1;
+
+        match self.file.replace(next) {
+            Some(done) => done.finish(),
+            None => Ok(chunk_path(&self.base, self.index - 1)),
+        }
+    }
+}
+
+impl Drop for RecorderState {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            if let Err(e) = file.finish() {
+                warn!("Failed to finish recording: {:?}", e);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for Recorder {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::MixedAudio(data) = ctx {
+            if let Err(e) = self.write(data.audio) {
+                warn!("Failed to record received audio: {:?}", e);
+            }
+        }
+
+        None
+    }
+}
+
+/// Returns the path of the `index`th file recorded alongside `base`.
+fn chunk_path(base: &Path, index: u32) -> PathBuf {
+    let stem = base.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match base.extension() {
+        Some(ext) => format!("{}-{:04}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}-{:04}", stem, index),
+    };
+
+    base.with_file_name(name)
+}
+
+#[derive(Debug)]
+struct WavFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    data_len: u32,
+}
+
+impl WavFile {
+    /// Largest amount of audio which a WAV file can describe.
+    const MAX_DATA_LEN: u64 = (u32::MAX - WAV_HEADER_LEN) as u64;
+
+    fn create(path: PathBuf) -> IoResult<Self> {
+        let mut writer = BufWriter::with_capacity(1 << 16, File::create(&path)?);
+        write_header(&mut writer, 0)?;
+
+        Ok(Self {
+            path,
+            writer,
+            data_len: 0,
+        })
+    }
+
+    fn write(&mut self, audio: &[i16]) -> IoResult<()> {
+        for sample in audio {
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.data_len += (audio.len() * BYTES_PER_SAMPLE as usize) as u32;
+
+        Ok(())
+    }
+
+    /// Fills in the header's lengths, now that all audio has been written.
+    fn finish(mut self) -> IoResult<PathBuf> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_header(&mut self.writer, self.data_len)?;
+        self.writer.flush()?;
+
+        Ok(self.path)
+    }
+}
+
+fn write_header(w: &mut impl Write, data_len: u32) -> IoResult<()> {
+    let block_align = CHANNELS * BYTES_PER_SAMPLE;
+    let byte_rate = SAMPLE_RATE_RAW as u32 * u32::from(block_align);
+
+    w.write_all(b"RIFF")?;
+    w.write_all(&(WAV_HEADER_LEN - 8 + data_len).to_le_bytes())?;
+    w.write_all(b"WAVEfmt ")?;
+    w.write_all(&16u32.to_le_bytes())?;
+    // PCM.
+    w.write_all(&1u16.to_le_bytes())?;
+    w.write_all(&CHANNELS.to_le_bytes())?;
+    w.write_all(&(SAMPLE_RATE_RAW as u32).to_le_bytes())?;
+    w.write_all(&byte_rate.to_le_bytes())?;
+    w.write_all(&block_align.to_le_bytes())?;
+    w.write_all(&(BYTES_PER_SAMPLE * 8).to_le_bytes())?;
+    w.write_all(b"data")?;
+    w.write_all(&data_len.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::STEREO_FRAME_SIZE;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn rotation_splits_audio_without_gaps() {
+        let dir = std::env::temp_dir().join(format!("songbird-{}", Uuid::new_v4()));
+        fs::create_dir(&dir).unwrap();
+
+        let recorder = Recorder::new(dir.join("call.wav")).unwrap();
+        let frame = |i: i16| vec![i; STEREO_FRAME_SIZE];
+
+        for i in 0..3 {
+            recorder.write(&frame(i)).unwrap();
+        }
+        let first = recorder.rotate().unwrap();
+        for i in 3..5 {
+            recorder.write(&frame(i)).unwrap();
+        }
+        let second = recorder.finish().unwrap().unwrap();
+
+        assert_eq!(first, dir.join("call-0000.wav"));
+        assert_eq!(second, dir.join("call-0001.wav"));
+        assert!(recorder.path().is_none());
+
+        // Each file holds a valid header, followed by exactly its own frames.
+        let mut samples = vec![];
+        for path in [first, second] {
+            let bytes = fs::read(path).unwrap();
+            let data_len = bytes.len() - WAV_HEADER_LEN as usize;
+            assert_eq!(&bytes[..4], b"RIFF");
+            assert_eq!(bytes[4..8], ((bytes.len() - 8) as u32).to_le_bytes());
+            assert_eq!(bytes[40..44], (data_len as u32).to_le_bytes());
+
+            samples.extend(
+                bytes[WAV_HEADER_LEN as usize..]
+                    .chunks(2)
+                    .step_by(STEREO_FRAME_SIZE)
+                    .map(|s| i16::from_le_bytes([s[0], s[1]])),
+            );
+        }
+        assert_eq!(samples, [0, 1, 2, 3, 4]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
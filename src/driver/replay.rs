@@ -0,0 +1,51 @@
+use std::{error::Error, fmt, time::Duration};
+
+/// Errors encountered when replaying a user's received audio via
+/// [`Driver::replay_user`].
+///
+/// [`Driver::replay_user`]: super::Driver::replay_user
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ReplayError {
+    /// No decoded audio is held for the requested user.
+    ///
+    /// This occurs if [`Config::receive_history`] is unset, if the driver is not
+    /// decoding received audio, or if the user has not spoken recently.
+    ///
+    /// [`Config::receive_history`]: crate::Config::receive_history
+    NoAudio,
+    /// Less audio is held for the requested user than was asked for, and
+    /// [`Config::replay_partial`] is disabled.
+    ///
+    /// [`Config::replay_partial`]: crate::Config::replay_partial
+    Insufficient {
+        /// Length of audio held for the user.
+        available: Duration,
+        /// Length of audio requested.
+        requested: Duration,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to replay received audio: ")?;
+        match self {
+            ReplayError::NoAudio => write!(f, "no audio held for user"),
+            ReplayError::Insufficient {
+                available,
+                requested,
+            } => write!(
+                f,
+                "only {:?} of audio held for user, but {:?} requested",
+                available, requested
+            ),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+/// Alias for the result of [`Driver::replay_user`].
+///
+/// [`Driver::replay_user`]: super::Driver::replay_user
+pub type ReplayResult<T> = Result<T, ReplayError>;
@@ -40,10 +40,14 @@ impl Retry {
         last_wait: Option<Duration>,
         attempts: usize,
     ) -> Option<Duration> {
-        if self.retry_limit.map(|a| attempts < a).unwrap_or(true) {
+        if self.attempts_remain(attempts) {
             Some(self.strategy.retry_in(last_wait))
         } else {
             None
         }
     }
+
+    pub(crate) fn attempts_remain(&self, attempts: usize) -> bool {
+        self.retry_limit.map(|a| attempts < a).unwrap_or(true)
+    }
 }
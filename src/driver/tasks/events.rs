@@ -1,7 +1,7 @@
 use super::message::*;
 use crate::{
     events::{EventStore, GlobalEvents, TrackEvent},
-    tracks::{TrackHandle, TrackState},
+    tracks::{PlayMode, TrackHandle, TrackState},
 };
 use flume::Receiver;
 use tracing::{debug, info, instrument, trace};
@@ -43,9 +43,25 @@ pub(crate) async fn runner(_interconnect: Interconnect, evt_rx: Receiver<EventMe
 
                 global.fire_core_event(evt, ctx).await;
             },
+            Ok(RemoveGlobalEvent(handle)) =>
+                if global.remove_event(handle) {
+                    info!("Global event {:?} removed.", handle);
+                } else {
+                    debug!("Global event {:?} already removed.", handle);
+                },
             Ok(RemoveGlobalEvents) => {
                 global.remove_handlers();
             },
+            Ok(ListGlobalEvents(tx)) => {
+                let _ = tx.send(global.store.list_events());
+            },
+            Ok(ListTrackEvents(i, tx)) => {
+                let event_store = events
+                    .get(i)
+                    .expect("Event thread was given an illegal store index for ListTrackEvents.");
+
+                let _ = tx.send(event_store.list_events());
+            },
             Ok(AddTrack(store, state, handle)) => {
                 events.push(store);
                 states.push(state);
@@ -74,9 +90,20 @@ pub(crate) async fn runner(_interconnect: Interconnect, evt_rx: Receiver<EventMe
                             global.fire_track_event(mode.as_track_event(), i);
                         }
                     },
+                    Ended(reason) => {
+                        let old = state.playing;
+                        state.playing = reason.mode();
+                        state.end_reason = Some(reason);
+                        if !old.is_done() {
+                            global.fire_track_event(TrackEvent::End, i);
+                        }
+                    },
                     Volume(vol) => {
                         state.volume = vol;
                     },
+                    EffectiveVolume(vol) => {
+                        state.effective_volume = vol;
+                    },
                     Position(pos) => {
                         // Currently, only Tick should fire time events.
                         state.position = pos;
@@ -87,6 +114,32 @@ pub(crate) async fn runner(_interconnect: Interconnect, evt_rx: Receiver<EventMe
                             global.fire_track_event(TrackEvent::Loop, i);
                         }
                     },
+                    Failed => {
+                        global.fire_track_event(TrackEvent::Error, i);
+                    },
+                    Restarted => {
+                        state.playing = PlayMode::Play;
+                        state.position = Default::default();
+                        global.fire_track_event(TrackEvent::Play, i);
+                    },
+                    OverBudget => {
+                        global.fire_track_event(TrackEvent::OverBudget, i);
+                    },
+                    Silent(true) => {
+                        global.fire_track_event(TrackEvent::Silent, i);
+                    },
+                    Silent(false) => {
+                        global.fire_track_event(TrackEvent::Audible, i);
+                    },
+                    MetadataUpdate => {
+                        global.fire_track_event(TrackEvent::MetadataUpdate, i);
+                    },
+                    Resumed => {
+                        global.fire_track_event(TrackEvent::Resumed, i);
+                    },
+                    Ready => {
+                        global.fire_track_event(TrackEvent::Ready, i);
+                    },
                     Total(new) => {
                         // Massive, unprecedented state changes.
                         *state = new;
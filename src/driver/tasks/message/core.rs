@@ -1,12 +1,24 @@
 #![allow(missing_docs)]
 
 use crate::{
+    broadcast::Subscriber,
     driver::{connection::error::Error, Bitrate, Config},
-    events::{context_data::DisconnectReason, EventData},
-    tracks::Track,
+    events::{
+        context_data::{BitrateData, DisconnectReason},
+        EventData,
+        EventHandle,
+        EventInfo,
+    },
+    id::UserId,
+    model::{payload::SessionDescription, SpeakingState},
+    tracks::{EmptyAction, Track, TrackQueue},
     ConnectionInfo,
 };
 use flume::Sender;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
@@ -17,13 +29,40 @@ pub enum CoreMessage {
     Disconnect,
     SetTrack(Option<Track>),
     AddTrack(Track),
+    PlayInstantOpus(Vec<u8>),
+    SetBroadcast(Option<Subscriber>),
+    SetBroadcastVolume(f32),
     SetBitrate(Bitrate),
+    GetBitrate(Sender<BitrateData>),
+    GetUserActivity(Sender<HashMap<u32, Instant>>),
+    GetReplayDropped(Sender<u64>),
+    GetMalformedDropped(Sender<u64>),
+    GetSocketDropped(Sender<Option<u64>>),
+    GetUserHistory(UserId, Duration, Sender<Vec<i16>>),
+    GetEncodeErrors(Sender<u64>),
+    GetSsrc(Sender<Option<u32>>),
+    GetLatency(Sender<Option<Duration>>),
+    GetListenerCount(Sender<Option<usize>>),
+    #[cfg(feature = "internals")]
+    InjectRtp(Vec<u8>),
     AddEvent(EventData),
+    RemoveGlobalEvent(EventHandle),
     RemoveGlobalEvents,
+    ListGlobalEvents(Sender<Vec<EventInfo>>),
+    QueueEmpty(EmptyAction),
+    RenegotiateCrypto,
+    SessionDescription(SessionDescription),
     SetConfig(Config),
     Mute(bool),
+    SetMasterGain(f32),
+    SetReceiving(bool),
+    SetSpeakingFlags(SpeakingState),
     Reconnect,
     FullReconnect,
     RebuildInterconnect,
+    CheckTasks(Sender<bool>),
+    RestartTasks(Option<TrackQueue>),
+    SignalRecreated,
+    SignalTasksFailed(Vec<EventData>),
     Poison,
 }
@@ -3,7 +3,7 @@
 use crate::tracks::Track;
 
 pub enum DisposalMessage {
-    Track(Track),
+    Track(Box<Track>),
 
     Poison,
 }
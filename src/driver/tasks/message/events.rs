@@ -1,9 +1,10 @@
 #![allow(missing_docs)]
 
 use crate::{
-    events::{CoreContext, EventData, EventStore},
-    tracks::{LoopState, PlayMode, TrackHandle, TrackState},
+    events::{CoreContext, EventData, EventHandle, EventInfo, EventStore},
+    tracks::{EndReason, LoopState, PlayMode, TrackHandle, TrackState},
 };
+use flume::Sender;
 use std::time::Duration;
 
 pub enum EventMessage {
@@ -12,7 +13,10 @@ pub enum EventMessage {
     AddGlobalEvent(EventData),
     AddTrackEvent(usize, EventData),
     FireCoreEvent(CoreContext),
+    RemoveGlobalEvent(EventHandle),
     RemoveGlobalEvents,
+    ListGlobalEvents(Sender<Vec<EventInfo>>),
+    ListTrackEvents(usize, Sender<Vec<EventInfo>>),
 
     AddTrack(EventStore, TrackState, TrackHandle),
     ChangeState(usize, TrackStateChange),
@@ -26,9 +30,19 @@ pub enum EventMessage {
 #[derive(Debug)]
 pub enum TrackStateChange {
     Mode(PlayMode),
+    Ended(EndReason),
     Volume(f32),
+    EffectiveVolume(f32),
     Position(Duration),
     // Bool indicates user-set.
     Loops(LoopState, bool),
     Total(TrackState),
+    Failed,
+    Restarted,
+    OverBudget,
+    // Bool indicates whether the track fell silent.
+    Silent(bool),
+    MetadataUpdate,
+    Resumed,
+    Ready,
 }
@@ -3,10 +3,18 @@
 use super::{Interconnect, UdpRxMessage, UdpTxMessage, WsMessage};
 
 use crate::{
+    broadcast::Subscriber,
     driver::{Bitrate, Config, CryptoState},
+    events::context_data::BitrateData,
+    id::UserId,
+    model::SpeakingState,
     tracks::Track,
 };
 use flume::Sender;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 use xsalsa20poly1305::XSalsa20Poly1305 as Cipher;
 
 pub struct MixerConnection {
@@ -26,13 +34,28 @@ impl Drop for MixerConnection {
 pub enum MixerMessage {
     AddTrack(Track),
     SetTrack(Option<Track>),
+    PlayInstantOpus(Vec<u8>),
+    SetBroadcast(Option<Subscriber>),
+    SetBroadcastVolume(f32),
 
     SetBitrate(Bitrate),
+    GetBitrate(Sender<BitrateData>),
+    GetUserActivity(Sender<HashMap<u32, Instant>>),
+    GetReplayDropped(Sender<u64>),
+    GetMalformedDropped(Sender<u64>),
+    GetSocketDropped(Sender<Option<u64>>),
+    GetUserHistory(UserId, Duration, Sender<Vec<i16>>),
+    GetEncodeErrors(Sender<u64>),
     SetConfig(Config),
     SetMute(bool),
+    SetMasterGain(f32),
+    SetReceiving(bool),
+    SetSpeakingFlags(SpeakingState),
 
     SetConn(MixerConnection, u32),
+    SetCipher(Cipher),
     Ws(Option<Sender<WsMessage>>),
+    WsOutage(bool),
     DropConn,
 
     ReplaceInterconnect(Interconnect),
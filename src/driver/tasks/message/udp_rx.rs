@@ -1,11 +1,28 @@
 #![allow(missing_docs)]
 
 use super::Interconnect;
-use crate::driver::Config;
+use crate::{driver::Config, id::UserId};
+use flume::Sender;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use xsalsa20poly1305::XSalsa20Poly1305 as Cipher;
 
 pub enum UdpRxMessage {
-    SetConfig(Config),
+    SetConfig(Box<Config>),
+    SetReceiving(bool),
+    SetCipher(Cipher),
+    SetSsrcUser(u32, UserId),
+    RemoveUser(UserId),
+    GetUserActivity(Sender<HashMap<u32, Instant>>),
+    GetReplayDropped(Sender<u64>),
+    GetMalformedDropped(Sender<u64>),
+    GetSocketDropped(Sender<Option<u64>>),
+    GetUserHistory(UserId, Duration, Sender<Vec<i16>>),
     ReplaceInterconnect(Interconnect),
+    #[cfg(feature = "internals")]
+    Inject(Vec<u8>),
 
     Poison,
 }
@@ -2,5 +2,6 @@
 
 pub enum UdpTxMessage {
     Packet(Vec<u8>), // TODO: do something cheaper.
+    SetTarget(u32),
     Poison,
 }
@@ -1,14 +1,23 @@
 #![allow(missing_docs)]
 
 use super::Interconnect;
-use crate::ws::WsStream;
+use crate::{
+    model::{ProtocolData, SpeakingState},
+    ws::WsStream,
+};
+use flume::Sender;
 
 #[allow(dead_code)]
 pub enum WsMessage {
     Ws(Box<WsStream>),
     ReplaceInterconnect(Interconnect),
     SetKeepalive(f64),
-    Speaking(bool),
+    Speaking(SpeakingState),
+    /// Resends the voice protocol selection, prompting a new session description.
+    SelectProtocol(ProtocolData),
+    GetListenerCount(Sender<Option<usize>>),
+    /// Closes the websocket, replying once the closing handshake completes or times out.
+    Close(Sender<()>),
 
     Poison,
 }
@@ -1,7 +1,11 @@
-use super::{disposal, error::Result, message::*};
+use super::{disposal, error::Result, message::*, packet_log::PacketLog};
 use crate::{
+    broadcast::Subscriber,
     constants::*,
-    tracks::{PlayMode, Track},
+    driver::{tx_redundancy, Bandwidth, ReconnectResume, TxRedundancy, VbrMode},
+    events::{context_data::BitrateData, CoreContext},
+    model::SpeakingState,
+    tracks::{Ducker, EndReason, PlayMode, SilenceAction, SoftStop, Track, TrackError},
     Config,
 };
 use audiopus::{
@@ -17,34 +21,74 @@ use discortp::{
 };
 use flume::{Receiver, Sender, TryRecvError};
 use rand::random;
-use std::{convert::TryInto, time::Instant};
+use std::{
+    collections::VecDeque,
+    convert::TryInto,
+    mem,
+    time::{Duration, Instant},
+};
 use tokio::runtime::Handle;
-use tracing::{debug, error, instrument};
+use tracing::{debug, error, instrument, warn, Span};
+use uuid::Uuid;
 use xsalsa20poly1305::TAG_SIZE;
 
 pub struct Mixer {
     pub async_handle: Handle,
     pub bitrate: Bitrate,
+    pub broadcast: Option<Subscriber>,
+    pub broadcast_volume: f32,
     pub config: Config,
     pub conn_active: Option<MixerConnection>,
     pub deadline: Instant,
     pub disposer: Sender<DisposalMessage>,
+    pub(crate) ducker: Ducker,
+    pub encode_errors: u64,
+    pub encode_strikes: usize,
     pub encoder: OpusEncoder,
+    pub instant_opus: VecDeque<Vec<u8>>,
     pub interconnect: Interconnect,
+    pub last_opus: Vec<u8>,
+    pub master_gain: f32,
     pub mix_rx: Receiver<MixerMessage>,
     pub muted: bool,
     pub packet: [u8; VOICE_PACKET_MAX],
+    pub(crate) packet_log: PacketLog,
     pub prevent_events: bool,
-    pub silence_frames: u8,
+    pub receiving: bool,
+    pub resume_points: Option<Vec<(Uuid, Duration)>>,
+    pub silence_frames: usize,
     pub skip_sleep: bool,
     pub soft_clip: SoftClip,
+    pub speaking_flags: SpeakingState,
     pub tracks: Vec<Track>,
+    pub transmitting: bool,
     pub ws: Option<Sender<WsMessage>>,
+    pub ws_outage: Option<Instant>,
+}
+
+/// Computes when the tick after one due at `deadline` (and begun at `now`) should be sent.
+///
+/// Compensated deadlines advance from the intended time of the last tick, so that
+/// oversleeping is corrected on the next.
+#[inline]
+fn next_deadline(deadline: Instant, now: Instant, compensate: bool) -> Instant {
+    if compensate {
+        deadline + TIMESTEP_LENGTH
+    } else {
+        now + TIMESTEP_LENGTH
+    }
 }
 
-fn new_encoder(bitrate: Bitrate) -> Result<OpusEncoder> {
-    let mut encoder = OpusEncoder::new(SAMPLE_RATE, Channels::Stereo, CodingMode::Audio)?;
+fn new_encoder(
+    bitrate: Bitrate,
+    application: CodingMode,
+    vbr: VbrMode,
+    max_bandwidth: Bandwidth,
+) -> Result<OpusEncoder> {
+    let mut encoder = OpusEncoder::new(SAMPLE_RATE, Channels::Stereo, application)?;
     encoder.set_bitrate(bitrate)?;
+    vbr.apply(&mut encoder)?;
+    max_bandwidth.apply(&mut encoder)?;
 
     Ok(encoder)
 }
@@ -57,8 +101,13 @@ impl Mixer {
         config: Config,
     ) -> Self {
         let bitrate = DEFAULT_BITRATE;
-        let encoder = new_encoder(bitrate)
-            .expect("Failed to create encoder in mixing thread with known-good values.");
+        let encoder = new_encoder(
+            bitrate,
+            config.opus_application,
+            config.opus_vbr,
+            config.opus_max_bandwidth,
+        )
+        .expect("Failed to create encoder in mixing thread with known-good values.");
         let soft_clip = SoftClip::new(Channels::Stereo);
 
         let mut packet = [0u8; VOICE_PACKET_MAX];
@@ -76,26 +125,41 @@ impl Mixer {
 
         // Create an object disposal thread here.
         let (disposer, disposal_rx) = flume::unbounded();
-        std::thread::spawn(move || disposal::runner(disposal_rx));
+        let span = Span::current();
+        std::thread::spawn(move || span.in_scope(|| disposal::runner(disposal_rx)));
 
         Self {
             async_handle,
             bitrate,
+            broadcast: None,
+            broadcast_volume: 1.0,
             config,
             conn_active: None,
             deadline: Instant::now(),
             disposer,
+            ducker: Default::default(),
+            encode_errors: 0,
+            encode_strikes: 0,
             encoder,
             interconnect,
+            instant_opus: VecDeque::new(),
+            last_opus: Vec::new(),
+            master_gain: 1.0,
             mix_rx,
             muted: false,
             packet,
+            packet_log: PacketLog::new(),
             prevent_events: false,
+            receiving: true,
+            resume_points: None,
             silence_frames: 0,
             skip_sleep: false,
             soft_clip,
+            speaking_flags: SpeakingState::empty(),
             tracks,
+            transmitting: false,
             ws: None,
+            ws_outage: None,
         }
     }
 
@@ -104,7 +168,7 @@ impl Mixer {
         let mut conn_failure = false;
 
         'runner: loop {
-            if self.conn_active.is_some() {
+            if self.conn_active.is_some() && !self.outage_exceeded() {
                 loop {
                     match self.mix_rx.try_recv() {
                         Ok(m) => {
@@ -128,7 +192,7 @@ impl Mixer {
                 }
 
                 // The above action may have invalidated the connection; need to re-check!
-                if self.conn_active.is_some() {
+                if self.conn_active.is_some() && !self.outage_exceeded() {
                     if let Err(e) = self.cycle().and_then(|_| self.audio_commands_events()) {
                         events_failure |= e.should_trigger_interconnect_rebuild();
                         conn_failure |= e.should_trigger_connect();
@@ -198,6 +262,23 @@ impl Mixer {
                 t.source.prep_with_handle(self.async_handle.clone());
                 self.add_track(t)
             },
+            PlayInstantOpus(frame) => {
+                if self.conn_active.is_some() {
+                    self.instant_opus.push_back(frame);
+                }
+
+                Ok(())
+            },
+            SetBroadcast(subscriber) => {
+                self.broadcast = subscriber;
+
+                Ok(())
+            },
+            SetBroadcastVolume(volume) => {
+                self.broadcast_volume = volume;
+
+                Ok(())
+            },
             SetTrack(t) => {
                 self.tracks.clear();
 
@@ -220,13 +301,45 @@ impl Mixer {
                 if let Err(e) = self.set_bitrate(b) {
                     error!("Failed to update bitrate {:?}", e);
                 }
+                self.send_bitrate_target();
+                match self.bitrate_mismatch() {
+                    Some(evt) => self.fire_event(evt),
+                    None => Ok(()),
+                }
+            },
+            GetBitrate(tx) => {
+                if let Ok(actual) = self.encoder.bitrate() {
+                    let _ = tx.send(BitrateData {
+                        requested: self.bitrate,
+                        actual,
+                    });
+                }
                 Ok(())
             },
             SetMute(m) => {
                 self.muted = m;
                 Ok(())
             },
+            SetMasterGain(gain) => {
+                self.master_gain = gain;
+                Ok(())
+            },
+            SetReceiving(r) => {
+                self.receiving = r;
+                if let Some(conn) = &self.conn_active {
+                    conn_failure |= conn.udp_rx.send(UdpRxMessage::SetReceiving(r)).is_err();
+                }
+                Ok(())
+            },
+            SetSpeakingFlags(flags) => {
+                // The microphone bit tracks whether audio is being sent.
+                self.speaking_flags = flags - SpeakingState::MICROPHONE;
+                Ok(())
+            },
             SetConn(conn, ssrc) => {
+                if !self.receiving {
+                    let _ = conn.udp_rx.send(UdpRxMessage::SetReceiving(false));
+                }
                 self.conn_active = Some(conn);
                 let mut rtp = MutableRtpPacket::new(&mut self.packet[..]).expect(
                     "Too few bytes in self.packet for RTP header.\
@@ -236,10 +349,47 @@ impl Mixer {
                 rtp.set_sequence(random::<u16>().into());
                 rtp.set_timestamp(random::<u32>().into());
                 self.deadline = Instant::now();
+                self.transmitting = false;
+                self.instant_opus.clear();
+                self.ws_outage = None;
+
+                if let Some(points) = self.resume_points.take() {
+                    self.resume_tracks(&points);
+                }
+                self.send_bitrate_target();
+                Ok(())
+            },
+            SetCipher(cipher) => {
+                // Packets are now encrypted under a new key, so nonces start afresh.
+                if let Some(conn) = self.conn_active.as_mut() {
+                    let _ = conn.udp_rx.send(UdpRxMessage::SetCipher(cipher.clone()));
+                    conn.cipher = cipher;
+                    conn.crypto_state = self.config.crypto_mode.into();
+                }
                 Ok(())
             },
             DropConn => {
                 self.conn_active = None;
+                self.instant_opus.clear();
+                self.ws_outage = None;
+                self.save_resume_points();
+                Ok(())
+            },
+            WsOutage(true) => {
+                self.ws_outage.get_or_insert_with(Instant::now);
+                self.save_resume_points();
+                Ok(())
+            },
+            WsOutage(false) => {
+                // The session survived, so any audio sent in the meantime was heard.
+                self.resume_points = None;
+
+                if self.outage_exceeded() {
+                    // Resume pacing from now, rather than bursting out held frames.
+                    self.deadline = Instant::now();
+                    self.transmitting = false;
+                }
+                self.ws_outage = None;
                 Ok(())
             },
             ReplaceInterconnect(i) => {
@@ -257,7 +407,66 @@ impl Mixer {
 
                 self.rebuild_tracks()
             },
+            GetUserActivity(tx) => {
+                // Without a connection, dropping `tx` reports that no streams are known.
+                if let Some(conn) = &self.conn_active {
+                    conn_failure |= conn
+                        .udp_rx
+                        .send(UdpRxMessage::GetUserActivity(tx))
+                        .is_err();
+                }
+
+                Ok(())
+            },
+            GetEncodeErrors(tx) => {
+                let _ = tx.send(self.encode_errors);
+
+                Ok(())
+            },
+            GetReplayDropped(tx) => {
+                if let Some(conn) = &self.conn_active {
+                    conn_failure |= conn
+                        .udp_rx
+                        .send(UdpRxMessage::GetReplayDropped(tx))
+                        .is_err();
+                }
+
+                Ok(())
+            },
+            GetMalformedDropped(tx) => {
+                if let Some(conn) = &self.conn_active {
+                    conn_failure |= conn
+                        .udp_rx
+                        .send(UdpRxMessage::GetMalformedDropped(tx))
+                        .is_err();
+                }
+
+                Ok(())
+            },
+            GetSocketDropped(tx) => {
+                if let Some(conn) = &self.conn_active {
+                    conn_failure |= conn
+                        .udp_rx
+                        .send(UdpRxMessage::GetSocketDropped(tx))
+                        .is_err();
+                }
+
+                Ok(())
+            },
+            GetUserHistory(user, duration, tx) => {
+                if let Some(conn) = &self.conn_active {
+                    conn_failure |= conn
+                        .udp_rx
+                        .send(UdpRxMessage::GetUserHistory(user, duration, tx))
+                        .is_err();
+                }
+
+                Ok(())
+            },
             SetConfig(new_config) => {
+                let rebuild = new_config.opus_application != self.config.opus_application
+                    || new_config.opus_vbr != self.config.opus_vbr
+                    || new_config.opus_max_bandwidth != self.config.opus_max_bandwidth;
                 self.config = new_config.clone();
 
                 if self.tracks.capacity() < self.config.preallocated_tracks {
@@ -268,24 +477,24 @@ impl Mixer {
                 if let Some(conn) = &self.conn_active {
                     conn_failure |= conn
                         .udp_rx
-                        .send(UdpRxMessage::SetConfig(new_config))
+                        .send(UdpRxMessage::SetConfig(Box::new(new_config)))
                         .is_err();
                 }
 
-                Ok(())
+                let mismatch = if rebuild {
+                    self.rebuild_encoder()
+                } else {
+                    None
+                };
+
+                match mismatch {
+                    Some(evt) => self.fire_event(evt),
+                    None => Ok(()),
+                }
             },
-            RebuildEncoder => match new_encoder(self.bitrate) {
-                Ok(encoder) => {
-                    self.encoder = encoder;
-                    Ok(())
-                },
-                Err(e) => {
-                    error!("Failed to rebuild encoder. Resetting bitrate. {:?}", e);
-                    self.bitrate = DEFAULT_BITRATE;
-                    self.encoder = new_encoder(self.bitrate)
-                        .expect("Failed fallback rebuild of OpusEncoder with safe inputs.");
-                    Ok(())
-                },
+            RebuildEncoder => match self.rebuild_encoder() {
+                Some(evt) => self.fire_event(evt),
+                None => Ok(()),
             },
             Ws(new_ws_handle) => {
                 self.ws = new_ws_handle;
@@ -305,7 +514,135 @@ impl Mixer {
         (events_failure, conn_failure, should_exit)
     }
 
+    /// Returns whether playback should be held, as the websocket has been down
+    /// for longer than [`Config::ws_outage_playback`].
+    ///
+    /// [`Config::ws_outage_playback`]: crate::Config::ws_outage_playback
+    fn outage_exceeded(&self) -> bool {
+        match (self.ws_outage, self.config.ws_outage_playback) {
+            (Some(start), Some(limit)) => start.elapsed() >= limit,
+            _ => false,
+        }
+    }
+
+    /// Recreates the Opus encoder using the current bitrate, application mode, VBR mode,
+    /// and bandwidth limit, returning an event to fire if the new encoder's bitrate differs.
+    fn rebuild_encoder(&mut self) -> Option<EventMessage> {
+        match new_encoder(
+            self.bitrate,
+            self.config.opus_application,
+            self.config.opus_vbr,
+            self.config.opus_max_bandwidth,
+        ) {
+            Ok(encoder) => {
+                self.encoder = encoder;
+                self.bitrate_mismatch()
+            },
+            Err(e) => {
+                error!("Failed to rebuild encoder. Resetting bitrate. {:?}", e);
+                self.bitrate = DEFAULT_BITRATE;
+                self.encoder = new_encoder(
+                    self.bitrate,
+                    self.config.opus_application,
+                    self.config.opus_vbr,
+                    self.config.opus_max_bandwidth,
+                )
+                .expect("Failed fallback rebuild of OpusEncoder with safe inputs.");
+                None
+            },
+        }
+    }
+
+    /// Returns an event to fire if the encoder has not applied the exact requested bitrate.
+    fn bitrate_mismatch(&self) -> Option<EventMessage> {
+        let requested = self.bitrate;
+
+        // `Auto` and `Max` are resolved to concrete values by the encoder.
+        if !matches!(requested, Bitrate::BitsPerSecond(_)) {
+            return None;
+        }
+
+        match self.encoder.bitrate() {
+            Ok(actual) if actual != requested => {
+                debug!("Encoder bitrate {:?} differs from {:?}.", actual, requested);
+                Some(EventMessage::FireCoreEvent(CoreContext::BitrateMismatch(
+                    BitrateData { requested, actual },
+                )))
+            },
+            Ok(_) => None,
+            Err(e) => {
+                debug!("Failed to query encoder bitrate: {:?}", e);
+                None
+            },
+        }
+    }
+
+    /// Tells the UDP transmit task which bitrate the encoder is aiming for.
+    fn send_bitrate_target(&self) {
+        if let (Some(conn), Ok(Bitrate::BitsPerSecond(b))) =
+            (self.conn_active.as_ref(), self.encoder.bitrate())
+        {
+            let _ = conn.udp_tx.send(UdpTxMessage::SetTarget(b.max(0) as u32));
+        }
+    }
+
     #[inline]
+    /// Notes the position of each playing track when the connection may have been lost,
+    /// unless already noted for this outage.
+    fn save_resume_points(&mut self) {
+        if !self.config.reconnect_resume.is_enabled() || self.resume_points.is_some() {
+            return;
+        }
+
+        self.resume_points = Some(
+            self.tracks
+                .iter()
+                .filter(|track| !track.playing().is_done())
+                .map(|track| (track.uuid(), track.position()))
+                .collect(),
+        );
+    }
+
+    /// Returns tracks to their positions saved by [`save_resume_points`], following a reconnect.
+    ///
+    /// [`save_resume_points`]: Mixer::save_resume_points
+    fn resume_tracks(&mut self, points: &[(Uuid, Duration)]) {
+        let mode = self.config.reconnect_resume;
+
+        for (i, track) in self.tracks.iter_mut().enumerate() {
+            let saved = points
+                .iter()
+                .find(|(uuid, _)| *uuid == track.uuid())
+                .map(|(_, pos)| *pos);
+
+            let saved = match saved {
+                Some(pos) if !track.playing().is_done() => pos,
+                _ => continue,
+            };
+
+            if track.source.is_seekable() {
+                if track.position() != saved {
+                    if let Ok(pos) = track.seek_time(saved) {
+                        track.stats.seeks += 1;
+                        let _ = self.interconnect.events.send(EventMessage::ChangeState(
+                            i,
+                            TrackStateChange::Position(pos),
+                        ));
+                    }
+                }
+            } else if mode == ReconnectResume::RewindOrStop {
+                // Removed, firing `TrackEvent::End`, on the next mixer tick.
+                track.end(EndReason::Error(TrackError::SeekUnsupported));
+                continue;
+            }
+
+            let _ = self
+                .interconnect
+                .events
+                .send(EventMessage::ChangeState(i, TrackStateChange::Resumed));
+        }
+    }
+
     fn fire_event(&self, event: EventMessage) -> Result<()> {
         // As this task is responsible for noticing the potential death of an event context,
         // it's responsible for not forcibly recreating said context repeatedly.
@@ -319,6 +656,8 @@ impl Mixer {
 
     #[inline]
     fn add_track(&mut self, mut track: Track) -> Result<()> {
+        track.apply_start_offset();
+
         let evts = track.events.take().unwrap_or_default();
         let state = track.state();
         let handle = track.handle.clone();
@@ -350,13 +689,30 @@ impl Mixer {
 
     #[inline]
     fn audio_commands_events(&mut self) -> Result<()> {
+        // The next frame is sent at its deadline, or immediately if that has passed.
+        let send_time = self.deadline.max(Instant::now());
+
         // Apply user commands.
         for (i, track) in self.tracks.iter_mut().enumerate() {
             // This causes fallible event system changes,
             // but if the event thread has died then we'll certainly
             // detect that on the tick later.
             // Changes to play state etc. MUST all be handled.
-            track.process_commands(i, &self.interconnect);
+            track.process_commands(i, &self.interconnect, self.config.command_debounce);
+
+            if track.poll_ready() {
+                let _ = self
+                    .interconnect
+                    .events
+                    .send(EventMessage::ChangeState(i, TrackStateChange::Ready));
+            }
+
+            if track.poll_start(send_time) {
+                let _ = self.interconnect.events.send(EventMessage::ChangeState(
+                    i,
+                    TrackStateChange::Mode(track.playing),
+                ));
+            }
         }
 
         // TODO: do without vec?
@@ -369,14 +725,11 @@ impl Mixer {
                 .expect("Tried to remove an illegal track index.");
 
             if track.playing.is_done() {
-                let p_state = track.playing();
+                let change = track.mode_change();
                 let to_drop = self.tracks.swap_remove(i);
                 to_remove.push(i);
-                self.fire_event(EventMessage::ChangeState(
-                    i,
-                    TrackStateChange::Mode(p_state),
-                ))?;
-                let _ = self.disposer.send(DisposalMessage::Track(to_drop));
+                self.fire_event(EventMessage::ChangeState(i, change))?;
+                let _ = self.disposer.send(DisposalMessage::Track(Box::new(to_drop)));
             } else {
                 i += 1;
             }
@@ -399,8 +752,13 @@ impl Mixer {
             return;
         }
 
+        // `Instant` is monotonic, so wall clock changes cannot affect packet pacing.
         std::thread::sleep(self.deadline.saturating_duration_since(Instant::now()));
-        self.deadline += TIMESTEP_LENGTH;
+        self.deadline = next_deadline(
+            self.deadline,
+            Instant::now(),
+            self.config.drift_compensation,
+        );
     }
 
     pub fn cycle(&mut self) -> Result<()> {
@@ -408,6 +766,7 @@ impl Mixer {
 
         // Walk over all the audio files, combining into one audio frame according
         // to volume, play state, etc.
+        let broadcast_volume = self.broadcast_volume;
         let mut mix_len = {
             let mut rtp = MutableRtpPacket::new(&mut self.packet[..]).expect(
                 "FATAL: Too few bytes in self.packet for RTP header.\
@@ -421,15 +780,44 @@ impl Mixer {
                 &mut payload[TAG_SIZE..],
                 &mut mix_buffer,
                 &mut self.tracks,
+                &mut self.ducker,
                 &self.interconnect,
                 self.prevent_events,
+                &self.config,
+                self.master_gain,
+                self.broadcast.as_mut().map(|b| (b, broadcast_volume)),
             )
         };
 
         self.soft_clip.apply((&mut mix_buffer[..]).try_into()?)?;
 
+        // Instant frames replace any mixed audio, which is written to the same space.
+        if let Some(frame) = self.instant_opus.pop_front() {
+            let suffix_len = self
+                .conn_active
+                .as_ref()
+                .map(|conn| conn.crypto_state.kind().payload_suffix_len())
+                .unwrap_or_default();
+            let mut rtp = MutableRtpPacket::new(&mut self.packet[..]).expect(
+                "FATAL: Too few bytes in self.packet for RTP header.\
+                    (Blame: VOICE_PACKET_MAX?)",
+            );
+
+            let payload = rtp.payload_mut();
+            let space = payload.len() - TAG_SIZE - suffix_len;
+
+            if frame.len() <= space {
+                payload[TAG_SIZE..TAG_SIZE + frame.len()].copy_from_slice(&frame);
+                mix_len = MixType::Passthrough(frame.len());
+                self.clear_contributions();
+            } else {
+                warn!("Dropped instant Opus frame of {}B: too large.", frame.len());
+            }
+        }
+
         if self.muted {
             mix_len = MixType::MixedPcm(0);
+            self.clear_contributions();
         }
 
         if mix_len == MixType::MixedPcm(0) {
@@ -455,33 +843,86 @@ impl Mixer {
                     // A full reconnect might cause an inner closed connection.
                     // It's safer to leave the central task to clean this up and
                     // pass the mixer a new channel.
-                    let _ = ws.send(WsMessage::Speaking(false));
+                    let _ = ws.send(WsMessage::Speaking(SpeakingState::empty()));
                 }
 
+                // Redundant data must come from the immediately preceding frame.
+                self.last_opus.clear();
+                self.transmitting = false;
+
                 self.march_deadline();
 
                 return Ok(());
             }
         } else {
-            self.silence_frames = 5;
+            // Optionally hold the session open with silence over short gaps
+            // (e.g., between queued tracks), before the usual 5 trailing frames.
+            let gap_frames = self
+                .config
+                .gap_silence
+                .map(|gap| (gap.as_millis() / FRAME_LEN_MS as u128) as usize)
+                .unwrap_or(0);
+            self.silence_frames = 5 + gap_frames;
         }
 
         if let Some(ws) = &self.ws {
-            ws.send(WsMessage::Speaking(true))?;
+            ws.send(WsMessage::Speaking(
+                SpeakingState::MICROPHONE | self.speaking_flags,
+            ))?;
         }
 
         self.march_deadline();
-        self.prep_and_send_packet(mix_buffer, mix_len)?;
+
+        if !self.transmitting {
+            self.transmitting = true;
+
+            // Burst out any lead-in silence ahead of the first frame of audio.
+            // Silent frames are written where passthrough Opus data is held.
+            let opus_start = RtpPacket::minimum_packet_size() + TAG_SIZE;
+            let stashed = match mix_len {
+                MixType::Passthrough(len) if self.config.lead_in_silence > 0 =>
+                    Some(self.packet[opus_start..opus_start + len].to_vec()),
+                _ => None,
+            };
+
+            for _ in 0..self.config.lead_in_silence {
+                self.packet[opus_start..opus_start + SILENT_FRAME.len()]
+                    .copy_from_slice(&SILENT_FRAME[..]);
+                self.prep_and_send_packet(
+                    [0.0; STEREO_FRAME_SIZE],
+                    MixType::Passthrough(SILENT_FRAME.len()),
+                )?;
+            }
+
+            if let Some(stashed) = stashed {
+                self.packet[opus_start..opus_start + stashed.len()].copy_from_slice(&stashed);
+            }
+        }
+
+        let sent = self.prep_and_send_packet(mix_buffer, mix_len)?;
+
+        for track in self.tracks.iter_mut().filter(|t| t.contributed) {
+            track.stats.bytes += sent as u64;
+        }
 
         Ok(())
     }
 
+    /// Marks that no track's audio will be sent this tick.
+    fn clear_contributions(&mut self) {
+        for track in self.tracks.iter_mut() {
+            track.contributed = false;
+        }
+    }
+
     fn set_bitrate(&mut self, bitrate: Bitrate) -> Result<()> {
         self.encoder.set_bitrate(bitrate).map_err(Into::into)
     }
 
+    /// Encodes (if needed), encrypts, and sends one packet, returning the number of
+    /// encrypted payload bytes handed to the UDP task.
     #[inline]
-    fn prep_and_send_packet(&mut self, buffer: [f32; 1920], mix_len: MixType) -> Result<()> {
+    fn prep_and_send_packet(&mut self, buffer: [f32; 1920], mix_len: MixType) -> Result<usize> {
         let conn = self
             .conn_active
             .as_mut()
@@ -495,18 +936,62 @@ impl Mixer {
 
             let payload = rtp.payload_mut();
             let crypto_mode = conn.crypto_state.kind();
+            let total_payload_space = payload.len() - crypto_mode.payload_suffix_len();
+
+            // RFC 2198 headers and the previous frame are placed ahead of this frame.
+            let mut red_len = match self.config.tx_redundancy {
+                TxRedundancy::Red => tx_redundancy::red_prefix_len(&self.last_opus),
+                _ => 0,
+            };
+            if let MixType::Passthrough(opus_len) = mix_len {
+                if TAG_SIZE + red_len + opus_len > total_payload_space {
+                    red_len = 0;
+                }
+            }
+            let opus_start = TAG_SIZE + red_len;
 
-            let payload_len = match mix_len {
-                MixType::Passthrough(opus_len) => opus_len,
-                MixType::MixedPcm(_samples) => {
-                    let total_payload_space = payload.len() - crypto_mode.payload_suffix_len();
-                    self.encoder.encode_float(
-                        &buffer[..STEREO_FRAME_SIZE],
-                        &mut payload[TAG_SIZE..total_payload_space],
-                    )?
+            let opus_len = match mix_len {
+                MixType::Passthrough(opus_len) => {
+                    payload.copy_within(TAG_SIZE..TAG_SIZE + opus_len, opus_start);
+                    opus_len
+                },
+                MixType::MixedPcm(_samples) => match self.encoder.encode_float(
+                    &buffer[..STEREO_FRAME_SIZE],
+                    &mut payload[opus_start..total_payload_space],
+                ) {
+                    Ok(len) => {
+                        self.encode_strikes = 0;
+                        len
+                    },
+                    Err(e) => {
+                        // A bad frame should not end the call: send silence in its place.
+                        warn!("Opus encoding failed, sending silence: {:?}", e);
+                        self.encode_errors += 1;
+                        self.encode_strikes += 1;
+
+                        payload[opus_start..opus_start + SILENT_FRAME.len()]
+                            .copy_from_slice(&SILENT_FRAME[..]);
+                        SILENT_FRAME.len()
+                    },
                 },
             };
 
+            if red_len > 0 {
+                tx_redundancy::write_red_prefix(&mut payload[TAG_SIZE..], &self.last_opus);
+                self.last_opus.clear();
+                self.last_opus
+                    .extend_from_slice(&payload[opus_start..opus_start + opus_len]);
+            } else {
+                self.last_opus.clear();
+            }
+            let payload_len = red_len + opus_len;
+
+            rtp.set_payload_type(if red_len > 0 {
+                RTP_RED_PROFILE_TYPE
+            } else {
+                RTP_PROFILE_TYPE
+            });
+
             let final_payload_size = conn
                 .crypto_state
                 .write_packet_nonce(&mut rtp, TAG_SIZE + payload_len);
@@ -517,12 +1002,26 @@ impl Mixer {
                 final_payload_size,
             )?;
 
-            RtpPacket::minimum_packet_size() + final_payload_size
+            let index = RtpPacket::minimum_packet_size() + final_payload_size;
+            self.packet_log.sent(
+                rtp.get_ssrc(),
+                rtp.get_sequence().into(),
+                rtp.get_timestamp().into(),
+                index,
+            );
+
+            index
         };
+        let mut sent = index - RtpPacket::minimum_packet_size();
 
         // TODO: This is dog slow, don't do this.
         // Can we replace this with a shared ring buffer + semaphore?
         // i.e., do something like double/triple buffering in graphics.
+        if self.config.tx_redundancy == TxRedundancy::Duplicate {
+            conn.udp_tx
+                .send(UdpTxMessage::Packet(self.packet[..index].to_vec()))?;
+            sent *= 2;
+        }
         conn.udp_tx
             .send(UdpTxMessage::Packet(self.packet[..index].to_vec()))?;
 
@@ -533,7 +1032,16 @@ impl Mixer {
         rtp.set_sequence(rtp.get_sequence() + 1);
         rtp.set_timestamp(rtp.get_timestamp() + MONO_FRAME_SIZE as u32);
 
-        Ok(())
+        if self.encode_strikes >= ENCODER_REBUILD_STRIKES {
+            warn!("Opus encoding failed repeatedly, rebuilding encoder.");
+            self.encode_strikes = 0;
+
+            if let Some(evt) = self.rebuild_encoder() {
+                self.fire_event(evt)?;
+            }
+        }
+
+        Ok(sent)
     }
 }
 
@@ -544,42 +1052,183 @@ enum MixType {
 }
 
 #[inline]
+#[allow(clippy::too_many_arguments)]
 fn mix_tracks<'a>(
     opus_frame: &'a mut [u8],
     mix_buffer: &mut [f32; STEREO_FRAME_SIZE],
     tracks: &mut Vec<Track>,
+    ducker: &mut Ducker,
     interconnect: &Interconnect,
     prevent_events: bool,
+    config: &Config,
+    master_gain: f32,
+    broadcast: Option<(&mut Subscriber, f32)>,
 ) -> MixType {
     let mut len = 0;
 
+    // Find the strongest ducking track which is currently audible.
+    let active_duck = tracks
+        .iter()
+        .filter(|t| t.playing == PlayMode::Play && t.soft_stop != Some(SoftStop::TrailingFrame))
+        .filter_map(|t| t.ducking)
+        .min_by(|a, b| {
+            a.gain
+                .partial_cmp(&b.gain)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    let duck_gain = ducker.step(active_duck);
+
     // Opus frame passthrough.
     // This requires that we have only one track, who has volume 1.0, and an
-    // Opus codec type.
-    let do_passthrough = tracks.len() == 1 && {
+    // Opus codec type, with no master gain applied.
+    let do_passthrough = tracks.len() == 1 && broadcast.is_none() && {
         let track = &tracks[0];
-        (track.volume - 1.0).abs() < f32::EPSILON && track.source.supports_passthrough()
+        (track.volume - 1.0).abs() < f32::EPSILON
+            && (master_gain - 1.0).abs() < f32::EPSILON
+            && (track.ducking.is_some() || (duck_gain - 1.0).abs() < f32::EPSILON)
+            && track.soft_stop.is_none()
+            && !track.has_effects()
+            && track.source.supports_passthrough()
     };
 
     for (i, track) in tracks.iter_mut().enumerate() {
-        let vol = track.volume;
-        let stream = &mut track.source;
+        let vol = if track.ducking.is_some() {
+            track.volume
+        } else {
+            track.volume * duck_gain
+        };
 
+        let effective_volume = vol * master_gain;
+        if (effective_volume - track.effective_volume).abs() > f32::EPSILON {
+            track.effective_volume = effective_volume;
+            if !prevent_events {
+                let _ = interconnect.events.send(EventMessage::ChangeState(
+                    i,
+                    TrackStateChange::EffectiveVolume(effective_volume),
+                ));
+            }
+        }
+        track.contributed = false;
         if track.playing != PlayMode::Play {
             continue;
         }
 
+        match track.soft_stop {
+            Some(SoftStop::CurrentFrame) => track.soft_stop = Some(SoftStop::TrailingFrame),
+            Some(SoftStop::TrailingFrame) => {
+                // Contribute one frame of silence, so that the encoder
+                // winds down smoothly rather than cutting off.
+                len = len.max(STEREO_FRAME_BYTE_SIZE);
+                track.stop();
+                continue;
+            },
+            None => {},
+        }
+
+        let budget = config.track_budget;
+        let started = budget.map(|_| Instant::now());
+
         let (temp_len, opus_len) = if do_passthrough {
             (0, track.source.read_opus_frame(opus_frame).ok())
+        } else if track.has_effects() {
+            (track.mix_effects(mix_buffer, vol), None)
         } else {
-            (stream.mix(mix_buffer, vol), None)
+            (track.source.mix(mix_buffer, vol), None)
         };
+        track.record_frame(opus_len.unwrap_or(temp_len));
+
+        if let Some(timeout) = config.input_timeout {
+            if track.stats.frames == 0 && track.stats.buffering >= timeout {
+                warn!(
+                    "Track {} produced no audio within {:?}: ending.",
+                    track.uuid, timeout
+                );
+                track.handle.set_timed_out();
+                track.end(EndReason::Error(TrackError::TimedOut));
+
+                if !prevent_events {
+                    let _ = interconnect
+                        .events
+                        .send(EventMessage::ChangeState(i, TrackStateChange::Failed));
+                }
+
+                continue;
+            }
+        }
+
+        if let (Some(budget), Some(started)) = (budget, started) {
+            let elapsed = started.elapsed();
+
+            if elapsed > budget {
+                track.budget_overruns += 1;
+                debug!(
+                    "Track {} took {:?} to mix (budget {:?}).",
+                    track.uuid, elapsed, budget
+                );
+            } else {
+                track.budget_overruns = 0;
+            }
+
+            if track.budget_overruns >= TRACK_BUDGET_STRIKES {
+                warn!(
+                    "Track {} exceeded its processing budget for {} frames: pausing.",
+                    track.uuid, TRACK_BUDGET_STRIKES
+                );
+                track.budget_overruns = 0;
+                track.pause();
+
+                if !prevent_events {
+                    let _ = interconnect.events.send(EventMessage::ChangeState(
+                        i,
+                        TrackStateChange::Mode(track.playing),
+                    ));
+                    let _ = interconnect
+                        .events
+                        .send(EventMessage::ChangeState(i, TrackStateChange::OverBudget));
+                }
+            }
+        }
+
+        if let Some(silent) = track.silence.as_mut().and_then(|s| s.take_change()) {
+            if !prevent_events {
+                let _ = interconnect
+                    .events
+                    .send(EventMessage::ChangeState(i, TrackStateChange::Silent(silent)));
+            }
+
+            if silent && track.silence_policy().map(|p| p.action) == Some(SilenceAction::Skip) {
+                debug!("Track {} fell silent: skipping.", track.uuid);
+                track.end(EndReason::Skipped);
+                continue;
+            }
+        }
 
-        len = len.max(temp_len);
+        if !track.is_idle() {
+            len = len.max(temp_len);
+        }
         if temp_len > 0 || opus_len.is_some() {
+            // Approximate seeks report where they landed once the new source plays.
+            if let Some(landed) = track.source.take_seek_landing() {
+                track.position = landed;
+
+                if !prevent_events {
+                    let _ = interconnect.events.send(EventMessage::ChangeState(
+                        i,
+                        TrackStateChange::Position(landed),
+                    ));
+                }
+            }
+
+            let seekable = track.source.is_seekable();
+            if seekable != track.handle.is_seekable() {
+                track.handle.set_seekable(seekable);
+            }
+
             track.step_frame();
         } else if track.do_loop() {
             if let Ok(time) = track.seek_time(Default::default()) {
+                track.stats.loops += 1;
+
                 // have to reproduce self.fire_event here
                 // to circumvent the borrow checker's lack of knowledge.
                 //
@@ -598,7 +1247,25 @@ fn mix_tracks<'a>(
                 }
             }
         } else {
-            track.end();
+            let reason = if let Some(failure) = track.source.failure() {
+                track.handle.set_failure(failure);
+
+                if !prevent_events {
+                    let _ = interconnect
+                        .events
+                        .send(EventMessage::ChangeState(i, TrackStateChange::Failed));
+                }
+
+                EndReason::Error(TrackError::SourceFailed)
+            } else {
+                EndReason::Completed
+            };
+
+            track.end(reason);
+        }
+
+        if matches!(track.max_duration, Some(max) if track.play_time >= max) {
+            track.end(EndReason::MaxDuration);
         }
 
         if let Some(opus_len) = opus_len {
@@ -606,6 +1273,24 @@ fn mix_tracks<'a>(
         }
     }
 
+    // Broadcast audio is ducked like any track without its own ducking.
+    if let Some((subscriber, volume)) = broadcast {
+        if let Some(frame) = subscriber.next_frame() {
+            let vol = volume * duck_gain;
+            for (sample, broadcast_sample) in mix_buffer.iter_mut().zip(frame.iter()) {
+                *sample += vol * broadcast_sample;
+            }
+            len = len.max(frame.len() * mem::size_of::<f32>());
+        }
+    }
+
+    // Master gain scales the sum of all tracks, ahead of the soft clipper.
+    if len > 0 && (master_gain - 1.0).abs() > f32::EPSILON {
+        for sample in mix_buffer.iter_mut() {
+            *sample *= master_gain;
+        }
+    }
+
     MixType::MixedPcm(len)
 }
 
@@ -626,3 +1311,1157 @@ pub(crate) fn runner(
 
     let _ = mixer.disposer.send(DisposalMessage::Poison);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{driver::CryptoState, input::Input, test_utils::make_sine, tracks};
+    use audiopus::Bandwidth as OpusBandwidth;
+    use xsalsa20poly1305::{KeyInit, XSalsa20Poly1305 as Cipher, KEY_SIZE};
+
+    /// Channels to the tasks around a test mixer, standing in for the rest of the driver.
+    struct TestTasks {
+        mix_rx: Receiver<MixerMessage>,
+        event_rx: Receiver<EventMessage>,
+        udp_rx_rx: Receiver<UdpRxMessage>,
+        udp_tx_rx: Receiver<UdpTxMessage>,
+        _core_rx: Receiver<CoreMessage>,
+        udp_rx_tx: Sender<UdpRxMessage>,
+        udp_tx_tx: Sender<UdpTxMessage>,
+    }
+
+    impl TestTasks {
+        /// Builds a connection to these tasks, as though a session had been established.
+        fn conn(&self) -> MixerConnection {
+            MixerConnection {
+                cipher: Cipher::new_from_slice(&[0u8; KEY_SIZE]).unwrap(),
+                crypto_state: CryptoState::Normal,
+                udp_rx: self.udp_rx_tx.clone(),
+                udp_tx: self.udp_tx_tx.clone(),
+            }
+        }
+    }
+
+    fn test_interconnect() -> (Interconnect, TestTasks) {
+        let (mix_tx, mix_rx) = flume::unbounded();
+        let (core_tx, core_rx) = flume::unbounded();
+        let (event_tx, event_rx) = flume::unbounded();
+        let (udp_rx_tx, udp_rx_rx) = flume::unbounded();
+        let (udp_tx_tx, udp_tx_rx) = flume::unbounded();
+
+        let ic = Interconnect {
+            core: core_tx,
+            events: event_tx,
+            mixer: mix_tx,
+        };
+        let tasks = TestTasks {
+            mix_rx,
+            event_rx,
+            udp_rx_rx,
+            udp_tx_rx,
+            _core_rx: core_rx,
+            udp_rx_tx,
+            udp_tx_tx,
+        };
+
+        (ic, tasks)
+    }
+
+    /// Builds a connected mixer, which sends each frame without waiting.
+    fn test_mixer(config: Config) -> (Mixer, TestTasks) {
+        let (ic, tasks) = test_interconnect();
+        let mut mixer = Mixer::new(tasks.mix_rx.clone(), Handle::current(), ic, config);
+        mixer.conn_active = Some(tasks.conn());
+        mixer.skip_sleep = true;
+
+        (mixer, tasks)
+    }
+
+    #[test]
+    fn encoder_uses_requested_application() {
+        for application in [CodingMode::Voip, CodingMode::Audio, CodingMode::LowDelay] {
+            let encoder = new_encoder(
+                DEFAULT_BITRATE,
+                application,
+                VbrMode::ConstrainedVbr,
+                Bandwidth::Full,
+            )
+            .unwrap();
+            assert_eq!(encoder.application().unwrap(), application);
+        }
+    }
+
+    #[test]
+    fn encoder_uses_requested_vbr_mode() {
+        for vbr in [VbrMode::Vbr, VbrMode::ConstrainedVbr, VbrMode::Cbr] {
+            let encoder =
+                new_encoder(DEFAULT_BITRATE, CodingMode::Audio, vbr, Bandwidth::Full).unwrap();
+            assert_eq!(encoder.vbr().unwrap(), vbr != VbrMode::Cbr);
+            assert_eq!(
+                encoder.vbr_constraint().unwrap(),
+                vbr == VbrMode::ConstrainedVbr
+            );
+        }
+    }
+
+    #[test]
+    fn encoder_uses_requested_max_bandwidth() {
+        for bandwidth in [
+            Bandwidth::Narrow,
+            Bandwidth::Medium,
+            Bandwidth::Wide,
+            Bandwidth::SuperWide,
+            Bandwidth::Full,
+        ] {
+            let encoder = new_encoder(
+                DEFAULT_BITRATE,
+                CodingMode::Audio,
+                VbrMode::ConstrainedVbr,
+                bandwidth,
+            )
+            .unwrap();
+            assert_eq!(encoder.max_bandwidth().unwrap(), bandwidth.into());
+
+            // Coded audio never exceeds the limit, even for a full-range signal.
+            let input: Vec<f32> = (0..STEREO_FRAME_SIZE)
+                .map(|i| ((i * 7919) % 200) as f32 / 100.0 - 1.0)
+                .collect();
+            let mut packet = [0u8; VOICE_PACKET_MAX];
+            for _ in 0..10 {
+                encoder.encode_float(&input, &mut packet[..]).unwrap();
+            }
+            // CELT has no mediumband mode, so Opus widens this when it is in use.
+            let limit = match bandwidth {
+                Bandwidth::Medium => OpusBandwidth::Wideband,
+                _ => bandwidth.into(),
+            };
+            assert!(encoder.bandwidth().unwrap() as i32 <= limit as i32);
+        }
+    }
+
+    #[test]
+    fn drift_compensation_holds_packet_cadence() {
+        // Simulates 60s of ticks, where each wakeup is late by 0--4ms
+        // and every 100th tick stalls for a further 35ms.
+        fn packets_sent(compensate: bool) -> usize {
+            let start = Instant::now();
+            let end = start + Duration::from_secs(60);
+            let mut deadline = start;
+            let mut now = start;
+            let mut sent = 0;
+
+            while now < end {
+                let delay = Duration::from_millis((sent as u64 * 7) % 5)
+                    + if sent % 100 == 99 {
+                        Duration::from_millis(35)
+                    } else {
+                        Duration::default()
+                    };
+                now = deadline.max(now) + delay;
+                sent += 1;
+                deadline = next_deadline(deadline, now, compensate);
+            }
+
+            sent
+        }
+
+        let target = 60 * AUDIO_FRAME_RATE;
+        assert!((packets_sent(true) as isize - target as isize).abs() <= 2);
+        assert!(packets_sent(false) < target * 9 / 10);
+    }
+
+    #[tokio::test]
+    async fn ws_outages_hold_playback_after_limit() {
+        let (mut mixer, _tasks) = test_mixer(Default::default());
+
+        // By default, audio is sent throughout an outage.
+        mixer.handle_message(MixerMessage::WsOutage(true));
+        assert!(!mixer.outage_exceeded());
+
+        let config = Config::default().ws_outage_playback(Some(Duration::default()));
+        mixer.handle_message(MixerMessage::SetConfig(config));
+        assert!(mixer.outage_exceeded());
+
+        // Pacing restarts from the moment the websocket recovers.
+        let stale = mixer.deadline;
+        mixer.handle_message(MixerMessage::WsOutage(false));
+        assert!(!mixer.outage_exceeded());
+        assert!(mixer.deadline > stale);
+    }
+
+    #[tokio::test]
+    async fn lead_in_silence_precedes_audio() {
+
+        let lead_in = 3;
+        let (mut mixer, tasks) = test_mixer(Config::default().lead_in_silence(lead_in));
+
+        let input = Input::float_pcm(true, make_sine(10 * MONO_FRAME_SIZE, true).into());
+        mixer.tracks.push(tracks::create_player(input).0);
+
+        let packet_lens = |rx: &Receiver<UdpTxMessage>| {
+            rx.try_iter()
+                .map(|msg| match msg {
+                    UdpTxMessage::Packet(pkt) => pkt.len(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let silent_len = RtpPacket::minimum_packet_size() + TAG_SIZE + SILENT_FRAME.len();
+
+        mixer.cycle().unwrap();
+        let sent = packet_lens(&tasks.udp_tx_rx);
+        assert_eq!(sent.len(), lead_in + 1);
+        assert!(sent[..lead_in].iter().all(|len| *len == silent_len));
+        assert!(sent[lead_in] > silent_len);
+
+        // Lead-in is only sent when transmission (re)starts.
+        mixer.cycle().unwrap();
+        assert_eq!(packet_lens(&tasks.udp_tx_rx).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn encode_failures_send_silence_and_rebuild_encoder() {
+        use audiopus::SampleRate;
+
+        let (mut mixer, tasks) = test_mixer(Default::default());
+
+        let input = Input::float_pcm(true, make_sine(50 * MONO_FRAME_SIZE, true).into());
+        mixer.tracks.push(tracks::create_player(input).0);
+
+        // A full mixed frame is an illegal frame length for this encoder.
+        mixer.encoder =
+            OpusEncoder::new(SampleRate::Hz12000, Channels::Mono, CodingMode::Audio).unwrap();
+
+        let silent_len = RtpPacket::minimum_packet_size() + TAG_SIZE + SILENT_FRAME.len();
+        let next_len = || match tasks.udp_tx_rx.try_recv().unwrap() {
+            UdpTxMessage::Packet(pkt) => pkt.len(),
+            _ => unreachable!(),
+        };
+
+        for _ in 0..ENCODER_REBUILD_STRIKES {
+            mixer.cycle().unwrap();
+            assert_eq!(next_len(), silent_len);
+        }
+        assert_eq!(mixer.encode_errors, ENCODER_REBUILD_STRIKES as u64);
+
+        // The rebuilt encoder recovers.
+        mixer.cycle().unwrap();
+        assert!(next_len() > silent_len);
+        assert_eq!(mixer.encode_errors, ENCODER_REBUILD_STRIKES as u64);
+    }
+
+    #[tokio::test]
+    async fn track_stats_count_frames_seeks_and_loops() {
+        let (mut mixer, tasks) = test_mixer(Default::default());
+
+        let input = Input::float_pcm(true, make_sine(2 * MONO_FRAME_SIZE, true).into());
+        let (mut track, handle) = tracks::create_player(input);
+        track.set_loops(tracks::LoopState::Finite(1)).unwrap();
+        mixer.tracks.push(track);
+
+        handle.seek_time(Duration::default()).unwrap();
+        for _ in 0..6 {
+            mixer.audio_commands_events().unwrap();
+            mixer.cycle().unwrap();
+        }
+
+        // Two frames per pass: one before the loop, and one after.
+        let stats = mixer.tracks[0].stats();
+        assert_eq!(stats.frames, 4);
+        assert_eq!(stats.seeks, 1);
+        assert_eq!(stats.loops, 1);
+        assert_eq!(stats.underruns, 0);
+
+        // Only the packets carrying the track's audio count towards it.
+        let payloads: Vec<_> = tasks
+            .udp_tx_rx
+            .try_iter()
+            .map(|msg| match msg {
+                UdpTxMessage::Packet(pkt) => (pkt.len() - RtpPacket::minimum_packet_size()) as u64,
+                _ => unreachable!(),
+            })
+            .collect();
+        let silent = (TAG_SIZE + SILENT_FRAME.len()) as u64;
+        let audible: Vec<_> = payloads.into_iter().filter(|len| *len > silent).collect();
+        assert_eq!(audible.len(), 4);
+        assert_eq!(stats.bytes, audible.iter().sum::<u64>());
+
+        // Nor does audio which is never sent.
+        let input = Input::float_pcm(true, make_sine(MONO_FRAME_SIZE, true).into());
+        mixer.tracks = vec![tracks::create_player(input).0];
+        mixer.muted = true;
+        mixer.cycle().unwrap();
+        assert_eq!(mixer.tracks[0].stats().frames, 1);
+        assert_eq!(mixer.tracks[0].stats().bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn reconnects_rewind_tracks_to_saved_positions() {
+
+        let config = Config::default().reconnect_resume(ReconnectResume::Rewind);
+        let (mut mixer, tasks) = test_mixer(config);
+        let event_rx = &tasks.event_rx;
+
+        let input = Input::float_pcm(true, make_sine(50 * MONO_FRAME_SIZE, true).into());
+        let (track, _handle) = tracks::create_player(input);
+        mixer.tracks.push(track);
+
+        for _ in 0..2 {
+            mixer.cycle().unwrap();
+        }
+        let saved = mixer.tracks[0].position();
+
+        // Audio sent while the websocket resumes is lost if the session has died.
+        mixer.handle_message(MixerMessage::WsOutage(true));
+        for _ in 0..3 {
+            mixer.cycle().unwrap();
+        }
+        assert!(mixer.tracks[0].position() > saved);
+
+        while event_rx.try_recv().is_ok() {}
+        mixer.handle_message(MixerMessage::SetConn(tasks.conn(), 1));
+        assert_eq!(mixer.tracks[0].position(), saved);
+        assert!(event_rx.try_iter().any(|evt| matches!(
+            evt,
+            EventMessage::ChangeState(0, TrackStateChange::Resumed)
+        )));
+
+        // Tracks are left alone if the websocket recovers without a new session.
+        mixer.handle_message(MixerMessage::WsOutage(true));
+        mixer.cycle().unwrap();
+        mixer.handle_message(MixerMessage::WsOutage(false));
+        mixer.handle_message(MixerMessage::SetConn(tasks.conn(), 1));
+        assert!(mixer.tracks[0].position() > saved);
+    }
+
+    #[tokio::test]
+    async fn reconnects_end_unseekable_tracks_under_rewind_or_stop() {
+        use crate::input::{Codec, Container, Reader};
+        use std::io::{Cursor, Read, Result as IoResult, Seek, SeekFrom};
+        use symphonia_core::io::MediaSource;
+
+        struct Unseekable(Cursor<Vec<u8>>);
+
+        impl Read for Unseekable {
+            fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+                self.0.read(buf)
+            }
+        }
+
+        impl Seek for Unseekable {
+            fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+                self.0.seek(pos)
+            }
+        }
+
+        impl MediaSource for Unseekable {
+            fn is_seekable(&self) -> bool {
+                false
+            }
+
+            fn byte_len(&self) -> Option<u64> {
+                None
+            }
+        }
+
+        let config = Config::default().reconnect_resume(ReconnectResume::RewindOrStop);
+        let (mut mixer, tasks) = test_mixer(config);
+
+        let sine = make_sine(50 * MONO_FRAME_SIZE, true);
+        let input = Input::new(
+            true,
+            Reader::Extension(Box::new(Unseekable(Cursor::new(sine)))),
+            Codec::FloatPcm,
+            Container::Raw,
+            None,
+        );
+        mixer.tracks.push(tracks::create_player(input).0);
+        mixer.cycle().unwrap();
+
+        mixer.handle_message(MixerMessage::WsOutage(true));
+        mixer.cycle().unwrap();
+        mixer.handle_message(MixerMessage::SetConn(tasks.conn(), 1));
+
+        // A live source cannot be rewound, which is reported as an error rather
+        // than as though the user stopped it.
+        assert_eq!(
+            mixer.tracks[0].end_reason(),
+            Some(EndReason::Error(TrackError::SeekUnsupported))
+        );
+        assert_eq!(mixer.tracks[0].playing(), PlayMode::End);
+    }
+
+    #[tokio::test]
+    async fn speaking_flags_accompany_audio() {
+
+        let (mut mixer, _tasks) = test_mixer(Default::default());
+        let (ws_tx, ws_rx) = flume::unbounded();
+        mixer.ws = Some(ws_tx);
+
+        let input = Input::float_pcm(true, make_sine(MONO_FRAME_SIZE, true).into());
+        mixer.tracks.push(tracks::create_player(input).0);
+
+        mixer.handle_message(MixerMessage::SetSpeakingFlags(
+            SpeakingState::PRIORITY | SpeakingState::MICROPHONE,
+        ));
+        mixer.cycle().unwrap();
+
+        let sent = ws_rx.try_iter().find_map(|msg| match msg {
+            WsMessage::Speaking(state) => Some(state),
+            _ => None,
+        });
+        assert_eq!(sent, Some(SpeakingState::MICROPHONE | SpeakingState::PRIORITY));
+    }
+
+    #[tokio::test]
+    async fn instant_opus_preempts_mixed_audio() {
+
+        let (mut mixer, tasks) = test_mixer(Default::default());
+
+        let input = Input::float_pcm(true, make_sine(10 * MONO_FRAME_SIZE, true).into());
+        mixer.tracks.push(tracks::create_player(input).0);
+
+        let beep = vec![0xf8, 0xff, 0xfe];
+        let beep_len = RtpPacket::minimum_packet_size() + TAG_SIZE + beep.len();
+        mixer.handle_message(MixerMessage::PlayInstantOpus(beep.clone()));
+        mixer.handle_message(MixerMessage::PlayInstantOpus(beep));
+
+        let next_len = || match tasks.udp_tx_rx.try_recv().unwrap() {
+            UdpTxMessage::Packet(pkt) => pkt.len(),
+            _ => unreachable!(),
+        };
+
+        // Queued frames are sent one per tick, before mixed audio resumes.
+        mixer.cycle().unwrap();
+        assert_eq!(next_len(), beep_len);
+        mixer.cycle().unwrap();
+        assert_eq!(next_len(), beep_len);
+        mixer.cycle().unwrap();
+        assert!(next_len() > beep_len);
+
+        // The track kept playing underneath.
+        assert_eq!(mixer.tracks[0].position, 3 * TIMESTEP_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn master_gain_scales_summed_tracks() {
+        use byteorder::{ByteOrder, LittleEndian};
+
+        let (ic, _tasks) = test_interconnect();
+
+        let sine = make_sine(MONO_FRAME_SIZE, true);
+        let mut playing = (0..2)
+            .map(|_| tracks::create_player(Input::float_pcm(true, sine.clone().into())).0)
+            .collect();
+
+        let mut opus_frame = [0u8; VOICE_PACKET_MAX];
+        let mut mix_buffer = [0f32; STEREO_FRAME_SIZE];
+        let mix_len = mix_tracks(
+            &mut opus_frame,
+            &mut mix_buffer,
+            &mut playing,
+            &mut Default::default(),
+            &ic,
+            true,
+            &Default::default(),
+            0.25,
+            None,
+        );
+        assert_eq!(mix_len, MixType::MixedPcm(STEREO_FRAME_BYTE_SIZE));
+
+        // Two tracks at full volume, then quartered.
+        for (i, sample) in mix_buffer.iter().enumerate() {
+            let expected = LittleEndian::read_f32(&sine[4 * i..]) * 0.5;
+            assert!((sample - expected).abs() < 1e-6);
+        }
+    }
+
+    #[tokio::test]
+    async fn effective_volume_includes_ducking_and_master_gain() {
+        use crate::tracks::DuckConfig;
+
+        let (ic, tasks) = test_interconnect();
+
+        let sine = || Input::float_pcm(true, make_sine(MONO_FRAME_SIZE, true).into());
+        let (mut announcer, _) = tracks::create_player(sine());
+        announcer.set_ducking(Some(DuckConfig {
+            gain: 0.5,
+            attack: Duration::default(),
+            ..Default::default()
+        }));
+        let (mut music, _) = tracks::create_player(sine());
+        music.set_volume(0.5);
+        let mut playing = vec![announcer, music];
+
+        let mut opus_frame = [0u8; VOICE_PACKET_MAX];
+        let mut mix_buffer = [0f32; STEREO_FRAME_SIZE];
+        mix_tracks(
+            &mut opus_frame,
+            &mut mix_buffer,
+            &mut playing,
+            &mut Default::default(),
+            &ic,
+            false,
+            &Default::default(),
+            0.5,
+            None,
+        );
+
+        let state = playing[1].state();
+        assert_eq!(state.volume, 0.5);
+        assert!((state.effective_volume() - 0.125).abs() < f32::EPSILON);
+        assert!((playing[0].state().effective_volume() - 0.5).abs() < f32::EPSILON);
+
+        let reported = tasks
+            .event_rx
+            .try_iter()
+            .filter_map(|msg| match msg {
+                EventMessage::ChangeState(1, TrackStateChange::EffectiveVolume(v)) => Some(v),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(reported, [0.125]);
+    }
+
+    #[tokio::test]
+    async fn music_returns_to_full_volume_after_an_announcement() {
+        use crate::tracks::DuckConfig;
+
+        let (mut mixer, _tasks) = test_mixer(Default::default());
+
+        let sine =
+            |frames| Input::float_pcm(true, make_sine(frames * MONO_FRAME_SIZE, true).into());
+        let (mut announcer, _) = tracks::create_player(sine(2));
+        announcer.set_ducking(Some(DuckConfig {
+            gain: 0.25,
+            attack: Duration::default(),
+            release: TIMESTEP_LENGTH * 3,
+        }));
+        let (music, music_handle) = tracks::create_player(sine(20));
+        mixer.tracks.push(announcer);
+        mixer.tracks.push(music);
+
+        let music_volume = |mixer: &Mixer| {
+            mixer
+                .tracks
+                .iter()
+                .find(|t| t.uuid() == music_handle.uuid())
+                .map(|t| t.effective_volume)
+                .unwrap()
+        };
+
+        // Music is ducked while the announcement plays...
+        mixer.cycle().unwrap();
+        assert!((music_volume(&mixer) - 0.25).abs() < 1e-6);
+        mixer.cycle().unwrap();
+        assert!((music_volume(&mixer) - 0.25).abs() < 1e-6);
+
+        // ...then smoothly released once it ends.
+        let mut released = vec![];
+        for _ in 0..5 {
+            mixer.cycle().unwrap();
+            released.push(music_volume(&mixer));
+        }
+        assert!(released.windows(2).all(|w| w[0] <= w[1]));
+        assert!(released[1] > 0.25 && released[1] < 1.0);
+        assert!((released[4] - 1.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn broadcast_is_scaled_by_volume_ducking_and_master_gain() {
+        use crate::{tracks::DuckConfig, Broadcast};
+        use byteorder::{ByteOrder, LittleEndian};
+
+        let (ic, _tasks) = test_interconnect();
+
+        let sine = make_sine(MONO_FRAME_SIZE, true);
+        let broadcast = Broadcast::new(Input::float_pcm(true, sine.clone().into()));
+        let mut subscriber = broadcast.subscribe();
+
+        let (mut announcer, _handle) =
+            tracks::create_player(Input::float_pcm(true, sine.clone().into()));
+        announcer.set_ducking(Some(DuckConfig {
+            gain: 0.5,
+            attack: Duration::default(),
+            ..Default::default()
+        }));
+        let mut playing = vec![announcer];
+
+        let mut opus_frame = [0u8; VOICE_PACKET_MAX];
+        let mut mix_buffer = [0f32; STEREO_FRAME_SIZE];
+        let mix_len = mix_tracks(
+            &mut opus_frame,
+            &mut mix_buffer,
+            &mut playing,
+            &mut Default::default(),
+            &ic,
+            true,
+            &Default::default(),
+            0.5,
+            Some((&mut subscriber, 0.5)),
+        );
+        assert_eq!(mix_len, MixType::MixedPcm(STEREO_FRAME_BYTE_SIZE));
+
+        // The announcer at full volume, plus the broadcast at half volume and
+        // ducked by half, then all halved by the master gain.
+        for (i, sample) in mix_buffer.iter().enumerate() {
+            let expected = LittleEndian::read_f32(&sine[4 * i..]) * (1.0 + 0.25) * 0.5;
+            assert!((sample - expected).abs() < 1e-6);
+        }
+    }
+
+    #[tokio::test]
+    async fn broadcast_is_decoded_once_across_mixers() {
+        use crate::Broadcast;
+
+        let input = Input::float_pcm(true, make_sine(2 * MONO_FRAME_SIZE, true).into());
+        let broadcast = Broadcast::new(input);
+
+        let (mut first, first_tasks) = test_mixer(Default::default());
+        let (mut second, second_tasks) = test_mixer(Default::default());
+        for mixer in [&mut first, &mut second] {
+            mixer.handle_message(MixerMessage::SetBroadcast(Some(broadcast.subscribe())));
+        }
+
+        let silent_len = RtpPacket::minimum_packet_size() + TAG_SIZE + SILENT_FRAME.len();
+        let packet_lens = |rx: &Receiver<UdpTxMessage>| {
+            rx.try_iter()
+                .map(|msg| match msg {
+                    UdpTxMessage::Packet(pkt) => pkt.len(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Each mixer sends both frames of a source which can only be read once.
+        for _ in 0..2 {
+            first.cycle().unwrap();
+            second.cycle().unwrap();
+        }
+        for tasks in [&first_tasks, &second_tasks] {
+            let sent = packet_lens(&tasks.udp_tx_rx);
+            assert_eq!(sent.len(), 2);
+            assert!(sent.iter().all(|len| *len > silent_len));
+        }
+
+        assert!(!broadcast.is_finished());
+        first.cycle().unwrap();
+        assert!(broadcast.is_finished());
+    }
+
+    #[tokio::test]
+    async fn silent_tracks_idle_until_audio_returns() {
+        use crate::tracks::SilencePolicy;
+
+        let (ic, tasks) = test_interconnect();
+        let event_rx = &tasks.event_rx;
+
+        // Two frames of audio, ten of silence, then two more of audio.
+        let sine = make_sine(2 * MONO_FRAME_SIZE, true);
+        let mut source = sine.clone();
+        source.extend(vec![0u8; 10 * STEREO_FRAME_BYTE_SIZE]);
+        source.extend(sine);
+
+        let (mut track, _handle) = tracks::create_player(Input::float_pcm(true, source.into()));
+        track.set_silence_policy(Some(SilencePolicy {
+            window: Duration::from_millis(100),
+            ..Default::default()
+        }));
+        let mut playing = vec![track];
+
+        let mut opus_frame = [0u8; VOICE_PACKET_MAX];
+        let mut mix_buffer = [0f32; STEREO_FRAME_SIZE];
+        let mut mix_lens = vec![];
+        let mut changes = vec![];
+        for frame in 0..14 {
+            mix_buffer.fill(0.0);
+            let mix_len = mix_tracks(
+                &mut opus_frame,
+                &mut mix_buffer,
+                &mut playing,
+                &mut Default::default(),
+                &ic,
+                false,
+                &Default::default(),
+                1.0,
+                None,
+            );
+            mix_lens.push(mix_len);
+
+            for msg in event_rx.drain() {
+                if let EventMessage::ChangeState(0, TrackStateChange::Silent(silent)) = msg {
+                    changes.push((frame, silent));
+                }
+            }
+        }
+
+        // Five quiet frames fill the window, and the first loud frame ends it.
+        assert_eq!(changes, [(6, true), (12, false)]);
+        for (frame, mix_len) in mix_lens.into_iter().enumerate() {
+            let expected = match frame {
+                6..=11 => 0,
+                _ => STEREO_FRAME_BYTE_SIZE,
+            };
+            assert_eq!(mix_len, MixType::MixedPcm(expected), "frame {}", frame);
+        }
+        assert!(mix_buffer.iter().any(|s| *s != 0.0));
+    }
+
+    #[tokio::test]
+    async fn tracks_report_why_they_ended() {
+        use crate::tracks::TrackHandle;
+
+        let (ic, tasks) = test_interconnect();
+        let event_rx = &tasks.event_rx;
+
+        let mut opus_frame = [0u8; VOICE_PACKET_MAX];
+        let mut mix_buffer = [0f32; STEREO_FRAME_SIZE];
+        let mut mix = |track: Track, config: &Config| {
+            let mut playing = vec![track];
+            for _ in 0..2 {
+                mix_tracks(
+                    &mut opus_frame,
+                    &mut mix_buffer,
+                    &mut playing,
+                    &mut Default::default(),
+                    &ic,
+                    true,
+                    config,
+                    1.0,
+                    None,
+                );
+            }
+            playing.remove(0)
+        };
+        let sine = || Input::float_pcm(true, make_sine(3 * MONO_FRAME_SIZE, true).into());
+        let empty = || Input::float_pcm(true, Vec::<u8>::new().into());
+
+        let track = mix(tracks::create_player(sine()).0, &Default::default());
+        assert_eq!(track.playing(), PlayMode::Play);
+        assert_eq!(track.end_reason(), None);
+
+        let track = mix(tracks::create_player(empty()).0, &Default::default());
+        assert_eq!(track.playing(), PlayMode::End);
+        assert_eq!(track.end_reason(), Some(EndReason::Completed));
+
+        let config = Config::default().input_timeout(Some(Duration::default()));
+        let track = mix(tracks::create_player(empty()).0, &config);
+        assert_eq!(
+            track.state().end_reason(),
+            Some(EndReason::Error(TrackError::TimedOut))
+        );
+
+        let (mut track, _handle) = tracks::create_player(sine());
+        track.set_max_duration(Some(TIMESTEP_LENGTH));
+        let track = mix(track, &Default::default());
+        assert_eq!(track.end_reason(), Some(EndReason::MaxDuration));
+
+        // Explicit stops and skips are told apart, and reported to the event thread.
+        for (reason, send) in [
+            (EndReason::Stopped, TrackHandle::stop as fn(&TrackHandle) -> _),
+            (EndReason::Skipped, TrackHandle::skip),
+        ] {
+            let (mut track, handle) = tracks::create_player(sine());
+            send(&handle).unwrap();
+            track.process_commands(0, &ic, None);
+
+            assert_eq!(track.playing(), reason.mode());
+            assert_eq!(track.end_reason(), Some(reason));
+            assert!(event_rx.drain().any(|msg| matches!(
+                msg,
+                EventMessage::ChangeState(0, TrackStateChange::Ended(r)) if r == reason
+            )));
+        }
+
+        // A finished track keeps its first end reason.
+        let (mut track, _handle) = tracks::create_player(sine());
+        track.stop().end(EndReason::Completed);
+        assert_eq!(track.end_reason(), Some(EndReason::Stopped));
+    }
+
+    #[tokio::test]
+    async fn seeks_keep_their_order_and_are_rate_limited() {
+        let (ic, _tasks) = test_interconnect();
+
+        let input = Input::float_pcm(true, make_sine(50 * MONO_FRAME_SIZE, true).into());
+        let (mut track, handle) = tracks::create_player(input);
+        let interval = Some(Duration::from_secs(1));
+        let ms = Duration::from_millis;
+
+        // Reports position as seen by commands which follow a seek in the same batch.
+        let seek_then_request = |track: &mut Track, time| {
+            let (tx, rx) = flume::bounded(1);
+            handle.seek_time(time).unwrap();
+            handle.send(tracks::TrackCommand::Request(tx)).unwrap();
+            track.process_commands(0, &ic, interval);
+            rx.try_recv().unwrap().position
+        };
+
+        assert_eq!(seek_then_request(&mut track, ms(100)), ms(100));
+
+        // Seeks within the interval are held back, and merged into the latest.
+        assert_eq!(seek_then_request(&mut track, ms(200)), ms(100));
+        assert_eq!(seek_then_request(&mut track, ms(300)), ms(100));
+        assert_eq!(track.pending_seek, Some(ms(300)));
+
+        // ...which is carried out once the interval has passed.
+        track.last_seek = Instant::now().checked_sub(Duration::from_secs(1));
+        track.process_commands(0, &ic, interval);
+        assert_eq!(track.position(), ms(300));
+        assert_eq!(track.pending_seek, None);
+        assert_eq!(track.stats.seeks, 2);
+    }
+
+    #[tokio::test]
+    async fn soft_stops_finish_the_current_frame_then_send_silence() {
+        let (ic, _tasks) = test_interconnect();
+
+        let sine = Input::float_pcm(true, make_sine(4 * MONO_FRAME_SIZE, true).into());
+        let (track, handle) = tracks::create_player(sine);
+        let mut playing = vec![track];
+
+        handle.soft_stop().unwrap();
+        playing[0].process_commands(0, &ic, None);
+
+        let mut opus_frame = [0u8; VOICE_PACKET_MAX];
+        let mut mix = || {
+            let mut mix_buffer = [0f32; STEREO_FRAME_SIZE];
+            let mix_len = mix_tracks(
+                &mut opus_frame,
+                &mut mix_buffer,
+                &mut playing,
+                &mut Default::default(),
+                &ic,
+                true,
+                &Default::default(),
+                1.0,
+                None,
+            );
+            (mix_len, mix_buffer)
+        };
+
+        // The frame underway when the stop arrived is mixed as normal...
+        let (mix_len, mix_buffer) = mix();
+        assert_eq!(mix_len, MixType::MixedPcm(STEREO_FRAME_BYTE_SIZE));
+        assert!(mix_buffer.iter().any(|s| *s != 0.0));
+
+        // ...followed by one frame of silence, after which the track ends.
+        let (mix_len, mix_buffer) = mix();
+        assert_eq!(mix_len, MixType::MixedPcm(STEREO_FRAME_BYTE_SIZE));
+        assert!(mix_buffer.iter().all(|s| *s == 0.0));
+
+        assert_eq!(playing[0].playing(), PlayMode::Stop);
+        assert_eq!(playing[0].end_reason(), Some(EndReason::Stopped));
+        assert_eq!(playing[0].position, TIMESTEP_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn never_ready_sources_time_out() {
+        use crate::input::{
+            error::Result as InputResult,
+            restartable::{Restart, Restartable},
+            Codec,
+            Container,
+            Metadata,
+        };
+        use async_trait::async_trait;
+
+        struct Hung;
+
+        #[async_trait]
+        impl Restart for Hung {
+            async fn call_restart(&mut self, _time: Option<Duration>) -> InputResult<Input> {
+                futures::future::pending().await
+            }
+
+            async fn lazy_init(&mut self) -> InputResult<(Option<Metadata>, Codec, Container)> {
+                Ok((None, Codec::FloatPcm, Container::Raw))
+            }
+        }
+
+        let config = Config::default().input_timeout(Some(3 * TIMESTEP_LENGTH));
+        let (mut mixer, _tasks) = test_mixer(config);
+
+        let source = Restartable::new(Hung, true).await.unwrap();
+        let (track, handle) = tracks::create_player(source.into());
+        mixer.handle_message(MixerMessage::AddTrack(track));
+
+        for _ in 0..2 {
+            mixer.cycle().unwrap();
+        }
+        assert!(!handle.timed_out());
+
+        mixer.cycle().unwrap();
+        assert!(handle.timed_out());
+        assert!(mixer.tracks[0].playing.is_done());
+    }
+
+    #[tokio::test]
+    async fn ready_fires_once_per_start() {
+
+        let (mut mixer, tasks) = test_mixer(Default::default());
+        let event_rx = &tasks.event_rx;
+
+        let input = Input::float_pcm(true, make_sine(10 * MONO_FRAME_SIZE, true).into());
+        let (mut track, handle) = tracks::create_player(input);
+        track.pause();
+        mixer.tracks.push(track);
+
+        let readies = || {
+            event_rx
+                .drain()
+                .filter(|msg| {
+                    matches!(msg, EventMessage::ChangeState(0, TrackStateChange::Ready))
+                })
+                .count()
+        };
+
+        // Paused tracks still report readiness, but only once.
+        for _ in 0..3 {
+            mixer.audio_commands_events().unwrap();
+        }
+        assert_eq!(readies(), 1);
+
+        let restarted = handle.restart();
+        for _ in 0..3 {
+            mixer.audio_commands_events().unwrap();
+        }
+        restarted.await.unwrap();
+        assert_eq!(readies(), 1);
+    }
+
+    #[tokio::test]
+    async fn scheduled_tracks_start_on_time() {
+
+        let (mut mixer, tasks) = test_mixer(Default::default());
+        let event_rx = &tasks.event_rx;
+
+        let input = || Input::float_pcm(true, make_sine(10 * MONO_FRAME_SIZE, true).into());
+        let at = Instant::now() + Duration::from_secs(60);
+
+        let (mut track, _handle) = tracks::create_player(input());
+        track.set_start_at(at);
+        mixer.tracks.push(track);
+
+        // Scheduled tracks are held until a frame is due to be sent at their start.
+        mixer.audio_commands_events().unwrap();
+        assert_eq!(mixer.tracks[0].playing(), PlayMode::Pause);
+
+        mixer.deadline = at;
+        event_rx.drain();
+        mixer.audio_commands_events().unwrap();
+        assert_eq!(mixer.tracks[0].playing(), PlayMode::Play);
+        assert_eq!(mixer.tracks[0].start_at(), None);
+        assert!(event_rx.drain().any(|msg| matches!(
+            msg,
+            EventMessage::ChangeState(0, TrackStateChange::Mode(PlayMode::Play))
+        )));
+
+        // Times in the past start immediately.
+        let (mut track, _handle) = tracks::create_player(input());
+        track.set_start_at(Instant::now() - Duration::from_secs(1));
+        assert_eq!(track.playing(), PlayMode::Play);
+    }
+
+    #[tokio::test]
+    async fn forked_tracks_are_independent() {
+        use crate::input::cached::Memory;
+        use std::convert::TryFrom;
+
+        let (mut mixer, _tasks) = test_mixer(Default::default());
+
+        let source = || Input::float_pcm(true, make_sine(50 * MONO_FRAME_SIZE, true).into());
+        let memory = Memory::new(source()).unwrap();
+        let (mut track, handle) = tracks::create_player(Input::try_from(memory).unwrap());
+        for _ in 0..5 {
+            track.step_frame();
+        }
+        mixer.tracks.push(track);
+
+        let fork = tokio::spawn({
+            let handle = handle.clone();
+            async move { handle.fork().await }
+        });
+        tokio::task::yield_now().await;
+        mixer.audio_commands_events().unwrap();
+        while let Ok(msg) = mixer.mix_rx.try_recv() {
+            mixer.handle_message(msg);
+        }
+        let fork = fork.await.unwrap().unwrap();
+
+        // The fork starts where the original was, and is then controlled separately.
+        assert_eq!(mixer.tracks.len(), 2);
+        assert_ne!(fork.uuid(), handle.uuid());
+        assert_eq!(mixer.tracks[1].position(), mixer.tracks[0].position());
+
+        fork.set_volume(0.5).unwrap();
+        handle.pause().unwrap();
+        mixer.audio_commands_events().unwrap();
+        assert_eq!(mixer.tracks[0].playing(), PlayMode::Pause);
+        assert_eq!(mixer.tracks[0].volume(), 1.0);
+        assert_eq!(mixer.tracks[1].playing(), PlayMode::Play);
+        assert_eq!(mixer.tracks[1].volume(), 0.5);
+
+        // Single-use sources cannot be replayed.
+        let (track, handle) = tracks::create_player(source());
+        mixer.tracks.push(track);
+        let fork = tokio::spawn(async move { handle.fork().await });
+        tokio::task::yield_now().await;
+        mixer.audio_commands_events().unwrap();
+        assert_eq!(fork.await.unwrap().unwrap_err(), TrackError::ForkUnsupported);
+    }
+
+    #[tokio::test]
+    async fn new_session_key_resets_crypto_state() {
+
+        let (mut mixer, tasks) = test_mixer(Default::default());
+
+        mixer.conn_active.as_mut().unwrap().crypto_state = CryptoState::Suffix;
+
+        let cipher = Cipher::new_from_slice(&[1u8; KEY_SIZE]).unwrap();
+        mixer.handle_message(MixerMessage::SetCipher(cipher));
+
+        let conn = mixer.conn_active.as_ref().unwrap();
+        assert_eq!(conn.crypto_state, CryptoState::Normal);
+        assert!(matches!(
+            tasks.udp_rx_rx.try_recv(),
+            Ok(UdpRxMessage::SetCipher(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unattainable_bitrates_are_reported() {
+        let (mut mixer, tasks) = test_mixer(Default::default());
+        let mismatches = || {
+            tasks
+                .event_rx
+                .drain()
+                .filter_map(|msg| match msg {
+                    EventMessage::FireCoreEvent(CoreContext::BitrateMismatch(data)) => Some(data),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Opus caps stereo streams at 600kbps, so the encoder clamps this request.
+        let requested = Bitrate::BitsPerSecond(1_000_000);
+        mixer.handle_message(MixerMessage::SetBitrate(requested));
+        let reported = mismatches();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].requested, requested);
+        assert_eq!(reported[0].actual, Bitrate::BitsPerSecond(600_000));
+
+        let (tx, rx) = flume::bounded(1);
+        mixer.handle_message(MixerMessage::GetBitrate(tx));
+        assert_eq!(rx.try_recv().unwrap(), reported[0]);
+
+        mixer.handle_message(MixerMessage::SetBitrate(Bitrate::BitsPerSecond(64_000)));
+        assert!(mismatches().is_empty());
+    }
+
+    #[tokio::test]
+    async fn tracks_begin_from_their_start_offset() {
+        let (mut mixer, _tasks) = test_mixer(Default::default());
+
+        let input = Input::float_pcm(true, make_sine(10 * MONO_FRAME_SIZE, true).into());
+        let (mut track, _handle) = tracks::create_player(input);
+        track.set_start_offset(TIMESTEP_LENGTH * 4).unwrap();
+        mixer.handle_message(MixerMessage::AddTrack(track));
+
+        // The offset is applied before any audio is mixed.
+        assert_eq!(mixer.tracks[0].position, TIMESTEP_LENGTH * 4);
+        assert_eq!(mixer.tracks[0].start_offset(), None);
+
+        mixer.cycle().unwrap();
+        assert_eq!(mixer.tracks[0].position, TIMESTEP_LENGTH * 5);
+        assert_eq!(mixer.tracks[0].play_time, TIMESTEP_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn gap_silence_extends_trailing_silence() {
+        let silent_len = RtpPacket::minimum_packet_size() + TAG_SIZE + SILENT_FRAME.len();
+
+        for (gap, trailing) in [(None, 5), (Some(TIMESTEP_LENGTH * 3), 8)] {
+            let (mut mixer, tasks) = test_mixer(Config::default().gap_silence(gap));
+
+            let input = Input::float_pcm(true, make_sine(2 * MONO_FRAME_SIZE, true).into());
+            mixer.tracks.push(tracks::create_player(input).0);
+
+            for _ in 0..20 {
+                mixer.cycle().unwrap();
+            }
+
+            let sent = tasks
+                .udp_tx_rx
+                .try_iter()
+                .map(|msg| match msg {
+                    UdpTxMessage::Packet(pkt) => pkt.len(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>();
+            let silent = sent.iter().skip_while(|len| **len > silent_len).collect::<Vec<_>>();
+            assert_eq!(silent.len(), trailing, "{:?}", gap);
+            assert!(silent.iter().all(|len| **len == silent_len));
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_tracks_are_paused_once_over_budget() {
+        use crate::input::{Codec, Container, Reader};
+        use std::io::{Cursor, Read, Result as IoResult, Seek, SeekFrom};
+        use symphonia_core::io::MediaSource;
+
+        struct Slow(Cursor<Vec<u8>>);
+
+        impl Read for Slow {
+            fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+                std::thread::sleep(Duration::from_millis(2));
+                self.0.read(buf)
+            }
+        }
+
+        impl Seek for Slow {
+            fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+                self.0.seek(pos)
+            }
+        }
+
+        impl MediaSource for Slow {
+            fn is_seekable(&self) -> bool {
+                true
+            }
+
+            fn byte_len(&self) -> Option<u64> {
+                None
+            }
+        }
+
+        let config = Config::default().track_budget(Some(Duration::from_micros(500)));
+        let (mut mixer, tasks) = test_mixer(config);
+
+        let sine = make_sine(50 * MONO_FRAME_SIZE, true);
+        let slow = Input::new(
+            true,
+            Reader::Extension(Box::new(Slow(Cursor::new(sine.clone())))),
+            Codec::FloatPcm,
+            Container::Raw,
+            None,
+        );
+        let fast = Input::float_pcm(true, sine.into());
+        mixer.tracks.push(tracks::create_player(slow).0);
+        mixer.tracks.push(tracks::create_player(fast).0);
+
+        for _ in 0..TRACK_BUDGET_STRIKES - 1 {
+            mixer.cycle().unwrap();
+        }
+        assert_eq!(mixer.tracks[0].playing, PlayMode::Play);
+
+        mixer.cycle().unwrap();
+        assert_eq!(mixer.tracks[0].playing, PlayMode::Pause);
+        assert_eq!(mixer.tracks[1].playing, PlayMode::Play);
+        assert!(tasks.event_rx.try_iter().any(|msg| matches!(
+            msg,
+            EventMessage::ChangeState(0, TrackStateChange::OverBudget)
+        )));
+    }
+}
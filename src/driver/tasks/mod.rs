@@ -5,17 +5,19 @@ pub mod error;
 mod events;
 pub mod message;
 pub mod mixer;
+pub(crate) mod packet_log;
+pub mod recv_batch;
 pub(crate) mod udp_rx;
 pub(crate) mod udp_tx;
 pub(crate) mod ws;
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use super::connection::{error::Error as ConnectionError, Connection};
+use super::connection::{self, error::Error as ConnectionError, Connection};
 use crate::{
     events::{
         context_data::{DisconnectKind, DisconnectReason},
-        internal_data::{InternalConnect, InternalDisconnect},
+        internal_data::{InternalConnect, InternalDisconnect, InternalMigrate},
         CoreContext,
     },
     Config,
@@ -24,14 +26,22 @@ use crate::{
 use flume::{Receiver, RecvError, Sender};
 use message::*;
 use tokio::{runtime::Handle, spawn, time::sleep as tsleep};
-use tracing::{debug, instrument, trace};
+use tracing::{debug, info, instrument, trace, warn, Instrument, Span};
 
 pub(crate) fn start(config: Config, rx: Receiver<CoreMessage>, tx: Sender<CoreMessage>) {
-    spawn(async move {
-        trace!("Driver started.");
-        runner(config, rx, tx).await;
-        trace!("Driver finished.");
-    });
+    // All other driver tasks are spawned from within this one,
+    // so they inherit its runtime.
+    let runtime = config.spawn_on.clone().unwrap_or_else(Handle::current);
+    let span = config.tracing_span.clone().unwrap_or_else(Span::none);
+
+    runtime.spawn(
+        async move {
+            trace!("Driver started.");
+            runner(config, rx, tx).await;
+            trace!("Driver finished.");
+        }
+        .instrument(span),
+    );
 }
 
 fn start_internals(core: Sender<CoreMessage>, config: Config) -> Interconnect {
@@ -45,21 +55,29 @@ fn start_internals(core: Sender<CoreMessage>, config: Config) -> Interconnect {
     };
 
     let ic = interconnect.clone();
-    spawn(async move {
-        trace!("Event processor started.");
-        events::runner(ic, evt_rx).await;
-        trace!("Event processor finished.");
-    });
+    spawn(
+        async move {
+            trace!("Event processor started.");
+            events::runner(ic, evt_rx).await;
+            trace!("Event processor finished.");
+        }
+        .in_current_span(),
+    );
 
-    let ic = interconnect.clone();
+    start_mixer(interconnect.clone(), mix_rx, config);
+
+    interconnect
+}
+
+fn start_mixer(ic: Interconnect, mix_rx: Receiver<MixerMessage>, config: Config) {
     let handle = Handle::current();
+    let span = Span::current();
     std::thread::spawn(move || {
+        let _span = span.enter();
         trace!("Mixer started.");
         mixer::runner(ic, mix_rx, handle, config);
         trace!("Mixer finished.");
     });
-
-    interconnect
 }
 
 #[instrument(skip(rx, tx))]
@@ -69,6 +87,8 @@ async fn runner(mut config: Config, rx: Receiver<CoreMessage>, tx: Sender<CoreMe
     let mut interconnect = start_internals(tx, config.clone());
     let mut retrying = None;
     let mut attempt_idx = 0;
+    #[cfg(feature = "internals")]
+    let mut test_rx: Option<Sender<UdpRxMessage>> = None;
 
     loop {
         match rx.recv_async().await {
@@ -91,7 +111,22 @@ async fn runner(mut config: Config, rx: Receiver<CoreMessage>, tx: Sender<CoreMe
                     // active connection.
                     // This allows the gateway component to keep sending join requests independent
                     // of driver failures.
-                    connection = ConnectionRetryData::connect(tx, info, &mut attempt_idx)
+                    let previous_endpoint = connection
+                        .as_ref()
+                        .filter(|conn| is_migration(&conn.info, &info))
+                        .map(|conn| conn.info.endpoint.clone());
+
+                    let retry_data = match previous_endpoint {
+                        Some(previous) => {
+                            // The old server is going away: tracks are kept by the mixer.
+                            info!("Moving voice server from {} to {}.", previous, info.endpoint);
+                            drop(connection.take());
+                            ConnectionRetryData::migrate(tx, info, previous, &mut attempt_idx)
+                        },
+                        None => ConnectionRetryData::connect(tx, info, &mut attempt_idx),
+                    };
+
+                    connection = retry_data
                         .attempt(&mut retrying, &interconnect, &config)
                         .await;
                 } else {
@@ -111,11 +146,19 @@ async fn runner(mut config: Config, rx: Receiver<CoreMessage>, tx: Sender<CoreMe
                 }
             },
             Ok(CoreMessage::Disconnect) => {
+                // Injected packets after this point belong to a new session.
+                #[cfg(feature = "internals")]
+                if let Some(test_rx) = test_rx.take() {
+                    let _ = test_rx.send(UdpRxMessage::Poison);
+                }
+
                 let last_conn = connection.take();
                 let _ = interconnect.mixer.send(MixerMessage::DropConn);
                 let _ = interconnect.mixer.send(MixerMessage::RebuildEncoder);
 
                 if let Some(conn) = last_conn {
+                    conn.close().await;
+
                     let _ = interconnect.events.send(EventMessage::FireCoreEvent(
                         CoreContext::DriverDisconnect(InternalDisconnect {
                             kind: DisconnectKind::Runtime,
@@ -152,25 +195,133 @@ async fn runner(mut config: Config, rx: Receiver<CoreMessage>, tx: Sender<CoreMe
             Ok(CoreMessage::AddTrack(s)) => {
                 let _ = interconnect.mixer.send(MixerMessage::AddTrack(s));
             },
+            Ok(CoreMessage::PlayInstantOpus(frame)) => {
+                let _ = interconnect.mixer.send(MixerMessage::PlayInstantOpus(frame));
+            },
+            Ok(CoreMessage::SetBroadcast(subscriber)) => {
+                let _ = interconnect.mixer.send(MixerMessage::SetBroadcast(subscriber));
+            },
+            Ok(CoreMessage::SetBroadcastVolume(volume)) => {
+                let _ = interconnect.mixer.send(MixerMessage::SetBroadcastVolume(volume));
+            },
             Ok(CoreMessage::SetBitrate(b)) => {
                 let _ = interconnect.mixer.send(MixerMessage::SetBitrate(b));
             },
+            Ok(CoreMessage::GetBitrate(tx)) => {
+                let _ = interconnect.mixer.send(MixerMessage::GetBitrate(tx));
+            },
+            Ok(CoreMessage::GetSsrc(tx)) => {
+                let _ = tx.send(connection.as_ref().map(|conn| conn.ssrc));
+            },
+            Ok(CoreMessage::GetLatency(tx)) => {
+                let _ = tx.send(connection.as_ref().map(|conn| *conn.latency.lock()));
+            },
+            Ok(CoreMessage::GetListenerCount(tx)) =>
+                if let Some(conn) = &connection {
+                    let _ = conn.ws.send(WsMessage::GetListenerCount(tx));
+                } else {
+                    let _ = tx.send(None);
+                },
+            #[cfg(feature = "internals")]
+            Ok(CoreMessage::InjectRtp(packet)) => {
+                if test_rx.is_none() {
+                    let config = next_config.as_ref().unwrap_or(&config);
+                    test_rx = crate::driver::test::start_receiver(&interconnect, config).await;
+                }
+
+                if let Some(test_rx) = &test_rx {
+                    let _ = test_rx.send(UdpRxMessage::Inject(packet));
+                }
+            },
+            Ok(CoreMessage::GetUserActivity(tx)) => {
+                let _ = interconnect.mixer.send(MixerMessage::GetUserActivity(tx));
+            },
+            Ok(CoreMessage::GetReplayDropped(tx)) => {
+                let _ = interconnect.mixer.send(MixerMessage::GetReplayDropped(tx));
+            },
+            Ok(CoreMessage::GetMalformedDropped(tx)) => {
+                let _ = interconnect.mixer.send(MixerMessage::GetMalformedDropped(tx));
+            },
+            Ok(CoreMessage::GetSocketDropped(tx)) => {
+                let _ = interconnect.mixer.send(MixerMessage::GetSocketDropped(tx));
+            },
+            Ok(CoreMessage::GetUserHistory(user, duration, tx)) => {
+                let _ = interconnect
+                    .mixer
+                    .send(MixerMessage::GetUserHistory(user, duration, tx));
+            },
+            Ok(CoreMessage::GetEncodeErrors(tx)) => {
+                let _ = interconnect.mixer.send(MixerMessage::GetEncodeErrors(tx));
+            },
             Ok(CoreMessage::SetConfig(mut new_config)) => {
                 next_config = Some(new_config.clone());
 
                 new_config.make_safe(&config, connection.is_some());
 
+                #[cfg(feature = "internals")]
+                if let Some(test_rx) = &test_rx {
+                    let _ = test_rx.send(UdpRxMessage::SetConfig(Box::new(new_config.clone())));
+                }
+
                 let _ = interconnect.mixer.send(MixerMessage::SetConfig(new_config));
             },
             Ok(CoreMessage::AddEvent(evt)) => {
                 let _ = interconnect.events.send(EventMessage::AddGlobalEvent(evt));
             },
+            Ok(CoreMessage::RemoveGlobalEvent(handle)) => {
+                let _ = interconnect
+                    .events
+                    .send(EventMessage::RemoveGlobalEvent(handle));
+            },
             Ok(CoreMessage::RemoveGlobalEvents) => {
                 let _ = interconnect.events.send(EventMessage::RemoveGlobalEvents);
             },
+            Ok(CoreMessage::ListGlobalEvents(tx)) => {
+                let _ = interconnect.events.send(EventMessage::ListGlobalEvents(tx));
+            },
+            Ok(CoreMessage::RenegotiateCrypto) =>
+                if let Some(conn) = &mut connection {
+                    conn.renegotiating = true;
+                    let _ = conn.ws.send(WsMessage::SelectProtocol(conn.protocol.clone()));
+                },
+            Ok(CoreMessage::SessionDescription(desc)) =>
+                match &mut connection {
+                    Some(conn) if conn.renegotiating =>
+                        match connection::session_cipher(&desc, config.crypto_mode) {
+                            Ok((cipher, session)) => {
+                                conn.renegotiating = false;
+                                conn.session = session;
+                                let _ = interconnect.mixer.send(MixerMessage::SetCipher(cipher));
+                                let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+                                    CoreContext::CryptoRenegotiated(session),
+                                ));
+                            },
+                            Err(e) => warn!("Ignoring unusable session description: {:?}", e),
+                        },
+                    _ => debug!("Ignoring unrequested session description."),
+                },
+            Ok(CoreMessage::QueueEmpty(action)) => {
+                let _ = interconnect
+                    .events
+                    .send(EventMessage::FireCoreEvent(CoreContext::QueueEmpty(action)));
+            },
             Ok(CoreMessage::Mute(m)) => {
                 let _ = interconnect.mixer.send(MixerMessage::SetMute(m));
             },
+            Ok(CoreMessage::SetMasterGain(gain)) => {
+                let _ = interconnect.mixer.send(MixerMessage::SetMasterGain(gain));
+            },
+            Ok(CoreMessage::SetReceiving(r)) => {
+                #[cfg(feature = "internals")]
+                if let Some(test_rx) = &test_rx {
+                    let _ = test_rx.send(UdpRxMessage::SetReceiving(r));
+                }
+
+                let _ = interconnect.mixer.send(MixerMessage::SetReceiving(r));
+            },
+            Ok(CoreMessage::SetSpeakingFlags(flags)) => {
+                let _ = interconnect.mixer.send(MixerMessage::SetSpeakingFlags(flags));
+            },
             Ok(CoreMessage::Reconnect) => {
                 if let Some(mut conn) = connection.take() {
                     // try once: if interconnect, try again.
@@ -205,6 +356,12 @@ async fn runner(mut config: Config, rx: Receiver<CoreMessage>, tx: Sender<CoreMe
                             CoreContext::DriverReconnect(InternalConnect {
                                 info: connection.info.clone(),
                                 ssrc: connection.ssrc,
+                                session: connection.session,
+                                gateway_version: connection.gateway_version,
+                                ip_family: connection.ip_family,
+                                vbr_mode: config.opus_vbr,
+                                max_bandwidth: config.opus_max_bandwidth,
+                                latency: *connection.latency.lock(),
                             }),
                         ));
                     }
@@ -221,6 +378,80 @@ async fn runner(mut config: Config, rx: Receiver<CoreMessage>, tx: Sender<CoreMe
             Ok(CoreMessage::RebuildInterconnect) => {
                 interconnect.restart_volatile_internals();
             },
+            Ok(CoreMessage::CheckTasks(tx)) => {
+                let alive =
+                    !interconnect.mixer.is_disconnected() && !interconnect.events.is_disconnected();
+                let _ = tx.send(alive);
+            },
+            Ok(CoreMessage::RestartTasks(queue)) => {
+                let events_failed = interconnect.events.is_disconnected();
+                let mixer_failed = interconnect.mixer.is_disconnected();
+
+                if events_failed {
+                    warn!("Event processor exited unexpectedly: restarting.");
+                    interconnect.restart_volatile_internals();
+                }
+
+                // Tracks die with the mixer, but outlive the event processor
+                // (unlike the handlers which move the queue along).
+                if let Some(queue) = queue {
+                    if mixer_failed {
+                        queue.stop();
+                    } else if events_failed {
+                        queue.reattach_handlers();
+                    }
+                }
+
+                if mixer_failed {
+                    warn!("Mixer exited unexpectedly: restarting.");
+                    let (mix_tx, mix_rx) = flume::unbounded();
+                    interconnect.mixer = mix_tx;
+                    start_mixer(interconnect.clone(), mix_rx, config.clone());
+
+                    // The old mixer took this connection's UDP tasks down with it.
+                    if let Some(conn) = connection.take() {
+                        let info = conn.info.clone();
+                        drop(conn);
+
+                        connection = ConnectionRetryData::reconnect(info, &mut attempt_idx)
+                            .attempt(&mut retrying, &interconnect, &config)
+                            .await;
+                    }
+
+                    let _ = interconnect
+                        .events
+                        .send(EventMessage::FireCoreEvent(CoreContext::DriverRecreated));
+                }
+            },
+            Ok(CoreMessage::SignalRecreated) => {
+                let _ = interconnect
+                    .events
+                    .send(EventMessage::FireCoreEvent(CoreContext::DriverRecreated));
+            },
+            Ok(CoreMessage::SignalTasksFailed(handlers)) => {
+                // Handlers must hear of this even if the event processor was at fault.
+                if interconnect.events.is_disconnected() {
+                    interconnect.restart_volatile_internals();
+
+                    for data in handlers {
+                        let _ = interconnect.events.send(EventMessage::AddGlobalEvent(data));
+                    }
+                }
+
+                let _ = interconnect.mixer.send(MixerMessage::DropConn);
+
+                if let Some(conn) = connection.take() {
+                    conn.close().await;
+
+                    let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+                        CoreContext::DriverDisconnect(InternalDisconnect {
+                            kind: DisconnectKind::Runtime,
+                            reason: Some(DisconnectReason::TasksFailed),
+                            info: conn.info.clone(),
+                        }),
+                    ));
+                }
+            },
             Err(RecvError::Disconnected) | Ok(CoreMessage::Poison) => {
                 break;
             },
@@ -235,6 +466,7 @@ struct ConnectionRetryData {
     flavour: ConnectionFlavour,
     attempts: usize,
     last_wait: Option<Duration>,
+    started: Instant,
     info: ConnectionInfo,
     idx: usize,
 }
@@ -252,6 +484,15 @@ impl ConnectionRetryData {
         Self::base(ConnectionFlavour::Reconnect, info, idx_src)
     }
 
+    fn migrate(
+        tx: Sender<Result<(), ConnectionError>>,
+        info: ConnectionInfo,
+        previous_endpoint: String,
+        idx_src: &mut usize,
+    ) -> Self {
+        Self::base(ConnectionFlavour::Migrate(tx, previous_endpoint), info, idx_src)
+    }
+
     fn base(flavour: ConnectionFlavour, info: ConnectionInfo, idx_src: &mut usize) -> Self {
         *idx_src = idx_src.wrapping_add(1);
 
@@ -259,6 +500,7 @@ impl ConnectionRetryData {
             flavour,
             attempts: 0,
             last_wait: None,
+            started: Instant::now(),
             info,
             idx: *idx_src,
         }
@@ -281,6 +523,12 @@ impl ConnectionRetryData {
                             CoreContext::DriverConnect(InternalConnect {
                                 info: connection.info.clone(),
                                 ssrc: connection.ssrc,
+                                session: connection.session,
+                                gateway_version: connection.gateway_version,
+                                ip_family: connection.ip_family,
+                                vbr_mode: config.opus_vbr,
+                                max_bandwidth: config.opus_max_bandwidth,
+                                latency: *connection.latency.lock(),
                             }),
                         ));
                     },
@@ -289,6 +537,31 @@ impl ConnectionRetryData {
                             CoreContext::DriverReconnect(InternalConnect {
                                 info: connection.info.clone(),
                                 ssrc: connection.ssrc,
+                                session: connection.session,
+                                gateway_version: connection.gateway_version,
+                                ip_family: connection.ip_family,
+                                vbr_mode: config.opus_vbr,
+                                max_bandwidth: config.opus_max_bandwidth,
+                                latency: *connection.latency.lock(),
+                            }),
+                        ));
+                    },
+                    ConnectionFlavour::Migrate(tx, previous_endpoint) => {
+                        let _ = tx.send(Ok(()));
+
+                        let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+                            CoreContext::DriverMigrate(InternalMigrate {
+                                previous_endpoint,
+                                connect: InternalConnect {
+                                    info: connection.info.clone(),
+                                    ssrc: connection.ssrc,
+                                    session: connection.session,
+                                    gateway_version: connection.gateway_version,
+                                    ip_family: connection.ip_family,
+                                    vbr_mode: config.opus_vbr,
+                                    max_bandwidth: config.opus_max_bandwidth,
+                                    latency: *connection.latency.lock(),
+                                },
                             }),
                         ));
                     },
@@ -298,7 +571,22 @@ impl ConnectionRetryData {
             },
             Err(why) => {
                 debug!("Failed to connect for {:?}: {}", self.info.guild_id, why);
-                if let Some(t) = config.driver_retry.retry_in(self.last_wait, self.attempts) {
+                let retry_in = if why.should_retry() {
+                    let elapsed = self.started.elapsed();
+                    config
+                        .driver_retry
+                        .retry_in(self.last_wait, self.attempts)
+                        .filter(|wait| {
+                            config
+                                .reconnect_window
+                                .map(|window| elapsed + *wait <= window)
+                                .unwrap_or(true)
+                        })
+                } else {
+                    None
+                };
+
+                if let Some(t) = retry_in {
                     let remote_ic = interconnect.clone();
                     let idx = self.idx;
 
@@ -320,7 +608,19 @@ impl ConnectionRetryData {
 
                     *attempt_slot = Some(self);
                 } else {
-                    let reason = Some(DisconnectReason::from(&why));
+                    let reason = if why.should_retry()
+                        && config.driver_retry.attempts_remain(self.attempts)
+                    {
+                        // Attempts were left over, so the retry window must have elapsed.
+                        debug!(
+                            "Retry window exhausted for {:?} after {}s",
+                            self.info.guild_id,
+                            self.started.elapsed().as_secs_f32(),
+                        );
+                        Some(DisconnectReason::ReconnectExhausted)
+                    } else {
+                        Some(DisconnectReason::from(&why))
+                    };
 
                     match self.flavour {
                         ConnectionFlavour::Connect(tx) => {
@@ -344,6 +644,17 @@ impl ConnectionRetryData {
                                 }),
                             ));
                         },
+                        ConnectionFlavour::Migrate(tx, _) => {
+                            let _ = tx.send(Err(why));
+
+                            let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+                                CoreContext::DriverDisconnect(InternalDisconnect {
+                                    kind: DisconnectKind::Reconnect,
+                                    reason,
+                                    info: self.info,
+                                }),
+                            ));
+                        },
                     }
                 }
 
@@ -356,4 +667,171 @@ impl ConnectionRetryData {
 enum ConnectionFlavour {
     Connect(Sender<Result<(), ConnectionError>>),
     Reconnect,
+    Migrate(Sender<Result<(), ConnectionError>>, String),
+}
+
+/// Returns whether `new` moves the session described by `old` to another voice server.
+fn is_migration(old: &ConnectionInfo, new: &ConnectionInfo) -> bool {
+    old.guild_id == new.guild_id
+        && old.channel_id == new.channel_id
+        && old.session_id == new.session_id
+        && old.endpoint != new.endpoint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        driver::{Bandwidth, CryptoMode, GatewayVersion, IpFamily, VbrMode},
+        events::{context_data::SessionDescription, CoreEvent, EventContext},
+    };
+
+    #[test]
+    fn endpoint_changes_are_migrations() {
+        let old = ConnectionInfo {
+            channel_id: Some(2.into()),
+            endpoint: "old.discord.media".into(),
+            guild_id: 1.into(),
+            session_id: "session".into(),
+            token: "token".into(),
+            user_id: 3.into(),
+        };
+
+        let moved = ConnectionInfo {
+            endpoint: "new.discord.media".into(),
+            token: "new-token".into(),
+            ..old.clone()
+        };
+        assert!(is_migration(&old, &moved));
+
+        // Joining another channel, or a new session, is a fresh connection.
+        let other_channel = ConnectionInfo {
+            channel_id: Some(4.into()),
+            ..moved.clone()
+        };
+        assert!(!is_migration(&old, &other_channel));
+        assert!(!is_migration(&old, &old));
+
+        let ctx = CoreContext::DriverMigrate(InternalMigrate {
+            previous_endpoint: old.endpoint.clone(),
+            connect: InternalConnect {
+                info: moved,
+                ssrc: 5,
+                session: SessionDescription::new(CryptoMode::Normal, &[7; 32]),
+                gateway_version: GatewayVersion::V8,
+                ip_family: IpFamily::V4,
+                vbr_mode: VbrMode::ConstrainedVbr,
+                max_bandwidth: Bandwidth::Full,
+                latency: Duration::from_millis(20),
+            },
+        });
+        let user_ctx = ctx.to_user_context();
+        assert_eq!(user_ctx.to_core_event(), Some(CoreEvent::DriverMigrate));
+        match user_ctx {
+            EventContext::DriverMigrate(data) => {
+                assert_eq!(data.previous_server, "old.discord.media");
+                assert_eq!(data.connection.server, "new.discord.media");
+                assert_eq!(data.connection.session.mode, CryptoMode::Normal);
+                assert_ne!(
+                    data.connection.session.key_fingerprint,
+                    SessionDescription::new(CryptoMode::Normal, &[8; 32]).key_fingerprint
+                );
+            },
+            _ => panic!("Expected a migration event."),
+        }
+    }
+
+    #[tokio::test]
+    async fn dead_driver_is_recreated() {
+        use crate::{driver::Driver, events::Event, EventHandler};
+        use async_trait::async_trait;
+
+        struct Recreated(Sender<()>);
+
+        #[async_trait]
+        impl EventHandler for Recreated {
+            async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+                if let EventContext::DriverRecreated = ctx {
+                    let _ = self.0.send(());
+                }
+
+                None
+            }
+        }
+
+        let mut driver = Driver::new(Config::default());
+        let (tx, rx) = flume::unbounded();
+        let handle = driver.add_global_event(CoreEvent::DriverRecreated.into(), Recreated(tx));
+        assert_eq!(driver.check_tasks().await, Some(true));
+        assert!(!driver.restart_tasks());
+
+        driver.core_sender().send(CoreMessage::Poison).unwrap();
+        // Messages queued before the core exits are never answered.
+        for _ in 0..100 {
+            if driver.core_sender().is_disconnected() {
+                break;
+            }
+            tsleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(driver.check_tasks().await, None);
+
+        assert!(driver.restart_tasks());
+        assert_eq!(driver.check_tasks().await, Some(true));
+
+        // Global handlers are carried over, and told of the recreation.
+        tokio::time::timeout(Duration::from_secs(5), rx.recv_async())
+            .await
+            .expect("Driver recreated event fired.")
+            .unwrap();
+        let events = driver.list_global_events().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].handle, handle);
+    }
+    #[tokio::test]
+    async fn broadcast_is_resubscribed_after_restart() {
+        use crate::{driver::Driver, input::Input, Broadcast};
+
+        let mut driver = Driver::new(Config::default());
+        let broadcast = Broadcast::new(Input::float_pcm(true, Vec::<u8>::new().into()));
+        broadcast.add_driver(&mut driver);
+        driver.set_broadcast_volume(0.5);
+
+        let (tx, rx) = flume::unbounded();
+        driver.sender = tx;
+        driver.restart_tasks();
+
+        let mut volume = None;
+        let mut subscribed = false;
+        for msg in rx.drain() {
+            match msg {
+                CoreMessage::SetBroadcastVolume(v) => volume = Some(v),
+                CoreMessage::SetBroadcast(subscriber) => subscribed = subscriber.is_some(),
+                _ => {},
+            }
+        }
+        assert_eq!(volume, Some(0.5));
+        assert!(subscribed);
+
+        // Leaving the broadcast is remembered too.
+        broadcast.remove_driver(&mut driver);
+        assert!(driver.broadcast().is_none());
+    }
+
+    #[tokio::test]
+    async fn speaking_flags_are_resent_after_restart() {
+        use crate::{driver::Driver, model::SpeakingState};
+
+        let mut driver = Driver::new(Config::default());
+        driver.set_speaking_flags(SpeakingState::PRIORITY);
+
+        let (tx, rx) = flume::unbounded();
+        driver.sender = tx;
+        driver.restart_tasks();
+
+        let flags = rx.drain().find_map(|msg| match msg {
+            CoreMessage::SetSpeakingFlags(flags) => Some(flags),
+            _ => None,
+        });
+        assert_eq!(flags, Some(SpeakingState::PRIORITY));
+    }
 }
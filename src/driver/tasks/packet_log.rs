@@ -0,0 +1,128 @@
+//! Summaries of each voice packet sent and received, logged at `TRACE` level.
+//!
+//! These record RTP headers, sizes, and decryption outcomes, but never audio or
+//! key material, so that logs can be shared to reproduce intermittent packet issues.
+//! Each direction logs at most 100 packets per second, with a count of any skipped
+//! packets logged once each second ends.
+//!
+//! Logging is only compiled in with the `packet-log` feature: otherwise, [`PacketLog`]
+//! is empty and every call is a no-op.
+
+#[cfg(feature = "packet-log")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "packet-log")]
+use tracing::trace;
+
+/// Most packets logged per second, in each direction.
+#[cfg(feature = "packet-log")]
+const PACKETS_PER_SECOND: u32 = 100;
+
+/// Whether a received packet was decrypted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Decrypt {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+/// Rate-limited log of the packets passing through one driver task.
+#[derive(Debug, Default)]
+pub(crate) struct PacketLog {
+    #[cfg(feature = "packet-log")]
+    limit: RateLimit,
+}
+
+impl PacketLog {
+    #[inline(always)]
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Logs an encrypted RTP packet, as handed to the UDP socket.
+    #[inline(always)]
+    #[allow(unused_variables)]
+    pub(crate) fn sent(&mut self, ssrc: u32, sequence: u16, timestamp: u32, len: usize) {
+        #[cfg(feature = "packet-log")]
+        if self.limit.allow("sent") {
+            trace!(ssrc, sequence, timestamp, len, "Sent RTP packet.");
+        }
+    }
+
+    /// Logs a received RTP packet, once decryption has been attempted.
+    #[inline(always)]
+    #[allow(unused_variables)]
+    pub(crate) fn received(
+        &mut self,
+        ssrc: u32,
+        sequence: u16,
+        timestamp: u32,
+        len: usize,
+        decrypt: Decrypt,
+    ) {
+        #[cfg(feature = "packet-log")]
+        if self.limit.allow("received") {
+            trace!(ssrc, sequence, timestamp, len, ?decrypt, "Received RTP packet.");
+        }
+    }
+}
+
+#[cfg(feature = "packet-log")]
+#[derive(Debug)]
+struct RateLimit {
+    window_start: Instant,
+    logged: u32,
+    skipped: u64,
+}
+
+#[cfg(feature = "packet-log")]
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            window_start: Instant::now(),
+            logged: 0,
+            skipped: 0,
+        }
+    }
+}
+
+#[cfg(feature = "packet-log")]
+impl RateLimit {
+    /// Returns whether another packet may be logged in the current second.
+    fn allow(&mut self, direction: &'static str) -> bool {
+        if self.window_start.elapsed() >= Duration::from_secs(1) {
+            if self.skipped > 0 {
+                trace!(direction, skipped = self.skipped, "Skipped logging packets.");
+            }
+
+            *self = Self::default();
+        }
+
+        if self.logged < PACKETS_PER_SECOND {
+            self.logged += 1;
+            true
+        } else {
+            self.skipped += 1;
+            false
+        }
+    }
+}
+
+#[cfg(all(test, feature = "packet-log"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit_resets_each_second() {
+        let mut limit = RateLimit::default();
+
+        let allowed = (0..2 * PACKETS_PER_SECOND)
+            .filter(|_| limit.allow("sent"))
+            .count();
+        assert_eq!(allowed, PACKETS_PER_SECOND as usize);
+        assert_eq!(limit.skipped, u64::from(PACKETS_PER_SECOND));
+
+        limit.window_start -= Duration::from_secs(1);
+        assert!(limit.allow("sent"));
+        assert_eq!(limit.skipped, 0);
+    }
+}
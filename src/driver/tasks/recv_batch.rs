@@ -0,0 +1,312 @@
+//! Batched reads from the voice UDP socket.
+
+use crate::constants::VOICE_PACKET_MAX;
+use std::io::{ErrorKind as IoErrorKind, Result as IoResult};
+use tokio::net::UdpSocket;
+
+/// One byte longer than any legal packet, so that oversized datagrams
+/// can be told apart from those which fit exactly.
+pub type PacketBuffer = [u8; VOICE_PACKET_MAX + 1];
+
+/// Reads up to a fixed number of datagrams from a UDP socket per wakeup.
+///
+/// On Linux, each batch is read using a single `recvmmsg(2)` call, and the number of
+/// datagrams dropped by the OS for lack of buffer space is tracked via `SO_RXQ_OVFL`.
+/// Other platforms read the first datagram as usual, and then drain any others
+/// which are already queued without waiting, up to the batch size.
+pub struct RecvBatch {
+    buffers: Vec<PacketBuffer>,
+    lens: Vec<usize>,
+    #[cfg(target_os = "linux")]
+    controls: Vec<[u64; 8]>,
+    #[cfg(target_os = "linux")]
+    headers: sys::Headers,
+    socket_dropped: Option<u64>,
+}
+
+impl RecvBatch {
+    /// Prepares to read up to `size` datagrams at once from `socket`.
+    ///
+    /// A `size` of `0` is treated as `1`.
+    pub fn new(socket: &UdpSocket, size: usize) -> Self {
+        let size = size.max(1);
+
+        let mut buffers = vec![[0u8; VOICE_PACKET_MAX + 1]; size];
+        #[cfg(target_os = "linux")]
+        let mut controls = vec![[0u64; 8]; size];
+        #[cfg(target_os = "linux")]
+        let headers = sys::Headers::new(&mut buffers, &mut controls);
+
+        Self {
+            buffers,
+            lens: vec![0; size],
+            #[cfg(target_os = "linux")]
+            controls,
+            #[cfg(target_os = "linux")]
+            headers,
+            socket_dropped: sys::enable_drop_count(socket),
+        }
+    }
+
+    /// Waits for at least one datagram, returning how many were read.
+    pub async fn recv(&mut self, socket: &UdpSocket) -> IoResult<usize> {
+        #[cfg(target_os = "linux")]
+        loop {
+            socket.readable().await?;
+
+            match socket.try_io(tokio::io::Interest::READABLE, || self.recv_mmsg(socket)) {
+                Err(e) if e.kind() == IoErrorKind::WouldBlock => continue,
+                res => return res,
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            self.lens[0] = socket.recv_from(&mut self.buffers[0][..]).await?.0;
+
+            let mut count = 1;
+            while count < self.buffers.len() {
+                match socket.try_recv_from(&mut self.buffers[count][..]) {
+                    Ok((len, _addr)) => {
+                        self.lens[count] = len;
+                        count += 1;
+                    },
+                    Err(e) if e.kind() == IoErrorKind::WouldBlock => break,
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok(count)
+        }
+    }
+
+    /// Returns the `i`th datagram from the last batch read, and its length.
+    ///
+    /// Each buffer is one byte longer than [`VOICE_PACKET_MAX`], so that a length
+    /// above this marks a datagram which was too large to be legal.
+    ///
+    /// [`VOICE_PACKET_MAX`]: crate::constants::VOICE_PACKET_MAX
+    pub fn packet(&self, i: usize) -> (&PacketBuffer, usize) {
+        (&self.buffers[i], self.lens[i])
+    }
+
+    /// Returns the number of datagrams dropped by the OS since the socket was
+    /// created, because its receive buffer was full.
+    ///
+    /// This is `None` on platforms which do not report this.
+    pub fn socket_dropped(&self) -> Option<u64> {
+        self.socket_dropped
+    }
+
+    #[cfg(target_os = "linux")]
+    fn recv_mmsg(&mut self, socket: &UdpSocket) -> IoResult<usize> {
+        use std::{io::Error as IoError, mem, os::unix::io::AsRawFd, ptr};
+
+        let msgs = &mut self.headers.msgs;
+        for (msg, control) in msgs.iter_mut().zip(self.controls.iter()) {
+            // The kernel shrinks this to the space used by the previous read.
+            msg.msg_hdr.msg_controllen = mem::size_of_val(control) as _;
+        }
+
+        // SAFETY: each header points to one live iovec and control buffer of the
+        // stated sizes, all of which are owned by `self`.
+        let count = unsafe {
+            libc::recvmmsg(
+                socket.as_raw_fd(),
+                msgs.as_mut_ptr(),
+                msgs.len() as libc::c_uint,
+                libc::MSG_DONTWAIT,
+                ptr::null_mut(),
+            )
+        };
+
+        if count < 0 {
+            return Err(IoError::last_os_error());
+        }
+
+        let count = count as usize;
+        for (i, msg) in msgs[..count].iter().enumerate() {
+            self.lens[i] = if msg.msg_hdr.msg_flags & libc::MSG_TRUNC != 0 {
+                // Report the datagram as oversized, so that it is dropped.
+                self.buffers[i].len()
+            } else {
+                msg.msg_len as usize
+            };
+
+            // SAFETY: the kernel has filled in this header's control buffer.
+            if let Some(dropped) = unsafe { sys::drop_count(&msg.msg_hdr) } {
+                self.socket_dropped = Some(dropped);
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use super::PacketBuffer;
+    use std::{mem, os::unix::io::AsRawFd, ptr};
+    use tokio::net::UdpSocket;
+    use tracing::debug;
+
+    /// `recvmmsg(2)` headers for each buffer of a [`RecvBatch`], built once and
+    /// reused by every read.
+    ///
+    /// [`RecvBatch`]: super::RecvBatch
+    pub(super) struct Headers {
+        /// Each header points into this, so it must not be resized.
+        _iovecs: Vec<libc::iovec>,
+        pub(super) msgs: Vec<libc::mmsghdr>,
+    }
+
+    // SAFETY: these pointers only refer to heap buffers owned by the same
+    // `RecvBatch`, which are only read or written through `&mut RecvBatch`.
+    unsafe impl Send for Headers {}
+
+    impl Headers {
+        pub(super) fn new(buffers: &mut [PacketBuffer], controls: &mut [[u64; 8]]) -> Self {
+            let mut iovecs: Vec<libc::iovec> = buffers
+                .iter_mut()
+                .map(|buf| libc::iovec {
+                    iov_base: buf.as_mut_ptr().cast(),
+                    iov_len: buf.len(),
+                })
+                .collect();
+
+            let msgs = iovecs
+                .iter_mut()
+                .zip(controls.iter_mut())
+                .map(|(iov, control)| {
+                    // SAFETY: `mmsghdr` is a plain C struct, for which all-zeroes is valid.
+                    let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+                    msg.msg_hdr.msg_iov = iov;
+                    msg.msg_hdr.msg_iovlen = 1;
+                    msg.msg_hdr.msg_control = control.as_mut_ptr().cast();
+                    msg.msg_hdr.msg_controllen = mem::size_of_val(control) as _;
+                    msg
+                })
+                .collect();
+
+            Self {
+                _iovecs: iovecs,
+                msgs,
+            }
+        }
+    }
+
+    /// Asks the OS to report how many datagrams `socket` has dropped, returning the
+    /// starting count if supported.
+    pub(super) fn enable_drop_count(socket: &UdpSocket) -> Option<u64> {
+        let on: libc::c_int = 1;
+
+        // SAFETY: `on` is a valid `c_int` for the duration of the call.
+        let res = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RXQ_OVFL,
+                ptr::addr_of!(on).cast(),
+                mem::size_of_val(&on) as libc::socklen_t,
+            )
+        };
+
+        if res == 0 {
+            Some(0)
+        } else {
+            debug!("UDP socket cannot report dropped packets.");
+            None
+        }
+    }
+
+    /// Reads the drop count attached to a received datagram, if present.
+    ///
+    /// The OS only attaches this once the socket has dropped a datagram.
+    ///
+    /// # Safety
+    /// `hdr` must describe a control buffer filled in by the kernel.
+    pub(super) unsafe fn drop_count(hdr: &libc::msghdr) -> Option<u64> {
+        let mut cmsg = libc::CMSG_FIRSTHDR(hdr);
+
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SO_RXQ_OVFL {
+                let count = ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<u32>());
+                return Some(u64::from(count));
+            }
+
+            cmsg = libc::CMSG_NXTHDR(hdr, cmsg);
+        }
+
+        None
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use tokio::net::UdpSocket;
+
+    pub(super) fn enable_drop_count(_socket: &UdpSocket) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn batches_queued_datagrams() {
+        let rx = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let tx = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        tx.connect(rx.local_addr().unwrap()).await.unwrap();
+
+        let mut batch = RecvBatch::new(&rx, 4);
+        for i in 0..6u8 {
+            tx.send(&[i; 3]).await.unwrap();
+        }
+        tx.send(&[0u8; VOICE_PACKET_MAX + 100]).await.unwrap();
+
+        let mut seen = vec![];
+        while seen.len() < 7 {
+            let count = batch.recv(&rx).await.unwrap();
+            assert!((1..=4).contains(&count));
+            for i in 0..count {
+                let (buf, len) = batch.packet(i);
+                seen.push((buf[0], len));
+            }
+        }
+
+        let expected: Vec<_> = (0..6u8).map(|i| (i, 3)).collect();
+        assert_eq!(seen[..6], expected[..]);
+        // Oversized datagrams are reported as such, rather than silently cut short.
+        assert!(seen[6].1 > VOICE_PACKET_MAX);
+
+        #[cfg(target_os = "linux")]
+        assert_eq!(batch.socket_dropped(), Some(0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn counts_datagrams_dropped_by_os() {
+        use socket2::SockRef;
+
+        let rx = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        SockRef::from(&rx).set_recv_buffer_size(4096).unwrap();
+        let tx = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        tx.connect(rx.local_addr().unwrap()).await.unwrap();
+
+        let mut batch = RecvBatch::new(&rx, 32);
+
+        // Overflow the receive buffer before reading anything.
+        for _ in 0..256 {
+            tx.send(&[0u8; 200]).await.unwrap();
+        }
+        let count = batch.recv(&rx).await.unwrap();
+        assert!(count > 1);
+
+        // Drop counts arrive with the first datagram queued after a loss.
+        tx.send(&[1u8; 200]).await.unwrap();
+        while batch.recv(&rx).await.unwrap() == 32 {}
+        assert!(batch.socket_dropped().unwrap() > 0);
+    }
+}
@@ -1,12 +1,20 @@
 use super::{
     error::{Error, Result},
     message::*,
+    packet_log::{Decrypt, PacketLog},
+    recv_batch::RecvBatch,
     Config,
 };
 use crate::{
     constants::*,
-    driver::DecodeMode,
-    events::{internal_data::*, CoreContext},
+    driver::{DecodeMode, VoiceTickMode},
+    events::{
+        context_data::{DecodedAudioCapData, UserActivityData},
+        internal_data::*,
+        CoreContext,
+    },
+    id::UserId,
+    input::utils,
 };
 use audiopus::{
     coder::Decoder as OpusDecoder,
@@ -16,15 +24,26 @@ use audiopus::{
 };
 use discortp::{
     demux::{self, DemuxedMut},
+    discord::{IpDiscoveryPacket, IpDiscoveryType, MutableIpDiscoveryPacket},
     rtp::{RtpExtensionPacket, RtpPacket},
     FromPacket,
     Packet,
     PacketSize,
 };
 use flume::Receiver;
-use std::{collections::HashMap, convert::TryInto, sync::Arc};
-use tokio::{net::UdpSocket, select};
-use tracing::{error, instrument, trace, warn};
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::TryInto,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    net::UdpSocket,
+    select,
+    time::{self, Interval, MissedTickBehavior},
+};
+use tracing::{debug, error, instrument, trace, warn};
 use xsalsa20poly1305::XSalsa20Poly1305 as Cipher;
 
 #[derive(Debug)]
@@ -33,6 +52,128 @@ struct SsrcState {
     decoder: OpusDecoder,
     last_seq: u16,
     decode_size: PacketDecodeSize,
+    last_activity: Instant,
+    inactive: bool,
+    replay: ReplayWindow,
+    history: AudioHistory,
+    /// Decoded audio awaiting [`CoreEvent::MixedAudio`].
+    ///
+    /// [`CoreEvent::MixedAudio`]: crate::events::CoreEvent::MixedAudio
+    mix_queue: VecDeque<i16>,
+    /// RTP timestamp of the first sample in `mix_queue`, set by this stream's
+    /// first decoded packet and advanced on every tick.
+    mix_clock: Option<u32>,
+    /// Set when receiving is paused, after which this stream restarts from its
+    /// next packet (keeping only its history).
+    stale: bool,
+}
+
+impl SsrcState {
+    /// Size of all decoded audio held for this stream, in bytes.
+    fn buffered_bytes(&self) -> usize {
+        (self.history.samples.len() + self.mix_queue.len()) * std::mem::size_of::<i16>()
+    }
+
+    /// Places decoded stereo audio in `mix_queue` according to its RTP timestamp.
+    ///
+    /// Lost packets leave silence in their place, and reordered packets fill the gaps
+    /// they left. Audio whose tick has already been mixed is dropped, unless it lies
+    /// so far from the stream's clock (in either direction) that it would not fit
+    /// within `limit` samples: the stream has then jumped, and its clock restarts
+    /// from this packet.
+    fn queue_mixed(&mut self, timestamp: u32, audio: &[i16], limit: usize) {
+        let clock = *self.mix_clock.get_or_insert(timestamp);
+        let offset = timestamp.wrapping_sub(clock) as i32;
+        let mut start = 2 * offset.unsigned_abs() as usize;
+
+        if start + audio.len() > limit {
+            self.mix_queue.clear();
+            self.mix_clock = Some(timestamp);
+            start = 0;
+        } else if offset < 0 {
+            return;
+        }
+
+        if self.mix_queue.len() < start {
+            self.mix_queue.resize(start, 0);
+        }
+
+        for (i, sample) in audio.iter().enumerate() {
+            match self.mix_queue.get_mut(start + i) {
+                Some(slot) => *slot = *sample,
+                None => self.mix_queue.push_back(*sample),
+            }
+        }
+    }
+}
+
+/// Decoded stereo audio most recently received from one voice stream.
+#[derive(Debug, Default)]
+struct AudioHistory {
+    samples: VecDeque<i16>,
+}
+
+impl AudioHistory {
+    /// Appends `audio`, forgetting the oldest samples beyond `limit`.
+    fn push(&mut self, audio: &[i16], limit: usize) {
+        self.samples.extend(audio);
+
+        let excess = self.samples.len().saturating_sub(limit);
+        self.samples.drain(..excess);
+    }
+
+    /// Returns up to `count` of the most recently received samples.
+    fn latest(&self, count: usize) -> Vec<i16> {
+        let start = self.samples.len().saturating_sub(count);
+
+        self.samples.range(start..).copied().collect()
+    }
+}
+
+/// Sliding window of recently received RTP sequence numbers, following
+/// the replay protection scheme of RFC 3711.
+#[derive(Debug)]
+struct ReplayWindow {
+    highest: u16,
+    /// Bit `i` is set if packet `highest - i` has been received.
+    seen: u128,
+}
+
+impl ReplayWindow {
+    /// Largest window which can be tracked.
+    const MAX_LEN: u16 = 128;
+
+    fn new(first_seq: u16) -> Self {
+        Self {
+            highest: first_seq,
+            seen: 1,
+        }
+    }
+
+    /// Records the arrival of a packet, returning `false` if it is a duplicate
+    /// or falls outside of the window.
+    fn accept(&mut self, seq: u16, len: u16) -> bool {
+        let len = len.min(Self::MAX_LEN);
+        let ahead = seq.wrapping_sub(self.highest);
+
+        if ahead != 0 && ahead < (1 << 15) {
+            self.seen = self.seen.checked_shl(ahead.into()).unwrap_or(0) | 1;
+            self.highest = seq;
+
+            return true;
+        }
+
+        let age = self.highest.wrapping_sub(seq);
+        if age >= len {
+            return false;
+        }
+
+        let bit = 1 << age;
+        let fresh = self.seen & bit == 0;
+        self.seen |= bit;
+
+        fresh
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -93,6 +234,40 @@ impl SsrcState {
                 .expect("Failed to create new Opus decoder for source."),
             last_seq: pkt.get_sequence().into(),
             decode_size: PacketDecodeSize::TwentyMillis,
+            last_activity: Instant::now(),
+            inactive: false,
+            replay: ReplayWindow::new(pkt.get_sequence().into()),
+            history: Default::default(),
+            mix_queue: Default::default(),
+            mix_clock: None,
+            stale: false,
+        }
+    }
+
+    /// Restarts decoding this stream from `pkt`, keeping only its history for
+    /// [`Driver::replay_user`].
+    ///
+    /// [`Driver::replay_user`]: crate::driver::Driver::replay_user
+    fn restart(&mut self, pkt: RtpPacket<'_>) {
+        let history = std::mem::take(&mut self.history);
+
+        *self = Self {
+            history,
+            ..Self::new(pkt)
+        };
+    }
+
+    /// Records that a packet has arrived, returning how long this stream had been
+    /// idle if it was previously reported as inactive.
+    fn mark_active(&mut self) -> Option<Duration> {
+        let idle = self.last_activity.elapsed();
+        self.last_activity = Instant::now();
+
+        if self.inactive {
+            self.inactive = false;
+            Some(idle)
+        } else {
+            None
         }
     }
 
@@ -106,6 +281,10 @@ impl SsrcState {
     ) -> Result<(SpeakingDelta, Option<Vec<i16>>)> {
         let new_seq: u16 = pkt.get_sequence().into();
         let payload_len = pkt.payload().len();
+        let body_end = payload_len
+            .checked_sub(data_trailer)
+            .filter(|end| *end >= data_offset)
+            .ok_or(Error::IllegalVoicePacket)?;
 
         let extensions = pkt.get_extension() != 0;
         let seq_delta = new_seq.wrapping_sub(self.last_seq);
@@ -121,7 +300,7 @@ impl SsrcState {
             // user.
             let (audio, pkt_size) = if decode_mode.should_decrypt() && decrypted {
                 self.scan_and_decode(
-                    &pkt.payload()[data_offset..payload_len - data_trailer],
+                    &pkt.payload()[data_offset..body_end],
                     extensions,
                     missed_packets,
                     decode_mode == DecodeMode::Decode,
@@ -130,7 +309,7 @@ impl SsrcState {
                 // The latter part is an upper bound, as we cannot determine
                 // how long packet extensions are.
                 // WIthout decryption, speaking detection is thus broken.
-                (None, payload_len - data_offset - data_trailer)
+                (None, body_end - data_offset)
             };
 
             let delta = if pkt_size == SILENT_FRAME.len() {
@@ -169,8 +348,9 @@ impl SsrcState {
         let start = if extension {
             RtpExtensionPacket::new(data)
                 .map(|pkt| pkt.packet_size())
+                .filter(|size| *size <= data.len())
                 .ok_or_else(|| {
-                    error!("Extension packet indicated, but insufficient space.");
+                    warn!("Extension packet indicated, but insufficient space.");
                     Error::IllegalVoicePacket
                 })
         } else {
@@ -239,21 +419,101 @@ impl SsrcState {
 struct UdpRx {
     cipher: Cipher,
     decoder_map: HashMap<u32, SsrcState>,
-    #[allow(dead_code)]
     config: Config,
-    packet_buffer: [u8; VOICE_PACKET_MAX],
+    /// One byte longer than any legal packet, so that oversized datagrams
+    /// can be told apart from those which fit exactly.
+    packet_buffer: [u8; VOICE_PACKET_MAX + 1],
+    packet_log: PacketLog,
+    receiving: bool,
+    replay_dropped: u64,
+    malformed_dropped: u64,
+    /// Bytes of decoded audio dropped to respect [`Config::max_decoded_audio`].
+    capped_dropped: u64,
+    /// Bytes dropped by the cap since it was last reported.
+    capped_unreported: usize,
+    /// When dropped audio was last reported.
+    last_cap_report: Option<Instant>,
+    /// Size of all decoded audio held across every stream, in bytes.
+    buffered: usize,
     rx: Receiver<UdpRxMessage>,
+    ssrc_users: HashMap<u32, UserId>,
+    tick_buffer: Vec<InternalVoicePacket>,
 
+    batch: RecvBatch,
     udp_socket: Arc<UdpSocket>,
+
+    ssrc: u32,
+    /// Latest round-trip time to the voice server, shared with the driver.
+    latency: Arc<Mutex<Duration>>,
+    /// When the unanswered IP discovery request used to measure latency was sent.
+    probe_sent: Option<Instant>,
 }
 
 impl UdpRx {
+    fn new(
+        cipher: Cipher,
+        config: Config,
+        rx: Receiver<UdpRxMessage>,
+        udp_socket: Arc<UdpSocket>,
+        ssrc: u32,
+        latency: Arc<Mutex<Duration>>,
+    ) -> Self {
+        let batch = RecvBatch::new(&udp_socket, config.udp_socket_options.recv_batch);
+
+        Self {
+            cipher,
+            decoder_map: Default::default(),
+            config,
+            packet_buffer: [0u8; VOICE_PACKET_MAX + 1],
+            packet_log: PacketLog::new(),
+            receiving: true,
+            replay_dropped: 0,
+            malformed_dropped: 0,
+            capped_dropped: 0,
+            capped_unreported: 0,
+            last_cap_report: None,
+            buffered: 0,
+            rx,
+            ssrc_users: Default::default(),
+            tick_buffer: vec![],
+            batch,
+            udp_socket,
+            ssrc,
+            latency,
+            probe_sent: None,
+        }
+    }
+
     #[instrument(skip(self))]
     async fn run(&mut self, interconnect: &mut Interconnect) {
+        let mut ticker = time::interval(TIMESTEP_LENGTH);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut activity_ticker = time::interval(USER_ACTIVITY_CHECK_INTERVAL);
+        activity_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut probe_ticker = latency_ticker(&self.config);
+
         loop {
             select! {
-                Ok((len, _addr)) = self.udp_socket.recv_from(&mut self.packet_buffer[..]) => {
-                    self.process_udp_message(interconnect, len);
+                Ok(count) = self.batch.recv(&self.udp_socket) => {
+                    for i in 0..count {
+                        let (packet, len) = self.batch.packet(i);
+                        self.packet_buffer[..len].copy_from_slice(&packet[..len]);
+                        self.process_udp_message(interconnect, len);
+                    }
+                }
+                _ = ticker.tick(), if self.receiving && self.ticks_enabled() => {
+                    if self.config.voice_tick.is_enabled() {
+                        self.fire_voice_tick(interconnect);
+                    }
+                    if self.config.mixed_audio {
+                        self.fire_mixed_audio(interconnect);
+                    }
+                }
+                _ = activity_ticker.tick(), if self.config.user_inactivity.is_some() => {
+                    self.check_inactivity(interconnect);
+                }
+                _ = probe_ticker.tick(), if self.config.latency_interval.is_some() => {
+                    self.send_latency_probe().await;
                 }
                 msg = self.rx.recv_async() => {
                     use UdpRxMessage::*;
@@ -262,7 +522,62 @@ impl UdpRx {
                             *interconnect = i;
                         },
                         Ok(SetConfig(c)) => {
-                            self.config = c;
+                            if !c.voice_tick.is_enabled() {
+                                self.tick_buffer.clear();
+                            }
+                            if !c.mixed_audio {
+                                for state in self.decoder_map.values_mut() {
+                                    state.mix_queue.clear();
+                                    state.mix_clock = None;
+                                }
+                            }
+                            if c.latency_interval != self.config.latency_interval {
+                                probe_ticker = latency_ticker(&c);
+                            }
+                            self.config = *c;
+                            self.prune_filtered();
+                            self.recount_buffered();
+                        },
+                        Ok(SetCipher(cipher)) => {
+                            self.cipher = cipher;
+                        },
+                        Ok(SetReceiving(receiving)) => {
+                            self.set_receiving(receiving);
+                        },
+                        Ok(SetSsrcUser(ssrc, user)) => {
+                            self.ssrc_users.insert(ssrc, user);
+                            self.prune_filtered();
+                            self.recount_buffered();
+                        },
+                        Ok(RemoveUser(user)) => {
+                            self.remove_user(user);
+                            self.recount_buffered();
+                        },
+                        Ok(GetUserActivity(tx)) => {
+                            let _ = tx.send(
+                                self.decoder_map
+                                    .iter()
+                                    .map(|(ssrc, state)| (*ssrc, state.last_activity))
+                                    .collect(),
+                            );
+                        },
+                        Ok(GetReplayDropped(tx)) => {
+                            let _ = tx.send(self.replay_dropped);
+                        },
+                        Ok(GetMalformedDropped(tx)) => {
+                            let _ = tx.send(self.malformed_dropped);
+                        },
+                        Ok(GetSocketDropped(tx)) => {
+                            let _ = tx.send(self.batch.socket_dropped());
+                        },
+                        Ok(GetUserHistory(user, duration, tx)) => {
+                            let _ = tx.send(self.user_history(user, duration));
+                        },
+                        #[cfg(feature = "internals")]
+                        Ok(Inject(packet)) => {
+                            let len = packet.len().min(self.packet_buffer.len());
+                            self.packet_buffer[..len].copy_from_slice(&packet[..len]);
+                            self.process_udp_message(interconnect, len);
                         },
                         Ok(Poison) | Err(_) => break,
                     }
@@ -271,6 +586,254 @@ impl UdpRx {
         }
     }
 
+    /// Pauses or resumes handling received packets.
+    ///
+    /// Decoder state would be stale by the time audio resumes, so each stream
+    /// restarts from its next packet. Received history is kept for replay.
+    fn set_receiving(&mut self, receiving: bool) {
+        if !receiving {
+            for state in self.decoder_map.values_mut() {
+                state.mix_queue.clear();
+                state.mix_clock = None;
+                state.stale = true;
+            }
+            self.tick_buffer.clear();
+            self.recount_buffered();
+        }
+
+        self.receiving = receiving;
+    }
+
+    /// Recomputes the size of all buffered audio, after streams have been removed
+    /// or cleared wholesale.
+    fn recount_buffered(&mut self) {
+        self.buffered = self.decoder_map.values().map(SsrcState::buffered_bytes).sum();
+    }
+
+    /// Drops decoder state held for any SSRCs which are now filtered out.
+    fn prune_filtered(&mut self) {
+        let filter = &self.config.receive_filter;
+        let users = &self.ssrc_users;
+
+        self.decoder_map
+            .retain(|ssrc, _| filter.allows(*ssrc, users.get(ssrc).copied()));
+        self.tick_buffer
+            .retain(|pkt| filter.allows(pkt.packet.ssrc, users.get(&pkt.packet.ssrc).copied()));
+    }
+
+    /// Returns up to `duration` of the latest audio received from `user`.
+    ///
+    /// Should the user be known by several SSRCs, the stream holding the
+    /// most audio is chosen.
+    fn user_history(&self, user: UserId, duration: Duration) -> Vec<i16> {
+        let count = utils::timestamp_to_sample_count(duration, true);
+
+        self.ssrc_users
+            .iter()
+            .filter(|(_, ssrc_user)| **ssrc_user == user)
+            .filter_map(|(ssrc, _)| self.decoder_map.get(ssrc))
+            .map(|state| state.history.latest(count))
+            .max_by_key(Vec::len)
+            .unwrap_or_default()
+    }
+
+    /// Forgets all state held for a user who has left the call.
+    fn remove_user(&mut self, user: UserId) {
+        let users = &mut self.ssrc_users;
+        let decoders = &mut self.decoder_map;
+
+        users.retain(|ssrc, ssrc_user| {
+            let keep = *ssrc_user != user;
+            if !keep {
+                decoders.remove(ssrc);
+            }
+            keep
+        });
+    }
+
+    /// Fires [`CoreEvent::UserInactive`] for any streams which have passed
+    /// the configured inactivity threshold.
+    ///
+    /// [`CoreEvent::UserInactive`]: crate::events::CoreEvent::UserInactive
+    fn check_inactivity(&mut self, interconnect: &Interconnect) {
+        let threshold = match self.config.user_inactivity {
+            Some(threshold) => threshold,
+            None => return,
+        };
+
+        for (ssrc, state) in self.decoder_map.iter_mut() {
+            let idle = state.last_activity.elapsed();
+            if state.inactive || idle < threshold {
+                continue;
+            }
+
+            state.inactive = true;
+            let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+                CoreContext::UserInactive(UserActivityData {
+                    ssrc: *ssrc,
+                    user_id: self.ssrc_users.get(ssrc).copied(),
+                    idle,
+                }),
+            ));
+        }
+    }
+
+    /// Drops the buffered audio of the least recently active streams until all
+    /// decoded audio fits within [`Config::max_decoded_audio`].
+    ///
+    /// Only buffers are cleared, so each stream's decoder remains in sync with
+    /// its packet sequence. Drops are reported (by a warning and
+    /// [`CoreEvent::DecodedAudioCapped`]) at most once per
+    /// [`DECODED_AUDIO_CAP_REPORT_INTERVAL`].
+    ///
+    /// [`CoreEvent::DecodedAudioCapped`]: crate::events::CoreEvent::DecodedAudioCapped
+    fn enforce_audio_cap(&mut self, interconnect: &Interconnect) {
+        let limit = match self.config.max_decoded_audio {
+            Some(limit) => limit,
+            None => return,
+        };
+
+        let mut last_victim = None;
+
+        while self.buffered > limit {
+            let victim = self
+                .decoder_map
+                .iter_mut()
+                .filter(|(_, state)| state.buffered_bytes() > 0)
+                .min_by_key(|(_, state)| state.last_activity);
+
+            let (ssrc, state) = match victim {
+                Some(victim) => victim,
+                None => break,
+            };
+
+            let dropped = state.buffered_bytes();
+            state.history.samples.clear();
+            state.mix_queue.clear();
+
+            self.buffered -= dropped;
+            self.capped_dropped += dropped as u64;
+            self.capped_unreported += dropped;
+            last_victim = Some(*ssrc);
+        }
+
+        let ssrc = match last_victim {
+            Some(ssrc) => ssrc,
+            None => return,
+        };
+
+        let due = self
+            .last_cap_report
+            .map(|last| last.elapsed() >= DECODED_AUDIO_CAP_REPORT_INTERVAL)
+            .unwrap_or(true);
+        if !due {
+            return;
+        }
+
+        let dropped = std::mem::take(&mut self.capped_unreported);
+        self.last_cap_report = Some(Instant::now());
+
+        warn!(
+            "Dropped {} bytes of decoded audio (latest from SSRC {}) to respect memory limit.",
+            dropped, ssrc
+        );
+
+        let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+            CoreContext::DecodedAudioCapped(DecodedAudioCapData {
+                ssrc,
+                user_id: self.ssrc_users.get(&ssrc).copied(),
+                dropped,
+                total_dropped: self.capped_dropped,
+            }),
+        ));
+    }
+
+    /// Returns whether any per-tick events are enabled.
+    fn ticks_enabled(&self) -> bool {
+        self.config.voice_tick.is_enabled() || self.config.mixed_audio
+    }
+
+    /// Mixes one frame of queued audio from every stream, and fires
+    /// [`CoreEvent::MixedAudio`].
+    ///
+    /// [`CoreEvent::MixedAudio`]: crate::events::CoreEvent::MixedAudio
+    fn fire_mixed_audio(&mut self, interconnect: &Interconnect) {
+        let mut audio = vec![0i16; STEREO_FRAME_SIZE];
+        let mut speaking = vec![];
+
+        for (ssrc, state) in self.decoder_map.iter_mut() {
+            if let Some(clock) = &mut state.mix_clock {
+                *clock = clock.wrapping_add(MONO_FRAME_SIZE as u32);
+            }
+
+            if state.mix_queue.is_empty() {
+                continue;
+            }
+
+            let len = state.mix_queue.len().min(STEREO_FRAME_SIZE);
+            self.buffered -= len * std::mem::size_of::<i16>();
+            for (out, sample) in audio.iter_mut().zip(state.mix_queue.drain(..len)) {
+                *out = out.saturating_add(sample);
+            }
+            speaking.push(*ssrc);
+        }
+
+        let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+            CoreContext::MixedAudio(InternalMixedAudio { audio, speaking }),
+        ));
+    }
+
+    fn fire_voice_tick(&mut self, interconnect: &Interconnect) {
+        if self.tick_buffer.is_empty() && self.config.voice_tick == VoiceTickMode::SkipEmpty {
+            return;
+        }
+
+        let speaking = std::mem::take(&mut self.tick_buffer);
+        let silent = self
+            .decoder_map
+            .keys()
+            .filter(|ssrc| !speaking.iter().any(|pkt| pkt.packet.ssrc == **ssrc))
+            .copied()
+            .collect();
+
+        let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+            CoreContext::VoiceTick(InternalVoiceTick { speaking, silent }),
+        ));
+    }
+
+    /// Sends an IP discovery request, whose response measures the round-trip time
+    /// to the voice server over the same path as voice packets.
+    async fn send_latency_probe(&mut self) {
+        let mut bytes = [0; IpDiscoveryPacket::const_packet_size()];
+        {
+            let mut view = MutableIpDiscoveryPacket::new(&mut bytes[..])
+                .expect("Buffer is sized to hold an IP discovery packet.");
+            view.set_pkt_type(IpDiscoveryType::Request);
+            view.set_length(70);
+            view.set_ssrc(self.ssrc);
+        }
+
+        self.probe_sent = Some(Instant::now());
+        if let Err(e) = self.udp_socket.send(&bytes).await {
+            debug!("Failed to send latency probe: {:?}", e);
+        }
+    }
+
+    /// Records the round-trip time of the latest latency probe, now answered.
+    fn note_latency(&mut self, interconnect: &Interconnect) {
+        let sent = match self.probe_sent.take() {
+            Some(sent) => sent,
+            None => return,
+        };
+        let latency = sent.elapsed();
+
+        *self.latency.lock() = latency;
+
+        let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+            CoreContext::Latency(latency),
+        ));
+    }
+
     fn process_udp_message(&mut self, interconnect: &Interconnect, len: usize) {
         // NOTE: errors here (and in general for UDP) are not fatal to the connection.
         // Panics should be avoided due to adversarial nature of rx'd packets,
@@ -280,15 +843,36 @@ impl UdpRx {
         // context if it fails (hence, the `let _ =` statements.), as it will try to
         // make contact every 20ms.
         let crypto_mode = self.config.crypto_mode;
+
+        if len > VOICE_PACKET_MAX {
+            self.malformed_dropped += 1;
+            warn!("Dropped oversized UDP packet.");
+            return;
+        }
+
         let packet = &mut self.packet_buffer[..len];
 
+        // Discovery responses carry no RTP version, so would otherwise be rejected.
+        if is_discovery_response(packet) {
+            self.note_latency(interconnect);
+            return;
+        }
+
         match demux::demux_mut(packet) {
             DemuxedMut::Rtp(mut rtp) => {
                 if !rtp_valid(rtp.to_immutable()) {
+                    self.malformed_dropped += 1;
                     error!("Illegal RTP message received.");
                     return;
                 }
 
+                let ssrc = rtp.get_ssrc();
+                let user = self.ssrc_users.get(&ssrc).copied();
+                if !self.receiving || !self.config.receive_filter.allows(ssrc, user) {
+                    // Drop before decryption or decoder allocation.
+                    return;
+                }
+
                 let packet_data = if self.config.decode_mode.should_decrypt() {
                     let out = crypto_mode
                         .decrypt_in_place(&mut rtp, &self.cipher)
@@ -303,6 +887,18 @@ impl UdpRx {
                     None
                 };
 
+                self.packet_log.received(
+                    ssrc,
+                    rtp.get_sequence().into(),
+                    rtp.get_timestamp().into(),
+                    len,
+                    match packet_data {
+                        Some(_) => Decrypt::Ok,
+                        None if self.config.decode_mode.should_decrypt() => Decrypt::Failed,
+                        None => Decrypt::Skipped,
+                    },
+                );
+
                 let (rtp_body_start, rtp_body_tail, decrypted) = packet_data.unwrap_or_else(|| {
                     (
                         crypto_mode.payload_prefix_len(),
@@ -311,11 +907,42 @@ impl UdpRx {
                     )
                 });
 
+                // Handlers slice payloads using these bounds, so they must fit.
+                if rtp_body_start + rtp_body_tail > rtp.payload().len() {
+                    self.malformed_dropped += 1;
+                    warn!("Dropped truncated RTP packet from SSRC {}.", ssrc);
+                    return;
+                }
+
+                let mut is_new = !self.decoder_map.contains_key(&ssrc);
                 let entry = self
                     .decoder_map
                     .entry(rtp.get_ssrc())
                     .or_insert_with(|| SsrcState::new(rtp.to_immutable()));
 
+                if entry.stale {
+                    entry.restart(rtp.to_immutable());
+                    is_new = true;
+                }
+
+                // Only authenticated packets may move the replay window: anyone can
+                // forge a sequence number, so packets which were never decrypted
+                // (including all packets in `DecodeMode::Pass`) are left unchecked.
+                if let Some(len) = self.config.replay_window {
+                    if decrypted && !is_new && !entry.replay.accept(rtp.get_sequence().into(), len)
+                    {
+                        self.replay_dropped += 1;
+                        trace!("Dropped replayed packet from SSRC {}.", ssrc);
+                        return;
+                    }
+                }
+
+                if let Some(idle) = entry.mark_active() {
+                    let _ = interconnect.events.send(EventMessage::FireCoreEvent(
+                        CoreContext::UserActive(UserActivityData { ssrc, user_id: user, idle }),
+                    ));
+                }
+
                 if let Ok((delta, audio)) = entry.process(
                     rtp.to_immutable(),
                     rtp_body_start,
@@ -343,15 +970,43 @@ impl UdpRx {
                         _ => {},
                     }
 
+                    let pkt = InternalVoicePacket {
+                        audio,
+                        packet: rtp.from_packet(),
+                        payload_offset: rtp_body_start,
+                        payload_end_pad: rtp_body_tail,
+                    };
+
+                    let buffered_before = entry.buffered_bytes();
+
+                    if let (Some(limit), Some(audio)) = (self.config.receive_history, &pkt.audio) {
+                        let limit = utils::timestamp_to_sample_count(limit, true);
+                        entry.history.push(audio, limit);
+                    }
+
+                    if self.config.mixed_audio {
+                        if let Some(audio) = &pkt.audio {
+                            let limit =
+                                utils::timestamp_to_sample_count(MIXED_AUDIO_BACKLOG, true);
+                            entry.queue_mixed(rtp.get_timestamp().into(), audio, limit);
+                        }
+                    }
+
+                    self.buffered = self.buffered + entry.buffered_bytes() - buffered_before;
+
+                    if self.config.voice_tick.is_enabled() && entry.silent_frame_count == 0 {
+                        self.tick_buffer.push(pkt.clone());
+                    }
+
+                    if pkt.audio.is_some() {
+                        self.enforce_audio_cap(interconnect);
+                    }
+
                     let _ = interconnect.events.send(EventMessage::FireCoreEvent(
-                        CoreContext::VoicePacket(InternalVoicePacket {
-                            audio,
-                            packet: rtp.from_packet(),
-                            payload_offset: rtp_body_start,
-                            payload_end_pad: rtp_body_tail,
-                        }),
+                        CoreContext::VoicePacket(pkt),
                     ));
                 } else {
+                    self.malformed_dropped += 1;
                     warn!("RTP decoding/processing failed.");
                 }
             },
@@ -375,6 +1030,12 @@ impl UdpRx {
                     )
                 });
 
+                if start + tail > rtcp.payload().len() {
+                    self.malformed_dropped += 1;
+                    warn!("Dropped truncated RTCP packet.");
+                    return;
+                }
+
                 let _ =
                     interconnect
                         .events
@@ -387,9 +1048,11 @@ impl UdpRx {
                         )));
             },
             DemuxedMut::FailedParse(t) => {
+                self.malformed_dropped += 1;
                 warn!("Failed to parse message of type {:?}.", t);
             },
             _ => {
+                self.malformed_dropped += 1;
                 warn!("Illegal UDP packet from voice server.");
             },
         }
@@ -403,24 +1066,555 @@ pub(crate) async fn runner(
     cipher: Cipher,
     config: Config,
     udp_socket: Arc<UdpSocket>,
+    ssrc: u32,
+    latency: Arc<Mutex<Duration>>,
 ) {
     trace!("UDP receive handle started.");
 
-    let mut state = UdpRx {
-        cipher,
-        decoder_map: Default::default(),
-        config,
-        packet_buffer: [0u8; VOICE_PACKET_MAX],
-        rx,
-        udp_socket,
-    };
+    let mut state = UdpRx::new(cipher, config, rx, udp_socket, ssrc, latency);
 
     state.run(&mut interconnect).await;
 
     trace!("UDP receive handle stopped.");
 }
 
+/// Creates the timer for latency probes, which first fires one interval from now.
+///
+/// This timer is unused while [`Config::latency_interval`] is `None`.
+fn latency_ticker(config: &Config) -> Interval {
+    let period = config.latency_interval.unwrap_or(TIMESTEP_LENGTH);
+    let mut ticker = time::interval_at(time::Instant::now() + period, period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    ticker
+}
+
+#[inline]
+fn is_discovery_response(packet: &[u8]) -> bool {
+    IpDiscoveryPacket::new(packet)
+        .map(|view| view.get_pkt_type() == IpDiscoveryType::Response)
+        .unwrap_or(false)
+}
+
 #[inline]
 fn rtp_valid(packet: RtpPacket<'_>) -> bool {
-    packet.get_version() == RTP_VERSION && packet.get_payload_type() == RTP_PROFILE_TYPE
+    // Discord never mixes streams, and `discortp` sizes the CSRC list in bytes
+    // rather than words: reading a non-empty list from a packet will panic.
+    packet.get_version() == RTP_VERSION
+        && packet.get_payload_type() == RTP_PROFILE_TYPE
+        && packet.get_csrc_count() == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::CryptoMode;
+    use xsalsa20poly1305::KeyInit;
+
+    /// Builds a receive task bound to a local socket, along with the channel its
+    /// events are sent to.
+    async fn test_udp_rx(config: Config) -> (UdpRx, Interconnect, Receiver<EventMessage>) {
+        let (events, event_rx) = flume::unbounded();
+        let (core, _core_rx) = flume::unbounded();
+        let (mixer, _mixer_rx) = flume::unbounded();
+        let interconnect = Interconnect {
+            core,
+            events,
+            mixer,
+        };
+
+        let (_tx, rx) = flume::unbounded();
+        let cipher = Cipher::new_from_slice(&[0u8; 32]).unwrap();
+        let udp_socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+
+        (
+            UdpRx::new(cipher, config, rx, udp_socket, 1, Default::default()),
+            interconnect,
+            event_rx,
+        )
+    }
+
+    #[test]
+    fn replay_window_drops_duplicates_and_stale_packets() {
+        let mut window = ReplayWindow::new(100);
+
+        assert!(window.accept(101, 64));
+        assert!(window.accept(103, 64));
+        assert!(!window.accept(101, 64));
+        assert!(!window.accept(103, 64));
+
+        // Reordered packets are accepted once.
+        assert!(window.accept(102, 64));
+        assert!(!window.accept(102, 64));
+
+        // Packets older than the window are dropped, even if unseen.
+        assert!(window.accept(200, 64));
+        assert!(window.accept(137, 64));
+        assert!(!window.accept(136, 64));
+        assert!(!window.accept(103, 64));
+
+        // Sequence numbers wrap around.
+        let mut window = ReplayWindow::new(u16::MAX);
+        assert!(window.accept(0, 64));
+        assert!(!window.accept(u16::MAX, 64));
+        assert!(window.accept(u16::MAX - 1, 64));
+    }
+
+    #[test]
+    fn audio_history_keeps_latest_samples() {
+        let mut history = AudioHistory::default();
+        history.push(&[1, 2, 3, 4], 6);
+        history.push(&[5, 6, 7, 8], 6);
+
+        assert_eq!(history.latest(10), vec![3, 4, 5, 6, 7, 8]);
+        assert_eq!(history.latest(2), vec![7, 8]);
+    }
+
+    /// Builds a silent, encrypted Opus packet from SSRC 1 with sequence number `seq`.
+    fn sequenced_packet(cipher: &Cipher, seq: u16) -> Vec<u8> {
+        opus_packet(cipher, seq, &SILENT_FRAME)
+    }
+
+    fn opus_packet(cipher: &Cipher, seq: u16, payload: &[u8]) -> Vec<u8> {
+        use crate::driver::CryptoState;
+        use discortp::{rtp::MutableRtpPacket, MutablePacket};
+        use xsalsa20poly1305::TAG_SIZE;
+
+        let mode = CryptoMode::Normal;
+        let mut packet =
+            vec![0u8; RtpPacket::minimum_packet_size() + payload.len() + mode.payload_overhead()];
+
+        let mut rtp = MutableRtpPacket::new(&mut packet[..]).unwrap();
+        rtp.set_version(RTP_VERSION);
+        rtp.set_payload_type(RTP_PROFILE_TYPE);
+        rtp.set_ssrc(1);
+        rtp.set_sequence(seq.into());
+        rtp.set_timestamp((u32::from(seq) * MONO_FRAME_SIZE as u32).into());
+        let start = mode.payload_prefix_len();
+        rtp.payload_mut()[start..start + payload.len()].copy_from_slice(payload);
+        let len = CryptoState::from(mode).write_packet_nonce(&mut rtp, TAG_SIZE + payload.len());
+        mode.encrypt_in_place(&mut rtp, cipher, len).unwrap();
+
+        packet
+    }
+
+    #[tokio::test]
+    async fn voice_ticks_batch_speaking_and_silent_sources() {
+        let config = Config::default()
+            .decode_mode(DecodeMode::Decrypt)
+            .voice_tick(VoiceTickMode::SkipEmpty);
+        let (mut state, interconnect, event_rx) = test_udp_rx(config).await;
+        let cipher = state.cipher.clone();
+
+        let packet = opus_packet(&cipher, 1, &[0xf8, 0x01, 0x02, 0x03]);
+        state.packet_buffer[..packet.len()].copy_from_slice(&packet);
+        state.process_udp_message(&interconnect, packet.len());
+
+        let ticks = || {
+            event_rx
+                .drain()
+                .filter_map(|msg| match msg {
+                    EventMessage::FireCoreEvent(CoreContext::VoiceTick(tick)) => Some(tick),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        state.fire_voice_tick(&interconnect);
+        let tick = ticks();
+        assert_eq!(tick.len(), 1);
+        assert_eq!(tick[0].speaking.len(), 1);
+        assert_eq!(tick[0].speaking[0].packet.ssrc, 1);
+        assert!(tick[0].silent.is_empty());
+
+        // Nothing arrived during the next tick.
+        state.fire_voice_tick(&interconnect);
+        assert!(ticks().is_empty());
+
+        state.config.voice_tick = VoiceTickMode::Always;
+        state.fire_voice_tick(&interconnect);
+        let tick = ticks();
+        assert_eq!(tick.len(), 1);
+        assert!(tick[0].speaking.is_empty());
+        assert_eq!(tick[0].silent, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn forged_packets_do_not_move_the_replay_window() {
+        for decode_mode in [DecodeMode::Decrypt, DecodeMode::Pass] {
+            let config = Config::default()
+                .decode_mode(decode_mode)
+                .replay_window(Some(64));
+            let (mut state, interconnect, _event_rx) = test_udp_rx(config).await;
+            let cipher = state.cipher.clone();
+
+            let feed = |state: &mut UdpRx, packet: &[u8]| {
+                state.packet_buffer[..packet.len()].copy_from_slice(packet);
+                state.process_udp_message(&interconnect, packet.len());
+            };
+
+            feed(&mut state, &sequenced_packet(&cipher, 1));
+
+            // A spoofed packet far ahead of the stream, which fails authentication.
+            let mut forged = sequenced_packet(&cipher, 1_000);
+            *forged.last_mut().unwrap() ^= 0xff;
+            feed(&mut state, &forged);
+
+            feed(&mut state, &sequenced_packet(&cipher, 2));
+            assert_eq!(state.replay_dropped, 0, "{:?}", decode_mode);
+
+            // Genuine replays are still caught, where packets can be authenticated.
+            feed(&mut state, &sequenced_packet(&cipher, 2));
+            let expected = if decode_mode.should_decrypt() { 1 } else { 0 };
+            assert_eq!(state.replay_dropped, expected, "{:?}", decode_mode);
+        }
+    }
+
+    #[tokio::test]
+    async fn paused_receive_keeps_history() {
+        let config = Config::default()
+            .decode_mode(DecodeMode::Decode)
+            .receive_history(Some(Duration::from_secs(1)))
+            .replay_window(Some(64));
+        let (mut state, interconnect, _event_rx) = test_udp_rx(config).await;
+        let cipher = state.cipher.clone();
+        let user = UserId(7);
+        state.ssrc_users.insert(1, user);
+
+        let feed = |state: &mut UdpRx, seq: u16| {
+            let packet = sequenced_packet(&cipher, seq);
+            state.packet_buffer[..packet.len()].copy_from_slice(&packet);
+            state.process_udp_message(&interconnect, packet.len());
+        };
+        let held = |state: &UdpRx| state.user_history(user, Duration::from_secs(1)).len();
+
+        feed(&mut state, 1);
+        assert_eq!(held(&state), STEREO_FRAME_SIZE);
+
+        state.set_receiving(false);
+        assert_eq!(held(&state), STEREO_FRAME_SIZE);
+
+        // The stream restarts on resuming, even if its sequence has moved on too far
+        // for the old replay window to accept.
+        state.set_receiving(true);
+        feed(&mut state, 40_000);
+        assert_eq!(held(&state), 2 * STEREO_FRAME_SIZE);
+        assert_eq!(state.replay_dropped, 0);
+    }
+
+    fn encrypted_packet(cipher: &Cipher, mode: CryptoMode, extension: bool) -> Vec<u8> {
+        use crate::driver::CryptoState;
+        use discortp::{rtp::MutableRtpPacket, MutablePacket};
+        use xsalsa20poly1305::TAG_SIZE;
+
+        // An extension header claiming far more space than the packet holds.
+        let payload = [0xbe, 0xde, 0xff, 0xff, 0xf8, 0xff, 0xfe];
+        let mut packet =
+            vec![0u8; RtpPacket::minimum_packet_size() + payload.len() + mode.payload_overhead()];
+
+        let mut rtp = MutableRtpPacket::new(&mut packet[..]).unwrap();
+        rtp.set_version(RTP_VERSION);
+        rtp.set_payload_type(RTP_PROFILE_TYPE);
+        rtp.set_ssrc(1);
+        rtp.set_extension(extension as u8);
+
+        let start = mode.payload_prefix_len();
+        rtp.payload_mut()[start..start + payload.len()].copy_from_slice(&payload);
+
+        let len = CryptoState::from(mode).write_packet_nonce(&mut rtp, TAG_SIZE + payload.len());
+        mode.encrypt_in_place(&mut rtp, cipher, len).unwrap();
+
+        packet
+    }
+
+    #[tokio::test]
+    async fn malformed_packets_are_dropped_without_panicking() {
+        use rand::Rng;
+
+        for mode in [CryptoMode::Normal, CryptoMode::Suffix, CryptoMode::Lite] {
+            let config = Config::default()
+                .crypto_mode(mode)
+                .decode_mode(DecodeMode::Decode);
+            let (mut state, interconnect, _event_rx) = test_udp_rx(config).await;
+            let cipher = state.cipher.clone();
+
+            let feed = |state: &mut UdpRx, packet: &[u8]| {
+                let len = packet.len().min(state.packet_buffer.len());
+                state.packet_buffer[..len].copy_from_slice(&packet[..len]);
+                state.process_udp_message(&interconnect, len);
+            };
+
+            // Bad extension lengths survive decryption, but not decoding.
+            let packet = encrypted_packet(&cipher, mode, true);
+            feed(&mut state, &packet);
+            assert_eq!(state.malformed_dropped, 1);
+
+            // Every truncation of a well-formed packet.
+            let packet = encrypted_packet(&cipher, mode, false);
+            for len in 0..packet.len() {
+                feed(&mut state, &packet[..len]);
+            }
+
+            // Headers claiming CSRCs, which we cannot safely parse.
+            let mut csrc = packet.clone();
+            csrc[0] |= 0x0f;
+            feed(&mut state, &csrc);
+
+            let before = state.malformed_dropped;
+            feed(&mut state, &[0x80; VOICE_PACKET_MAX + 100]);
+            assert_eq!(state.malformed_dropped, before + 1);
+
+            let mut rng = rand::thread_rng();
+            for _ in 0..1000 {
+                let len = rng.gen_range(0..=VOICE_PACKET_MAX);
+                let mut packet = vec![0u8; len];
+                rng.fill(&mut packet[..]);
+                if len > 1 {
+                    // Steer most packets towards the RTP and RTCP parsers.
+                    packet[0] = 0x80 | (packet[0] & 0x3f);
+                }
+                feed(&mut state, &packet);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn latency_is_measured_by_discovery_requests() {
+        let config = Config::default().latency_interval(Some(Duration::from_secs(1)));
+        let (mut state, interconnect, event_rx) = test_udp_rx(config).await;
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        state
+            .udp_socket
+            .connect(server.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let answer = |state: &mut UdpRx, request: &mut [u8]| {
+            let mut view = MutableIpDiscoveryPacket::new(request).unwrap();
+            view.set_pkt_type(IpDiscoveryType::Response);
+
+            state.packet_buffer[..request.len()].copy_from_slice(request);
+            state.process_udp_message(&interconnect, request.len());
+        };
+
+        state.send_latency_probe().await;
+        let mut request = [0u8; IpDiscoveryPacket::const_packet_size()];
+        let (len, _peer) = server.recv_from(&mut request).await.unwrap();
+        {
+            let view = IpDiscoveryPacket::new(&request[..len]).unwrap();
+            assert_eq!(view.get_pkt_type(), IpDiscoveryType::Request);
+            assert_eq!(view.get_ssrc(), 1);
+        }
+
+        answer(&mut state, &mut request[..len]);
+        match event_rx.try_recv() {
+            Ok(EventMessage::FireCoreEvent(CoreContext::Latency(latency))) =>
+                assert_eq!(latency, *state.latency.lock()),
+            _ => panic!("Expected a latency update."),
+        }
+
+        // Responses which were not asked for are ignored, rather than treated as RTP.
+        answer(&mut state, &mut request[..len]);
+        assert!(event_rx.is_empty());
+        assert_eq!(state.malformed_dropped, 0);
+    }
+
+    #[tokio::test]
+    async fn mixed_audio_sums_speakers_over_silence() {
+        let config = Config::default().mixed_audio(true);
+        let (mut state, interconnect, event_rx) = test_udp_rx(config).await;
+
+        let header = [0x80u8; 12];
+        let pkt = RtpPacket::new(&header[..]).unwrap();
+
+        // One speaker sends two frames at once, the other sends one loud frame.
+        let mut quiet = SsrcState::new(pkt.to_immutable());
+        quiet.mix_queue.extend(vec![100i16; 2 * STEREO_FRAME_SIZE]);
+        state.decoder_map.insert(1, quiet);
+        let mut loud = SsrcState::new(pkt.to_immutable());
+        loud.mix_queue.extend(vec![i16::MAX; STEREO_FRAME_SIZE]);
+        state.decoder_map.insert(2, loud);
+        state.recount_buffered();
+
+        let mut frames = vec![];
+        for _ in 0..3 {
+            state.fire_mixed_audio(&interconnect);
+            match event_rx.try_recv() {
+                Ok(EventMessage::FireCoreEvent(CoreContext::MixedAudio(mut mixed))) => {
+                    assert_eq!(mixed.audio.len(), STEREO_FRAME_SIZE);
+                    mixed.speaking.sort_unstable();
+                    frames.push((mixed.audio[0], mixed.speaking));
+                },
+                _ => panic!("Expected mixed audio."),
+            }
+        }
+
+        // Late audio carries over to the next tick, and silence fills the gaps.
+        assert_eq!(frames[0], (i16::MAX, vec![1, 2]));
+        assert_eq!(frames[1], (100, vec![1]));
+        assert_eq!(frames[2], (0, vec![]));
+    }
+
+    #[tokio::test]
+    async fn mixed_audio_is_placed_by_rtp_timestamp() {
+        let config = Config::default().mixed_audio(true);
+        let (mut state, interconnect, event_rx) = test_udp_rx(config).await;
+
+        let header = [0x80u8; 12];
+        let pkt = RtpPacket::new(&header[..]).unwrap();
+        state.decoder_map.insert(1, SsrcState::new(pkt.to_immutable()));
+
+        let limit = utils::timestamp_to_sample_count(MIXED_AUDIO_BACKLOG, true);
+        let ts = |frame: u32| 1000 + frame * MONO_FRAME_SIZE as u32;
+        let queue = |state: &mut UdpRx, frame: u32, value: i16| {
+            let audio = vec![value; STEREO_FRAME_SIZE];
+            state.decoder_map.get_mut(&1).unwrap().queue_mixed(ts(frame), &audio, limit);
+            state.recount_buffered();
+        };
+        let mix = |state: &mut UdpRx, ticks: usize| {
+            (0..ticks)
+                .map(|_| {
+                    state.fire_mixed_audio(&interconnect);
+                    match event_rx.try_recv() {
+                        Ok(EventMessage::FireCoreEvent(CoreContext::MixedAudio(mixed))) =>
+                            mixed.audio[0],
+                        _ => panic!("Expected mixed audio."),
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Frame 1 is lost, frame 3 arrives after frame 4.
+        queue(&mut state, 0, 10);
+        queue(&mut state, 2, 30);
+        queue(&mut state, 4, 50);
+        queue(&mut state, 3, 40);
+        assert_eq!(mix(&mut state, 5), vec![10, 0, 30, 40, 50]);
+
+        // Frame 1 turns up after its tick, and is not heard.
+        queue(&mut state, 1, 20);
+        queue(&mut state, 5, 60);
+        assert_eq!(mix(&mut state, 1), vec![60]);
+
+        // Packets from far beyond the backlog restart the stream's timing.
+        queue(&mut state, 100, 70);
+        assert_eq!(mix(&mut state, 2), vec![70, 0]);
+    }
+
+    #[tokio::test]
+    async fn audio_cap_drops_least_active_stream() {
+        let frame_bytes = STEREO_FRAME_SIZE * std::mem::size_of::<i16>();
+        let config = Config::default()
+            .mixed_audio(true)
+            .max_decoded_audio(Some(2 * frame_bytes));
+        let (mut state, interconnect, event_rx) = test_udp_rx(config).await;
+
+        let header = [0x80u8; 12];
+        let pkt = RtpPacket::new(&header[..]).unwrap();
+
+        // Stream 1 fell silent a while ago; streams 2 and 3 are still talking.
+        let now = Instant::now();
+        for (ssrc, age) in [(1, 5), (2, 1), (3, 0)] {
+            let mut entry = SsrcState::new(pkt.to_immutable());
+            entry.mix_queue.extend(vec![1i16; STEREO_FRAME_SIZE]);
+            entry.last_activity = now - Duration::from_secs(age);
+            entry.last_seq = ssrc as u16;
+            state.decoder_map.insert(ssrc, entry);
+        }
+        state.recount_buffered();
+
+        state.enforce_audio_cap(&interconnect);
+
+        match event_rx.try_recv() {
+            Ok(EventMessage::FireCoreEvent(CoreContext::DecodedAudioCapped(data))) => {
+                assert_eq!(data.ssrc, 1);
+                assert_eq!(data.dropped, frame_bytes);
+                assert_eq!(data.total_dropped, frame_bytes as u64);
+            },
+            _ => panic!("Expected a cap warning."),
+        }
+        assert!(event_rx.try_recv().is_err());
+
+        // Only the quiet stream loses its audio, and no decode state is touched.
+        assert!(state.decoder_map[&1].mix_queue.is_empty());
+        for ssrc in [1, 2, 3] {
+            assert_eq!(state.decoder_map[&ssrc].last_seq, ssrc as u16);
+        }
+        assert_eq!(state.decoder_map[&2].mix_queue.len(), STEREO_FRAME_SIZE);
+        assert_eq!(state.decoder_map[&3].mix_queue.len(), STEREO_FRAME_SIZE);
+        assert_eq!(state.buffered, 2 * frame_bytes);
+
+        let grow = |state: &mut UdpRx, ssrc: u32, frames: usize| {
+            let entry = state.decoder_map.get_mut(&ssrc).unwrap();
+            entry.mix_queue.extend(vec![1i16; frames * STEREO_FRAME_SIZE]);
+            state.buffered += frames * frame_bytes;
+            state.enforce_audio_cap(&interconnect);
+        };
+
+        // Further drops soon after are not reported on their own...
+        grow(&mut state, 2, 1);
+        assert!(state.decoder_map[&2].mix_queue.is_empty());
+        assert!(event_rx.try_recv().is_err());
+
+        // ...but are counted in the next report.
+        state.last_cap_report = Instant::now().checked_sub(DECODED_AUDIO_CAP_REPORT_INTERVAL);
+        grow(&mut state, 3, 2);
+        match event_rx.try_recv() {
+            Ok(EventMessage::FireCoreEvent(CoreContext::DecodedAudioCapped(data))) => {
+                assert_eq!(data.ssrc, 3);
+                assert_eq!(data.dropped, 5 * frame_bytes);
+                assert_eq!(data.total_dropped, 6 * frame_bytes as u64);
+            },
+            _ => panic!("Expected a cap warning."),
+        }
+        assert_eq!(state.buffered, 0);
+    }
+
+    #[tokio::test]
+    async fn idle_streams_are_reported_once_until_they_return() {
+        let config = Config::default()
+            .decode_mode(DecodeMode::Decrypt)
+            .user_inactivity(Some(Duration::from_secs(1)));
+        let (mut state, interconnect, event_rx) = test_udp_rx(config).await;
+        let cipher = state.cipher.clone();
+        state.ssrc_users.insert(1, UserId(7));
+
+        let feed = |state: &mut UdpRx, seq: u16| {
+            let packet = opus_packet(&cipher, seq, &[0xf8, 0x01, 0x02, 0x03]);
+            state.packet_buffer[..packet.len()].copy_from_slice(&packet);
+            state.process_udp_message(&interconnect, packet.len());
+        };
+        let activity = || {
+            event_rx
+                .drain()
+                .filter_map(|msg| match msg {
+                    EventMessage::FireCoreEvent(CoreContext::UserInactive(data)) =>
+                        Some((false, data)),
+                    EventMessage::FireCoreEvent(CoreContext::UserActive(data)) =>
+                        Some((true, data)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        feed(&mut state, 1);
+        state.check_inactivity(&interconnect);
+        assert!(activity().is_empty());
+
+        state.decoder_map.get_mut(&1).unwrap().last_activity -= Duration::from_secs(2);
+        state.check_inactivity(&interconnect);
+        state.check_inactivity(&interconnect);
+        let reported = activity();
+        assert_eq!(reported.len(), 1);
+        assert!(!reported[0].0);
+        assert_eq!(reported[0].1.ssrc, 1);
+        assert_eq!(reported[0].1.user_id, Some(UserId(7)));
+        assert!(reported[0].1.idle >= Duration::from_secs(2));
+
+        feed(&mut state, 2);
+        let reported = activity();
+        assert_eq!(reported.len(), 1);
+        assert!(reported[0].0);
+        assert!(reported[0].1.idle >= Duration::from_secs(2));
+    }
 }
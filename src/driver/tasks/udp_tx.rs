@@ -1,19 +1,55 @@
 use super::message::*;
-use crate::constants::*;
-use discortp::discord::MutableKeepalivePacket;
+use crate::{
+    constants::*,
+    events::{context_data::BitrateShortfallData, CoreContext},
+    Config,
+};
+use discortp::{discord::MutableKeepalivePacket, rtp::RtpPacket};
 use flume::Receiver;
-use std::sync::Arc;
+use socket2::SockRef;
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::{
     net::UdpSocket,
     time::{timeout_at, Instant},
 };
-use tracing::{error, instrument, trace};
+use tracing::{debug, error, instrument, trace, warn};
+
+/// Pause in outgoing packets after which transmission is treated as having stopped,
+/// rather than being held up by the network.
+const SEND_IDLE_GAP: Duration = Duration::from_millis(100);
+
+/// Send buffer used while checking for a bitrate shortfall, unless one is configured.
+///
+/// A UDP send only waits once the socket's send buffer is full. With the OS default,
+/// seconds of audio can queue behind a congested uplink before this happens, hiding
+/// the shortfall. This holds a few hundred milliseconds of audio, so that sends are
+/// paced by the network soon after it falls behind.
+const SHORTFALL_SEND_BUFFER: usize = 16 * 1024;
+
+/// Opus data sent since the start of the current bitrate measurement.
+struct SendWindow {
+    start: Instant,
+    last: Instant,
+    bytes: u64,
+}
 
 struct UdpTx {
     ssrc: u32,
     rx: Receiver<UdpTxMessage>,
 
     udp_tx: Arc<UdpSocket>,
+
+    interconnect: Interconnect,
+    /// Bytes added to each packet's Opus data by RTP headers and encryption.
+    overhead: usize,
+    shortfall: Option<f32>,
+    target: Option<u32>,
+    window_len: Duration,
+    window: Option<SendWindow>,
 }
 
 impl UdpTx {
@@ -36,11 +72,25 @@ impl UdpTx {
                     }
                     ka_time += UDP_KEEPALIVE_GAP;
                 },
-                Ok(Ok(Packet(p))) =>
-                    if let Err(e) = self.udp_tx.send(&p[..]).await {
-                        error!("Fatal UDP packet send error: {:?}.", e);
-                        break;
-                    },
+                Ok(Ok(Packet(p))) => {
+                    let received = Instant::now();
+                    let sent = match self.udp_tx.send(&p[..]).await {
+                        Ok(len) => len,
+                        Err(e) if is_congestion(&e) => {
+                            trace!("UDP packet dropped by congested network: {:?}.", e);
+                            0
+                        },
+                        Err(e) => {
+                            error!("Fatal UDP packet send error: {:?}.", e);
+                            break;
+                        },
+                    };
+                    self.note_sent(sent, received);
+                },
+                Ok(Ok(SetTarget(bitrate))) => {
+                    self.target = Some(bitrate);
+                    self.window = None;
+                },
                 Ok(Err(e)) => {
                     error!("Fatal UDP packet receive error: {:?}.", e);
                     break;
@@ -51,19 +101,181 @@ impl UdpTx {
             }
         }
     }
+
+    /// Adds a sent packet of `len` bytes to the current bitrate measurement,
+    /// reporting a shortfall once a full window has been observed.
+    ///
+    /// Packets are timed from when they are `received` from the mixer, so that
+    /// sends held up by a full send buffer are counted against the measured rate,
+    /// while gaps in audio restart the measurement. Packets dropped by the OS count
+    /// towards the window's length, but not its bytes.
+    fn note_sent(&mut self, len: usize, received: Instant) {
+        let (shortfall, target) = match (self.shortfall, self.target) {
+            (Some(shortfall), Some(target)) => (shortfall, target),
+            _ => return,
+        };
+
+        let now = Instant::now();
+        let window = match self.window.as_mut() {
+            Some(window) if received - window.last < SEND_IDLE_GAP => window,
+            _ => {
+                // The first packet only marks the start of the window.
+                self.window = Some(SendWindow {
+                    start: now,
+                    last: now,
+                    bytes: 0,
+                });
+                return;
+            },
+        };
+
+        window.bytes += len.saturating_sub(self.overhead) as u64;
+        window.last = now;
+
+        let elapsed = now - window.start;
+        if elapsed < self.window_len {
+            return;
+        }
+
+        let actual = (window.bytes as f64 * 8.0 / elapsed.as_secs_f64()) as u32;
+        window.start = now;
+        window.bytes = 0;
+
+        if f64::from(actual) < f64::from(target) * (1.0 - f64::from(shortfall)) {
+            debug!("Sent bitrate {} is short of target {}.", actual, target);
+            let _ = self.interconnect.events.send(EventMessage::FireCoreEvent(
+                CoreContext::BitrateShortfall(BitrateShortfallData { target, actual }),
+            ));
+        }
+    }
+}
+
+/// Returns whether a send failed because the OS dropped the packet under
+/// congestion, rather than because the socket is unusable.
+fn is_congestion(e: &IoError) -> bool {
+    #[cfg(target_os = "linux")]
+    if e.raw_os_error() == Some(libc::ENOBUFS) {
+        return true;
+    }
+
+    e.kind() == IoErrorKind::WouldBlock
+}
+
+/// Shrinks the send buffer of `socket` while [`Config::bitrate_shortfall`] is set,
+/// unless a size was chosen in [`Config::udp_socket_options`].
+///
+/// [`Config::bitrate_shortfall`]: crate::Config::bitrate_shortfall
+/// [`Config::udp_socket_options`]: crate::Config::udp_socket_options
+fn limit_send_buffer(socket: &UdpSocket, config: &Config) {
+    if config.bitrate_shortfall.is_none() || config.udp_socket_options.send_buffer_size.is_some()
+    {
+        return;
+    }
+
+    if let Err(e) = SockRef::from(socket).set_send_buffer_size(SHORTFALL_SEND_BUFFER) {
+        warn!("Failed to limit UDP socket send buffer size: {:?}", e);
+    }
 }
 
-#[instrument(skip(udp_msg_rx))]
-pub(crate) async fn runner(udp_msg_rx: Receiver<UdpTxMessage>, ssrc: u32, udp_tx: Arc<UdpSocket>) {
+#[instrument(skip(interconnect, udp_msg_rx, config))]
+pub(crate) async fn runner(
+    interconnect: Interconnect,
+    udp_msg_rx: Receiver<UdpTxMessage>,
+    ssrc: u32,
+    config: Config,
+    udp_tx: Arc<UdpSocket>,
+) {
     trace!("UDP transmit handle started.");
 
+    limit_send_buffer(&udp_tx, &config);
+
     let mut txer = UdpTx {
         ssrc,
         rx: udp_msg_rx,
         udp_tx,
+        interconnect,
+        overhead: RtpPacket::minimum_packet_size() + config.crypto_mode.payload_overhead(),
+        shortfall: config.bitrate_shortfall,
+        target: None,
+        window_len: BITRATE_WINDOW,
+        window: None,
     };
 
     txer.run().await;
 
     trace!("UDP transmit handle stopped.");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn slow_sends_report_bitrate_shortfall() {
+        tokio::time::pause();
+
+        let (core_tx, _core_rx) = flume::unbounded();
+        let (event_tx, event_rx) = flume::unbounded();
+        let (mix_tx, _mix_rx) = flume::unbounded();
+        let (tx, rx) = flume::unbounded();
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.connect(socket.local_addr().unwrap()).await.unwrap();
+
+        let mut txer = UdpTx {
+            ssrc: 1,
+            rx,
+            udp_tx: Arc::new(socket),
+            interconnect: Interconnect {
+                core: core_tx,
+                events: event_tx,
+                mixer: mix_tx,
+            },
+            overhead: 0,
+            shortfall: Some(0.25),
+            target: None,
+            window_len: Duration::from_millis(100),
+            window: None,
+        };
+        let task = tokio::spawn(async move { txer.run().await });
+
+        // 10 bytes every 10ms is 8kbps, far below the target.
+        tx.send(UdpTxMessage::SetTarget(64_000)).unwrap();
+        for _ in 0..20 {
+            tx.send(UdpTxMessage::Packet(vec![0u8; 10])).unwrap();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tx.send(UdpTxMessage::Poison).unwrap();
+        task.await.unwrap();
+
+        let shortfall = event_rx
+            .try_iter()
+            .find_map(|msg| match msg {
+                EventMessage::FireCoreEvent(CoreContext::BitrateShortfall(data)) => Some(data),
+                _ => None,
+            })
+            .expect("Shortfall reported.");
+        assert_eq!(shortfall.target, 64_000);
+        assert!(shortfall.actual < 16_000);
+    }
+
+    #[tokio::test]
+    async fn shortfall_checks_shrink_the_send_buffer() {
+        let size = |socket: &UdpSocket| SockRef::from(socket).send_buffer_size().unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let default = size(&socket);
+
+        limit_send_buffer(&socket, &Config::default());
+        assert_eq!(size(&socket), default);
+
+        let config = Config::default().bitrate_shortfall(Some(0.25));
+        limit_send_buffer(&socket, &config);
+        assert!(size(&socket) < default);
+
+        // Explicitly chosen sizes are left to `UdpSocketOptions` to apply.
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let options = config.udp_socket_options.send_buffer_size(Some(1 << 20));
+        limit_send_buffer(&socket, &config.udp_socket_options(options));
+        assert_eq!(size(&socket), default);
+    }
+}
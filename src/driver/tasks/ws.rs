@@ -1,20 +1,23 @@
 use super::message::*;
 use crate::{
+    constants::*,
+    driver::GatewayVersion,
     events::CoreContext,
     model::{
-        payload::{Heartbeat, Speaking},
+        payload::{SelectProtocol, Speaking},
         CloseCode as VoiceCloseCode,
         Event as GatewayEvent,
         FromPrimitive,
         SpeakingState,
     },
-    ws::{Error as WsError, ReceiverExt, SenderExt, WsStream},
+    ws::{self as ws_util, Error as WsError, ReceiverExt, SenderExt, WsStream},
     ConnectionInfo,
 };
 use async_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
-use flume::Receiver;
+use flume::{Receiver, Sender};
+use parking_lot::Mutex;
 use rand::random;
-use std::time::Duration;
+use std::{collections::HashSet, sync::Arc, time::Duration};
 use tokio::{
     select,
     time::{sleep_until, Instant},
@@ -34,9 +37,17 @@ struct AuxNetwork {
 
     attempt_idx: usize,
     info: ConnectionInfo,
+
+    gateway_version: GatewayVersion,
+    last_seq: Arc<Mutex<Option<u64>>>,
+
+    udp_rx: Sender<UdpRxMessage>,
+
+    users: Option<HashSet<u64>>,
 }
 
 impl AuxNetwork {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         evt_rx: Receiver<WsMessage>,
         ws_client: WsStream,
@@ -44,6 +55,9 @@ impl AuxNetwork {
         heartbeat_interval: f64,
         attempt_idx: usize,
         info: ConnectionInfo,
+        gateway_version: GatewayVersion,
+        last_seq: Arc<Mutex<Option<u64>>>,
+        udp_rx: Sender<UdpRxMessage>,
     ) -> Self {
         Self {
             rx: evt_rx,
@@ -58,6 +72,13 @@ impl AuxNetwork {
 
             attempt_idx,
             info,
+
+            gateway_version,
+            last_seq,
+
+            udp_rx,
+
+            users: None,
         }
     }
 
@@ -95,8 +116,14 @@ impl AuxNetwork {
                             ws_reason = Some((&e).into());
                             true
                         },
-                        Ok(Some(msg)) => {
-                            self.process_ws(interconnect, msg);
+                        Ok(Some((msg, seq))) => {
+                            if let Some(seq) = seq {
+                                *self.last_seq.lock() = Some(seq);
+                            }
+                            match msg {
+                                Ok(msg) => self.process_ws(interconnect, msg),
+                                Err(e) => debug!("Unexpected JSON {:?}.", e),
+                            }
                             false
                         },
                         _ => false,
@@ -108,6 +135,8 @@ impl AuxNetwork {
                             self.ws_client = *data;
                             next_heartbeat = self.next_heartbeat();
                             self.dont_send = false;
+                            self.users = None;
+                            let _ = interconnect.mixer.send(MixerMessage::WsOutage(false));
                         },
                         Ok(WsMessage::ReplaceInterconnect(i)) => {
                             *interconnect = i;
@@ -116,9 +145,9 @@ impl AuxNetwork {
                             self.heartbeat_interval = Duration::from_secs_f64(keepalive / 1000.0);
                             next_heartbeat = self.next_heartbeat();
                         },
-                        Ok(WsMessage::Speaking(is_speaking)) => {
-                            if self.speaking.contains(SpeakingState::MICROPHONE) != is_speaking && !self.dont_send {
-                                self.speaking.set(SpeakingState::MICROPHONE, is_speaking);
+                        Ok(WsMessage::Speaking(speaking)) => {
+                            if self.speaking != speaking && !self.dont_send {
+                                self.speaking = speaking;
                                 info!("Changing to {:?}", self.speaking);
 
                                 let ssu_status = self.ws_client
@@ -140,7 +169,32 @@ impl AuxNetwork {
                                 }
                             }
                         },
+                        Ok(WsMessage::SelectProtocol(data)) => {
+                            info!("Renegotiating session encryption.");
+
+                            let select_status = self.ws_client
+                                .send_json(&GatewayEvent::from(SelectProtocol {
+                                    protocol: "udp".into(),
+                                    data,
+                                }))
+                                .await;
+
+                            if let Err(e) = select_status {
+                                should_reconnect = ws_error_is_not_final(&e);
+                                ws_reason = Some((&e).into());
+                                ws_error = true;
+                            }
+                        },
+                        Ok(WsMessage::GetListenerCount(tx)) => {
+                            let _ = tx.send(self.users.as_ref().map(HashSet::len));
+                        },
+                        Ok(WsMessage::Close(tx)) => {
+                            self.close().await;
+                            let _ = tx.send(());
+                            break;
+                        },
                         Err(_) | Ok(WsMessage::Poison) => {
+                            self.close().await;
                             break;
                         },
                     }
@@ -151,6 +205,7 @@ impl AuxNetwork {
                 self.dont_send = true;
 
                 if should_reconnect {
+                    let _ = interconnect.mixer.send(MixerMessage::WsOutage(true));
                     let _ = interconnect.core.send(CoreMessage::Reconnect);
                 } else {
                     let _ = interconnect.core.send(CoreMessage::SignalWsClosure(
@@ -164,6 +219,21 @@ impl AuxNetwork {
         }
     }
 
+    /// Performs the websocket closing handshake, if the connection is still usable.
+    async fn close(&mut self) {
+        if self.dont_send {
+            return;
+        }
+
+        match ws_util::close_gracefully(&mut self.ws_client, CloseCode::Normal, WS_CLOSE_TIMEOUT)
+            .await
+        {
+            Ok(true) => debug!("Voice websocket closed."),
+            Ok(false) => debug!("Voice websocket close was not acknowledged in time."),
+            Err(e) => debug!("Failed to close voice websocket: {:?}", e),
+        }
+    }
+
     fn next_heartbeat(&self) -> Instant {
         Instant::now() + self.heartbeat_interval
     }
@@ -175,8 +245,10 @@ impl AuxNetwork {
         trace!("Sent heartbeat {:?}", self.speaking);
 
         if !self.dont_send {
+            let seq_ack = *self.last_seq.lock();
+
             self.ws_client
-                .send_json(&GatewayEvent::from(Heartbeat { nonce }))
+                .send_json(&self.gateway_version.heartbeat(nonce, seq_ack))
                 .await?;
         }
 
@@ -186,18 +258,36 @@ impl AuxNetwork {
     fn process_ws(&mut self, interconnect: &Interconnect, value: GatewayEvent) {
         match value {
             GatewayEvent::Speaking(ev) => {
+                if let Some(user_id) = ev.user_id {
+                    self.users.get_or_insert_with(HashSet::new).insert(user_id.0);
+                    let _ = self
+                        .udp_rx
+                        .send(UdpRxMessage::SetSsrcUser(ev.ssrc, user_id.0.into()));
+                }
+
                 let _ = interconnect.events.send(EventMessage::FireCoreEvent(
                     CoreContext::SpeakingStateUpdate(ev),
                 ));
             },
             GatewayEvent::ClientConnect(ev) => {
                 debug!("Received discontinued ClientConnect: {:?}", ev);
+                self.users.get_or_insert_with(HashSet::new).insert(ev.user_id.0);
             },
             GatewayEvent::ClientDisconnect(ev) => {
+                if let Some(users) = self.users.as_mut() {
+                    users.remove(&ev.user_id.0);
+                }
+                let _ = self
+                    .udp_rx
+                    .send(UdpRxMessage::RemoveUser(ev.user_id.0.into()));
+
                 let _ = interconnect.events.send(EventMessage::FireCoreEvent(
                     CoreContext::ClientDisconnect(ev),
                 ));
             },
+            GatewayEvent::SessionDescription(desc) => {
+                let _ = interconnect.core.send(CoreMessage::SessionDescription(desc));
+            },
             GatewayEvent::HeartbeatAck(ev) => {
                 if let Some(nonce) = self.last_heartbeat_nonce.take() {
                     if ev.nonce == nonce {
@@ -217,6 +307,7 @@ impl AuxNetwork {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 #[instrument(skip(interconnect, ws_client))]
 pub(crate) async fn runner(
     mut interconnect: Interconnect,
@@ -226,6 +317,9 @@ pub(crate) async fn runner(
     heartbeat_interval: f64,
     attempt_idx: usize,
     info: ConnectionInfo,
+    gateway_version: GatewayVersion,
+    last_seq: Arc<Mutex<Option<u64>>>,
+    udp_rx: Sender<UdpRxMessage>,
 ) {
     trace!("WS thread started.");
     let mut aux = AuxNetwork::new(
@@ -235,6 +329,9 @@ pub(crate) async fn runner(
         heartbeat_interval,
         attempt_idx,
         info,
+        gateway_version,
+        last_seq,
+        udp_rx,
     );
 
     aux.run(&mut interconnect).await;
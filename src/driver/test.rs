@@ -0,0 +1,1255 @@
+//! Utilities for testing receive-side behaviour without a live voice connection.
+//!
+//! Included if using the `"internals"` feature flag.
+//!
+//! Packets passed to [`inject_rtp`] are handled by a receive task local to the
+//! [`Driver`], which decrypts, decodes, and fires events for them exactly as it
+//! would for packets sent by Discord. This task uses a fixed key, given by
+//! [`test_cipher`], and the driver's [`CryptoMode`] at the time of the first
+//! injected packet. Packets can be prepared using [`rtp_packet`].
+//!
+//! To test the full connection lifecycle, a [`MockServer`] plays the part of a
+//! Discord voice server on the loopback interface. Drivers connect to it through
+//! the usual handshake, after which it decrypts and collects every RTP packet
+//! they send.
+//!
+//! [`Driver`]: Driver
+//! [`CryptoMode`]: CryptoMode
+
+use super::{
+    tasks::{message::*, udp_rx},
+    CryptoMode,
+    CryptoState,
+    Driver,
+};
+use crate::{
+    constants::*,
+    id::{GuildId, UserId},
+    model::{
+        payload::{HeartbeatAck, Hello, Ready, SessionDescription},
+        Event as GatewayEvent,
+    },
+    Config,
+    ConnectionInfo,
+};
+use async_tungstenite::{
+    tokio::{accept_async, TokioAdapter},
+    tungstenite::Message,
+    WebSocketStream,
+};
+use discortp::{
+    demux::{self, DemuxedMut},
+    discord::{IpDiscoveryPacket, IpDiscoveryType, MutableIpDiscoveryPacket},
+    rtp::{MutableRtpPacket, RtpPacket},
+    MutablePacket,
+    Packet,
+};
+use flume::{Receiver, Sender};
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::{
+    io::Result as IoResult,
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+use tokio::{
+    net::{TcpListener, TcpStream, UdpSocket},
+    spawn,
+    task::JoinHandle,
+};
+use tracing::{trace, warn, Instrument};
+use xsalsa20poly1305::{KeyInit, TAG_SIZE};
+
+pub use xsalsa20poly1305::XSalsa20Poly1305 as Cipher;
+
+/// Key used to decrypt packets passed to [`inject_rtp`].
+pub const TEST_KEY: [u8; 32] = [0u8; 32];
+
+/// Returns the cipher used to decrypt packets passed to [`inject_rtp`].
+pub fn test_cipher() -> Cipher {
+    Cipher::new_from_slice(&TEST_KEY).expect("Test key has a valid length.")
+}
+
+/// Builds an encrypted RTP packet from `ssrc` holding an Opus `payload`,
+/// as it would be sent by a Discord voice server.
+///
+/// `mode` must match [`Config::crypto_mode`] for the packet to be decrypted.
+///
+/// [`Config::crypto_mode`]: crate::Config::crypto_mode
+pub fn rtp_packet(
+    cipher: &Cipher,
+    mode: CryptoMode,
+    ssrc: u32,
+    sequence: u16,
+    timestamp: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut packet =
+        vec![0u8; RtpPacket::minimum_packet_size() + payload.len() + mode.payload_overhead()];
+
+    let mut rtp = MutableRtpPacket::new(&mut packet[..])
+        .expect("Packet is sized to hold an RTP header.");
+    rtp.set_version(RTP_VERSION);
+    rtp.set_payload_type(RTP_PROFILE_TYPE);
+    rtp.set_ssrc(ssrc);
+    rtp.set_sequence(sequence.into());
+    rtp.set_timestamp(timestamp.into());
+
+    let payload_start = mode.payload_prefix_len();
+    rtp.payload_mut()[payload_start..payload_start + payload.len()].copy_from_slice(payload);
+
+    let final_payload_size =
+        CryptoState::from(mode).write_packet_nonce(&mut rtp, TAG_SIZE + payload.len());
+    mode.encrypt_in_place(&mut rtp, cipher, final_payload_size)
+        .expect("Packet is sized to hold a nonce and tag.");
+
+    packet
+}
+
+/// Passes a crafted RTP packet through the receive path of `driver`, as though
+/// it were sent by a Discord voice server.
+///
+/// The packet must be encrypted using [`test_cipher`], i.e., via [`rtp_packet`].
+pub fn inject_rtp(driver: &mut Driver, packet: Vec<u8>) {
+    driver.send(CoreMessage::InjectRtp(packet));
+}
+
+/// Starts a receive task for injected packets, bound to a local socket
+/// which is otherwise unused.
+pub(crate) async fn start_receiver(
+    interconnect: &Interconnect,
+    config: &Config,
+) -> Option<Sender<UdpRxMessage>> {
+    let socket = match UdpSocket::bind("127.0.0.1:0").await {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("Failed to bind socket for test receiver: {:?}", e);
+            return None;
+        },
+    };
+
+    let (tx, rx) = flume::unbounded();
+    let ic = interconnect.clone();
+    let config = config.clone();
+
+    spawn(
+        async move {
+            trace!("Test receiver started.");
+            let socket = Arc::new(socket);
+            udp_rx::runner(ic, rx, test_cipher(), config, socket, 0, Default::default()).await;
+            trace!("Test receiver finished.");
+        }
+        .in_current_span(),
+    );
+
+    Some(tx)
+}
+
+/// SSRC assigned to drivers by a [`MockServer`].
+pub const MOCK_SSRC: u32 = 0x5eed;
+
+/// Heartbeat interval, in milliseconds, requested by a [`MockServer`].
+pub const MOCK_HEARTBEAT_INTERVAL: f64 = 5_000.0;
+
+/// An RTP packet sent by a driver to a [`MockServer`], after decryption.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MockPacket {
+    /// SSRC of the sender.
+    pub ssrc: u32,
+    /// RTP sequence number.
+    pub sequence: u16,
+    /// RTP timestamp.
+    pub timestamp: u32,
+    /// The Opus frame carried by the packet.
+    pub opus: Vec<u8>,
+}
+
+/// A stand-in for a Discord voice server, listening on the loopback interface.
+///
+/// Each server accepts voice connections one at a time, and plays back a minimal
+/// handshake script for each:
+/// * on `Identify`, it sends `Hello` and `Ready`, offering every [`CryptoMode`],
+///   assigning [`MOCK_SSRC`], and pointing the driver at its UDP socket;
+/// * it answers the driver's IP discovery requests with the driver's own address;
+/// * on `SelectProtocol`, it sends a `SessionDescription` for the chosen mode,
+///   using [`TEST_KEY`];
+/// * from then on, it acknowledges every heartbeat, answers any further
+///   `SelectProtocol` with the same `SessionDescription`, and ignores all other
+///   gateway messages.
+///
+/// RTP packets sent by the driver are decrypted and made available through
+/// [`packets`]. Further gateway events, such as users joining or speaking, can be
+/// sent to the driver using [`send_event`]. The server shuts down when dropped.
+///
+/// As the gateway is served over plain `ws://`, the endpoint given by
+/// [`connection_info`] is only accepted by drivers built with the
+/// `"internals"` feature.
+///
+/// [`CryptoMode`]: CryptoMode
+/// [`MOCK_SSRC`]: MOCK_SSRC
+/// [`TEST_KEY`]: TEST_KEY
+/// [`packets`]: MockServer::packets
+/// [`send_event`]: MockServer::send_event
+/// [`connection_info`]: MockServer::connection_info
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    packets: Receiver<MockPacket>,
+    events: Sender<GatewayEvent>,
+    rtp: Sender<Vec<u8>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Binds a new server to free local ports, and begins waiting for a driver.
+    pub async fn start() -> IoResult<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let udp = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let (tx, rx) = flume::unbounded();
+        let (events_tx, events_rx) = flume::unbounded();
+        let (rtp_tx, rtp_rx) = flume::unbounded();
+
+        let task = spawn(async move {
+            trace!("Mock server started.");
+            if let Err(e) = mock_server(listener, udp, tx, events_rx, rtp_rx).await {
+                warn!("Mock server failed: {:?}", e);
+            }
+            trace!("Mock server finished.");
+        });
+
+        Ok(Self {
+            addr,
+            packets: rx,
+            events: events_tx,
+            rtp: rtp_tx,
+            task,
+        })
+    }
+
+    /// Returns connection info which directs a [`Driver`] to this server.
+    ///
+    /// [`Driver`]: Driver
+    pub fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            channel_id: None,
+            endpoint: format!("ws://{}", self.addr),
+            guild_id: GuildId(1),
+            session_id: "mock-session".into(),
+            token: "mock-token".into(),
+            user_id: UserId(2),
+        }
+    }
+
+    /// Returns a receiver for all RTP packets sent to this server.
+    pub fn packets(&self) -> &Receiver<MockPacket> {
+        &self.packets
+    }
+
+    /// Sends a gateway event to the connected driver.
+    ///
+    /// Events sent before the handshake has finished are held until afterwards.
+    pub fn send_event(&self, event: impl Into<GatewayEvent>) {
+        let _ = self.events.send(event.into());
+    }
+
+    /// Sends an RTP packet to the connected driver's UDP socket.
+    ///
+    /// The packet must be encrypted using [`test_cipher`], i.e., via [`rtp_packet`].
+    /// Packets sent before the handshake has finished are held until afterwards.
+    pub fn send_rtp(&self, packet: Vec<u8>) {
+        let _ = self.rtp.send(packet);
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+type MockStream = WebSocketStream<TokioAdapter<TcpStream>>;
+
+type MockResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const MOCK_MODES: [CryptoMode; 3] = [CryptoMode::Normal, CryptoMode::Suffix, CryptoMode::Lite];
+
+async fn mock_server(
+    listener: TcpListener,
+    udp: UdpSocket,
+    tx: Sender<MockPacket>,
+    events: Receiver<GatewayEvent>,
+    rtp: Receiver<Vec<u8>>,
+) -> MockResult<()> {
+    loop {
+        let (tcp, _) = listener.accept().await?;
+        let ws = accept_async(tcp).await?;
+
+        if let Err(e) = mock_session(ws, &udp, &tx, &events, &rtp).await {
+            warn!("Mock session failed: {:?}", e);
+        }
+    }
+}
+
+async fn mock_session(
+    mut ws: MockStream,
+    udp: &UdpSocket,
+    tx: &Sender<MockPacket>,
+    events: &Receiver<GatewayEvent>,
+    rtp: &Receiver<Vec<u8>>,
+) -> MockResult<()> {
+    expect_op(&mut ws, 0).await?;
+    send_event(
+        &mut ws,
+        Hello {
+            heartbeat_interval: MOCK_HEARTBEAT_INTERVAL,
+        },
+    )
+    .await?;
+    send_event(
+        &mut ws,
+        Ready {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            modes: MOCK_MODES
+                .iter()
+                .map(|m| m.to_request_str().into())
+                .collect(),
+            port: udp.local_addr()?.port(),
+            ssrc: MOCK_SSRC,
+        },
+    )
+    .await?;
+
+    // Audio from an earlier session may still be in flight.
+    let mut buf = [0u8; VOICE_PACKET_MAX];
+    let peer = loop {
+        let (len, peer) = udp.recv_from(&mut buf).await?;
+
+        if is_discovery_request(&buf[..len]) {
+            break peer;
+        }
+    };
+
+    udp.send_to(&discovery_response(peer), peer).await?;
+
+    let select = expect_op(&mut ws, 1).await?;
+    let requested = select["d"]["data"]["mode"].as_str().unwrap_or_default();
+    let mode = MOCK_MODES
+        .iter()
+        .copied()
+        .find(|m| m.to_request_str() == requested)
+        .ok_or("unknown crypto mode selected")?;
+    send_event(
+        &mut ws,
+        SessionDescription {
+            mode: requested.into(),
+            secret_key: TEST_KEY.to_vec(),
+        },
+    )
+    .await?;
+
+    let cipher = test_cipher();
+
+    loop {
+        tokio::select! {
+            value = next_json(&mut ws) => {
+                let value = match value? {
+                    Some(value) => value,
+                    None => break,
+                };
+
+                match value["op"].as_u64() {
+                    Some(1) => send_event(
+                        &mut ws,
+                        SessionDescription {
+                            mode: requested.into(),
+                            secret_key: TEST_KEY.to_vec(),
+                        },
+                    )
+                    .await?,
+                    Some(3) =>
+                        if let Some(nonce) = heartbeat_nonce(&value) {
+                            send_event(&mut ws, HeartbeatAck { nonce }).await?;
+                        },
+                    _ => {},
+                }
+            },
+            event = events.recv_async() => match event {
+                Ok(event) => send_event(&mut ws, event).await?,
+                Err(_) => break,
+            },
+            packet = rtp.recv_async() => match packet {
+                Ok(packet) => {
+                    udp.send_to(&packet, peer).await?;
+                },
+                Err(_) => break,
+            },
+            len = udp.recv(&mut buf) => {
+                let len = len?;
+
+                // Drivers measure latency with further discovery requests.
+                if is_discovery_request(&buf[..len]) {
+                    udp.send_to(&discovery_response(peer), peer).await?;
+                    continue;
+                }
+
+                if let DemuxedMut::Rtp(mut rtp) = demux::demux_mut(&mut buf[..len]) {
+                    let (start, end_pad) = match mode.decrypt_in_place(&mut rtp, &cipher) {
+                        Ok(pads) => pads,
+                        Err(_) => continue,
+                    };
+                    let payload = rtp.payload();
+
+                    let _ = tx.send(MockPacket {
+                        ssrc: rtp.get_ssrc(),
+                        sequence: rtp.get_sequence().into(),
+                        timestamp: rtp.get_timestamp().into(),
+                        opus: payload[start..payload.len() - end_pad].to_vec(),
+                    });
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn is_discovery_request(packet: &[u8]) -> bool {
+    IpDiscoveryPacket::new(packet)
+        .map(|view| view.get_pkt_type() == IpDiscoveryType::Request)
+        .unwrap_or(false)
+}
+
+/// Builds a discovery response telling `peer` its own address.
+fn discovery_response(peer: SocketAddr) -> [u8; IpDiscoveryPacket::const_packet_size()] {
+    let mut bytes = [0u8; IpDiscoveryPacket::const_packet_size()];
+    {
+        let mut view = MutableIpDiscoveryPacket::new(&mut bytes[..])
+            .expect("Buffer is sized to hold an IP discovery packet.");
+        let address = peer.ip().to_string();
+        view.set_pkt_type(IpDiscoveryType::Response);
+        view.set_length(70);
+        view.set_ssrc(MOCK_SSRC);
+        view.get_address_raw_mut()[..address.len()].copy_from_slice(address.as_bytes());
+        view.set_port(peer.port());
+    }
+
+    bytes
+}
+
+async fn next_json(ws: &mut MockStream) -> MockResult<Option<Value>> {
+    while let Some(msg) = ws.next().await {
+        match msg? {
+            Message::Text(text) => return Ok(Some(serde_json::from_str(&text)?)),
+            Message::Close(_) => break,
+            _ => {},
+        }
+    }
+
+    Ok(None)
+}
+
+async fn expect_op(ws: &mut MockStream, op: u64) -> MockResult<Value> {
+    loop {
+        let value = next_json(ws)
+            .await?
+            .ok_or("gateway closed during handshake")?;
+
+        if value["op"].as_u64() == Some(op) {
+            return Ok(value);
+        }
+    }
+}
+
+async fn send_event(ws: &mut MockStream, event: impl Into<GatewayEvent>) -> MockResult<()> {
+    let text = serde_json::to_string(&event.into())?;
+    ws.send(Message::Text(text)).await?;
+
+    Ok(())
+}
+
+/// Newer gateway versions nest the heartbeat nonce alongside `seq_ack`.
+fn heartbeat_nonce(value: &Value) -> Option<u64> {
+    value["d"]["t"].as_u64().or_else(|| value["d"].as_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::{context_data::DisconnectReason, CoreEvent, EventContext},
+        input::{streaming, tone, CodecType, Input, StreamingSource, Waveform},
+        driver::{ReceiveFilter, ReceiveTarget, ReplayError},
+        tracks::PlayError,
+        Event,
+        EventHandler,
+    };
+    use async_trait::async_trait;
+    use std::{io::Result as IoResult, time::Duration};
+    use typemap_rev::TypeMapKey;
+
+    struct VoiceForwarder(Sender<(u32, Vec<u8>)>);
+
+    #[async_trait]
+    impl EventHandler for VoiceForwarder {
+        async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+            if let EventContext::VoicePacket(data) = ctx {
+                let body = &data.packet.payload[data.payload_offset..];
+                let body = &body[..body.len() - data.payload_end_pad];
+                let _ = self.0.send((data.packet.ssrc, body.to_vec()));
+            }
+
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn injected_rtp_fires_voice_events() {
+        let mut driver = Driver::new(Default::default());
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::VoicePacket.into(), VoiceForwarder(tx));
+
+        let opus = [0xf8, 0xff, 0xfe];
+        let packet = rtp_packet(&test_cipher(), CryptoMode::Normal, 1234, 1, 960, &opus);
+        inject_rtp(&mut driver, packet);
+
+        let (ssrc, body) = tokio::time::timeout(Duration::from_secs(5), rx.recv_async())
+            .await
+            .expect("Voice packet event fired.")
+            .unwrap();
+        assert_eq!(ssrc, 1234);
+        assert_eq!(body, opus);
+
+        // Packets which fail to decrypt are not delivered as voice.
+        let mut packet = rtp_packet(&test_cipher(), CryptoMode::Normal, 1234, 2, 1920, &opus);
+        let last = packet.len() - 1;
+        packet[last] ^= 0xff;
+        inject_rtp(&mut driver, packet);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rx.try_recv().map(|(_, body)| body != opus).unwrap_or(true));
+    }
+
+    #[tokio::test]
+    async fn paused_receive_drops_voice_packets() {
+        let mut driver = Driver::new(Default::default());
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::VoicePacket.into(), VoiceForwarder(tx));
+
+        let opus = [0xf8, 0xff, 0xfe];
+        let recv = || tokio::time::timeout(Duration::from_secs(5), rx.recv_async());
+        let packet = |seq: u16| {
+            rtp_packet(&test_cipher(), CryptoMode::Normal, 1234, seq, 960 * seq as u32, &opus)
+        };
+
+        inject_rtp(&mut driver, packet(1));
+        assert!(recv().await.is_ok());
+
+        driver.set_receiving(false);
+        assert!(!driver.is_receiving());
+        inject_rtp(&mut driver, packet(2));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rx.try_recv().is_err());
+
+        driver.set_receiving(true);
+        inject_rtp(&mut driver, packet(3));
+        let (ssrc, _) = recv().await.expect("Voice packet event fired.").unwrap();
+        assert_eq!(ssrc, 1234);
+    }
+
+    struct ConnectForwarder(Sender<u32>);
+
+    #[async_trait]
+    impl EventHandler for ConnectForwarder {
+        async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+            if let EventContext::DriverConnect(data) = ctx {
+                let _ = self.0.send(data.ssrc);
+            }
+
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn driver_connects_and_sends_to_mock_server() {
+        let server = MockServer::start().await.unwrap();
+        let mut driver = Driver::new(Default::default());
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::DriverConnect.into(), ConnectForwarder(tx));
+
+        driver
+            .connect(server.connection_info())
+            .await
+            .expect("Handshake with mock server succeeded.");
+
+        let ssrc = tokio::time::timeout(Duration::from_secs(5), rx.recv_async())
+            .await
+            .expect("Driver connect event fired.")
+            .unwrap();
+        assert_eq!(ssrc, MOCK_SSRC);
+
+        driver.play_source(tone(440.0, None, Waveform::Sine, 0.25));
+
+        let mut last_sequence = None;
+        for _ in 0..5 {
+            let packet =
+                tokio::time::timeout(Duration::from_secs(5), server.packets().recv_async())
+                    .await
+                    .expect("Driver sent audio to mock server.")
+                    .unwrap();
+
+            assert_eq!(packet.ssrc, MOCK_SSRC);
+            assert!(!packet.opus.is_empty());
+            if let Some(last) = last_sequence {
+                assert_eq!(packet.sequence, u16::wrapping_add(last, 1));
+            }
+            last_sequence = Some(packet.sequence);
+        }
+    }
+
+    struct LatencyForwarder(Sender<Duration>);
+
+    #[async_trait]
+    impl EventHandler for LatencyForwarder {
+        async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+            if let EventContext::Latency(latency) = ctx {
+                let _ = self.0.send(*latency);
+            }
+
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn latency_updates_follow_udp_probes() {
+        let server = MockServer::start().await.unwrap();
+        let config = Config::default().latency_interval(Some(Duration::from_millis(50)));
+        let mut driver = Driver::new(config);
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::Latency.into(), LatencyForwarder(tx));
+
+        driver.connect(server.connection_info()).await.unwrap();
+        assert!(driver.latency().await.is_some());
+
+        // Probes are answered over UDP, well before the mock server's
+        // requested heartbeat interval elapses.
+        for _ in 0..2 {
+            let latency = tokio::time::timeout(Duration::from_secs(2), rx.recv_async())
+                .await
+                .expect("Latency event fired.")
+                .unwrap();
+            assert!(latency < Duration::from_secs(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn listener_count_follows_gateway_events() {
+        use crate::model::{
+            id::UserId as ModelUserId,
+            payload::{ClientConnect, ClientDisconnect, Speaking},
+            SpeakingState,
+        };
+
+        let server = MockServer::start().await.unwrap();
+        let mut driver = Driver::new(Default::default());
+        driver.connect(server.connection_info()).await.unwrap();
+
+        // Nobody has been announced yet, so the count is unknown.
+        assert_eq!(driver.listener_count().await, None);
+
+        async fn await_count(driver: &mut Driver, count: usize) {
+            for _ in 0..500 {
+                if driver.listener_count().await == Some(count) {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(2)).await;
+            }
+            panic!("Listener count never reached {}.", count);
+        }
+
+        // Silent users are counted as they connect...
+        server.send_event(ClientConnect {
+            audio_ssrc: 10,
+            user_id: ModelUserId(10),
+            video_ssrc: 0,
+        });
+        await_count(&mut driver, 1).await;
+
+        // ...alongside those who speak...
+        server.send_event(Speaking {
+            delay: None,
+            speaking: SpeakingState::MICROPHONE,
+            ssrc: 11,
+            user_id: Some(ModelUserId(11)),
+        });
+        await_count(&mut driver, 2).await;
+
+        // ...until they leave.
+        server.send_event(ClientDisconnect {
+            user_id: ModelUserId(10),
+        });
+        await_count(&mut driver, 1).await;
+    }
+
+    struct RenegotiationForwarder(Sender<()>);
+
+    #[async_trait]
+    impl EventHandler for RenegotiationForwarder {
+        async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+            if let EventContext::CryptoRenegotiated(_) = ctx {
+                let _ = self.0.send(());
+            }
+
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn crypto_is_renegotiated_only_on_request() {
+        use crate::model::{id::UserId as ModelUserId, payload::ClientConnect};
+
+        let server = MockServer::start().await.unwrap();
+        let mut driver = Driver::new(Default::default());
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(
+            CoreEvent::CryptoRenegotiated.into(),
+            RenegotiationForwarder(tx),
+        );
+        driver.connect(server.connection_info()).await.unwrap();
+
+        // Events reach the driver in order, so once a later client is counted,
+        // the core task has been handed any description sent before it.
+        async fn send_unrequested(server: &MockServer, driver: &mut Driver, user: u64) {
+            server.send_event(SessionDescription {
+                mode: CryptoMode::Normal.to_request_str().into(),
+                secret_key: TEST_KEY.to_vec(),
+            });
+            server.send_event(ClientConnect {
+                audio_ssrc: user as u32,
+                user_id: ModelUserId(user),
+                video_ssrc: 0,
+            });
+
+            for _ in 0..500 {
+                if driver.listener_count().await == Some(user as usize) {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(2)).await;
+            }
+            panic!("Client {} was never counted.", user);
+        }
+
+        let no_event = |rx: &Receiver<()>| {
+            let rx = rx.clone();
+            async move {
+                tokio::time::timeout(Duration::from_millis(100), rx.recv_async())
+                    .await
+                    .is_err()
+            }
+        };
+
+        send_unrequested(&server, &mut driver, 1).await;
+        assert!(no_event(&rx).await);
+
+        driver.renegotiate_crypto();
+        tokio::time::timeout(Duration::from_secs(5), rx.recv_async())
+            .await
+            .expect("Renegotiation completed.")
+            .unwrap();
+
+        send_unrequested(&server, &mut driver, 2).await;
+        assert!(no_event(&rx).await);
+
+        // Audio is still sent under the renegotiated session.
+        driver.play_source(tone(440.0, None, Waveform::Sine, 0.25));
+        let packet = tokio::time::timeout(Duration::from_secs(5), server.packets().recv_async())
+            .await
+            .expect("Driver sent audio after renegotiating.")
+            .unwrap();
+        assert!(!packet.opus.is_empty());
+    }
+
+    struct RecreationForwarder(Sender<()>);
+
+    #[async_trait]
+    impl EventHandler for RecreationForwarder {
+        async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+            if let EventContext::DriverRecreated = ctx {
+                let _ = self.0.send(());
+            }
+
+            None
+        }
+    }
+
+    async fn await_failure(driver: &Driver) {
+        for _ in 0..500 {
+            if driver.check_tasks().await == Some(false) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        panic!("Driver tasks never failed.");
+    }
+
+    #[tokio::test]
+    async fn failed_mixers_are_restarted_and_reconnect() {
+        let server = MockServer::start().await.unwrap();
+        let mut driver = Driver::new(Default::default());
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::DriverRecreated.into(), RecreationForwarder(tx));
+        driver.connect(server.connection_info()).await.unwrap();
+
+        let first = driver.enqueue_source(tone(440.0, None, Waveform::Sine, 0.25));
+        driver.enqueue_source(tone(440.0, None, Waveform::Sine, 0.25));
+
+        // Stand in for a bug which takes down the mixer thread.
+        first.action(|_| panic!("Simulated mixer failure.")).unwrap();
+        await_failure(&driver).await;
+
+        assert!(!driver.restart_tasks());
+        tokio::time::timeout(Duration::from_secs(5), rx.recv_async())
+            .await
+            .expect("Driver recreated event fired.")
+            .unwrap();
+
+        // Queued tracks died with the mixer, but audio can be replayed.
+        assert!(driver.queue().is_empty());
+        assert_eq!(driver.check_tasks().await, Some(true));
+
+        server.packets().drain();
+        driver.play_source(tone(440.0, None, Waveform::Sine, 0.25));
+        tokio::time::timeout(Duration::from_secs(5), server.packets().recv_async())
+            .await
+            .expect("Restarted mixer sent audio.")
+            .unwrap();
+    }
+
+    struct Panicker;
+
+    #[async_trait]
+    impl EventHandler for Panicker {
+        async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
+            panic!("Simulated event handler failure.");
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_event_processors_keep_the_queue_moving() {
+        use crate::driver::retry::Retry;
+
+        let server = MockServer::start().await.unwrap();
+        let config = Config::default().driver_retry(Retry {
+            retry_limit: Some(0),
+            ..Default::default()
+        });
+        let mut driver = Driver::new(config);
+
+        let first = driver.enqueue_source(tone(
+            440.0,
+            Some(Duration::from_millis(60)),
+            Waveform::Sine,
+            0.25,
+        ));
+        driver.enqueue_source(tone(440.0, None, Waveform::Sine, 0.25));
+
+        // A failed connection attempt sets off a handler which takes down the
+        // event processor, and every track event along with it.
+        driver.add_global_event(CoreEvent::DriverDisconnect.into(), Panicker);
+        let mut unreachable = server.connection_info();
+        unreachable.endpoint = "ws://127.0.0.1:1".into();
+        assert!(driver.connect(unreachable).await.is_err());
+        await_failure(&driver).await;
+
+        assert!(!driver.restart_tasks());
+        assert_eq!(driver.queue().len(), 2);
+
+        driver.connect(server.connection_info()).await.unwrap();
+        for _ in 0..500 {
+            if driver.queue().len() == 1 {
+                assert!(first.get_info().await.is_err());
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        panic!("Queue never moved on from its first track.");
+    }
+
+    #[tokio::test]
+    async fn try_play_reports_whether_sources_start() {
+        let server = MockServer::start().await.unwrap();
+        let mut driver = Driver::new(Default::default());
+        driver.connect(server.connection_info()).await.unwrap();
+
+        let empty = Input::float_pcm(false, Vec::<u8>::new().into());
+        assert_eq!(driver.try_play(empty).await.err(), Some(PlayError::Ended));
+
+        let handle = driver
+            .try_play(tone(440.0, None, Waveform::Sine, 0.25))
+            .await
+            .expect("Tone produced audio.");
+        assert!(handle.get_info().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn pending_replays_do_not_borrow_the_driver() {
+        let config = Config::default().receive_history(Some(Duration::from_secs(1)));
+        let mut driver = Driver::new(config);
+
+        let replay = driver.replay_user(1, Duration::from_secs(1));
+        driver.set_receiving(false);
+
+        // Nothing is held while disconnected.
+        assert!(matches!(replay.await, Err(ReplayError::NoAudio)));
+    }
+
+    /// Plays back Opus frames taken from a listening driver.
+    struct Relay(Receiver<(u32, Vec<u8>)>);
+
+    #[async_trait]
+    impl StreamingSource for Relay {
+        async fn next_chunk(&mut self) -> IoResult<Option<Vec<u8>>> {
+            Ok(self.0.recv_async().await.ok().map(|(_, body)| body))
+        }
+    }
+
+    /// Marks a relayed track with the SSRC it was forwarded from.
+    struct RelayedFrom;
+
+    impl TypeMapKey for RelayedFrom {
+        type Value = u32;
+    }
+
+    #[tokio::test]
+    async fn bridged_audio_is_not_relayed_back() {
+        let server = MockServer::start().await.unwrap();
+        let mut speaker = Driver::new(Default::default());
+        assert_eq!(speaker.ssrc().await, None);
+
+        speaker.connect(server.connection_info()).await.unwrap();
+        let own_ssrc = speaker.ssrc().await.expect("Connected driver has an SSRC.");
+        assert_eq!(own_ssrc, MOCK_SSRC);
+
+        let mut listener = Driver::new(Default::default());
+        listener.set_receive_filter(ReceiveFilter::Deny(vec![ReceiveTarget::Ssrc(own_ssrc)]));
+        let (tx, rx) = flume::unbounded();
+        listener.add_global_event(CoreEvent::VoicePacket.into(), VoiceForwarder(tx));
+
+        let handle = speaker.play_source(streaming(Relay(rx), CodecType::Opus, true).unwrap());
+        handle.typemap().write().await.insert::<RelayedFrom>(1234);
+
+        // The speaker's own output, heard by the listener, must not be relayed again.
+        let echo = [0xf8, 0x0e, 0xc0];
+        let voice = [0xf8, 0x01, 0x02, 0x03];
+        let cipher = test_cipher();
+        inject_rtp(&mut listener, rtp_packet(&cipher, CryptoMode::Normal, own_ssrc, 1, 960, &echo));
+        inject_rtp(&mut listener, rtp_packet(&cipher, CryptoMode::Normal, 1234, 1, 960, &voice));
+
+        let relayed = loop {
+            let packet =
+                tokio::time::timeout(Duration::from_secs(5), server.packets().recv_async())
+                    .await
+                    .expect("Speaker relayed audio to mock server.")
+                    .unwrap();
+
+            if packet.opus != SILENT_FRAME {
+                break packet;
+            }
+        };
+
+        assert_eq!(relayed.ssrc, own_ssrc);
+        assert_eq!(relayed.opus, voice);
+        assert_eq!(handle.typemap().read().await.get::<RelayedFrom>(), Some(&1234));
+    }
+
+    #[tokio::test]
+    async fn user_ssrcs_are_forgotten_between_sessions() {
+        use crate::model::{id::UserId as ModelUserId, payload::Speaking, SpeakingState};
+
+        let server = MockServer::start().await.unwrap();
+        let filter = ReceiveFilter::Allow(vec![ReceiveTarget::User(UserId(7))]);
+        let mut driver = Driver::new(Config::default().receive_filter(filter));
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::VoicePacket.into(), VoiceForwarder(tx));
+
+        let speaking = |ssrc| Speaking {
+            delay: None,
+            speaking: SpeakingState::MICROPHONE,
+            ssrc,
+            user_id: Some(ModelUserId(7)),
+        };
+        let mut seq = 0;
+        let mut packet = |ssrc| {
+            seq += 1;
+            rtp_packet(&test_cipher(), CryptoMode::Normal, ssrc, seq, 960 * seq as u32, &[1])
+        };
+
+        // Packets may beat the speaking update to the receive task, so keep sending
+        // until one arrives. Every packet heard must come from the expected SSRC.
+        async fn await_voice(
+            server: &MockServer,
+            rx: &Receiver<(u32, Vec<u8>)>,
+            mut packets: impl FnMut() -> Vec<Vec<u8>>,
+            ssrc: u32,
+        ) {
+            for _ in 0..100 {
+                for packet in packets() {
+                    server.send_rtp(packet);
+                }
+                let heard = tokio::time::timeout(Duration::from_millis(50), rx.recv_async());
+                if let Ok(Ok((heard, _))) = heard.await {
+                    assert_eq!(heard, ssrc);
+                    return;
+                }
+            }
+            panic!("Never heard voice from SSRC {}.", ssrc);
+        }
+
+        driver.connect(server.connection_info()).await.unwrap();
+        server.send_event(speaking(1234));
+        await_voice(&server, &rx, || vec![packet(1234)], 1234).await;
+
+        driver.leave();
+        driver.connect(server.connection_info()).await.unwrap();
+        rx.drain();
+
+        // The user now speaks from a new SSRC: their old one must not map to them.
+        server.send_event(speaking(5678));
+        await_voice(&server, &rx, || vec![packet(1234), packet(5678)], 5678).await;
+    }
+
+    struct DisconnectForwarder(Sender<Option<DisconnectReason>>);
+
+    #[async_trait]
+    impl EventHandler for DisconnectForwarder {
+        async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+            if let EventContext::DriverDisconnect(data) = ctx {
+                let _ = self.0.send(data.reason);
+            }
+
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_stop_once_the_window_elapses() {
+        use crate::driver::retry::{Retry, Strategy};
+
+        // Nothing listens on this port once the listener is dropped.
+        let addr = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let info = ConnectionInfo {
+            channel_id: None,
+            endpoint: format!("ws://{}", addr),
+            guild_id: GuildId(1),
+            session_id: "mock-session".into(),
+            token: "mock-token".into(),
+            user_id: UserId(2),
+        };
+
+        let window = Duration::from_millis(150);
+        let config = Config::default()
+            .driver_retry(Retry {
+                strategy: Strategy::Every(Duration::from_millis(20)),
+                retry_limit: None,
+            })
+            .reconnect_window(Some(window));
+        let mut driver = Driver::new(config);
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::DriverDisconnect.into(), DisconnectForwarder(tx));
+
+        let start = std::time::Instant::now();
+        assert!(driver.connect(info).await.is_err());
+        let elapsed = start.elapsed();
+        assert!(elapsed >= window - Duration::from_millis(20), "{:?}", elapsed);
+
+        let reason = tokio::time::timeout(Duration::from_secs(5), rx.recv_async())
+            .await
+            .expect("Driver disconnect event fired.")
+            .unwrap();
+        assert_eq!(reason, Some(DisconnectReason::ReconnectExhausted));
+    }
+
+    #[tokio::test]
+    async fn abandoned_tasks_drop_the_connection() {
+        let server = MockServer::start().await.unwrap();
+        let mut driver = Driver::new(Default::default());
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::DriverDisconnect.into(), DisconnectForwarder(tx));
+        driver.connect(server.connection_info()).await.unwrap();
+
+        driver.signal_tasks_failed();
+
+        let reason = tokio::time::timeout(Duration::from_secs(5), rx.recv_async())
+            .await
+            .expect("Driver disconnect event fired.")
+            .unwrap();
+        assert_eq!(reason, Some(DisconnectReason::TasksFailed));
+    }
+
+    struct ThreadForwarder(Sender<Option<String>>);
+
+    #[async_trait]
+    impl EventHandler for ThreadForwarder {
+        async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
+            let _ = self.0.send(std::thread::current().name().map(String::from));
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn driver_tasks_run_on_the_chosen_runtime() {
+        // A runtime driven by its own named thread, until `stop` is dropped.
+        let (handle_tx, handle_rx) = flume::bounded(1);
+        let (stop, stopped) = flume::bounded::<()>(1);
+        let runtime = std::thread::Builder::new()
+            .name("songbird-audio".into())
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                let _ = handle_tx.send(runtime.handle().clone());
+                let _ = runtime.block_on(stopped.recv_async());
+            })
+            .unwrap();
+
+        let config = Config::default().spawn_on(Some(handle_rx.recv_async().await.unwrap()));
+        let mut driver = Driver::new(config);
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::VoicePacket.into(), ThreadForwarder(tx));
+
+        let packet = rtp_packet(&test_cipher(), CryptoMode::Normal, 1234, 1, 960, &[1]);
+        inject_rtp(&mut driver, packet);
+
+        let thread = tokio::time::timeout(Duration::from_secs(5), rx.recv_async())
+            .await
+            .expect("Voice packet event fired.")
+            .unwrap();
+        assert_eq!(thread.as_deref(), Some("songbird-audio"));
+
+        drop(driver);
+        drop(stop);
+        runtime.join().unwrap();
+    }
+
+    struct LoggingForwarder(Sender<()>);
+
+    #[async_trait]
+    impl EventHandler for LoggingForwarder {
+        async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
+            tracing::info!("Handling voice packet.");
+            let _ = self.0.send(());
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn driver_tasks_run_inside_the_configured_span() {
+        use parking_lot::Mutex;
+        use std::{
+            collections::HashMap,
+            sync::atomic::{AtomicU64, Ordering},
+            thread::{self, ThreadId},
+        };
+        use tracing::{
+            span::{Attributes, Id, Record},
+            Metadata,
+            Subscriber,
+        };
+        use tracing_core::span::Current;
+
+        /// Records the spans, and their ancestors, which were entered on the current
+        /// thread whenever an event is logged.
+        #[derive(Default)]
+        struct SpanRecorder {
+            next_id: AtomicU64,
+            spans: Mutex<HashMap<u64, (&'static Metadata<'static>, Option<u64>)>>,
+            entered: Mutex<HashMap<ThreadId, Vec<u64>>>,
+            logged: Mutex<Vec<Vec<u64>>>,
+        }
+
+        impl SpanRecorder {
+            fn innermost(&self) -> Option<u64> {
+                let entered = self.entered.lock();
+                entered.get(&thread::current().id()).and_then(|ids| ids.last().copied())
+            }
+        }
+
+        impl Subscriber for SpanRecorder {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+                let parent = match span.parent() {
+                    Some(parent) => Some(parent.into_u64()),
+                    None if span.is_contextual() => self.innermost(),
+                    None => None,
+                };
+                self.spans.lock().insert(id, (span.metadata(), parent));
+                Id::from_u64(id)
+            }
+
+            fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+            fn event(&self, _event: &tracing::Event<'_>) {
+                let spans = self.spans.lock();
+                let mut scope = vec![];
+                let mut next = self.innermost();
+                while let Some(id) = next {
+                    scope.push(id);
+                    next = spans[&id].1;
+                }
+                self.logged.lock().push(scope);
+            }
+
+            fn enter(&self, span: &Id) {
+                let mut entered = self.entered.lock();
+                entered.entry(thread::current().id()).or_default().push(span.into_u64());
+            }
+
+            fn exit(&self, span: &Id) {
+                if let Some(entered) = self.entered.lock().get_mut(&thread::current().id()) {
+                    if let Some(i) = entered.iter().rposition(|id| *id == span.into_u64()) {
+                        entered.remove(i);
+                    }
+                }
+            }
+
+            fn current_span(&self) -> Current {
+                match self.innermost() {
+                    Some(id) => Current::new(Id::from_u64(id), self.spans.lock()[&id].0),
+                    None => Current::none(),
+                }
+            }
+        }
+
+        let recorder = Arc::new(SpanRecorder::default());
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+
+        let span = tracing::info_span!("call", guild_id = 1);
+        let span_id = span.id().expect("Span is enabled.").into_u64();
+        let mut driver = Driver::new(Config::default().tracing_span(Some(span)));
+        let (tx, rx) = flume::unbounded();
+        driver.add_global_event(CoreEvent::VoicePacket.into(), LoggingForwarder(tx));
+
+        let packet = rtp_packet(&test_cipher(), CryptoMode::Normal, 1234, 1, 960, &[1]);
+        inject_rtp(&mut driver, packet);
+
+        tokio::time::timeout(Duration::from_secs(5), rx.recv_async())
+            .await
+            .expect("Voice packet event fired.")
+            .unwrap();
+
+        let logged = recorder.logged.lock();
+        assert!(!logged.is_empty());
+        assert!(logged.iter().all(|scope| scope.contains(&span_id)));
+    }
+}
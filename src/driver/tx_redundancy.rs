@@ -0,0 +1,110 @@
+use crate::constants::*;
+use discortp::pnet::packet::PrimitiveValues;
+
+/// Largest redundant block which can be described by an RFC 2198 header.
+const RED_MAX_BLOCK_LEN: usize = 0x3ff;
+
+/// Size of an RFC 2198 header describing a redundant block.
+const RED_BLOCK_HEADER_LEN: usize = 4;
+
+/// Packet-level redundancy applied to outgoing audio, trading bandwidth
+/// for resilience on lossy links.
+///
+/// **This is experimental.** Discord's voice servers and official clients expect
+/// plain Opus packets: only [`Duplicate`] is understood by standard receivers,
+/// while [`Red`] is only useful alongside custom receivers.
+///
+/// [`Duplicate`]: TxRedundancy::Duplicate
+/// [`Red`]: TxRedundancy::Red
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TxRedundancy {
+    /// Each packet is sent once.
+    None,
+    /// Each packet is sent twice.
+    ///
+    /// Receivers discard whichever copy arrives second, so this is compatible
+    /// with Discord. This doubles outgoing bandwidth.
+    Duplicate,
+    /// Each packet also carries the previous frame's Opus data, as described
+    /// in [RFC 2198].
+    ///
+    /// These packets are sent with RTP payload type [`RTP_RED_PROFILE_TYPE`],
+    /// and *will not* be decoded by Discord clients.
+    ///
+    /// [RFC 2198]: https://tools.ietf.org/html/rfc2198
+    /// [`RTP_RED_PROFILE_TYPE`]: crate::constants::RTP_RED_PROFILE_TYPE
+    Red,
+}
+
+/// Returns the length of RFC 2198 headers and redundant data which will be
+/// placed before a frame, given the previous frame's Opus data.
+pub(crate) fn red_prefix_len(previous: &[u8]) -> usize {
+    if previous.is_empty() || previous.len() > RED_MAX_BLOCK_LEN {
+        1
+    } else {
+        RED_BLOCK_HEADER_LEN + 1 + previous.len()
+    }
+}
+
+/// Writes RFC 2198 headers and the previous frame's Opus data into the start of `buf`.
+///
+/// `buf` must have space for [`red_prefix_len`] bytes.
+pub(crate) fn write_red_prefix(buf: &mut [u8], previous: &[u8]) {
+    let (opus_type,) = RTP_PROFILE_TYPE.to_primitive_values();
+
+    if red_prefix_len(previous) == 1 {
+        buf[0] = opus_type;
+        return;
+    }
+
+    // The previous frame always sits one frame's worth of samples earlier.
+    let offset = MONO_FRAME_SIZE;
+    let len = previous.len();
+
+    buf[0] = 0x80 | opus_type;
+    buf[1] = (offset >> 6) as u8;
+    buf[2] = (((offset & 0x3f) << 2) | (len >> 8)) as u8;
+    buf[3] = len as u8;
+    buf[4] = opus_type;
+    buf[RED_BLOCK_HEADER_LEN + 1..][..len].copy_from_slice(previous);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn red_prefix_describes_previous_frame() {
+        let previous = [0xaa; 300];
+        let len = red_prefix_len(&previous);
+        assert_eq!(len, RED_BLOCK_HEADER_LEN + 1 + previous.len());
+
+        let mut buf = vec![0u8; len];
+        write_red_prefix(&mut buf, &previous);
+
+        // Redundant block header: F bit set, Opus payload type.
+        assert_eq!(buf[0], 0x80 | 120);
+        let offset = ((buf[1] as usize) << 6) | (buf[2] as usize >> 2);
+        let block_len = (((buf[2] & 0x03) as usize) << 8) | buf[3] as usize;
+        assert_eq!(offset, MONO_FRAME_SIZE);
+        assert_eq!(block_len, previous.len());
+
+        // Primary block header, followed by the redundant data.
+        assert_eq!(buf[4], 120);
+        assert_eq!(&buf[5..], &previous[..]);
+    }
+
+    #[test]
+    fn red_prefix_omits_unusable_frames() {
+        let oversized = [0xaa; RED_MAX_BLOCK_LEN + 1];
+
+        for previous in [&[][..], &oversized[..]] {
+            assert_eq!(red_prefix_len(previous), 1);
+
+            let mut buf = [0xffu8; 1];
+            write_red_prefix(&mut buf, previous);
+            assert_eq!(buf[0], 120);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use socket2::SockRef;
+use std::io::Result as IoResult;
+use tokio::net::UdpSocket;
+use tracing::{debug, warn};
+
+/// Options applied to the driver's UDP socket once it has been created.
+///
+/// These are applied on a best-effort basis: any option which the host platform
+/// does not support, or which the OS refuses, is logged and otherwise ignored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct UdpSocketOptions {
+    /// Differentiated Services Code Point marked on each outgoing voice packet.
+    ///
+    /// This allows network equipment to prioritise voice traffic, *e.g.*, using
+    /// `46` for Expedited Forwarding. Only the low 6 bits are used.
+    ///
+    /// Defaults to `None`, which leaves the OS default marking.
+    pub dscp: Option<u8>,
+    /// Size of the socket's send buffer, in bytes.
+    ///
+    /// The OS may round or clamp this value.
+    ///
+    /// Defaults to `None`, which leaves the OS default size.
+    pub send_buffer_size: Option<usize>,
+    /// Size of the socket's receive buffer, in bytes.
+    ///
+    /// Large, busy channels can deliver bursts of packets faster than the receive
+    /// task wakes to read them: the OS drops any which do not fit in this buffer.
+    /// [`Driver::socket_dropped`] reports how often this happens, where supported.
+    /// The OS may round or clamp this value.
+    ///
+    /// Defaults to `None`, which leaves the OS default size.
+    ///
+    /// [`Driver::socket_dropped`]: crate::driver::Driver::socket_dropped
+    pub recv_buffer_size: Option<usize>,
+    /// Maximum number of packets read from the socket each time the receive task wakes.
+    ///
+    /// On Linux, each batch is read in a single `recvmmsg(2)` call, saving a system call
+    /// per packet. Other platforms fall back to reading any packets which are already
+    /// queued, one at a time, without waiting. Values of `0` are treated as `1`.
+    ///
+    /// Defaults to `1`.
+    pub recv_batch: usize,
+}
+
+impl Default for UdpSocketOptions {
+    fn default() -> Self {
+        Self {
+            dscp: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+            recv_batch: 1,
+        }
+    }
+}
+
+impl UdpSocketOptions {
+    /// Sets the DSCP marking for outgoing packets.
+    pub fn dscp(mut self, dscp: Option<u8>) -> Self {
+        self.dscp = dscp;
+        self
+    }
+
+    /// Sets the size of the socket's send buffer.
+    pub fn send_buffer_size(mut self, send_buffer_size: Option<usize>) -> Self {
+        self.send_buffer_size = send_buffer_size;
+        self
+    }
+
+    /// Sets the size of the socket's receive buffer.
+    pub fn recv_buffer_size(mut self, recv_buffer_size: Option<usize>) -> Self {
+        self.recv_buffer_size = recv_buffer_size;
+        self
+    }
+
+    /// Sets the maximum number of packets read from the socket at once.
+    pub fn recv_batch(mut self, recv_batch: usize) -> Self {
+        self.recv_batch = recv_batch;
+        self
+    }
+
+    /// Applies each set option to `socket`, logging any which fail.
+    pub(crate) fn apply(&self, socket: &UdpSocket) {
+        let sock = SockRef::from(socket);
+
+        if let Some(dscp) = self.dscp {
+            match set_dscp(&sock, dscp) {
+                Ok(()) => debug!("Set UDP socket DSCP to {}.", dscp & 0x3f),
+                Err(e) => warn!("Failed to set UDP socket DSCP: {:?}", e),
+            }
+        }
+
+        if let Some(size) = self.send_buffer_size {
+            match sock.set_send_buffer_size(size) {
+                Ok(()) => debug!("Set UDP socket send buffer to {} bytes.", size),
+                Err(e) => warn!("Failed to set UDP socket send buffer size: {:?}", e),
+            }
+        }
+
+        if let Some(size) = self.recv_buffer_size {
+            match sock.set_recv_buffer_size(size) {
+                Ok(()) => debug!("Set UDP socket receive buffer to {} bytes.", size),
+                Err(e) => warn!("Failed to set UDP socket receive buffer size: {:?}", e),
+            }
+        }
+    }
+}
+
+fn set_dscp(sock: &SockRef<'_>, dscp: u8) -> IoResult<()> {
+    // DSCP occupies the upper 6 bits of the ToS byte, or IPv6's traffic class.
+    let tos = u32::from(dscp & 0x3f) << 2;
+
+    if sock.local_addr()?.as_socket_ipv6().is_some() {
+        set_tclass_v6(sock, tos)
+    } else {
+        set_tos_v4(sock, tos)
+    }
+}
+
+#[cfg(not(any(
+    target_os = "fuchsia",
+    target_os = "redox",
+    target_os = "solaris",
+    target_os = "illumos",
+)))]
+fn set_tos_v4(sock: &SockRef<'_>, tos: u32) -> IoResult<()> {
+    sock.set_tos(tos)
+}
+
+#[cfg(any(
+    target_os = "fuchsia",
+    target_os = "redox",
+    target_os = "solaris",
+    target_os = "illumos",
+))]
+fn set_tos_v4(_sock: &SockRef<'_>, _tos: u32) -> IoResult<()> {
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+
+    Err(IoError::new(
+        IoErrorKind::Unsupported,
+        "IP_TOS is not supported on this platform.",
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn set_tclass_v6(sock: &SockRef<'_>, tclass: u32) -> IoResult<()> {
+    use std::{io::Error as IoError, mem, os::unix::io::AsRawFd};
+
+    // socket2 0.4 (our MSRV-compatible line) does not expose `IPV6_TCLASS`.
+    let tclass = tclass as libc::c_int;
+    let res = unsafe {
+        libc::setsockopt(
+            sock.as_raw_fd(),
+            libc::IPPROTO_IPV6,
+            libc::IPV6_TCLASS,
+            &tclass as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+
+    if res == 0 {
+        Ok(())
+    } else {
+        Err(IoError::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_tclass_v6(_sock: &SockRef<'_>, _tclass: u32) -> IoResult<()> {
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+
+    Err(IoError::new(
+        IoErrorKind::Unsupported,
+        "IPV6_TCLASS is not supported on this platform.",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn options_are_applied_to_socket() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        UdpSocketOptions::default()
+            .dscp(Some(46))
+            .send_buffer_size(Some(64 * 1024))
+            .recv_buffer_size(Some(128 * 1024))
+            .apply(&socket);
+
+        let sock = SockRef::from(&socket);
+        assert!(sock.send_buffer_size().unwrap() >= 64 * 1024);
+        assert!(sock.recv_buffer_size().unwrap() >= 128 * 1024);
+        #[cfg(target_os = "linux")]
+        assert_eq!(sock.tos().unwrap(), 46 << 2);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn dscp_is_applied_to_ipv6_sockets() {
+        // Hosts without IPv6 have nothing to test.
+        let socket = match UdpSocket::bind("[::1]:0").await {
+            Ok(socket) => socket,
+            Err(_) => return,
+        };
+        UdpSocketOptions::default().dscp(Some(46)).apply(&socket);
+
+        let mut tclass: libc::c_int = 0;
+        let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let res = unsafe {
+            libc::getsockopt(
+                std::os::unix::io::AsRawFd::as_raw_fd(&socket),
+                libc::IPPROTO_IPV6,
+                libc::IPV6_TCLASS,
+                &mut tclass as *mut libc::c_int as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        assert_eq!(res, 0);
+        assert_eq!(tclass, 46 << 2);
+    }
+}
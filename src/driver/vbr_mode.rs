@@ -0,0 +1,29 @@
+use audiopus::{coder::Encoder as OpusEncoder, Result};
+
+/// Bitrate control used by the driver's Opus encoder.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum VbrMode {
+    /// Unconstrained variable bitrate.
+    ///
+    /// Each frame uses as many bits as its content needs, giving the best
+    /// quality for a given average bitrate at the cost of bursty packet sizes.
+    Vbr,
+    /// Variable bitrate, limited so that sizes stay close to the target bitrate.
+    ///
+    /// This is the Opus encoder's default.
+    ConstrainedVbr,
+    /// Constant bitrate.
+    ///
+    /// Every frame is encoded to the same size, which may suit networks where
+    /// smooth pacing matters more than audio quality.
+    Cbr,
+}
+
+impl VbrMode {
+    /// Applies this mode to `encoder`.
+    pub(crate) fn apply(self, encoder: &mut OpusEncoder) -> Result<()> {
+        encoder.set_vbr(self != VbrMode::Cbr)?;
+        encoder.set_vbr_constraint(self == VbrMode::ConstrainedVbr)
+    }
+}
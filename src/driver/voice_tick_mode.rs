@@ -0,0 +1,25 @@
+/// Batching behaviour for [`CoreEvent::VoiceTick`] within the driver.
+///
+/// [`CoreEvent::VoiceTick`]: crate::events::CoreEvent::VoiceTick
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum VoiceTickMode {
+    /// No per-tick batching is performed, and [`CoreEvent::VoiceTick`]
+    /// will never fire.
+    ///
+    /// [`CoreEvent::VoiceTick`]: crate::events::CoreEvent::VoiceTick
+    Disabled,
+    /// Received packets are batched every 20ms, and an event is fired
+    /// only if at least one packet containing audio arrived during that time.
+    SkipEmpty,
+    /// Received packets are batched every 20ms, and an event is fired
+    /// every tick regardless of how many packets arrived.
+    Always,
+}
+
+impl VoiceTickMode {
+    /// Returns whether this mode requires received packets to be batched.
+    pub fn is_enabled(self) -> bool {
+        self != VoiceTickMode::Disabled
+    }
+}
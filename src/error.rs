@@ -20,6 +20,11 @@ pub enum JoinError {
     /// No available gateway connection was provided to send
     /// voice state update messages.
     NoSender,
+    /// No [`StageUpdate`] handler was provided to send Stage speaker
+    /// state updates.
+    ///
+    /// [`StageUpdate`]: crate::shards::StageUpdate
+    NoStageHandler,
     /// Tried to leave a [`Call`] which was not found.
     ///
     /// [`Call`]: crate::Call
@@ -89,6 +94,7 @@ impl fmt::Display for JoinError {
         match self {
             JoinError::Dropped => write!(f, "request was cancelled/dropped"),
             JoinError::NoSender => write!(f, "no gateway destination"),
+            JoinError::NoStageHandler => write!(f, "no stage state destination"),
             JoinError::NoCall => write!(f, "tried to leave a non-existent call"),
             JoinError::TimedOut => write!(f, "gateway response from Discord timed out"),
             JoinError::IllegalGuild => write!(f, "target guild ID was zero"),
@@ -111,6 +117,7 @@ impl Error for JoinError {
         match self {
             JoinError::Dropped => None,
             JoinError::NoSender => None,
+            JoinError::NoStageHandler => None,
             JoinError::NoCall => None,
             JoinError::TimedOut => None,
             JoinError::IllegalGuild => None,
@@ -0,0 +1,39 @@
+use crate::id::UserId;
+use std::time::Duration;
+
+/// Change in whether a voice stream has recently sent any packets.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct UserActivityData {
+    /// RTP SSRC of the affected stream.
+    pub ssrc: u32,
+    /// ID of the user who owns this stream, if it is known.
+    ///
+    /// This mapping is learned from [`SpeakingStateUpdate`] events.
+    ///
+    /// [`SpeakingStateUpdate`]: crate::events::CoreEvent::SpeakingStateUpdate
+    pub user_id: Option<UserId>,
+    /// Length of time since this stream sent a packet, measured before
+    /// any packet which caused this event.
+    pub idle: Duration,
+}
+
+/// Decoded audio dropped from voice streams to respect [`Config::max_decoded_audio`].
+///
+/// These are reported at most once per [`DECODED_AUDIO_CAP_REPORT_INTERVAL`], so one
+/// event may cover audio dropped from several streams.
+///
+/// [`Config::max_decoded_audio`]: crate::Config::max_decoded_audio
+/// [`DECODED_AUDIO_CAP_REPORT_INTERVAL`]: crate::constants::DECODED_AUDIO_CAP_REPORT_INTERVAL
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct DecodedAudioCapData {
+    /// RTP SSRC of the stream whose audio was most recently dropped.
+    pub ssrc: u32,
+    /// ID of the user who owns this stream, if it is known.
+    pub user_id: Option<UserId>,
+    /// Size of the audio dropped since the previous event, in bytes.
+    pub dropped: usize,
+    /// Total size of audio dropped by this limit on the current connection, in bytes.
+    pub total_dropped: u64,
+}
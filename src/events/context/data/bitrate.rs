@@ -0,0 +1,27 @@
+use crate::driver::Bitrate;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+/// Bitrate settings of the driver's Opus encoder.
+///
+/// The encoder may not honour the exact bitrate requested by the user,
+/// depending on its mode and the limits of the Opus codec.
+pub struct BitrateData {
+    /// Bitrate most recently requested via [`Driver::set_bitrate`].
+    ///
+    /// [`Driver::set_bitrate`]: crate::driver::Driver::set_bitrate
+    pub requested: Bitrate,
+    /// Bitrate reported by the encoder after applying the request.
+    pub actual: Bitrate,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+/// Measured bitrate of audio sent by the driver, compared with the
+/// encoder's target.
+pub struct BitrateShortfallData {
+    /// Bitrate targeted by the Opus encoder, in bits per second.
+    pub target: u32,
+    /// Bitrate of Opus data which reached the network, in bits per second.
+    pub actual: u32,
+}
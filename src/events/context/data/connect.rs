@@ -1,4 +1,8 @@
-use crate::id::*;
+use crate::{
+    driver::{Bandwidth, CryptoMode, GatewayVersion, IpFamily, VbrMode},
+    id::*,
+};
+use std::time::Duration;
 
 /// Voice connection details gathered at setup/reinstantiation.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -29,4 +33,70 @@ pub struct ConnectData<'a> {
     ///
     /// [RTP SSRC]: https://tools.ietf.org/html/rfc3550#section-3
     pub ssrc: u32,
+    /// Encryption details negotiated by the voice gateway's session description.
+    pub session: SessionDescription,
+    /// The version of the voice gateway protocol negotiated for this session.
+    pub gateway_version: GatewayVersion,
+    /// The address family used to reach the voice gateway.
+    ///
+    /// This may differ from the family requested in [`Config::ip_preference`]
+    /// if connecting over the preferred family failed.
+    ///
+    /// [`Config::ip_preference`]: crate::Config::ip_preference
+    pub ip_family: IpFamily,
+    /// The Opus bitrate control used when this connection was established.
+    ///
+    /// Later changes to [`Config::opus_vbr`] apply without firing a new connect event.
+    ///
+    /// [`Config::opus_vbr`]: crate::Config::opus_vbr
+    pub vbr_mode: VbrMode,
+    /// The Opus bandwidth limit used when this connection was established.
+    ///
+    /// Later changes to [`Config::opus_max_bandwidth`] apply without firing a new
+    /// connect event.
+    ///
+    /// [`Config::opus_max_bandwidth`]: crate::Config::opus_max_bandwidth
+    pub max_bandwidth: Bandwidth,
+    /// Round-trip time to the voice server's UDP endpoint, measured during IP discovery.
+    ///
+    /// Later measurements are reported by [`CoreEvent::Latency`].
+    ///
+    /// [`CoreEvent::Latency`]: crate::events::CoreEvent::Latency
+    pub latency: Duration,
+}
+
+/// Encryption details agreed with the voice gateway during the handshake, as
+/// sent in its session description.
+///
+/// Every field is safe to log or share. The secret key itself is never exposed:
+/// [`key_fingerprint`] only identifies it, so that (*e.g.*) logs can show whether
+/// two connections used the same key.
+///
+/// [`key_fingerprint`]: SessionDescription::key_fingerprint
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct SessionDescription {
+    /// The encryption mode confirmed by the voice gateway.
+    pub mode: CryptoMode,
+    /// A 64-bit FNV-1a hash of the secret key used to encrypt voice packets.
+    ///
+    /// This is far too short to recover the key from, but should not be treated
+    /// as a security boundary: it is meant for comparing keys, not verifying them.
+    pub key_fingerprint: u64,
+}
+
+impl SessionDescription {
+    pub(crate) fn new(mode: CryptoMode, secret_key: &[u8]) -> Self {
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+        let key_fingerprint = secret_key.iter().fold(FNV_OFFSET, |hash, b| {
+            (hash ^ u64::from(*b)).wrapping_mul(FNV_PRIME)
+        });
+
+        Self {
+            mode,
+            key_fingerprint,
+        }
+    }
 }
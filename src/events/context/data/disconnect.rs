@@ -73,6 +73,18 @@ pub enum DisconnectReason {
     ///
     /// This should never happen; if this is ever seen, raise an issue with logs.
     ProtocolViolation,
+    /// The driver spent longer than its configured [`reconnect_window`] retrying
+    /// a connection, and has given up.
+    ///
+    /// [`reconnect_window`]: crate::Config::reconnect_window
+    ReconnectExhausted,
+    /// The driver's background tasks kept failing, and the watchdog set up by
+    /// [`Config::watchdog`] has given up restarting them.
+    ///
+    /// The call's connection is dropped, as it can no longer play audio reliably.
+    ///
+    /// [`Config::watchdog`]: crate::Config::watchdog
+    TasksFailed,
     /// A voice connection was not established in the specified time.
     TimedOut,
     /// The Websocket connection was closed by Discord.
@@ -88,6 +100,7 @@ impl From<&ConnectionError> for DisconnectReason {
 
         match e {
             AttemptDiscarded => Self::AttemptDiscarded,
+            AuthRejected(code) => Self::WsClosed(*code),
             CryptoModeInvalid
             | CryptoModeUnavailable
             | EndpointUrl
@@ -95,10 +108,10 @@ impl From<&ConnectionError> for DisconnectReason {
             | IllegalDiscoveryResponse
             | IllegalIp
             | Json(_) => Self::ProtocolViolation,
-            Io(_) => Self::Io,
+            Dns(_) | Io(_) | WsHandshake(_) => Self::Io,
             Crypto(_) | InterconnectFailure(_) => Self::Internal,
             Ws(ws) => ws.into(),
-            TimedOut => Self::TimedOut,
+            IpDiscoveryTimedOut | TimedOut => Self::TimedOut,
         }
     }
 }
@@ -0,0 +1,159 @@
+use super::*;
+use discortp::{
+    pnet::packet::PrimitiveValues,
+    rtcp::report::{ReceiverReport, SenderReport},
+};
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+/// Fixed header fields of a received RTP packet, as described by [RFC 3550].
+///
+/// These are the on-wire values, read after decryption. Fields such as the
+/// sequence number and timestamp are the sender's, and have not been adjusted
+/// for loss or reordering.
+///
+/// [RFC 3550]: https://tools.ietf.org/html/rfc3550#section-5.1
+pub struct RtpHeader {
+    /// RTP version, which should always be `2`.
+    pub version: u8,
+    /// Whether the packet ends with padding bytes.
+    pub padding: bool,
+    /// Whether a header extension follows the CSRC list.
+    pub extension: bool,
+    /// Number of contributing source identifiers in the header.
+    pub csrc_count: u8,
+    /// Marker bit, whose meaning is set by the payload's profile.
+    pub marker: bool,
+    /// Payload type of the packet.
+    pub payload_type: u8,
+    /// Sequence number of the packet.
+    pub sequence: u16,
+    /// RTP timestamp of the packet's first sample.
+    pub timestamp: u32,
+    /// Synchronisation source identifier, i.e., the sender of this packet.
+    pub ssrc: u32,
+}
+
+impl From<&Rtp> for RtpHeader {
+    fn from(packet: &Rtp) -> Self {
+        Self {
+            version: packet.version,
+            padding: packet.padding != 0,
+            extension: packet.extension != 0,
+            csrc_count: packet.csrc_count,
+            marker: packet.marker != 0,
+            payload_type: packet.payload_type.to_primitive_values().0,
+            sequence: packet.sequence.into(),
+            timestamp: packet.timestamp.into(),
+            ssrc: packet.ssrc,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+/// Common header fields of a received RTCP sender or receiver report, as
+/// described by [RFC 3550].
+///
+/// These are the on-wire values, read after decryption.
+///
+/// [RFC 3550]: https://tools.ietf.org/html/rfc3550#section-6.4
+pub struct RtcpHeader {
+    /// RTP version, which should always be `2`.
+    pub version: u8,
+    /// Whether the packet ends with padding bytes.
+    pub padding: bool,
+    /// Number of reception report blocks in the packet.
+    pub report_count: u8,
+    /// RTCP packet type.
+    pub packet_type: u8,
+    /// Length of the packet in 32-bit words, minus one.
+    pub length: u16,
+    /// Synchronisation source identifier of the report's sender.
+    pub ssrc: u32,
+}
+
+impl From<&SenderReport> for RtcpHeader {
+    fn from(packet: &SenderReport) -> Self {
+        Self {
+            version: packet.version,
+            padding: packet.padding != 0,
+            report_count: packet.rx_report_count,
+            packet_type: packet.packet_type.to_primitive_values().0,
+            length: packet.pkt_length,
+            ssrc: packet.ssrc,
+        }
+    }
+}
+
+impl From<&ReceiverReport> for RtcpHeader {
+    fn from(packet: &ReceiverReport) -> Self {
+        Self {
+            version: packet.version,
+            padding: packet.padding != 0,
+            report_count: packet.rx_report_count,
+            packet_type: packet.packet_type.to_primitive_values().0,
+            length: packet.pkt_length,
+            ssrc: packet.ssrc,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use discortp::{
+        rtcp::report::SenderReportPacket,
+        rtp::{MutableRtpPacket, RtpPacket, RtpType},
+        FromPacket,
+    };
+
+    #[test]
+    fn rtp_headers_hold_wire_values() {
+        let mut buf = [0u8; 16];
+        let mut rtp = MutableRtpPacket::new(&mut buf[..]).unwrap();
+        rtp.set_version(2);
+        rtp.set_extension(1);
+        rtp.set_marker(1);
+        rtp.set_payload_type(RtpType::Dynamic(120));
+        rtp.set_sequence(65_000.into());
+        rtp.set_timestamp(123_456.into());
+        rtp.set_ssrc(0xdead_beef);
+
+        let packet = RtpPacket::new(&buf[..]).unwrap().from_packet();
+        assert_eq!(
+            RtpHeader::from(&packet),
+            RtpHeader {
+                version: 2,
+                padding: false,
+                extension: true,
+                csrc_count: 0,
+                marker: true,
+                payload_type: 120,
+                sequence: 65_000,
+                timestamp: 123_456,
+                ssrc: 0xdead_beef,
+            }
+        );
+    }
+
+    #[test]
+    fn rtcp_headers_hold_wire_values() {
+        // A sender report with one reception block, from SSRC 7.
+        let mut buf = [0u8; 52];
+        buf[..8].copy_from_slice(&[0x81, 200, 0, 12, 0, 0, 0, 7]);
+
+        let packet = SenderReportPacket::new(&buf[..]).unwrap().from_packet();
+        assert_eq!(
+            RtcpHeader::from(&packet),
+            RtcpHeader {
+                version: 2,
+                padding: false,
+                report_count: 1,
+                packet_type: 200,
+                length: 12,
+                ssrc: 7,
+            }
+        );
+    }
+}
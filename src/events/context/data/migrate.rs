@@ -0,0 +1,11 @@
+use super::ConnectData;
+
+/// Voice connection details following a move to a new voice server.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct MigrateData<'a> {
+    /// The domain name of the voice server which the driver has left.
+    pub previous_server: &'a str,
+    /// Details of the new connection, whose `server` is that of the new host.
+    pub connection: ConnectData<'a>,
+}
@@ -0,0 +1,13 @@
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+/// Decoded audio from all other speakers, mixed together over a single 20ms tick.
+pub struct MixedAudioData<'a> {
+    /// One 20ms frame of interleaved stereo audio at 48kHz.
+    ///
+    /// Speakers are summed with saturation, and any time where no-one is
+    /// speaking is filled with silence, so consecutive frames form one
+    /// continuous stream.
+    pub audio: &'a [i16],
+    /// SSRCs of the streams which contributed audio to this frame.
+    pub speaking: &'a [u32],
+}
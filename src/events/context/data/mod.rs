@@ -1,12 +1,30 @@
 //! Types containing the main body of an [`EventContext`].
 //!
 //! [`EventContext`]: super::EventContext
+mod activity;
+mod bitrate;
 mod connect;
 mod disconnect;
+mod header;
+mod migrate;
+mod mixed_audio;
 mod rtcp;
 mod speaking;
 mod voice;
+mod voice_tick;
 
 use discortp::{rtcp::Rtcp, rtp::Rtp};
 
-pub use self::{connect::*, disconnect::*, rtcp::*, speaking::*, voice::*};
+pub use self::{
+    activity::*,
+    bitrate::*,
+    connect::*,
+    disconnect::*,
+    header::*,
+    migrate::*,
+    mixed_audio::*,
+    rtcp::*,
+    speaking::*,
+    voice::*,
+    voice_tick::*,
+};
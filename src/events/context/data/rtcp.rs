@@ -13,3 +13,18 @@ pub struct RtcpData<'a> {
     /// Number of bytes at the end of the packet to discard.
     pub payload_end_pad: usize,
 }
+
+impl RtcpData<'_> {
+    /// Returns the common header fields of this packet as plain values, if it
+    /// is a sender or receiver report.
+    ///
+    /// These are the on-wire values after decryption, and are also available
+    /// through `packet`.
+    pub fn header(&self) -> Option<RtcpHeader> {
+        match self.packet {
+            Rtcp::SenderReport(report) => Some(report.into()),
+            Rtcp::ReceiverReport(report) => Some(report.into()),
+            _ => None,
+        }
+    }
+}
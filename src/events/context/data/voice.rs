@@ -26,3 +26,13 @@ pub struct VoiceData<'a> {
     /// Number of bytes at the end of the packet to discard.
     pub payload_end_pad: usize,
 }
+
+impl VoiceData<'_> {
+    /// Returns the fixed header fields of this packet as plain values.
+    ///
+    /// These are the on-wire values after decryption, and are also available
+    /// through `packet`.
+    pub fn header(&self) -> RtpHeader {
+        self.packet.into()
+    }
+}
@@ -0,0 +1,18 @@
+use super::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+/// All voice packets received from other streams during a single 20ms tick.
+///
+/// Each entry in `speaking` pairs the SSRC of a source with one of its packets,
+/// in order of arrival. A source may appear more than once if several of its
+/// packets arrived late and were delivered together.
+///
+/// `silent` contains every known SSRC which did not send any audio during this tick,
+/// either because it sent silent frames or because no packets arrived.
+pub struct VoiceTickData<'a> {
+    /// Audio packets received during this tick, keyed by SSRC.
+    pub speaking: Vec<(u32, VoiceData<'a>)>,
+    /// Known SSRCs which did not transmit audio during this tick.
+    pub silent: &'a [u32],
+}
@@ -1,11 +1,27 @@
 use super::context_data::*;
-use crate::ConnectionInfo;
+use crate::{
+    driver::{Bandwidth, GatewayVersion, IpFamily, VbrMode},
+    ConnectionInfo,
+};
 use discortp::{rtcp::Rtcp, rtp::Rtp};
+use std::time::Duration;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct InternalConnect {
     pub info: ConnectionInfo,
     pub ssrc: u32,
+    pub session: SessionDescription,
+    pub gateway_version: GatewayVersion,
+    pub ip_family: IpFamily,
+    pub vbr_mode: VbrMode,
+    pub max_bandwidth: Bandwidth,
+    pub latency: Duration,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct InternalMigrate {
+    pub previous_endpoint: String,
+    pub connect: InternalConnect,
 }
 
 #[derive(Debug)]
@@ -29,6 +45,18 @@ pub struct InternalVoicePacket {
     pub payload_end_pad: usize,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InternalVoiceTick {
+    pub speaking: Vec<InternalVoicePacket>,
+    pub silent: Vec<u32>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InternalMixedAudio {
+    pub audio: Vec<i16>,
+    pub speaking: Vec<u32>,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct InternalRtcpPacket {
     pub packet: Rtcp,
@@ -44,6 +72,21 @@ impl<'a> From<&'a InternalConnect> for ConnectData<'a> {
             session_id: &val.info.session_id,
             server: &val.info.endpoint,
             ssrc: val.ssrc,
+            session: val.session,
+            gateway_version: val.gateway_version,
+            ip_family: val.ip_family,
+            vbr_mode: val.vbr_mode,
+            max_bandwidth: val.max_bandwidth,
+            latency: val.latency,
+        }
+    }
+}
+
+impl<'a> From<&'a InternalMigrate> for MigrateData<'a> {
+    fn from(val: &'a InternalMigrate) -> Self {
+        Self {
+            previous_server: &val.previous_endpoint,
+            connection: ConnectData::from(&val.connect),
         }
     }
 }
@@ -80,6 +123,19 @@ impl<'a> From<&'a InternalVoicePacket> for VoiceData<'a> {
     }
 }
 
+impl<'a> From<&'a InternalVoiceTick> for VoiceTickData<'a> {
+    fn from(val: &'a InternalVoiceTick) -> Self {
+        Self {
+            speaking: val
+                .speaking
+                .iter()
+                .map(|pkt| (pkt.packet.ssrc, VoiceData::from(pkt)))
+                .collect(),
+            silent: &val.silent,
+        }
+    }
+}
+
 impl<'a> From<&'a InternalRtcpPacket> for RtcpData<'a> {
     fn from(val: &'a InternalRtcpPacket) -> Self {
         Self {
@@ -89,3 +145,12 @@ impl<'a> From<&'a InternalRtcpPacket> for RtcpData<'a> {
         }
     }
 }
+
+impl<'a> From<&'a InternalMixedAudio> for MixedAudioData<'a> {
+    fn from(val: &'a InternalMixedAudio) -> Self {
+        Self {
+            audio: &val.audio,
+            speaking: &val.speaking,
+        }
+    }
+}
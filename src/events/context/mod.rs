@@ -4,8 +4,9 @@ pub(crate) mod internal_data;
 use super::*;
 use crate::{
     model::payload::{ClientDisconnect, Speaking},
-    tracks::{TrackHandle, TrackState},
+    tracks::{EmptyAction, TrackHandle, TrackState},
 };
+use std::time::Duration;
 pub use data as context_data;
 use data::*;
 use internal_data::*;
@@ -36,6 +37,14 @@ pub enum EventContext<'a> {
     SpeakingUpdate(SpeakingUpdateData),
     /// Opus audio packet, received from another stream.
     VoicePacket(VoiceData<'a>),
+    /// All audio packets received from other streams during the last 20ms.
+    VoiceTick(VoiceTickData<'a>),
+    /// Decoded audio from all other speakers, mixed over the last 20ms.
+    MixedAudio(MixedAudioData<'a>),
+    /// Fires when a track queue runs out of tracks.
+    QueueEmpty(EmptyAction),
+    /// Fires when this driver has adopted a new session key.
+    CryptoRenegotiated(SessionDescription),
     /// Telemetry/statistics packet, received from another stream.
     RtcpPacket(RtcpData<'a>),
     /// Fired whenever a client disconnects.
@@ -44,8 +53,25 @@ pub enum EventContext<'a> {
     DriverConnect(ConnectData<'a>),
     /// Fires when this driver successfully reconnects after a network error.
     DriverReconnect(ConnectData<'a>),
+    /// Fires when this driver has moved an ongoing call to a new voice server.
+    DriverMigrate(MigrateData<'a>),
     /// Fires when this driver fails to connect to, or drops from, a voice channel.
     DriverDisconnect(DisconnectData<'a>),
+    /// Fires when the driver's encoder does not apply the exact bitrate requested.
+    BitrateMismatch(BitrateData),
+    /// Fires when the bitrate of sent audio falls short of the encoder's target.
+    BitrateShortfall(BitrateShortfallData),
+    /// Fires when a voice stream has sent no packets for the configured duration.
+    UserInactive(UserActivityData),
+    /// Fires when an inactive voice stream sends a packet.
+    UserActive(UserActivityData),
+    /// Fires with the latest round-trip time to the voice server.
+    Latency(Duration),
+    /// Fires when a voice stream's decoded audio is dropped to respect the
+    /// configured memory limit.
+    DecodedAudioCapped(DecodedAudioCapData),
+    /// Fires when this driver's mixer has been restarted after a failure.
+    DriverRecreated,
 }
 
 #[derive(Debug)]
@@ -53,11 +79,23 @@ pub enum CoreContext {
     SpeakingStateUpdate(Speaking),
     SpeakingUpdate(InternalSpeakingUpdate),
     VoicePacket(InternalVoicePacket),
+    VoiceTick(InternalVoiceTick),
+    MixedAudio(InternalMixedAudio),
+    QueueEmpty(EmptyAction),
+    CryptoRenegotiated(SessionDescription),
     RtcpPacket(InternalRtcpPacket),
     ClientDisconnect(ClientDisconnect),
     DriverConnect(InternalConnect),
     DriverReconnect(InternalConnect),
+    DriverMigrate(InternalMigrate),
     DriverDisconnect(InternalDisconnect),
+    BitrateMismatch(BitrateData),
+    BitrateShortfall(BitrateShortfallData),
+    UserInactive(UserActivityData),
+    UserActive(UserActivityData),
+    Latency(Duration),
+    DecodedAudioCapped(DecodedAudioCapData),
+    DriverRecreated,
 }
 
 impl<'a> CoreContext {
@@ -68,11 +106,23 @@ impl<'a> CoreContext {
             SpeakingStateUpdate(evt) => EventContext::SpeakingStateUpdate(*evt),
             SpeakingUpdate(evt) => EventContext::SpeakingUpdate(SpeakingUpdateData::from(evt)),
             VoicePacket(evt) => EventContext::VoicePacket(VoiceData::from(evt)),
+            VoiceTick(evt) => EventContext::VoiceTick(VoiceTickData::from(evt)),
+            MixedAudio(evt) => EventContext::MixedAudio(MixedAudioData::from(evt)),
+            QueueEmpty(evt) => EventContext::QueueEmpty(*evt),
+            CryptoRenegotiated(evt) => EventContext::CryptoRenegotiated(*evt),
             RtcpPacket(evt) => EventContext::RtcpPacket(RtcpData::from(evt)),
             ClientDisconnect(evt) => EventContext::ClientDisconnect(*evt),
             DriverConnect(evt) => EventContext::DriverConnect(ConnectData::from(evt)),
             DriverReconnect(evt) => EventContext::DriverReconnect(ConnectData::from(evt)),
+            DriverMigrate(evt) => EventContext::DriverMigrate(MigrateData::from(evt)),
             DriverDisconnect(evt) => EventContext::DriverDisconnect(DisconnectData::from(evt)),
+            BitrateMismatch(evt) => EventContext::BitrateMismatch(*evt),
+            BitrateShortfall(evt) => EventContext::BitrateShortfall(*evt),
+            UserInactive(evt) => EventContext::UserInactive(*evt),
+            UserActive(evt) => EventContext::UserActive(*evt),
+            Latency(evt) => EventContext::Latency(*evt),
+            DecodedAudioCapped(evt) => EventContext::DecodedAudioCapped(*evt),
+            DriverRecreated => EventContext::DriverRecreated,
         }
     }
 }
@@ -87,11 +137,23 @@ impl EventContext<'_> {
             SpeakingStateUpdate(_) => Some(CoreEvent::SpeakingStateUpdate),
             SpeakingUpdate(_) => Some(CoreEvent::SpeakingUpdate),
             VoicePacket(_) => Some(CoreEvent::VoicePacket),
+            VoiceTick(_) => Some(CoreEvent::VoiceTick),
+            MixedAudio(_) => Some(CoreEvent::MixedAudio),
+            QueueEmpty(_) => Some(CoreEvent::QueueEmpty),
+            CryptoRenegotiated(_) => Some(CoreEvent::CryptoRenegotiated),
             RtcpPacket(_) => Some(CoreEvent::RtcpPacket),
             ClientDisconnect(_) => Some(CoreEvent::ClientDisconnect),
             DriverConnect(_) => Some(CoreEvent::DriverConnect),
             DriverReconnect(_) => Some(CoreEvent::DriverReconnect),
+            DriverMigrate(_) => Some(CoreEvent::DriverMigrate),
             DriverDisconnect(_) => Some(CoreEvent::DriverDisconnect),
+            BitrateMismatch(_) => Some(CoreEvent::BitrateMismatch),
+            BitrateShortfall(_) => Some(CoreEvent::BitrateShortfall),
+            UserInactive(_) => Some(CoreEvent::UserInactive),
+            UserActive(_) => Some(CoreEvent::UserActive),
+            Latency(_) => Some(CoreEvent::Latency),
+            DecodedAudioCapped(_) => Some(CoreEvent::DecodedAudioCapped),
+            DriverRecreated => Some(CoreEvent::DriverRecreated),
             _ => None,
         }
     }
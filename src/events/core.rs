@@ -41,6 +41,38 @@ pub enum CoreEvent {
     /// back using the user IDs seen through client connection, disconnection,
     /// or speaking state update.
     VoicePacket,
+    /// Fires once per 20ms tick, batching all voice packets received from
+    /// other streams during that interval, alongside the SSRCs which were silent.
+    ///
+    /// This event only fires if enabled via [`Config::voice_tick`].
+    ///
+    /// [`Config::voice_tick`]: crate::Config::voice_tick
+    VoiceTick,
+    /// Fires once per 20ms tick with decoded audio from all other speakers,
+    /// mixed into a single stream.
+    ///
+    /// Each speaker's audio is aligned to the tick on which it arrived, and
+    /// silence is sent while no-one is speaking, so that frames can be written
+    /// back-to-back (*e.g.*, to record a whole call as one file). Speakers may
+    /// join and leave at any time.
+    ///
+    /// This event only fires if enabled via [`Config::mixed_audio`], and requires
+    /// [`DecodeMode::Decode`].
+    ///
+    /// [`Config::mixed_audio`]: crate::Config::mixed_audio
+    /// [`DecodeMode::Decode`]: crate::driver::DecodeMode::Decode
+    MixedAudio,
+    /// Fires when a [`TrackQueue`] runs out of tracks, describing the
+    /// [`EmptyBehavior`] it carried out.
+    ///
+    /// [`TrackQueue`]: crate::tracks::TrackQueue
+    /// [`EmptyBehavior`]: crate::tracks::EmptyBehavior
+    QueueEmpty,
+    /// Fires when the driver has adopted a new session key, following a call to
+    /// [`Driver::renegotiate_crypto`].
+    ///
+    /// [`Driver::renegotiate_crypto`]: crate::driver::Driver::renegotiate_crypto
+    CryptoRenegotiated,
     /// Fires on receipt of an RTCP packet, containing various call stats
     /// such as latency reports.
     RtcpPacket,
@@ -50,6 +82,59 @@ pub enum CoreEvent {
     DriverConnect,
     /// Fires when this driver successfully reconnects after a network error.
     DriverReconnect,
+    /// Fires when Discord moves an ongoing call to a new voice server, once the
+    /// driver has connected to the new host.
+    ///
+    /// Playing tracks are kept across the move. This fires in place of
+    /// [`DriverConnect`] for the new connection.
+    ///
+    /// [`DriverConnect`]: Self::DriverConnect
+    DriverMigrate,
     /// Fires when this driver fails to connect to, or drops from, a voice channel.
     DriverDisconnect,
+    /// Fires when the driver's Opus encoder reports a different bitrate from
+    /// that requested, after setting the bitrate or rebuilding the encoder.
+    ///
+    /// This is purely informational: the encoder's chosen value remains in use.
+    BitrateMismatch,
+    /// Fires when the bitrate of audio reaching the network falls short of the
+    /// encoder's target by more than [`Config::bitrate_shortfall`].
+    ///
+    /// This typically indicates that the host's connection cannot keep up with
+    /// the chosen bitrate.
+    ///
+    /// [`Config::bitrate_shortfall`]: crate::Config::bitrate_shortfall
+    BitrateShortfall,
+    /// Fires when a voice stream has sent no packets for the duration set by
+    /// [`Config::user_inactivity`].
+    ///
+    /// [`Config::user_inactivity`]: crate::Config::user_inactivity
+    UserInactive,
+    /// Fires when a voice stream sends a packet after having been reported
+    /// by [`UserInactive`].
+    ///
+    /// [`UserInactive`]: Self::UserInactive
+    UserActive,
+    /// Fires periodically with the round-trip time to the voice server, once
+    /// [`Config::latency_interval`] is set.
+    ///
+    /// [`Config::latency_interval`]: crate::Config::latency_interval
+    Latency,
+    /// Fires when decoded audio from a voice stream is dropped to stay within
+    /// [`Config::max_decoded_audio`].
+    ///
+    /// Frequent events suggest that this limit is too low for the call's size.
+    ///
+    /// [`Config::max_decoded_audio`]: crate::Config::max_decoded_audio
+    DecodedAudioCapped,
+    /// Fires when a driver's mixer has been restarted after exiting unexpectedly,
+    /// once the driver has reconnected, or when the whole driver has been recreated
+    /// (before it rejoins its channel).
+    ///
+    /// All tracks played by the failed mixer are lost, and should be replayed by
+    /// handlers of this event. Restarts are carried out by the watchdog set up by
+    /// [`Config::watchdog`].
+    ///
+    /// [`Config::watchdog`]: crate::Config::watchdog
+    DriverRecreated,
 }
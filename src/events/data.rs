@@ -1,8 +1,31 @@
 use super::*;
-use std::{cmp::Ordering, time::Duration};
+use std::{
+    cmp::Ordering,
+    sync::atomic::{AtomicU64, Ordering as AtomicOrdering},
+    time::Duration,
+};
+
+static NEXT_EVENT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Opaque identifier for an event handler registered with a driver or track.
+///
+/// These are returned by [`Driver::add_global_event`], and may be passed to
+/// [`Driver::remove_global_event`] to remove that handler.
+///
+/// [`Driver::add_global_event`]: crate::driver::Driver::add_global_event
+/// [`Driver::remove_global_event`]: crate::driver::Driver::remove_global_event
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct EventHandle(u64);
+
+impl EventHandle {
+    pub(crate) fn next() -> Self {
+        Self(NEXT_EVENT_ID.fetch_add(1, AtomicOrdering::Relaxed))
+    }
+}
 
 /// Internal representation of an event, as handled by the audio context.
 pub struct EventData {
+    pub(crate) handle: EventHandle,
     pub(crate) event: Event,
     pub(crate) fire_time: Option<Duration>,
     pub(crate) action: Box<dyn EventHandler>,
@@ -23,12 +46,18 @@ impl EventData {
     /// [`Cancel`]: Event::Cancel
     pub fn new<F: EventHandler + 'static>(event: Event, action: F) -> Self {
         Self {
+            handle: EventHandle::next(),
             event,
             fire_time: None,
             action: Box::new(action),
         }
     }
 
+    /// Returns the unique identifier of this event handler.
+    pub fn handle(&self) -> EventHandle {
+        self.handle
+    }
+
     /// Computes the next firing time for a timer event.
     pub fn compute_activation(&mut self, now: Duration) {
         match self.event {
@@ -41,14 +70,58 @@ impl EventData {
             _ => {},
         }
     }
+
+    /// Creates a lightweight, read-only description of this event.
+    pub fn info(&self) -> EventInfo {
+        EventInfo {
+            handle: self.handle,
+            event: self.event,
+            fire_time: self.fire_time,
+            remaining_fires: match self.event {
+                Event::Delayed(_) => Some(1),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Read-only description of a registered event, excluding its handler.
+///
+/// These are produced by [`TrackHandle::list_events`] and
+/// [`Driver::list_global_events`] to help inspect which events are
+/// currently registered.
+///
+/// [`TrackHandle::list_events`]: crate::tracks::TrackHandle::list_events
+/// [`Driver::list_global_events`]: crate::driver::Driver::list_global_events
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct EventInfo {
+    /// The identifier of this event handler.
+    pub handle: EventHandle,
+    /// The class of event this handler is listening for.
+    ///
+    /// Timed events include their period or delay.
+    pub event: Event,
+    /// The time at which a timed event will next fire, if any.
+    ///
+    /// This is measured against the global timer for global events, or
+    /// against a track's playback time for local events.
+    pub fire_time: Option<Duration>,
+    /// The number of times this handler will fire before it is removed, if limited.
+    ///
+    /// [`Delayed`] events fire once. Other events fire until their handler
+    /// replaces or cancels them, and report `None`.
+    ///
+    /// [`Delayed`]: Event::Delayed
+    pub remaining_fires: Option<u64>,
 }
 
 impl std::fmt::Debug for EventData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         write!(
             f,
-            "Event {{ event: {:?}, fire_time: {:?}, action: <fn> }}",
-            self.event, self.fire_time
+            "Event {{ handle: {:?}, event: {:?}, fire_time: {:?}, action: <fn> }}",
+            self.handle, self.event, self.fire_time
         )
     }
 }
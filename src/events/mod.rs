@@ -33,6 +33,13 @@
 //!
 //! [`CoreEvent`]s may only be registered globally.
 //!
+//! ## Timing
+//! Timed events never consult the system's wall clock. The global timer advances
+//! by exactly 20ms each time the driver mixes a frame, track timers advance with
+//! each 20ms of playback, and the driver paces itself using a monotonic clock.
+//! As a result, changes to the system time (*e.g.*, NTP adjustments) cannot cause
+//! timed events to fire early, late, or repeatedly.
+//!
 //! [`Event`]: Event
 //! [`EventHandler`]: EventHandler
 //! [`EventContext`]: EventContext
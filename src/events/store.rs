@@ -76,6 +76,37 @@ impl EventStore {
         }
     }
 
+    /// Removes the event handler with the given identifier from this store.
+    ///
+    /// Returns whether a matching handler was found.
+    pub fn remove_event(&mut self, handle: EventHandle) -> bool {
+        let before = self.timed.len();
+        self.timed.retain(|evt| evt.handle != handle);
+        if self.timed.len() != before {
+            return true;
+        }
+
+        for events in self.untimed.values_mut() {
+            if let Some(idx) = events.iter().position(|evt| evt.handle == handle) {
+                events.remove(idx);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Returns descriptions of all events currently held in this store.
+    ///
+    /// Timed events are listed first, in no particular order.
+    pub fn list_events(&self) -> Vec<EventInfo> {
+        self.timed
+            .iter()
+            .chain(self.untimed.values().flatten())
+            .map(EventData::info)
+            .collect()
+    }
+
     /// Processes all events due up to and including `now`.
     pub(crate) async fn process_timed(&mut self, now: Duration, ctx: EventContext<'_>) {
         while let Some(evt) = self.timed.peek() {
@@ -177,6 +208,10 @@ impl GlobalEvents {
         holder.push(index);
     }
 
+    pub(crate) fn remove_event(&mut self, handle: EventHandle) -> bool {
+        self.store.remove_event(handle)
+    }
+
     pub(crate) fn remove_handlers(&mut self) {
         self.store = EventStore::new();
     }
@@ -271,3 +306,110 @@ impl GlobalEvents {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    struct Counter(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl EventHandler for Counter {
+        async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn listed_events_report_remaining_fires() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut global = GlobalEvents::default();
+        global.add_event(EventData::new(
+            Event::Delayed(TIMESTEP_LENGTH),
+            Counter(count.clone()),
+        ));
+        global.add_event(EventData::new(
+            Event::Periodic(TIMESTEP_LENGTH * 5, None),
+            Counter(count.clone()),
+        ));
+
+        let remaining = |global: &GlobalEvents| {
+            let mut out = global
+                .store
+                .list_events()
+                .into_iter()
+                .map(|info| (info.event, info.remaining_fires))
+                .collect::<Vec<_>>();
+            out.sort_by_key(|(evt, _)| matches!(evt, Event::Periodic(..)));
+            out
+        };
+
+        assert_eq!(remaining(&global), vec![
+            (Event::Delayed(TIMESTEP_LENGTH), Some(1)),
+            (Event::Periodic(TIMESTEP_LENGTH * 5, None), None),
+        ]);
+
+        // Once fired, delayed events are removed rather than listed with no fires left.
+        global.tick(&mut vec![], &mut vec![], &mut vec![]).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(remaining(&global), vec![(
+            Event::Periodic(TIMESTEP_LENGTH * 5, None),
+            None
+        )]);
+    }
+
+    #[tokio::test]
+    async fn timed_events_follow_mixed_frames() {
+        tokio::time::pause();
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut global = GlobalEvents::default();
+        global.add_event(EventData::new(
+            Event::Delayed(TIMESTEP_LENGTH * 5),
+            Counter(count.clone()),
+        ));
+
+        // Only ticks advance the event clock, regardless of any time passing.
+        // A large clock step between ticks therefore has no effect.
+        for _ in 0..4 {
+            global.tick(&mut vec![], &mut vec![], &mut vec![]).await;
+            tokio::time::advance(Duration::from_secs(3600)).await;
+        }
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+
+        global.tick(&mut vec![], &mut vec![], &mut vec![]).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        global.tick(&mut vec![], &mut vec![], &mut vec![]).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn removed_handlers_no_longer_fire() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut global = GlobalEvents::default();
+
+        let kept = EventData::new(CoreEvent::Latency.into(), Counter(count.clone()));
+        let removed = EventData::new(CoreEvent::Latency.into(), Counter(count.clone()));
+        let delayed = EventData::new(Event::Delayed(TIMESTEP_LENGTH), Counter(count.clone()));
+        let (removed_handle, delayed_handle) = (removed.handle, delayed.handle);
+        global.add_event(kept);
+        global.add_event(removed);
+        global.add_event(delayed);
+
+        assert!(global.remove_event(removed_handle));
+        assert!(global.remove_event(delayed_handle));
+        assert!(!global.remove_event(removed_handle));
+
+        let latency = EventContext::Latency(Duration::from_millis(20));
+        global.fire_core_event(CoreEvent::Latency, latency).await;
+        global.tick(&mut vec![], &mut vec![], &mut vec![]).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(global.store.list_events().len(), 1);
+    }
+}
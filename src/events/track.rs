@@ -22,4 +22,61 @@ pub enum TrackEvent {
     End,
     /// The attached track has looped.
     Loop,
+    /// The attached track's source process exited unsuccessfully, or produced
+    /// no audio within [`Config::input_timeout`].
+    ///
+    /// This fires alongside [`End`]. Details of a process failure, including the
+    /// end of the process's stderr output, are available via [`TrackHandle::failure`],
+    /// while timeouts are reported by [`TrackHandle::timed_out`].
+    ///
+    /// [`Config::input_timeout`]: crate::Config::input_timeout
+    /// [`End`]: TrackEvent::End
+    /// [`TrackHandle::failure`]: crate::tracks::TrackHandle::failure
+    /// [`TrackHandle::timed_out`]: crate::tracks::TrackHandle::timed_out
+    Error,
+    /// The attached track was paused after repeatedly taking too long to process.
+    ///
+    /// This fires alongside [`Pause`]. See [`Config::track_budget`].
+    ///
+    /// [`Pause`]: TrackEvent::Pause
+    /// [`Config::track_budget`]: crate::Config::track_budget
+    OverBudget,
+    /// The attached track's source announced new metadata, such as the song
+    /// now playing on an internet radio stream.
+    ///
+    /// The new metadata is available via [`TrackHandle::current_metadata`].
+    ///
+    /// [`TrackHandle::current_metadata`]: crate::tracks::TrackHandle::current_metadata
+    MetadataUpdate,
+    /// The attached track has been returned to its position from before the driver
+    /// lost its connection, or has carried on if it cannot seek.
+    ///
+    /// This fires once the driver has reconnected. See [`Config::reconnect_resume`].
+    ///
+    /// [`Config::reconnect_resume`]: crate::Config::reconnect_resume
+    Resumed,
+    /// The attached track's source has finished any setup and has audio ready
+    /// to play.
+    ///
+    /// Unlike [`Play`], this fires even while the track is paused, so can be used
+    /// to tell when a [preloaded] track is loaded. Sources which need no setup
+    /// are ready on the driver's next tick after being added. This fires once per
+    /// track, and again after each [`TrackHandle::restart`].
+    ///
+    /// [`Play`]: TrackEvent::Play
+    /// [preloaded]: crate::tracks::TrackHandle::make_playable
+    /// [`TrackHandle::restart`]: crate::tracks::TrackHandle::restart
+    Ready,
+    /// The attached track's audio has stayed below the threshold of its
+    /// [`SilencePolicy`] for the policy's window.
+    ///
+    /// Tracks which are skipped on silence also fire [`End`].
+    ///
+    /// [`SilencePolicy`]: crate::tracks::SilencePolicy
+    /// [`End`]: TrackEvent::End
+    Silent,
+    /// The attached track's audio has returned after it fell [`Silent`].
+    ///
+    /// [`Silent`]: TrackEvent::Silent
+    Audible,
 }
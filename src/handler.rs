@@ -5,15 +5,30 @@ use crate::{
     id::{ChannelId, GuildId, UserId},
     info::{ConnectionInfo, ConnectionProgress},
     join::*,
-    shards::{Shard, VoiceUpdate},
+    shards::{Shard, StageUpdate, VoiceUpdate},
     Config,
 };
+use derivative::Derivative;
 use flume::Sender;
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 use tracing::instrument;
 
 #[cfg(feature = "driver-core")]
-use std::ops::{Deref, DerefMut};
+use crate::{
+    events::{Event, EventContext, EventHandler},
+    tracks::EmptyAction,
+};
+#[cfg(feature = "driver-core")]
+use async_trait::async_trait;
+#[cfg(feature = "driver-core")]
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Weak,
+};
+#[cfg(feature = "driver-core")]
+use tokio::sync::Mutex;
+#[cfg(feature = "driver-core")]
+use tracing::warn;
 
 #[derive(Clone, Debug)]
 enum Return {
@@ -35,7 +50,8 @@ enum Return {
 /// [`Driver`] via `Deref(Mut)`.
 ///
 /// [`Driver`]: struct@Driver
-#[derive(Clone, Debug)]
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
 pub struct Call {
     #[cfg(not(feature = "driver-core"))]
     config: Config,
@@ -51,6 +67,15 @@ pub struct Call {
     self_deaf: bool,
     /// Whether the current handler is set to mute voice connections.
     self_mute: bool,
+    /// Whether the current handler is set to be suppressed in Stage channels,
+    /// if this has been chosen.
+    suppress: Option<bool>,
+    /// Whether the current handler is set to request to speak in Stage channels.
+    request_to_speak: bool,
+    /// Whether Stage speaker state must be sent once connected.
+    stage_pending: bool,
+    #[derivative(Debug = "ignore")]
+    stage_handler: Option<Arc<dyn StageUpdate + Send + Sync>>,
     user_id: UserId,
     /// Will be set when a `Call` is made via the [`new`]
     /// method.
@@ -131,6 +156,10 @@ impl Call {
             guild_id,
             self_deaf: false,
             self_mute: false,
+            suppress: None,
+            request_to_speak: false,
+            stage_pending: false,
+            stage_handler: None,
             user_id,
             ws,
         }
@@ -138,6 +167,9 @@ impl Call {
 
     #[instrument(skip(self))]
     fn do_connect(&mut self) {
+        // Discord resets Stage speaker state whenever a channel is (re)joined.
+        self.stage_pending = self.suppress.is_some() || self.request_to_speak;
+
         match &self.connection {
             Some((ConnectionProgress::Complete(c), Return::Info(tx))) => {
                 // It's okay if the receiver hung up.
@@ -190,6 +222,11 @@ impl Call {
     where
         F: FnOnce(&Self) -> G,
     {
+        #[cfg(feature = "driver-core")]
+        if let Some(span) = &self.config().tracing_span {
+            span.record("channel_id", channel_id.0);
+        }
+
         Ok(if let Some(conn) = &self.connection {
             if conn.0.in_progress() {
                 self.leave().await?;
@@ -388,6 +425,91 @@ impl Call {
         self.self_mute
     }
 
+    /// Sets the handler used to send Stage speaker state to Discord.
+    ///
+    /// This is needed by [`set_suppress`] and [`request_to_speak`], as Discord does not
+    /// accept these updates over the gateway.
+    ///
+    /// [`set_suppress`]: Call::set_suppress
+    /// [`request_to_speak`]: Call::request_to_speak
+    pub fn set_stage_handler(&mut self, handler: Arc<dyn StageUpdate + Send + Sync>) {
+        self.stage_handler = Some(handler);
+    }
+
+    /// Sets whether the current user is suppressed in a Stage channel.
+    ///
+    /// Bots joining a Stage are suppressed (*i.e.*, audience members) by default, and
+    /// their audio will not be heard. Unsuppressing the bot makes it a speaker, which
+    /// requires the `MUTE_MEMBERS` permission in that channel. Unsuppressing also
+    /// withdraws any [request to speak].
+    ///
+    /// If there is no live voice connection, then this only acts as a settings
+    /// update: this state is sent once connected, and is resent whenever the call
+    /// rejoins a channel.
+    ///
+    /// This has no effect in standard voice channels.
+    ///
+    /// [request to speak]: Call::request_to_speak
+    #[instrument(skip(self))]
+    pub async fn set_suppress(&mut self, suppress: bool) -> JoinResult<()> {
+        self.suppress = Some(suppress);
+        if !suppress {
+            self.request_to_speak = false;
+        }
+
+        self.update_stage().await
+    }
+
+    /// Returns whether the current user is set to be suppressed in Stage channels,
+    /// if this has been chosen via [`set_suppress`].
+    ///
+    /// [`set_suppress`]: Call::set_suppress
+    #[instrument(skip(self))]
+    pub fn is_suppressed(&self) -> Option<bool> {
+        self.suppress
+    }
+
+    /// Sets whether the current user is asking to speak in a Stage channel.
+    ///
+    /// Stage moderators may then accept the request, unsuppressing the bot. This
+    /// requires the `REQUEST_TO_SPEAK` permission in that channel.
+    ///
+    /// As with [`set_suppress`], this state is resent whenever the call rejoins a
+    /// channel.
+    ///
+    /// [`set_suppress`]: Call::set_suppress
+    #[instrument(skip(self))]
+    pub async fn request_to_speak(&mut self, request: bool) -> JoinResult<()> {
+        self.request_to_speak = request;
+
+        self.update_stage().await
+    }
+
+    /// Returns whether the current user is set to ask to speak in Stage channels.
+    #[instrument(skip(self))]
+    pub fn is_requesting_to_speak(&self) -> bool {
+        self.request_to_speak
+    }
+
+    /// Sends any Stage speaker state which is awaiting a completed connection.
+    ///
+    /// [`Songbird`] calls this after processing each voice state or server update.
+    /// You should only need to use this if you initialized the `Call` via
+    /// [`standalone`], after calling [`update_state`] or [`update_server`].
+    ///
+    /// [`Songbird`]: crate::Songbird
+    /// [`standalone`]: Call::standalone
+    /// [`update_state`]: Call::update_state
+    /// [`update_server`]: Call::update_server
+    #[instrument(skip(self))]
+    pub async fn refresh_stage_state(&mut self) -> JoinResult<()> {
+        if self.stage_pending {
+            self.update_stage().await
+        } else {
+            Ok(())
+        }
+    }
+
     /// Updates the voice server data.
     ///
     /// You should only need to use this if you initialized the `Call` via
@@ -439,6 +561,18 @@ impl Call {
         }
     }
 
+    #[cfg(feature = "driver-core")]
+    /// Restarts any of this call's driver tasks which have exited, rejoining the
+    /// current voice channel if the whole driver was recreated.
+    pub(crate) fn restart_tasks(&mut self) {
+        if self.driver.restart_tasks() {
+            if let Some(info) = self.current_connection().cloned() {
+                let (tx, _rx) = flume::bounded(1);
+                self.driver.raw_connect(info, tx);
+            }
+        }
+    }
+
     /// Send an update for the current session over WS.
     ///
     /// Does nothing if initialized via [`standalone`].
@@ -458,6 +592,34 @@ impl Call {
             Err(JoinError::NoSender)
         }
     }
+
+    /// Send Stage speaker state for the current channel, or defer this until
+    /// connected.
+    async fn update_stage(&mut self) -> JoinResult<()> {
+        let channel_id = match &self.connection {
+            Some((progress, _)) if progress.get_connection_info().is_some() =>
+                progress.channel_id(),
+            _ => {
+                self.stage_pending = true;
+                return Ok(());
+            },
+        };
+
+        let handler = self.stage_handler.as_ref().ok_or(JoinError::NoStageHandler)?;
+
+        handler
+            .update_stage_state(
+                self.guild_id,
+                channel_id,
+                self.suppress.unwrap_or(true),
+                self.request_to_speak,
+            )
+            .await?;
+
+        self.stage_pending = false;
+
+        Ok(())
+    }
 }
 
 #[cfg(not(feature = "driver-core"))]
@@ -493,3 +655,148 @@ impl DerefMut for Call {
         &mut self.driver
     }
 }
+
+/// Leaves a [`Call`] gracefully once its [`TrackQueue`] has been empty for too
+/// long, as requested by [`EmptyBehavior::LeaveAfter`].
+///
+/// [`Call`]: Call
+/// [`TrackQueue`]: crate::tracks::TrackQueue
+/// [`EmptyBehavior::LeaveAfter`]: crate::tracks::EmptyBehavior::LeaveAfter
+#[cfg(feature = "driver-core")]
+pub(crate) struct QueueLeaver(pub(crate) Weak<Mutex<Call>>);
+
+#[cfg(feature = "driver-core")]
+#[async_trait]
+impl EventHandler for QueueLeaver {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::QueueEmpty(EmptyAction::Leave) = ctx {
+            if let Some(call) = self.0.upgrade() {
+                // The call may be locked by a caller awaiting the driver, so leave
+                // outside of the event task.
+                tokio::spawn(async move {
+                    if let Err(e) = call.lock().await.leave().await {
+                        warn!("Failed to leave call after queue emptied: {:?}", e);
+                    }
+                });
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(all(test, feature = "driver-core"))]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    struct NullShard;
+
+    #[async_trait]
+    impl VoiceUpdate for NullShard {
+        async fn update_voice_state(
+            &self,
+            _guild_id: GuildId,
+            _channel_id: Option<ChannelId>,
+            _self_deaf: bool,
+            _self_mute: bool,
+        ) -> JoinResult<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct VoiceRecorder(Mutex<Vec<Option<ChannelId>>>);
+
+    #[async_trait]
+    impl VoiceUpdate for VoiceRecorder {
+        async fn update_voice_state(
+            &self,
+            _guild_id: GuildId,
+            channel_id: Option<ChannelId>,
+            _self_deaf: bool,
+            _self_mute: bool,
+        ) -> JoinResult<()> {
+            self.0.lock().unwrap().push(channel_id);
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct StageRecorder(Mutex<Vec<(ChannelId, bool, bool)>>);
+
+    #[async_trait]
+    impl StageUpdate for StageRecorder {
+        async fn update_stage_state(
+            &self,
+            _guild_id: GuildId,
+            channel_id: ChannelId,
+            suppress: bool,
+            request_to_speak: bool,
+        ) -> JoinResult<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((channel_id, suppress, request_to_speak));
+            Ok(())
+        }
+    }
+
+    async fn connect(call: &mut Call, channel_id: u64) {
+        call.join_gateway(channel_id).await.unwrap();
+        call.update_state("session".into(), Some(channel_id));
+        call.update_server("endpoint".into(), "token".into());
+        call.refresh_stage_state().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stage_state_is_reapplied_on_rejoin() {
+        let recorder = Arc::new(StageRecorder::default());
+        let mut call = Call::new(1, Shard::Generic(Arc::new(NullShard)), 2);
+        call.set_stage_handler(recorder.clone());
+
+        // Chosen before joining, so only sent once connected.
+        call.set_suppress(false).await.unwrap();
+        assert_eq!(call.is_suppressed(), Some(false));
+        assert!(recorder.0.lock().unwrap().is_empty());
+
+        connect(&mut call, 3).await;
+        call.refresh_stage_state().await.unwrap();
+        assert_eq!(*recorder.0.lock().unwrap(), vec![(ChannelId(3), false, false)]);
+
+        call.leave().await.unwrap();
+        connect(&mut call, 4).await;
+        assert_eq!(call.is_suppressed(), Some(false));
+        assert_eq!(recorder.0.lock().unwrap()[1..], [(ChannelId(4), false, false)]);
+
+        call.request_to_speak(true).await.unwrap();
+        assert_eq!(recorder.0.lock().unwrap().len(), 3);
+        assert!(call.is_requesting_to_speak());
+    }
+
+    #[tokio::test]
+    async fn empty_queues_leave_through_the_call() {
+        let recorder = Arc::new(VoiceRecorder::default());
+        let call = Arc::new(tokio::sync::Mutex::new(Call::new(
+            1,
+            Shard::Generic(recorder.clone()),
+            2,
+        )));
+        connect(&mut *call.lock().await, 3).await;
+
+        let leaver = QueueLeaver(Arc::downgrade(&call));
+        leaver
+            .act(&EventContext::QueueEmpty(EmptyAction::Stay))
+            .await;
+        assert_eq!(call.lock().await.current_channel(), Some(ChannelId(3)));
+
+        leaver
+            .act(&EventContext::QueueEmpty(EmptyAction::Leave))
+            .await;
+        while call.lock().await.current_channel().is_some() {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(*recorder.0.lock().unwrap(), vec![Some(ChannelId(3)), None]);
+    }
+}
@@ -0,0 +1,225 @@
+use super::{
+    error::{Error, Result},
+    gain,
+    pad,
+    slice,
+    with_sample_rate,
+    Input,
+};
+use std::time::Duration;
+
+/// Stacks source-level transforms onto an [`Input`], producing a single [`Input`].
+///
+/// Transforms are applied in the order they are added, so that:
+/// ```rust,no_run
+/// # use songbird::input::{ffmpeg, InputBuilder};
+/// # use std::time::Duration;
+/// # async fn example() -> songbird::input::error::Result<()> {
+/// let clip = InputBuilder::new(ffmpeg("sprites.wav").await?)
+///     .slice(Duration::from_secs(3), Duration::from_secs(5))
+///     .pad(Duration::from_millis(250), Duration::default())
+///     .gain(-6.0, false)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+/// is equivalent to `gain(pad(slice(input, ..)?, ..), ..)`. Each transform behaves
+/// as its standalone counterpart: [`with_sample_rate`], [`slice`], [`pad`], and
+/// [`gain`].
+///
+/// The whole chain is checked by [`build`] before any transform is applied, and
+/// adjacent gain stages are fused into one where this does not change the output.
+///
+/// [`Input`]: Input
+/// [`with_sample_rate`]: with_sample_rate
+/// [`slice`]: slice()
+/// [`pad`]: pad()
+/// [`gain`]: gain()
+/// [`build`]: InputBuilder::build
+#[derive(Debug)]
+pub struct InputBuilder {
+    input: Input,
+    stages: Vec<Stage>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Stage {
+    SampleRate(u32),
+    Slice(Duration, Duration),
+    Pad(Duration, Duration),
+    Gain(f32, bool),
+}
+
+impl InputBuilder {
+    /// Starts a chain of transforms over `input`.
+    pub fn new(input: Input) -> Self {
+        Self {
+            input,
+            stages: vec![],
+        }
+    }
+
+    /// Treats the source as though it were sampled at `hz`.
+    ///
+    /// This must be the first transform in the chain, as every other transform
+    /// assumes audio at the correct rate.
+    pub fn sample_rate(mut self, hz: u32) -> Self {
+        self.stages.push(Stage::SampleRate(hz));
+        self
+    }
+
+    /// Keeps only the audio between `start` and `end`.
+    ///
+    /// This requires a source which supports seeking.
+    pub fn slice(mut self, start: Duration, end: Duration) -> Self {
+        self.stages.push(Stage::Slice(start, end));
+        self
+    }
+
+    /// Surrounds the audio with `before` and `after` of silence.
+    pub fn pad(mut self, before: Duration, after: Duration) -> Self {
+        self.stages.push(Stage::Pad(before, after));
+        self
+    }
+
+    /// Applies a fixed gain of `db` decibels, optionally clamping the result.
+    pub fn gain(mut self, db: f32, clamp: bool) -> Self {
+        self.stages.push(Stage::Gain(db, clamp));
+        self
+    }
+
+    /// Checks and applies each transform in order.
+    ///
+    /// Returns [`Error::Unseekable`] if the chain slices a source which does not
+    /// support seeking, or [`Error::InvalidChain`] if its transforms cannot be
+    /// combined.
+    ///
+    /// [`Error::Unseekable`]: Error::Unseekable
+    /// [`Error::InvalidChain`]: Error::InvalidChain
+    pub fn build(self) -> Result<Input> {
+        let Self { mut input, stages } = self;
+
+        let stages = fuse_gains(stages);
+        validate(&stages, input.is_seekable())?;
+
+        for stage in stages {
+            input = match stage {
+                Stage::SampleRate(hz) => with_sample_rate(input, hz),
+                Stage::Slice(start, end) => slice(input, start, end)?,
+                Stage::Pad(before, after) => pad(input, before, after),
+                Stage::Gain(db, clamp) => gain(input, db, clamp),
+            };
+        }
+
+        Ok(input)
+    }
+}
+
+/// Merges each run of gain stages, where no earlier stage in the run clamps.
+fn fuse_gains(stages: Vec<Stage>) -> Vec<Stage> {
+    let mut out: Vec<Stage> = Vec::with_capacity(stages.len());
+
+    for stage in stages {
+        match (out.last_mut(), stage) {
+            (Some(Stage::Gain(db, clamp)), Stage::Gain(next_db, next_clamp)) if !*clamp => {
+                *db += next_db;
+                *clamp = next_clamp;
+            },
+            _ => out.push(stage),
+        }
+    }
+
+    out
+}
+
+fn validate(stages: &[Stage], seekable: bool) -> Result<()> {
+    for (i, stage) in stages.iter().enumerate() {
+        match *stage {
+            Stage::SampleRate(0) =>
+                return Err(Error::InvalidChain("sample rate must be non-zero")),
+            Stage::SampleRate(_) if i > 0 =>
+                return Err(Error::InvalidChain(
+                    "sample rate must be overridden before other transforms",
+                )),
+            Stage::Slice(start, end) if start > end =>
+                return Err(Error::InvalidChain("slice ends before it starts")),
+            // Every other transform preserves seekability.
+            Stage::Slice(..) if !seekable => return Err(Error::Unseekable),
+            _ => {},
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{reader::MediaSource, Reader};
+    use byteorder::{ByteOrder, LittleEndian};
+    use std::io::{Cursor, Read, Result as IoResult, Seek, SeekFrom};
+
+    struct Unseekable(Cursor<Vec<u8>>);
+
+    impl Read for Unseekable {
+        fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl Seek for Unseekable {
+        fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+            self.0.seek(pos)
+        }
+    }
+
+    impl MediaSource for Unseekable {
+        fn is_seekable(&self) -> bool {
+            false
+        }
+
+        fn byte_len(&self) -> Option<u64> {
+            None
+        }
+    }
+
+    fn clip() -> Vec<u8> {
+        let mut raw = vec![0u8; 960 * 4];
+        LittleEndian::write_f32_into(&[0.75; 960], &mut raw);
+        raw
+    }
+
+    #[test]
+    fn chain_fuses_gains_and_rejects_invalid_orders() {
+        let stages = fuse_gains(vec![
+            Stage::Gain(3.0, false),
+            Stage::Gain(3.0, true),
+            Stage::Gain(-6.0, false),
+        ]);
+        assert_eq!(stages, vec![Stage::Gain(6.0, true), Stage::Gain(-6.0, false)]);
+
+        // Clamping between stages survives fusion.
+        let mut input = InputBuilder::new(Input::float_pcm(false, clip().into()))
+            .gain(3.0, false)
+            .gain(3.0, true)
+            .gain(-6.0, false)
+            .build()
+            .unwrap();
+        let mut out = vec![];
+        input.read_to_end(&mut out).unwrap();
+        assert!((LittleEndian::read_f32(&out) - 0.5).abs() < 0.01);
+
+        let late_rate = InputBuilder::new(Input::float_pcm(false, clip().into()))
+            .gain(1.0, false)
+            .sample_rate(24_000)
+            .build();
+        assert!(matches!(late_rate, Err(Error::InvalidChain(_))));
+
+        let unseekable = Reader::Extension(Box::new(Unseekable(Cursor::new(clip()))));
+        let sliced = InputBuilder::new(Input::float_pcm(false, unseekable))
+            .pad(Duration::from_secs(1), Duration::default())
+            .slice(Duration::default(), Duration::from_millis(10))
+            .build();
+        assert!(matches!(sliced, Err(Error::Unseekable)));
+    }
+}
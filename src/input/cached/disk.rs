@@ -0,0 +1,330 @@
+use crate::input::{
+    error::{Error, Result},
+    CodecType,
+    Container,
+    Input,
+    Metadata,
+    Reader,
+};
+use parking_lot::Mutex;
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt::{Debug, Error as FormatError, Formatter},
+    fs::{self, File, OpenOptions},
+    io::{
+        Error as IoError,
+        ErrorKind as IoErrorKind,
+        Read,
+        Result as IoResult,
+        Seek,
+        SeekFrom,
+        Write,
+    },
+    path::{Path, PathBuf},
+    result::Result as StdResult,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use symphonia_core::io::MediaSource;
+use uuid::Uuid;
+
+/// A wrapper around an existing [`Input`] which caches
+/// its bytestream in a temporary file on disk as it is played.
+///
+/// The main purpose of this wrapper is to allow large network sources
+/// (i.e., from [`ytdl`]) to be replayed or seeked without either re-downloading
+/// the source or holding its contents in RAM. [`Memory`] and [`Compressed`]
+/// offer the same functionality at the cost of memory use.
+///
+/// Data is stored in the same codec and framing as the wrapped source, and
+/// is only pulled from the source on demand. Seeking past the downloaded
+/// region will read (and store) the source up to the target position.
+///
+/// The backing file is deleted once every handle to it has been dropped.
+/// Files left behind by an abnormal process exit are named `songbird-*.cache`
+/// in the system's temporary directory.
+///
+/// [`Input`]: Input
+/// [`ytdl`]: crate::input::ytdl
+/// [`Memory`]: super::Memory
+/// [`Compressed`]: super::Compressed
+#[derive(Debug)]
+pub struct Disk {
+    /// Inner shared, file-backed bytestore.
+    pub raw: DiskStore,
+    /// Metadata moved out of the captured source.
+    pub metadata: Metadata,
+    /// Codec used to read the inner bytestore.
+    pub kind: CodecType,
+    /// Stereo-ness of the captured source.
+    pub stereo: bool,
+    /// Framing mechanism for the inner bytestore.
+    pub container: Container,
+}
+
+impl Disk {
+    /// Wrap an existing [`Input`] with a disk-backed store with the same codec and framing.
+    ///
+    /// The backing file is created in the system's temporary directory.
+    ///
+    /// [`Input`]: Input
+    pub fn new(source: Input) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("songbird-{}.cache", Uuid::new_v4()));
+
+        Self::with_path(source, path)
+    }
+
+    /// Wrap an existing [`Input`] with a disk-backed store with the same codec and framing,
+    /// storing its data at `path`.
+    ///
+    /// Any existing file at `path` will be truncated, and will be removed once
+    /// every handle to this store has been dropped.
+    ///
+    /// [`Input`]: Input
+    pub fn with_path(mut source: Input, path: impl Into<PathBuf>) -> Result<Self> {
+        let stereo = source.stereo;
+        let kind = (&source.kind).into();
+        let container = source.container;
+        let metadata = source.metadata.take();
+
+        let raw = DiskStore::new(Box::new(source.reader), path.into()).map_err(Error::Io)?;
+
+        Ok(Self {
+            raw,
+            metadata,
+            kind,
+            stereo,
+            container,
+        })
+    }
+
+    /// Acquire a new handle to this object, creating a new
+    /// view of the existing cached data from the beginning.
+    pub fn new_handle(&self) -> Result<Self> {
+        Ok(Self {
+            raw: self.raw.new_handle().map_err(Error::Io)?,
+            metadata: self.metadata.clone(),
+            kind: self.kind,
+            stereo: self.stereo,
+            container: self.container,
+        })
+    }
+}
+
+impl TryFrom<Disk> for Input {
+    type Error = Error;
+
+    fn try_from(src: Disk) -> Result<Self> {
+        Ok(Input::new(
+            src.stereo,
+            Reader::Extension(Box::new(src.raw)),
+            src.kind.try_into()?,
+            src.container,
+            Some(src.metadata),
+        ))
+    }
+}
+
+struct DiskCore {
+    path: PathBuf,
+    /// Number of bytes safely written to the backing file.
+    len: AtomicU64,
+    fill: Mutex<DiskFill>,
+}
+
+struct DiskFill {
+    /// Upstream source, removed once it has been fully read.
+    source: Option<Box<Reader>>,
+    file: File,
+}
+
+impl Drop for DiskCore {
+    fn drop(&mut self) {
+        // The file may already have been removed by the user.
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A seekable view into a file-backed bytestore, shared between all handles
+/// made from the same [`Disk`] source.
+///
+/// [`Disk`]: Disk
+pub struct DiskStore {
+    core: Arc<DiskCore>,
+    file: File,
+    pos: u64,
+}
+
+impl DiskStore {
+    fn new(source: Box<Reader>, path: PathBuf) -> IoResult<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)?;
+
+        let core = Arc::new(DiskCore {
+            path,
+            len: AtomicU64::new(0),
+            fill: Mutex::new(DiskFill {
+                source: Some(source),
+                file,
+            }),
+        });
+
+        Self::from_core(core)
+    }
+
+    fn from_core(core: Arc<DiskCore>) -> IoResult<Self> {
+        let file = File::open(&core.path)?;
+
+        Ok(Self { core, file, pos: 0 })
+    }
+
+    /// Acquire a new handle to this store, starting from the beginning.
+    pub fn new_handle(&self) -> IoResult<Self> {
+        Self::from_core(self.core.clone())
+    }
+
+    /// Returns the location of the backing file.
+    pub fn path(&self) -> &Path {
+        &self.core.path
+    }
+
+    /// Returns the number of bytes downloaded and stored so far.
+    pub fn len(&self) -> u64 {
+        self.core.len.load(Ordering::Acquire)
+    }
+
+    /// Returns whether no bytes have been downloaded and stored so far.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the upstream source has been read to completion.
+    pub fn is_finished(&self) -> bool {
+        self.core.fill.lock().source.is_none()
+    }
+
+    /// Pulls more data from the upstream source into `buf`, storing it on disk.
+    ///
+    /// Returns `None` if another handle has extended the file past this
+    /// handle's position in the meantime.
+    fn fill(&mut self, buf: &mut [u8]) -> IoResult<Option<usize>> {
+        let mut fill = self.core.fill.lock();
+
+        if self.len() > self.pos {
+            return Ok(None);
+        }
+
+        let read = match fill.source.as_mut() {
+            Some(source) => source.read(buf)?,
+            None => return Ok(Some(0)),
+        };
+
+        if read == 0 {
+            fill.source = None;
+        } else {
+            fill.file.write_all(&buf[..read])?;
+            self.core.len.fetch_add(read as u64, Ordering::AcqRel);
+            self.pos += read as u64;
+        }
+
+        Ok(Some(read))
+    }
+}
+
+impl Read for DiskStore {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        loop {
+            let available = self.len().saturating_sub(self.pos);
+
+            if available > 0 {
+                let to_read = buf.len().min(available.try_into().unwrap_or(usize::MAX));
+
+                self.file.seek(SeekFrom::Start(self.pos))?;
+                let read = self.file.read(&mut buf[..to_read])?;
+                self.pos += read as u64;
+
+                return Ok(read);
+            }
+
+            if let Some(read) = self.fill(buf)? {
+                return Ok(read);
+            }
+        }
+    }
+}
+
+impl Seek for DiskStore {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => checked_offset(self.pos, offset),
+            SeekFrom::End(offset) => {
+                // The full length is only known once the source is exhausted.
+                let mut scratch = [0u8; 4096];
+                self.pos = self.len();
+                while self.read(&mut scratch[..])? != 0 {}
+
+                checked_offset(self.len(), offset)
+            },
+        }
+        .ok_or_else(|| {
+            IoError::new(
+                IoErrorKind::InvalidInput,
+                "Attempted to seek before the start of the stream.",
+            )
+        })?;
+
+        // Download up to the target if it lies outside of the stored region.
+        if target > self.len() {
+            let mut scratch = [0u8; 4096];
+            self.pos = self.len();
+            while self.pos < target {
+                let remaining = (target - self.pos).min(scratch.len() as u64) as usize;
+                if self.read(&mut scratch[..remaining])? == 0 {
+                    break;
+                }
+            }
+        }
+
+        self.pos = target.min(self.len());
+
+        Ok(self.pos)
+    }
+}
+
+impl MediaSource for DiskStore {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        if self.is_finished() {
+            Some(self.len())
+        } else {
+            None
+        }
+    }
+}
+
+impl Debug for DiskStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> StdResult<(), FormatError> {
+        f.debug_struct("DiskStore")
+            .field("path", &self.core.path)
+            .field("len", &self.len())
+            .field("pos", &self.pos)
+            .finish()
+    }
+}
+
+fn checked_offset(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.unsigned_abs())
+    }
+}
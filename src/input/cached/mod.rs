@@ -1,13 +1,14 @@
-//! In-memory, shared input sources for reuse between calls, fast seeking, and
+//! In-memory and on-disk, shared input sources for reuse between calls, fast seeking, and
 //! direct Opus frame passthrough.
 
 mod compressed;
+mod disk;
 mod hint;
 mod memory;
 #[cfg(test)]
 mod tests;
 
-pub use self::{compressed::*, hint::*, memory::*};
+pub use self::{compressed::*, disk::*, hint::*, memory::*};
 
 use crate::constants::*;
 use crate::input::utils;
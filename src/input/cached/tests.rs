@@ -8,7 +8,7 @@ use audiopus::{coder::Decoder, Bitrate, Channels, SampleRate};
 use byteorder::{LittleEndian, ReadBytesExt};
 use std::{
     convert::TryInto,
-    io::{Cursor, Read},
+    io::{Cursor, Read, Seek, SeekFrom},
 };
 
 #[tokio::test]
@@ -29,6 +29,34 @@ async fn streamcatcher_preserves_file() {
     assert_eq!(input, out_buf);
 }
 
+#[test]
+fn disk_preserves_and_replays_file() {
+    let data = make_sine(50 * MONO_FRAME_SIZE, true);
+    let input = Input::new(true, data.clone().into(), Codec::FloatPcm, Container::Raw, None);
+
+    let mut disk = Disk::new(input).unwrap();
+    let path = disk.raw.path().to_path_buf();
+
+    let mut half_buf = vec![0u8; data.len() / 2];
+    disk.raw.read_exact(&mut half_buf[..]).unwrap();
+    assert_eq!(&data[..half_buf.len()], &half_buf[..]);
+
+    // Seek back within the stored region, then read beyond it.
+    disk.raw.seek(SeekFrom::Start(0)).unwrap();
+    let mut out_buf = vec![];
+    disk.raw.read_to_end(&mut out_buf).unwrap();
+    assert_eq!(data, out_buf);
+
+    let mut replay = disk.new_handle().unwrap();
+    let mut replay_buf = vec![];
+    replay.raw.read_to_end(&mut replay_buf).unwrap();
+    assert_eq!(data, replay_buf);
+
+    drop(disk);
+    drop(replay);
+    assert!(!path.exists());
+}
+
 #[test]
 fn compressed_scans_frames_decodes_mono() {
     let data = one_s_compressed_sine(false);
@@ -62,6 +90,31 @@ fn compressed_triggers_valid_passthrough() {
         .unwrap();
 }
 
+#[test]
+fn compressed_backward_seek_matches_fresh_seek() {
+    let data = make_sine(150 * MONO_FRAME_SIZE, true);
+    let input = Input::new(true, data.into(), Codec::FloatPcm, Container::Raw, None);
+    let src = Compressed::new(input, Bitrate::BitsPerSecond(128_000)).unwrap();
+
+    let target = SeekFrom::Start((125 * STEREO_FRAME_BYTE_SIZE) as u64);
+
+    // Reading through the source lets later seeks start from a known frame.
+    let mut indexed = Input::from(src.new_handle());
+    let end = SeekFrom::Start((150 * STEREO_FRAME_BYTE_SIZE) as u64);
+    indexed.seek(end).unwrap();
+    indexed.seek(target).unwrap();
+
+    let mut fresh = Input::from(src.new_handle());
+    fresh.seek(target).unwrap();
+
+    let mut indexed_buf = [0u8; STEREO_FRAME_BYTE_SIZE];
+    let mut fresh_buf = [0u8; STEREO_FRAME_BYTE_SIZE];
+    indexed.read_exact(&mut indexed_buf[..]).unwrap();
+    fresh.read_exact(&mut fresh_buf[..]).unwrap();
+
+    assert_eq!(&indexed_buf[..], &fresh_buf[..]);
+}
+
 fn one_s_compressed_sine(stereo: bool) -> Compressed {
     let data = make_sine(50 * MONO_FRAME_SIZE, stereo);
 
@@ -0,0 +1,317 @@
+use super::{wrapped::Wrapped, ChildFailure, Input, Metadata, Reader};
+use crate::constants::*;
+use byteorder::{ByteOrder, LittleEndian};
+use std::{
+    fmt::Write,
+    io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Seek, SeekFrom},
+    mem,
+    time::Duration,
+};
+use tokio::runtime::Handle;
+
+/// Gain of each surround channel folded into the front pair, as given by
+/// ITU-R BS.775 (-3 dB).
+const SURROUND_GAIN: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Describes how each channel of a multichannel source is mixed into stereo output.
+///
+/// Each input channel is given a pair of weights, applied as it is added to the
+/// left and right output channels respectively. Channels are expected in the
+/// usual WAV/`ffmpeg` order, *e.g.*, `FL FR FC LFE BL BR` for 5.1 audio.
+///
+/// [`ffmpeg`] sources with more than two channels are downmixed using
+/// [`ChannelMapper::itu_downmix`] by default.
+///
+/// [`ffmpeg`]: super::ffmpeg
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelMapper {
+    weights: Vec<[f32; 2]>,
+}
+
+impl ChannelMapper {
+    /// Creates a mapper from one `[left, right]` weight pair per input channel.
+    ///
+    /// # Panics
+    /// Panics if `weights` is empty.
+    pub fn new(weights: Vec<[f32; 2]>) -> Self {
+        assert!(!weights.is_empty(), "A source must have at least one channel.");
+
+        Self { weights }
+    }
+
+    /// Returns the standard ITU-R BS.775 stereo downmix for common layouts of
+    /// `channels` channels, if one is known.
+    ///
+    /// Centre and surround channels are added to the front pair at -3 dB, while
+    /// the LFE channel is discarded. Layouts are assumed to be:
+    /// * 1: mono, copied to both outputs,
+    /// * 2: stereo, passed through,
+    /// * 3: `FL FR FC`,
+    /// * 4: `FL FR BL BR`,
+    /// * 5: `FL FR FC BL BR`,
+    /// * 6: `FL FR FC LFE BL BR` (5.1),
+    /// * 8: `FL FR FC LFE BL BR SL SR` (7.1).
+    ///
+    /// The result is not normalised, so loud passages may need to be
+    /// attenuated (*e.g.*, via [`gain`]) to avoid clipping.
+    ///
+    /// [`gain`]: super::gain()
+    pub fn itu_downmix(channels: u8) -> Option<Self> {
+        const L: [f32; 2] = [1.0, 0.0];
+        const R: [f32; 2] = [0.0, 1.0];
+        const C: [f32; 2] = [SURROUND_GAIN, SURROUND_GAIN];
+        const LFE: [f32; 2] = [0.0, 0.0];
+        const SL: [f32; 2] = [SURROUND_GAIN, 0.0];
+        const SR: [f32; 2] = [0.0, SURROUND_GAIN];
+
+        let weights = match channels {
+            1 => vec![[1.0, 1.0]],
+            2 => vec![L, R],
+            3 => vec![L, R, C],
+            4 => vec![L, R, SL, SR],
+            5 => vec![L, R, C, SL, SR],
+            6 => vec![L, R, C, LFE, SL, SR],
+            8 => vec![L, R, C, LFE, SL, SR, SL, SR],
+            _ => return None,
+        };
+
+        Some(Self { weights })
+    }
+
+    /// Returns the number of input channels this mapper expects.
+    pub fn channels(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Mixes one frame (a sample from each input channel) down to stereo.
+    ///
+    /// Missing channels are treated as silent, and extra channels are ignored.
+    pub fn map(&self, frame: &[f32]) -> [f32; 2] {
+        self.weights
+            .iter()
+            .zip(frame)
+            .fold([0.0, 0.0], |[l, r], ([wl, wr], s)| [l + wl * s, r + wr * s])
+    }
+
+    /// Formats this mapping as an `ffmpeg` `pan` filter.
+    pub(crate) fn pan_filter(&self) -> String {
+        let mut out = String::from("pan=stereo");
+
+        for (side, name) in ["FL", "FR"].iter().enumerate() {
+            let _ = write!(out, "|{}=", name);
+
+            let terms = self
+                .weights
+                .iter()
+                .enumerate()
+                .filter(|(_, w)| w[side] != 0.0)
+                .map(|(i, w)| format!("{}*c{}", w[side], i))
+                .collect::<Vec<_>>();
+
+            if terms.is_empty() {
+                out.push_str("0*c0");
+            } else {
+                out.push_str(&terms.join("+"));
+            }
+        }
+
+        out
+    }
+}
+
+/// Creates a stereo [`Input`] from `reader`, which must hold interleaved
+/// floating-point PCM at 48kHz with [`ChannelMapper::channels`] channels.
+///
+/// The result supports seeking if `reader` does.
+///
+/// [`Input`]: Input
+/// [`ChannelMapper::channels`]: ChannelMapper::channels
+pub fn remap_channels(reader: Reader, mapper: ChannelMapper) -> Input {
+    Input::wrapping(true, ChannelRemap::new(reader, mapper), Metadata {
+        channels: Some(2),
+        sample_rate: Some(SAMPLE_RATE_RAW as u32),
+        ..Default::default()
+    })
+}
+
+const SAMPLE_LEN: usize = mem::size_of::<f32>();
+const OUT_FRAME_LEN: usize = 2 * SAMPLE_LEN;
+
+/// Reader state for a multichannel source mixed down to stereo, as created
+/// by [`remap_channels`].
+///
+/// This outputs floating-point PCM at 48kHz.
+///
+/// [`remap_channels`]: remap_channels
+#[derive(Debug)]
+struct ChannelRemap {
+    reader: Box<Reader>,
+    mapper: ChannelMapper,
+    /// Raw multichannel bytes, read ahead of conversion.
+    raw: Vec<u8>,
+    /// Converted stereo bytes not yet returned to the caller.
+    pending: Vec<u8>,
+    pending_pos: usize,
+    /// Byte position within the output.
+    pos: u64,
+}
+
+impl ChannelRemap {
+    fn new(reader: Reader, mapper: ChannelMapper) -> Self {
+        Self {
+            reader: Box::new(reader),
+            mapper,
+            raw: vec![],
+            pending: vec![],
+            pending_pos: 0,
+            pos: 0,
+        }
+    }
+
+    fn in_frame_len(&self) -> usize {
+        self.mapper.channels() * SAMPLE_LEN
+    }
+
+    /// Converts the next block of input frames, returning `false` at the end
+    /// of the source.
+    fn fill(&mut self) -> IoResult<bool> {
+        let in_frame_len = self.in_frame_len();
+        self.raw.resize(MONO_FRAME_SIZE * in_frame_len, 0);
+
+        // Only whole frames can be mixed, so wait for as many as fit.
+        let mut len = 0;
+        while len < self.raw.len() {
+            match self.reader.read(&mut self.raw[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == IoErrorKind::Interrupted => {},
+                Err(e) => return Err(e),
+            }
+        }
+
+        let frames = len / in_frame_len;
+        let mut samples = vec![0f32; self.mapper.channels()];
+
+        self.pending.resize(frames * OUT_FRAME_LEN, 0);
+        self.pending_pos = 0;
+
+        for (raw, out) in self
+            .raw
+            .chunks_exact(in_frame_len)
+            .take(frames)
+            .zip(self.pending.chunks_exact_mut(OUT_FRAME_LEN))
+        {
+            LittleEndian::read_f32_into(raw, &mut samples);
+            LittleEndian::write_f32_into(&self.mapper.map(&samples), out);
+        }
+
+        Ok(frames > 0)
+    }
+}
+
+impl Wrapped for ChannelRemap {
+    fn is_seekable(&self) -> bool {
+        self.reader.is_seekable()
+    }
+
+    fn prep_with_handle(&mut self, handle: Handle) {
+        self.reader.prep_with_handle(handle);
+    }
+
+    fn is_buffering(&self) -> bool {
+        self.reader.is_buffering()
+    }
+
+    fn make_playable(&mut self) {
+        self.reader.make_playable();
+    }
+
+    fn take_seek_landing(&mut self) -> Option<Duration> {
+        self.reader.take_seek_landing()
+    }
+
+    fn failure(&self) -> Option<ChildFailure> {
+        self.reader.failure()
+    }
+}
+
+impl Read for ChannelRemap {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        if self.pending_pos >= self.pending.len() && !self.fill()? {
+            return Ok(0);
+        }
+
+        let len = buffer.len().min(self.pending.len() - self.pending_pos);
+        buffer[..len].copy_from_slice(&self.pending[self.pending_pos..self.pending_pos + len]);
+        self.pending_pos += len;
+        self.pos += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl Seek for ChannelRemap {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => n,
+            SeekFrom::Current(0) => return Ok(self.pos),
+            _ =>
+                return Err(IoError::new(
+                    IoErrorKind::Unsupported,
+                    "Remapped sources only support seeking from their start.",
+                )),
+        };
+
+        let frame = target / OUT_FRAME_LEN as u64;
+        self.reader
+            .seek(SeekFrom::Start(frame * self.in_frame_len() as u64))?;
+
+        self.pending.clear();
+        self.pending_pos = 0;
+        self.pos = frame * OUT_FRAME_LEN as u64;
+
+        // Skip into the middle of a frame if asked.
+        let offset = (target - self.pos) as usize;
+        if offset > 0 && self.fill()? {
+            self.pending_pos = offset.min(self.pending.len());
+            self.pos += self.pending_pos as u64;
+        }
+
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surround_downmix_weights_centre_and_lfe() {
+        // One second of 5.1 audio, with a distinct constant on each channel.
+        let frame = [0.1f32, 0.2, 0.3, 0.9, 0.4, 0.5];
+        let mut raw = vec![0u8; SAMPLE_RATE_RAW * frame.len() * SAMPLE_LEN];
+        for chunk in raw.chunks_exact_mut(frame.len() * SAMPLE_LEN) {
+            LittleEndian::write_f32_into(&frame, chunk);
+        }
+
+        let mapper = ChannelMapper::itu_downmix(6).unwrap();
+        let mut input = remap_channels(raw.into(), mapper);
+        assert!(input.stereo);
+
+        let mut out = [0f32; STEREO_FRAME_SIZE];
+        assert_eq!(input.mix(&mut out, 1.0), STEREO_FRAME_BYTE_SIZE);
+
+        // The centre is shared equally at -3 dB, and the LFE is dropped.
+        let left = 0.1 + 0.3 * SURROUND_GAIN + 0.4 * SURROUND_GAIN;
+        let right = 0.2 + 0.3 * SURROUND_GAIN + 0.5 * SURROUND_GAIN;
+        for pair in out.chunks_exact(2) {
+            assert!((pair[0] - left).abs() < 1e-6);
+            assert!((pair[1] - right).abs() < 1e-6);
+        }
+
+        assert_eq!(
+            ChannelMapper::new(vec![[1.0, 0.0], [0.0, 0.5]]).pan_filter(),
+            "pan=stereo|FL=1*c0|FR=0.5*c1",
+        );
+    }
+}
@@ -1,12 +1,39 @@
-use super::*;
+use super::{process_limit::ProcessSlot, *};
+use parking_lot::Mutex;
 use std::{
+    collections::VecDeque,
     io::{BufReader, Read},
     mem,
-    process::Child,
+    process::{Child, ChildStderr, ExitStatus},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
-use tokio::runtime::Handle;
+use tokio::{io::AsyncReadExt, process::ChildStderr as AsyncChildStderr, runtime::Handle};
 use tracing::debug;
 
+/// Maximum number of bytes of stderr output kept from a child process chain.
+const STDERR_TAIL_LEN: usize = 4096;
+
+/// Interval between checks on a child process which is still exiting.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// Diagnostics gathered from a child process which exited unsuccessfully
+/// while being used as an audio source.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ChildFailure {
+    /// Exit status of the failed process.
+    pub status: ExitStatus,
+    /// The final output written to stderr by all processes in the chain.
+    ///
+    /// This is limited to the last few kilobytes of output, and is decoded lossily.
+    pub stderr_tail: String,
+}
+
 /// Handle for a child process which ensures that any subprocesses are properly closed
 /// on drop.
 ///
@@ -14,13 +41,207 @@ use tracing::debug;
 /// To allow proper cleanup of child processes, if you create a process chain you must
 /// make sure to use `From<Vec<Child>>`. Here, the *last* process in the `Vec` will be
 /// used as the audio byte source.
+///
+/// Any piped stderr of these processes is drained in the background, on the current
+/// Tokio runtime if there is one, with the last few kilobytes kept to help diagnose
+/// failed sources.
 #[derive(Debug)]
-pub struct ChildContainer(Vec<Child>);
+pub struct ChildContainer {
+    children: Vec<Child>,
+    stderr: Arc<Mutex<VecDeque<u8>>>,
+    open_stderr: Arc<AtomicUsize>,
+    titles: Arc<Mutex<Option<TitleSink>>>,
+    failure: Option<ChildFailure>,
+    slot: Option<ProcessSlot>,
+}
+
+/// Destination for stream titles reported in a child process's stderr output.
+#[derive(Debug)]
+struct TitleSink {
+    updater: MetadataUpdater,
+    metadata: Metadata,
+}
 
 impl ChildContainer {
     /// Create a new [`ChildContainer`] from a child process
-    pub fn new(children: Vec<Child>) -> Self {
-        Self(children)
+    pub fn new(mut children: Vec<Child>) -> Self {
+        let stderr = Arc::new(Mutex::new(VecDeque::with_capacity(STDERR_TAIL_LEN)));
+        let titles = Arc::new(Mutex::new(None));
+        let open_stderr = Arc::new(AtomicUsize::new(0));
+
+        for child in children.iter_mut() {
+            if let Some(pipe) = child.stderr.take() {
+                open_stderr.fetch_add(1, Ordering::AcqRel);
+                let sink = StderrSink {
+                    tail: stderr.clone(),
+                    titles: titles.clone(),
+                    line: Vec::new(),
+                    open: open_stderr.clone(),
+                };
+
+                if let Ok(handle) = Handle::try_current() {
+                    let _guard = handle.enter();
+                    match AsyncChildStderr::from_std(pipe) {
+                        Ok(pipe) => {
+                            handle.spawn(sink.drain_async(pipe));
+                        },
+                        Err(e) => debug!("Error registering child stderr: {:?}", e),
+                    }
+                } else {
+                    thread::spawn(move || sink.drain(pipe));
+                }
+            }
+        }
+
+        Self {
+            children,
+            stderr,
+            open_stderr,
+            titles,
+            failure: None,
+            slot: None,
+        }
+    }
+
+    /// Announces ICY stream titles (as logged by `ffmpeg`) found in these processes'
+    /// stderr output through `updater`, applied on top of `metadata`.
+    pub(crate) fn forward_stream_titles(&self, updater: MetadataUpdater, metadata: Metadata) {
+        *self.titles.lock() = Some(TitleSink { updater, metadata });
+    }
+
+    /// Returns the last output written to stderr by these processes.
+    pub fn stderr_tail(&self) -> String {
+        let tail = self.stderr.lock();
+        let (a, b) = tail.as_slices();
+
+        String::from_utf8_lossy(&[a, b].concat()).into_owned()
+    }
+
+    /// Returns diagnostics for the audio source process if its output
+    /// has ended and it exited unsuccessfully.
+    pub fn failure(&self) -> Option<&ChildFailure> {
+        self.failure.as_ref()
+    }
+
+    /// Checks the exit status of the audio source process once its output has ended.
+    ///
+    /// Output usually closes just before the process exits and its stderr has been
+    /// drained. As this runs on the mixer thread, both are waited upon for at most
+    /// [`CHILD_EXIT_TIMEOUT`]: a process which has not exited by then is not
+    /// reported as failed.
+    ///
+    /// [`CHILD_EXIT_TIMEOUT`]: crate::constants::CHILD_EXIT_TIMEOUT
+    fn check_exit(&mut self) {
+        if self.failure.is_some() {
+            return;
+        }
+
+        let child = match self.children.last_mut() {
+            Some(child) => child,
+            None => return,
+        };
+
+        let deadline = Instant::now() + CHILD_EXIT_TIMEOUT;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() < deadline => thread::sleep(EXIT_POLL_INTERVAL),
+                Ok(None) => return,
+                Err(e) => {
+                    debug!("Error checking child process status: {:?}", e);
+                    return;
+                },
+            }
+        };
+
+        if status.success() {
+            return;
+        }
+
+        while self.open_stderr.load(Ordering::Acquire) > 0 && Instant::now() < deadline {
+            thread::sleep(EXIT_POLL_INTERVAL);
+        }
+
+        self.failure = Some(ChildFailure {
+            status,
+            stderr_tail: self.stderr_tail(),
+        });
+    }
+}
+
+/// Destination for the stderr output of one child process.
+struct StderrSink {
+    tail: Arc<Mutex<VecDeque<u8>>>,
+    titles: Arc<Mutex<Option<TitleSink>>>,
+    line: Vec<u8>,
+    open: Arc<AtomicUsize>,
+}
+
+impl Drop for StderrSink {
+    fn drop(&mut self) {
+        self.open.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl StderrSink {
+    async fn drain_async(mut self, mut pipe: AsyncChildStderr) {
+        let mut buf = [0u8; 1024];
+
+        while let Ok(len) = pipe.read(&mut buf[..]).await {
+            if len == 0 {
+                break;
+            }
+
+            self.record(&buf[..len]);
+        }
+    }
+
+    fn drain(mut self, mut pipe: ChildStderr) {
+        let mut buf = [0u8; 1024];
+
+        while let Ok(len) = pipe.read(&mut buf[..]) {
+            if len == 0 {
+                break;
+            }
+
+            self.record(&buf[..len]);
+        }
+    }
+
+    fn record(&mut self, buf: &[u8]) {
+        {
+            let mut tail = self.tail.lock();
+            tail.extend(buf);
+            let excess = tail.len().saturating_sub(STDERR_TAIL_LEN);
+            tail.drain(..excess);
+        }
+
+        for &byte in buf {
+            if byte == b'\n' || byte == b'\r' {
+                forward_stream_title(&self.line, &self.titles);
+                self.line.clear();
+            } else if self.line.len() < STDERR_TAIL_LEN {
+                self.line.push(byte);
+            }
+        }
+    }
+}
+
+fn forward_stream_title(line: &[u8], titles: &Mutex<Option<TitleSink>>) {
+    let line = String::from_utf8_lossy(line);
+    let title = match metadata::parse_stream_title(&line) {
+        Some(title) => title,
+        None => return,
+    };
+
+    if let Some(sink) = titles.lock().as_mut() {
+        let mut updated = sink.metadata.clone();
+        updated.apply_stream_title(title);
+
+        if updated != sink.metadata {
+            sink.metadata = updated.clone();
+            sink.updater.update(updated);
+        }
     }
 }
 
@@ -28,7 +249,19 @@ impl ChildContainer {
 pub fn children_to_reader<T>(children: Vec<Child>) -> Reader {
     Reader::Pipe(BufReader::with_capacity(
         STEREO_FRAME_SIZE * mem::size_of::<T>() * CHILD_BUFFER_LEN,
-        ChildContainer(children),
+        ChildContainer::new(children),
+    ))
+}
+
+/// Create a [`Reader`] from a child process chain, counted under the process limit
+/// for as long as it is alive.
+pub(crate) fn limited_children_to_reader<T>(children: Vec<Child>, slot: ProcessSlot) -> Reader {
+    let mut container = ChildContainer::new(children);
+    container.slot = Some(slot);
+
+    Reader::Pipe(BufReader::with_capacity(
+        STEREO_FRAME_SIZE * mem::size_of::<T>() * CHILD_BUFFER_LEN,
+        container,
     ))
 }
 
@@ -46,20 +279,29 @@ impl From<Vec<Child>> for Reader {
 
 impl Read for ChildContainer {
     fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
-        match self.0.last_mut() {
-            Some(ref mut child) => child.stdout.as_mut().unwrap().read(buffer),
-            None => Ok(0),
+        let read = match self.children.last_mut() {
+            Some(ref mut child) => child.stdout.as_mut().unwrap().read(buffer)?,
+            None => return Ok(0),
+        };
+
+        if read == 0 && !buffer.is_empty() {
+            self.check_exit();
         }
+
+        Ok(read)
     }
 }
 
 impl Drop for ChildContainer {
     fn drop(&mut self) {
-        let children = mem::take(&mut self.0);
+        let children = mem::take(&mut self.children);
+        // Processes count against the limit until they have exited.
+        let slot = self.slot.take();
 
         if let Ok(handle) = Handle::try_current() {
             handle.spawn_blocking(move || {
                 cleanup_child_processes(children);
+                drop(slot);
             });
         } else {
             cleanup_child_processes(children);
@@ -85,3 +327,63 @@ fn cleanup_child_processes(mut children: Vec<Child>) {
         debug!("Error awaiting child process: {:?}", e);
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{
+        process::{Command, Stdio},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn failed_processes_report_their_stderr() {
+        let child = Command::new("sh")
+            .args(["-c", "echo oops >&2; exit 3"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut container = ChildContainer::new(vec![child]);
+
+        // stderr is drained on the runtime...
+        for _ in 0..500 {
+            if !container.stderr_tail().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+
+        // ...and end-of-output checks wait only briefly, so poll until the process has exited.
+        let mut buf = [0u8; 64];
+        for _ in 0..500 {
+            assert_eq!(container.read(&mut buf).unwrap(), 0);
+            if container.failure().is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+
+        let failure = container.failure().expect("Process failure reported.");
+        assert_eq!(failure.status.code(), Some(3));
+        assert_eq!(failure.stderr_tail, "oops\n");
+    }
+
+    #[test]
+    fn failures_are_reported_when_output_closes_before_exit() {
+        let child = Command::new("sh")
+            .args(["-c", "exec >&-; sleep 0.05; echo oops >&2; exit 3"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut container = ChildContainer::new(vec![child]);
+
+        let mut buf = [0u8; 64];
+        assert_eq!(container.read(&mut buf).unwrap(), 0);
+
+        let failure = container.failure().expect("Process failure reported.");
+        assert_eq!(failure.status.code(), Some(3));
+        assert_eq!(failure.stderr_tail, "oops\n");
+    }
+}
@@ -0,0 +1,303 @@
+use super::{utils, wrapped::Wrapped, ChildFailure, Input};
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Seek, SeekFrom},
+    mem,
+    time::Duration,
+};
+use tokio::runtime::Handle;
+
+/// Combines several inputs into a single [`Input`], which plays each
+/// source back-to-back.
+///
+/// Unlike a [`TrackQueue`], the result is one logical source: a single
+/// [`Track`] plays the whole sequence with one handle and one set of events,
+/// and its position and seeks span all of the combined sources.
+///
+/// The output is stereo if any input is stereo, in which case mono inputs are
+/// upmixed. As all [`Input`]s already produce 48kHz audio, no resampling is
+/// needed between sources. Metadata is taken from the first input, with its
+/// duration replaced by the sum of all durations (if every duration is known).
+///
+/// Seeking is supported if every input is seekable. Seeking past a source which
+/// has not yet been played will read through it to learn its length, unless it
+/// is the final source.
+///
+/// [`Input`]: Input
+/// [`TrackQueue`]: crate::tracks::TrackQueue
+/// [`Track`]: crate::tracks::Track
+pub fn concat(mut inputs: Vec<Input>) -> Input {
+    let stereo = inputs.iter().any(|input| input.stereo);
+
+    let durations: Option<Vec<Duration>> = inputs
+        .iter()
+        .map(|input| input.metadata.duration)
+        .collect();
+
+    let mut metadata = inputs
+        .first_mut()
+        .map(|input| input.metadata.take())
+        .unwrap_or_default();
+    metadata.duration = durations.map(|d| d.iter().sum());
+    metadata.channels = Some(if stereo { 2 } else { 1 });
+
+    Input::wrapping(stereo, Concat::new(inputs, stereo), metadata)
+}
+
+const SAMPLE_LEN: usize = mem::size_of::<f32>();
+
+#[derive(Debug)]
+struct Segment {
+    input: Input,
+    /// Byte offset of this segment in the output stream, once known.
+    start: Option<u64>,
+    /// Length of this segment in the output stream, once known.
+    len: Option<u64>,
+    upmix: bool,
+}
+
+/// Reader state for several [`Input`]s played one after another,
+/// as created by [`concat`].
+///
+/// This outputs floating-point PCM at 48kHz.
+///
+/// [`Input`]: Input
+/// [`concat`]: concat
+#[derive(Debug)]
+struct Concat {
+    segments: Vec<Segment>,
+    current: usize,
+    pos: u64,
+    stereo: bool,
+    scratch: Vec<u8>,
+}
+
+impl Concat {
+    fn new(inputs: Vec<Input>, stereo: bool) -> Self {
+        let mut segments: Vec<Segment> = inputs
+            .into_iter()
+            .map(|input| Segment {
+                upmix: stereo && !input.stereo,
+                input,
+                start: None,
+                len: None,
+            })
+            .collect();
+
+        if let Some(first) = segments.first_mut() {
+            first.start = Some(0);
+        }
+
+        Self {
+            segments,
+            current: 0,
+            pos: 0,
+            stereo,
+            scratch: vec![],
+        }
+    }
+
+    /// Marks the current segment as finished at the current position,
+    /// and moves to the start of the next.
+    fn advance(&mut self) -> IoResult<()> {
+        let pos = self.pos;
+        let segment = &mut self.segments[self.current];
+        let start = segment.start.unwrap_or(pos);
+        segment.len = Some(pos - start);
+
+        self.current += 1;
+
+        if let Some(next) = self.segments.get_mut(self.current) {
+            next.start = Some(pos);
+
+            if next.input.pos != 0 {
+                Seek::seek(&mut next.input, SeekFrom::Start(0))?;
+            }
+            next.input.make_playable();
+        }
+
+        Ok(())
+    }
+
+    fn read_segment(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        let segment = &mut self.segments[self.current];
+
+        if !segment.upmix {
+            return segment.input.read(buffer);
+        }
+
+        // Duplicate each mono sample into both stereo channels.
+        let samples = buffer.len() / (2 * SAMPLE_LEN);
+        if samples == 0 {
+            return Err(IoError::new(
+                IoErrorKind::InvalidInput,
+                "Buffer too small to hold a stereo sample.",
+            ));
+        }
+
+        self.scratch.resize(samples * SAMPLE_LEN, 0);
+        let mut filled = 0;
+        while filled < self.scratch.len() {
+            match segment.input.read(&mut self.scratch[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        // Any trailing partial sample is discarded.
+        let samples = filled / SAMPLE_LEN;
+
+        for (i, sample) in self.scratch[..samples * SAMPLE_LEN]
+            .chunks_exact(SAMPLE_LEN)
+            .enumerate()
+        {
+            buffer[2 * i * SAMPLE_LEN..(2 * i + 1) * SAMPLE_LEN].copy_from_slice(sample);
+            buffer[(2 * i + 1) * SAMPLE_LEN..(2 * i + 2) * SAMPLE_LEN].copy_from_slice(sample);
+        }
+
+        Ok(samples * 2 * SAMPLE_LEN)
+    }
+}
+
+impl Wrapped for Concat {
+    fn is_seekable(&self) -> bool {
+        self.segments.iter().all(|s| s.input.is_seekable())
+    }
+
+    fn prep_with_handle(&mut self, handle: Handle) {
+        for segment in self.segments.iter_mut() {
+            segment.input.prep_with_handle(handle.clone());
+        }
+    }
+
+    fn is_buffering(&self) -> bool {
+        self.segments
+            .get(self.current)
+            .map(|segment| segment.input.is_buffering())
+            .unwrap_or(false)
+    }
+
+    fn make_playable(&mut self) {
+        if let Some(segment) = self.segments.get_mut(self.current) {
+            segment.input.make_playable();
+        }
+    }
+
+    fn buffered(&self) -> Duration {
+        self.segments
+            .get(self.current)
+            .map(|segment| segment.input.buffered())
+            .unwrap_or_default()
+    }
+
+    fn take_seek_landing(&mut self) -> Option<Duration> {
+        let stereo = self.stereo;
+        let segment = self.segments.get_mut(self.current)?;
+        let landed = segment.input.take_seek_landing()?;
+        let start = segment.start.unwrap_or_default() as usize;
+
+        Some(utils::byte_count_to_timestamp(start, stereo) + landed)
+    }
+
+    fn failure(&self) -> Option<ChildFailure> {
+        self.segments
+            .get(self.current)
+            .and_then(|segment| segment.input.failure())
+    }
+}
+
+impl Read for Concat {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        while self.current < self.segments.len() {
+            let read = self.read_segment(buffer)?;
+
+            if read != 0 {
+                self.pos += read as u64;
+                return Ok(read);
+            }
+
+            self.advance()?;
+        }
+
+        Ok(0)
+    }
+}
+
+impl Seek for Concat {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let target = match pos {
+            SeekFrom::Start(target) => Some(target),
+            SeekFrom::Current(rel) if rel >= 0 => self.pos.checked_add(rel as u64),
+            SeekFrom::Current(rel) => self.pos.checked_sub(rel.unsigned_abs()),
+            SeekFrom::End(_) =>
+                return Err(IoError::new(
+                    IoErrorKind::InvalidInput,
+                    "Seeking from the end of a sequence is not supported.",
+                )),
+        }
+        .ok_or_else(|| {
+            IoError::new(
+                IoErrorKind::InvalidInput,
+                "Attempted to seek before the start of the stream.",
+            )
+        })?;
+
+        // Keep all segment boundaries aligned to whole samples.
+        let frame = if self.stereo { 2 * SAMPLE_LEN } else { SAMPLE_LEN } as u64;
+        let target = target - target % frame;
+
+        // Find the segment holding `target`, learning segment lengths as we go.
+        let mut start = 0;
+        let seg_count = self.segments.len();
+        for (idx, segment) in self.segments.iter_mut().enumerate() {
+            let last = idx + 1 == seg_count;
+            let scale = if segment.upmix { 2 } else { 1 };
+            segment.start = Some(start);
+
+            let reached = match segment.len {
+                Some(len) if target >= start + len && !last => {
+                    start += len;
+                    continue;
+                },
+                // Nothing follows the last segment, so it may seek (and restart)
+                // even if its length is unknown.
+                None if !last => consume_to(&mut segment.input, (target - start) / scale)?,
+                _ => {
+                    let offset = (target - start) / scale;
+                    Seek::seek(&mut segment.input, SeekFrom::Start(offset))?
+                },
+            };
+            let reached = start + reached * scale;
+
+            if reached < target && !last {
+                // This segment ended early: its length is now known.
+                segment.len = Some(reached - start);
+                start = reached;
+                continue;
+            }
+
+            self.current = idx;
+            self.pos = reached;
+            return Ok(reached);
+        }
+
+        Ok(self.pos)
+    }
+}
+
+/// Moves `input` to `offset` by reading, stopping early if the input ends.
+///
+/// Unlike seeking, this cannot overshoot the end of the input.
+fn consume_to(input: &mut Input, offset: u64) -> IoResult<u64> {
+    if input.pos as u64 > offset {
+        Seek::seek(input, SeekFrom::Start(0))?;
+    }
+
+    let mut scratch = [0u8; 4096];
+    while (input.pos as u64) < offset {
+        let to_read = (offset - input.pos as u64).min(scratch.len() as u64) as usize;
+        if input.read(&mut scratch[..to_read])? == 0 {
+            break;
+        }
+    }
+
+    Ok(input.pos as u64)
+}
@@ -1,10 +1,31 @@
-use super::{codec::OpusDecoderState, error::DcaError, Codec, Container, Input, Metadata, Reader};
+use super::{
+    codec::OpusDecoderState,
+    error::DcaError,
+    resync,
+    utils,
+    Codec,
+    Container,
+    Input,
+    Metadata,
+    Reader,
+};
 use serde::Deserialize;
-use std::{ffi::OsStr, mem};
-use tokio::{fs::File as TokioFile, io::AsyncReadExt};
+use crate::constants::*;
+use std::{ffi::OsStr, io::SeekFrom, mem};
+use tokio::{
+    fs::File as TokioFile,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
 
 /// Creates a streamed audio source from a DCA file.
 /// Currently only accepts the [DCA1 format](https://github.com/bwmarrin/dca).
+///
+/// If the file does not begin with a DCA1 header, the first
+/// [`RESYNC_WINDOW`] bytes are searched for one, and any data before it is
+/// skipped. The number of bytes skipped is given by [`Metadata::skipped_bytes`].
+///
+/// [`RESYNC_WINDOW`]: crate::constants::RESYNC_WINDOW
+/// [`Metadata::skipped_bytes`]: Metadata::skipped_bytes
 pub async fn dca<P: AsRef<OsStr>>(path: P) -> Result<Input, DcaError> {
     _dca(path.as_ref()).await
 }
@@ -20,9 +41,26 @@ async fn _dca(path: &OsStr) -> Result<Input, DcaError> {
         .await
         .map_err(DcaError::IoError)?;
 
-    if header != b"DCA1"[..] {
-        return Err(DcaError::InvalidHeader);
-    }
+    let skipped = if header == b"DCA1"[..] {
+        0
+    } else {
+        // Look for a header past any corrupt leading data.
+        let mut window = header.to_vec();
+        (&mut reader)
+            .take((RESYNC_WINDOW - header.len()) as u64)
+            .read_to_end(&mut window)
+            .await
+            .map_err(DcaError::IoError)?;
+
+        let skipped = resync::find_dca_start(&window).ok_or(DcaError::InvalidHeader)?;
+
+        reader
+            .seek(SeekFrom::Start((skipped + header.len()) as u64))
+            .await
+            .map_err(DcaError::IoError)?;
+
+        skipped
+    };
 
     let size = reader
         .read_i32_le()
@@ -45,18 +83,22 @@ async fn _dca(path: &OsStr) -> Result<Input, DcaError> {
 
     let reader = json_reader.into_inner().into_std().await;
 
-    let metadata: Metadata = serde_json::from_slice::<DcaMetadata>(raw_json.as_slice())
+    let mut metadata: Metadata = serde_json::from_slice::<DcaMetadata>(raw_json.as_slice())
         .map_err(DcaError::InvalidMetadata)?
         .into();
 
-    let stereo = metadata.channels == Some(2);
+    if skipped > 0 {
+        metadata.skipped_bytes = Some(skipped as u64);
+    }
+
+    let stereo = utils::is_stereo_layout(metadata.channels);
 
     Ok(Input::new(
         stereo,
         Reader::from_file(reader),
         Codec::Opus(OpusDecoderState::new().map_err(DcaError::Opus)?),
         Container::Dca {
-            first_frame: (size as usize) + mem::size_of::<i32>() + header.len(),
+            first_frame: skipped + (size as usize) + mem::size_of::<i32>() + header.len(),
         },
         Some(metadata),
     ))
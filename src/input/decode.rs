@@ -0,0 +1,140 @@
+use super::{ChildFailure, Input};
+use crate::constants::*;
+use flume::Sender;
+use std::{mem, thread, time::Duration};
+use tokio::{runtime::Handle, task};
+
+/// Destination for the audio produced by [`decode_to`].
+///
+/// This is implemented for closures taking each frame of samples, and for
+/// channels of owned frames. A bounded channel limits how far decoding may run
+/// ahead of its consumer.
+///
+/// [`decode_to`]: decode_to
+pub trait DecodeSink: Send {
+    /// Receives the next frame of interleaved stereo PCM at 48kHz.
+    ///
+    /// Frames hold 20ms of audio, except for the last, which may be shorter.
+    /// Returning `false` stops decoding.
+    fn frame(&mut self, pcm: &[f32]) -> bool;
+}
+
+impl<F> DecodeSink for F
+where
+    F: FnMut(&[f32]) -> bool + Send,
+{
+    fn frame(&mut self, pcm: &[f32]) -> bool {
+        self(pcm)
+    }
+}
+
+impl DecodeSink for Sender<Vec<f32>> {
+    fn frame(&mut self, pcm: &[f32]) -> bool {
+        self.send(pcm.to_vec()).is_ok()
+    }
+}
+
+/// Summary of the audio delivered by [`decode_to`].
+///
+/// [`decode_to`]: decode_to
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct DecodeSummary {
+    /// Number of samples delivered per channel.
+    pub samples: usize,
+    /// Whether the sink stopped decoding before the source ended.
+    pub stopped: bool,
+    /// Diagnostics from the source's process, if it exited unsuccessfully.
+    pub failure: Option<ChildFailure>,
+}
+
+impl DecodeSummary {
+    /// Returns the length of audio delivered.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples as f64 / SAMPLE_RATE_RAW as f64)
+    }
+}
+
+/// Decodes `input` to 48kHz stereo PCM, handing each 20ms frame to `sink`
+/// rather than playing it in a call.
+///
+/// No voice connection or Opus encoder is involved, so this suits building
+/// waveform previews, checking that a source plays before queueing it, or
+/// running voice activity detection locally. Audio is streamed a frame at a
+/// time, and is decoded as fast as the source and `sink` allow.
+///
+/// Lazy sources such as [`Restartable`]s are started as needed, and produce no
+/// frames until ready. This resolves once the source ends or `sink` returns `false`.
+///
+/// [`Restartable`]: super::Restartable
+pub async fn decode_to<S>(mut input: Input, mut sink: S) -> DecodeSummary
+where
+    S: DecodeSink + 'static,
+{
+    input.prep_with_handle(Handle::current());
+
+    task::spawn_blocking(move || {
+        let mut summary = DecodeSummary::default();
+
+        loop {
+            let was_buffering = input.is_buffering();
+            if was_buffering {
+                input.make_playable();
+            }
+
+            // Buffering sources fill with silence, but must still be polled to
+            // make progress.
+            let mut buffer = [0f32; STEREO_FRAME_SIZE];
+            let len = input.mix(&mut buffer, 1.0) / mem::size_of::<f32>();
+
+            if was_buffering && input.is_buffering() {
+                thread::sleep(TIMESTEP_LENGTH);
+                continue;
+            }
+
+            if len == 0 {
+                summary.failure = input.failure();
+                break;
+            }
+
+            summary.samples += len / 2;
+
+            if !sink.frame(&buffer[..len]) {
+                summary.stopped = true;
+                break;
+            }
+        }
+
+        summary
+    })
+    .await
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::make_sine;
+
+    #[tokio::test]
+    async fn decoded_frames_stream_to_channel() {
+        let sine = make_sine(SAMPLE_RATE_RAW / 2, true);
+        let input = Input::float_pcm(true, sine.clone().into());
+        let (tx, rx) = flume::bounded(2);
+
+        let consumer = tokio::spawn(async move {
+            let mut out = vec![];
+            while let Ok(frame) = rx.recv_async().await {
+                out.extend(frame);
+            }
+            out
+        });
+
+        let summary = decode_to(input, tx).await;
+        assert_eq!(summary.duration(), Duration::from_millis(500));
+        assert!(!summary.stopped);
+
+        let out = consumer.await.unwrap();
+        assert_eq!(out.len(), sine.len() / mem::size_of::<f32>());
+    }
+}
@@ -25,12 +25,35 @@ pub enum Error {
     },
     /// An error occurred within the Opus codec.
     Opus(OpusError),
+    /// An HTTP request for a network source failed.
+    #[cfg(feature = "http")]
+    Http(reqwest::Error),
+    /// The server refused access to a network source (HTTP 401 or 403).
+    ///
+    /// The status code is given.
+    Unauthorized(u16),
+    /// The server could not find a network source (HTTP 404 or 410).
+    NotFound,
+    /// The server returned an unsuccessful status code for a network source.
+    HttpStatus(u16),
     /// Failed to extract metadata from alternate pipe.
     Metadata,
     /// Apparently failed to create stdout.
     Stdout,
     /// An error occurred while checking if a path is stereo.
     Streams,
+    /// The requested audio track does not exist within the source's container.
+    ///
+    /// The requested track index is given.
+    TrackNotFound(usize),
+    /// The operation requires a source which supports seeking.
+    Unseekable,
+    /// The transforms requested of an [`InputBuilder`] cannot be combined.
+    ///
+    /// A description of the problem is given.
+    ///
+    /// [`InputBuilder`]: crate::input::InputBuilder
+    InvalidChain(&'static str),
     /// Configuration error for a cached Input.
     Streamcatcher(CatcherError),
     /// An error occurred while processing the JSON output from `youtube-dl`.
@@ -57,6 +80,13 @@ impl From<DcaError> for Error {
     }
 }
 
+#[cfg(feature = "http")]
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Error {
+        Error::Http(e)
+    }
+}
+
 impl From<IoError> for Error {
     fn from(e: IoError) -> Error {
         Error::Io(e)
@@ -79,9 +109,17 @@ impl fmt::Display for Error {
                 parsed_text: _,
             } => write!(f, "parsing JSON failed"),
             Error::Opus(e) => e.fmt(f),
+            #[cfg(feature = "http")]
+            Error::Http(e) => e.fmt(f),
+            Error::Unauthorized(code) => write!(f, "access denied by server ({})", code),
+            Error::NotFound => write!(f, "source not found on server"),
+            Error::HttpStatus(code) => write!(f, "request failed with status {}", code),
             Error::Metadata => write!(f, "extracting metadata failed"),
             Error::Stdout => write!(f, "creating stdout failed"),
             Error::Streams => write!(f, "checking if path is stereo failed"),
+            Error::TrackNotFound(i) => write!(f, "audio track {} does not exist", i),
+            Error::Unseekable => write!(f, "source does not support seeking"),
+            Error::InvalidChain(why) => write!(f, "invalid input transforms: {}", why),
             Error::Streamcatcher(_) => write!(f, "invalid config for cached input"),
             Error::YouTubeDlProcessing(_) => write!(f, "youtube-dl returned invalid JSON"),
             Error::YouTubeDlRun(o) => write!(f, "youtube-dl encontered an error: {:?}", o),
@@ -100,9 +138,17 @@ impl StdError for Error {
                 parsed_text: _,
             } => Some(error),
             Error::Opus(e) => e.source(),
+            #[cfg(feature = "http")]
+            Error::Http(e) => Some(e),
+            Error::Unauthorized(_) => None,
+            Error::NotFound => None,
+            Error::HttpStatus(_) => None,
             Error::Metadata => None,
             Error::Stdout => None,
             Error::Streams => None,
+            Error::TrackNotFound(_) => None,
+            Error::Unseekable => None,
+            Error::InvalidChain(_) => None,
             Error::Streamcatcher(e) => Some(e),
             Error::YouTubeDlProcessing(_) => None,
             Error::YouTubeDlRun(_) => None,
@@ -1,17 +1,24 @@
 use super::{
-    children_to_reader,
+    child::limited_children_to_reader,
     error::{Error, Result},
+    process_limit,
+    utils,
+    ChannelMapper,
     Codec,
     Container,
     Input,
     Metadata,
+    Reader,
 };
 use serde_json::Value;
 use std::{
     ffi::OsStr,
+    fs::File,
+    io::BufReader,
     process::{Command, Stdio},
+    time::Duration,
 };
-use tokio::process::Command as TokioCommand;
+use tokio::{process::Command as TokioCommand, task};
 use tracing::debug;
 
 /// Opens an audio file through `ffmpeg` and creates an audio source.
@@ -29,28 +36,278 @@ pub(crate) async fn _ffmpeg(path: &OsStr) -> Result<Input> {
     // Will fail if the path is not to a file on the fs. Likely a YouTube URI.
     let is_stereo = is_stereo(path)
         .await
-        .unwrap_or_else(|_e| (false, Default::default()));
+        .unwrap_or_else(|_e| (utils::is_stereo_layout(None), Default::default()));
     let stereo_val = if is_stereo.0 { "2" } else { "1" };
 
+    // ffmpeg only logs ICY metadata updates at its verbose log level.
+    let pre_input_args: &[&str] = if is_http(path) {
+        &["-v", "verbose"]
+    } else {
+        &[]
+    };
+
+    let downmix = downmix_filter(is_stereo.1.channels);
+    let mut args = vec![
+        "-f",
+        "s16le",
+        "-ac",
+        stereo_val,
+        "-ar",
+        "48000",
+        "-acodec",
+        "pcm_f32le",
+    ];
+    if let Some(filter) = &downmix {
+        args.extend_from_slice(&["-af", filter]);
+    }
+    args.push("-");
+
+    _ffmpeg_optioned(path, pre_input_args, &args, Some(is_stereo)).await
+}
+
+/// Opens an audio file through `ffmpeg` and creates a stereo audio source,
+/// mixing its channels together as described by `mapper`.
+///
+/// `mapper` should expect as many channels as the file holds. Without a custom
+/// mapping, [`ffmpeg`] downmixes surround layouts using [`ChannelMapper::itu_downmix`].
+///
+/// This source is not seek-compatible.
+///
+/// [`ffmpeg`]: ffmpeg
+/// [`ChannelMapper::itu_downmix`]: ChannelMapper::itu_downmix
+pub async fn ffmpeg_with_channel_mapper<P: AsRef<OsStr>>(
+    path: P,
+    mapper: &ChannelMapper,
+) -> Result<Input> {
+    let path = path.as_ref();
+    let metadata = is_stereo(path)
+        .await
+        .map(|(_, metadata)| metadata)
+        .unwrap_or_default();
+    let filter = mapper.pan_filter();
+
     _ffmpeg_optioned(
         path,
         &[],
         &[
+            "-af",
+            &filter,
             "-f",
             "s16le",
             "-ac",
-            stereo_val,
+            "2",
             "-ar",
             "48000",
             "-acodec",
             "pcm_f32le",
             "-",
         ],
-        Some(is_stereo),
+        Some((true, metadata)),
     )
     .await
 }
 
+/// How `ffmpeg` should seek when a source is restarted partway through, as
+/// used by [`Restartable::ffmpeg_with_seek_mode`].
+///
+/// [`Restartable::ffmpeg_with_seek_mode`]: super::restartable::Restartable::ffmpeg_with_seek_mode
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SeekMode {
+    /// Jumps straight to the nearest seek point the container offers, without
+    /// decoding any audio before it.
+    ///
+    /// This restarts quickly, but may land some distance from the requested
+    /// time. The real position is found using `ffprobe`, and the track's position
+    /// is corrected once the new process begins playing.
+    Fast,
+    /// Jumps to a seek point shortly before the requested time, then decodes and
+    /// discards audio up to the exact time requested.
+    ///
+    /// This lands on the requested sample even in containers with sparse or
+    /// inaccurate seek points, at the cost of decoding a few extra seconds of audio.
+    Accurate,
+}
+
+/// Audio decoded and discarded after an input seek in [`SeekMode::Accurate`],
+/// covering for containers whose seek points are coarse or inaccurate.
+const ACCURATE_SEEK_PREROLL: Duration = Duration::from_secs(5);
+
+/// Returns the arguments placed before and after `-i` to begin playback at `time`.
+pub(crate) fn seek_args(time: Duration, mode: SeekMode) -> (Vec<String>, Vec<String>) {
+    let ts = |t: Duration| format!("{:.3}", t.as_secs_f64());
+
+    match mode {
+        SeekMode::Fast => (
+            vec!["-ss".into(), ts(time), "-noaccurate_seek".into()],
+            vec![],
+        ),
+        SeekMode::Accurate => {
+            let coarse = time.checked_sub(ACCURATE_SEEK_PREROLL).unwrap_or_default();
+            let pre = if coarse > Duration::default() {
+                vec!["-ss".into(), ts(coarse)]
+            } else {
+                vec![]
+            };
+
+            (pre, vec!["-ss".into(), ts(time - coarse)])
+        },
+    }
+}
+
+/// Opens an audio file through `ffmpeg`, beginning playback at `time`.
+///
+/// Returns the source along with the position it actually begins from, which may
+/// differ from `time` in [`SeekMode::Fast`].
+pub(crate) async fn ffmpeg_seeked(
+    path: &OsStr,
+    time: Duration,
+    mode: SeekMode,
+) -> Result<(Input, Duration)> {
+    let is_stereo = is_stereo(path)
+        .await
+        .unwrap_or_else(|_e| (utils::is_stereo_layout(None), Default::default()));
+    let stereo_val = if is_stereo.0 { "2" } else { "1" };
+
+    let landed = match mode {
+        SeekMode::Fast => probe_seek(path, time).await.unwrap_or(time),
+        SeekMode::Accurate => time,
+    };
+
+    let (pre_input_args, seek_out_args) = seek_args(time, mode);
+    let pre_input_args = pre_input_args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let downmix = downmix_filter(is_stereo.1.channels);
+    let mut args = seek_out_args.iter().map(String::as_str).collect::<Vec<_>>();
+    args.extend_from_slice(&[
+        "-f",
+        "s16le",
+        "-ac",
+        stereo_val,
+        "-ar",
+        "48000",
+        "-acodec",
+        "pcm_f32le",
+    ]);
+    if let Some(filter) = &downmix {
+        args.extend_from_slice(&["-af", filter]);
+    }
+    args.push("-");
+
+    _ffmpeg_optioned(path, &pre_input_args, &args, Some(is_stereo))
+        .await
+        .map(|input| (input, landed))
+}
+
+/// Asks `ffprobe` where a fast seek to `time` lands, by reading the timestamp of
+/// the first audio packet after seeking.
+async fn probe_seek(path: &OsStr, time: Duration) -> Option<Duration> {
+    let interval = format!("{:.3}%+#1", time.as_secs_f64());
+    let args = [
+        "-v",
+        "quiet",
+        "-select_streams",
+        "a:0",
+        "-read_intervals",
+        &interval,
+        "-show_entries",
+        "packet=pts_time",
+        "-of",
+        "csv=p=0",
+        "-i",
+    ];
+
+    let _slot = process_limit::acquire(1).await;
+
+    let out = TokioCommand::new("ffprobe")
+        .args(args)
+        .arg(path)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()?;
+
+    let landed = std::str::from_utf8(&out.stdout)
+        .ok()?
+        .lines()
+        .next()?
+        .trim()
+        .parse::<f64>()
+        .ok()?;
+
+    debug!("Fast seek to {:?} lands at {}s.", time, landed);
+
+    Some(Duration::from_secs_f64(landed.max(0.0)))
+}
+
+fn is_http(path: &OsStr) -> bool {
+    matches!(
+        path.to_str(),
+        Some(p) if p.starts_with("http://") || p.starts_with("https://")
+    )
+}
+
+/// Returns the `ffmpeg` filter which downmixes a surround source to stereo, if
+/// `channels` has a known layout.
+fn downmix_filter(channels: Option<u8>) -> Option<String> {
+    channels
+        .filter(|&n| n > 2)
+        .and_then(ChannelMapper::itu_downmix)
+        .map(|mapper| mapper.pan_filter())
+}
+
+/// Opens one audio track of a multi-track file through `ffmpeg` and creates
+/// an audio source.
+///
+/// `track` counts audio tracks only, starting from `0`; the tracks available are
+/// listed in [`Metadata::audio_tracks`]. [`ffmpeg`] instead lets `ffmpeg` choose,
+/// which is usually the track given by [`Metadata::default_audio_track`].
+///
+/// Returns [`Error::TrackNotFound`] if the file has no such track.
+///
+/// This source is not seek-compatible.
+///
+/// [`Metadata::audio_tracks`]: Metadata::audio_tracks
+/// [`ffmpeg`]: ffmpeg
+/// [`Metadata::default_audio_track`]: Metadata::default_audio_track
+/// [`Error::TrackNotFound`]: Error::TrackNotFound
+pub async fn ffmpeg_with_track<P: AsRef<OsStr>>(path: P, track: usize) -> Result<Input> {
+    let path = path.as_ref();
+    let (_, mut metadata) = is_stereo(path).await?;
+
+    let channels = metadata
+        .audio_tracks
+        .get(track)
+        .ok_or(Error::TrackNotFound(track))?
+        .channels;
+    metadata.channels = channels;
+
+    let is_stereo = utils::is_stereo_layout(channels);
+    let stereo_val = if is_stereo { "2" } else { "1" };
+    let map_val = format!("0:a:{}", track);
+
+    let downmix = downmix_filter(channels);
+    let mut args = vec![
+        "-map",
+        &map_val,
+        "-f",
+        "s16le",
+        "-ac",
+        stereo_val,
+        "-ar",
+        "48000",
+        "-acodec",
+        "pcm_f32le",
+    ];
+    if let Some(filter) = &downmix {
+        args.extend_from_slice(&["-af", filter]);
+    }
+    args.push("-");
+
+    _ffmpeg_optioned(path, &[], &args, Some((is_stereo, metadata))).await
+}
+
 /// Opens an audio file through `ffmpeg` and creates an audio source, with
 /// user-specified arguments to pass to ffmpeg.
 ///
@@ -103,23 +360,34 @@ pub(crate) async fn _ffmpeg_optioned(
             .unwrap_or_else(|| (false, Default::default()))
     };
 
+    let slot = process_limit::acquire(1).await;
+
     let command = Command::new("ffmpeg")
         .args(pre_input_args)
         .arg("-i")
         .arg(path)
         .args(args)
-        .stderr(Stdio::null())
+        .stderr(Stdio::piped())
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
         .spawn()?;
 
-    Ok(Input::new(
+    let mut input = Input::new(
         is_stereo,
-        children_to_reader::<f32>(vec![command]),
+        limited_children_to_reader::<f32>(vec![command], slot),
         Codec::FloatPcm,
         Container::Raw,
         Some(metadata),
-    ))
+    );
+
+    // Internet radio streams report the current song via ICY metadata,
+    // which ffmpeg logs as it changes.
+    let updater = input.metadata_updater();
+    if let Reader::Pipe(pipe) = &input.reader {
+        pipe.get_ref().forward_stream_titles(updater, (*input.metadata).clone());
+    }
+
+    Ok(input)
 }
 
 pub(crate) async fn is_stereo(path: &OsStr) -> Result<(bool, Metadata)> {
@@ -133,10 +401,13 @@ pub(crate) async fn is_stereo(path: &OsStr) -> Result<(bool, Metadata)> {
         "-i",
     ];
 
+    let _slot = process_limit::acquire(1).await;
+
     let out = TokioCommand::new("ffprobe")
         .args(&args)
         .arg(path)
         .stdin(Stdio::null())
+        .kill_on_drop(true)
         .output()
         .await?;
 
@@ -147,13 +418,63 @@ pub(crate) async fn is_stereo(path: &OsStr) -> Result<(bool, Metadata)> {
             .to_string(),
     })?;
 
-    let metadata = Metadata::from_ffprobe_json(&value);
+    let mut metadata = Metadata::from_ffprobe_json(&value);
+
+    // Local Ogg/Opus files may hold richer tags (i.e., album art) than ffprobe reports.
+    let is_ogg = value
+        .get("format")
+        .and_then(|m| m.get("format_name"))
+        .and_then(Value::as_str)
+        .map(|name| name.contains("ogg"))
+        .unwrap_or(false);
+
+    if is_ogg {
+        let path = path.to_owned();
+        let ogg_metadata = task::spawn_blocking(move || {
+            File::open(path)
+                .ok()
+                .map(|f| Metadata::from_ogg_opus(BufReader::new(f)))
+        })
+        .await
+        .ok()
+        .flatten();
+
+        if let Some(ogg_metadata) = ogg_metadata {
+            metadata.fill_from(ogg_metadata);
+        }
+    }
+
+    // Web streams with no known length are assumed to be live, e.g. internet radio.
+    if metadata.is_live.is_none() && metadata.duration.is_none() && is_http(path) {
+        metadata.is_live = Some(true);
+    }
 
     debug!("FFprobe metadata {:?}", metadata);
 
-    if let Some(count) = metadata.channels {
-        Ok((count == 2, metadata))
+    if metadata.channels.is_some() {
+        Ok((utils::is_stereo_layout(metadata.channels), metadata))
     } else {
         Err(Error::Streams)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accurate_seeks_split_around_preroll() {
+        let (pre, post) = seek_args(Duration::from_millis(65_250), SeekMode::Accurate);
+        assert_eq!(pre, ["-ss", "60.250"]);
+        assert_eq!(post, ["-ss", "5.000"]);
+
+        // Seeks within the preroll are made entirely after decoding.
+        let (pre, post) = seek_args(Duration::from_millis(1_500), SeekMode::Accurate);
+        assert!(pre.is_empty());
+        assert_eq!(post, ["-ss", "1.500"]);
+
+        let (pre, post) = seek_args(Duration::from_millis(65_250), SeekMode::Fast);
+        assert_eq!(pre, ["-ss", "65.250", "-noaccurate_seek"]);
+        assert!(post.is_empty());
+    }
+}
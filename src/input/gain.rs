@@ -0,0 +1,123 @@
+use super::{wrapped::Wrapped, ChildFailure, Input};
+use byteorder::{ByteOrder, LittleEndian};
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Seek, SeekFrom},
+    mem,
+    time::Duration,
+};
+use tokio::runtime::Handle;
+
+/// Applies a fixed gain of `db` decibels to an [`Input`] as it is decoded.
+///
+/// This is baked into the audio itself, and so is independent of (and
+/// multiplies with) the volume of any [`Track`] playing the result. This suits
+/// static assets such as soundboard clips, whose loudness is known in advance.
+///
+/// If `clamp` is set, boosted samples are limited to the range `[-1.0, 1.0]`.
+/// Otherwise, they are passed through unchanged and may clip once mixed.
+///
+/// The result outputs floating-point PCM, and supports seeking if `input` does.
+/// Its metadata and channel count are taken from `input`.
+///
+/// [`Input`]: Input
+/// [`Track`]: crate::tracks::Track
+pub fn gain(mut input: Input, db: f32, clamp: bool) -> Input {
+    let stereo = input.stereo;
+    let metadata = input.metadata.take();
+
+    Input::wrapping(stereo, Gain::new(input, db, clamp), metadata)
+}
+
+const SAMPLE_LEN: usize = mem::size_of::<f32>();
+
+/// Reader state for an [`Input`] scaled by a fixed gain, as created by [`gain`].
+///
+/// This outputs floating-point PCM at 48kHz.
+///
+/// [`Input`]: Input
+/// [`gain`]: gain()
+#[derive(Debug)]
+struct Gain {
+    input: Box<Input>,
+    factor: f32,
+    clamp: bool,
+}
+
+impl Gain {
+    fn new(input: Input, db: f32, clamp: bool) -> Self {
+        Self {
+            input: Box::new(input),
+            factor: 10f32.powf(db / 20.0),
+            clamp,
+        }
+    }
+}
+
+impl Wrapped for Gain {
+    fn is_seekable(&self) -> bool {
+        self.input.is_seekable()
+    }
+
+    fn prep_with_handle(&mut self, handle: Handle) {
+        self.input.prep_with_handle(handle);
+    }
+
+    fn is_buffering(&self) -> bool {
+        self.input.is_buffering()
+    }
+
+    fn make_playable(&mut self) {
+        self.input.make_playable();
+    }
+
+    fn buffered(&self) -> Duration {
+        self.input.buffered()
+    }
+
+    fn take_seek_landing(&mut self) -> Option<Duration> {
+        self.input.take_seek_landing()
+    }
+
+    fn failure(&self) -> Option<ChildFailure> {
+        self.input.failure()
+    }
+}
+
+impl Read for Gain {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        if buffer.len() < SAMPLE_LEN {
+            return Err(IoError::new(
+                IoErrorKind::InvalidInput,
+                "Buffer too small to hold a sample.",
+            ));
+        }
+
+        let mut read = self.input.read(buffer)?;
+
+        // Complete any partially read sample, so that it can be scaled.
+        while read % SAMPLE_LEN != 0 {
+            let end = read + SAMPLE_LEN - read % SAMPLE_LEN;
+            match self.input.read(&mut buffer[read..end])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        let read = read - read % SAMPLE_LEN;
+
+        for sample in buffer[..read].chunks_exact_mut(SAMPLE_LEN) {
+            let mut val = LittleEndian::read_f32(sample) * self.factor;
+            if self.clamp {
+                val = val.clamp(-1.0, 1.0);
+            }
+            LittleEndian::write_f32(sample, val);
+        }
+
+        Ok(read)
+    }
+}
+
+impl Seek for Gain {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        Seek::seek(&mut *self.input, pos)
+    }
+}
@@ -0,0 +1,177 @@
+use super::{
+    child::limited_children_to_reader,
+    error::{Error, Result},
+    process_limit,
+    resync,
+    Codec,
+    Container,
+    Input,
+    Metadata,
+};
+use crate::constants::*;
+use reqwest::{header::HeaderMap, Client, IntoUrl, Response, StatusCode};
+use std::{
+    io::Write,
+    process::{ChildStdin, Command, Stdio},
+};
+use tokio::task;
+use tracing::debug;
+
+/// Opens an audio stream over HTTP(S), sending custom `headers` (i.e., for
+/// authorization or a specific user-agent), and creates an audio source.
+///
+/// The request is made using `client`, so that connections may be pooled
+/// between sources. Redirects are followed according to `client`'s policy.
+/// The response body is then decoded through `ffmpeg`.
+///
+/// If the body does not begin with a recognised container or frame header, the
+/// first [`RESYNC_WINDOW`] bytes are searched for one, and any data before it is
+/// skipped. The number of bytes skipped is given by [`Metadata::skipped_bytes`].
+///
+/// Access denied (HTTP 401 or 403) and missing resources (HTTP 404 or 410)
+/// are reported as [`Error::Unauthorized`] and [`Error::NotFound`] respectively.
+///
+/// This source is not seek-compatible.
+///
+/// [`Error::Unauthorized`]: Error::Unauthorized
+/// [`Error::NotFound`]: Error::NotFound
+/// [`RESYNC_WINDOW`]: crate::constants::RESYNC_WINDOW
+/// [`Metadata::skipped_bytes`]: Metadata::skipped_bytes
+pub async fn http_with<U: IntoUrl>(url: U, headers: HeaderMap, client: &Client) -> Result<Input> {
+    let mut resp = client.get(url).headers(headers).send().await?;
+
+    if let Some(e) = status_error(resp.status()) {
+        return Err(e);
+    }
+
+    // The stream can't be probed without repeating the request,
+    // so ffmpeg converts it to stereo.
+    let is_stereo = true;
+    let stereo_val = "2";
+
+    let (prefix, skipped) = read_stream_start(&mut resp).await?;
+
+    let metadata = Metadata {
+        source_url: Some(resp.url().to_string()),
+        skipped_bytes: if skipped > 0 {
+            Some(skipped as u64)
+        } else {
+            None
+        },
+        ..Default::default()
+    };
+
+    let slot = process_limit::acquire(1).await;
+
+    let mut command = Command::new("ffmpeg")
+        .args([
+            "-i",
+            "-",
+            "-f",
+            "s16le",
+            "-ac",
+            stereo_val,
+            "-ar",
+            "48000",
+            "-acodec",
+            "pcm_f32le",
+            "-",
+        ])
+        .stderr(Stdio::piped())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdin = command.stdin.take().ok_or(Error::Stdout)?;
+    tokio::spawn(feed_body(resp, stdin, prefix));
+
+    Ok(Input::new(
+        is_stereo,
+        limited_children_to_reader::<f32>(vec![command], slot),
+        Codec::FloatPcm,
+        Container::Raw,
+        Some(metadata),
+    ))
+}
+
+/// Classifies an unsuccessful response status.
+pub(crate) fn status_error(status: StatusCode) -> Option<Error> {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN =>
+            Some(Error::Unauthorized(status.as_u16())),
+        StatusCode::NOT_FOUND | StatusCode::GONE => Some(Error::NotFound),
+        s if !s.is_success() => Some(Error::HttpStatus(s.as_u16())),
+        _ => None,
+    }
+}
+
+/// Reads the start of a response body, skipping any data before the first
+/// recognised container or frame header.
+///
+/// Bodies which begin with a recognised header are passed on as soon as this is
+/// known. Only otherwise is the body searched, so audio which lacks a signature is
+/// never mistaken for junk ahead of a later header.
+///
+/// Returns the remaining bytes read, and how many were skipped. If no header is
+/// found within [`RESYNC_WINDOW`] bytes, everything read is returned unchanged.
+async fn read_stream_start(resp: &mut Response) -> Result<(Vec<u8>, usize)> {
+    let mut buf = Vec::new();
+
+    while buf.len() < resync::PROBE_LEN {
+        match resp.chunk().await? {
+            Some(chunk) => buf.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+
+    if resync::is_stream_start(&buf) {
+        return Ok((buf, 0));
+    }
+
+    loop {
+        if let Some(start) = resync::find_stream_start(&buf) {
+            if start > 0 {
+                debug!("Skipped {} bytes before start of HTTP stream.", start);
+            }
+            return Ok((buf.split_off(start), start));
+        }
+
+        if buf.len() >= RESYNC_WINDOW {
+            break;
+        }
+
+        match resp.chunk().await? {
+            Some(chunk) => buf.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+
+    Ok((buf, 0))
+}
+
+/// Copies a response body into `ffmpeg` after `prefix`, until either the body
+/// ends or the process exits.
+async fn feed_body(mut resp: Response, mut stdin: ChildStdin, prefix: Vec<u8>) {
+    stdin = match task::spawn_blocking(move || stdin.write_all(&prefix).map(|_| stdin)).await {
+        Ok(Ok(stdin)) => stdin,
+        _ => return,
+    };
+
+    loop {
+        let chunk = match resp.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                debug!("HTTP body ended early: {:?}", e);
+                break;
+            },
+        };
+
+        let written = task::spawn_blocking(move || stdin.write_all(&chunk).map(|_| stdin)).await;
+
+        stdin = match written {
+            Ok(Ok(stdin)) => stdin,
+            _ => break,
+        };
+    }
+}
@@ -1,6 +1,14 @@
 use crate::constants::*;
+use flume::Sender;
 use serde_json::Value;
-use std::time::Duration;
+use std::{
+    convert::TryInto,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    io::Read,
+    mem,
+    sync::Arc,
+    time::Duration,
+};
 
 /// Information about an [`Input`] source.
 ///
@@ -26,6 +34,11 @@ pub struct Metadata {
     pub start_time: Option<Duration>,
     /// The reported duration of this stream.
     pub duration: Option<Duration>,
+    /// Whether this stream is a live broadcast, such as an internet radio station
+    /// or a livestream.
+    ///
+    /// Live streams cannot be seeked, even by recreating their source.
+    pub is_live: Option<bool>,
     /// The sample rate of this stream.
     pub sample_rate: Option<u32>,
     /// The source url of this stream.
@@ -34,6 +47,98 @@ pub struct Metadata {
     pub title: Option<String>,
     /// The thumbnail url of this stream.
     pub thumbnail: Option<String>,
+    /// The album this stream belongs to.
+    pub album: Option<String>,
+    /// Cover art embedded within this stream.
+    pub artwork: Option<Artwork>,
+    /// The number of corrupt or unrelated bytes skipped at the start of this stream
+    /// to reach valid audio, if any were found.
+    pub skipped_bytes: Option<u64>,
+    /// The audio tracks held by this stream's container, in order.
+    ///
+    /// Containers such as Matroska may hold several, *e.g.*, for each language.
+    pub audio_tracks: Vec<AudioTrack>,
+}
+
+/// Details of one audio track within a multi-track container.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AudioTrack {
+    /// Position of this track among the container's audio tracks, starting from `0`.
+    ///
+    /// This is the value to pass to [`ffmpeg_with_track`].
+    ///
+    /// [`ffmpeg_with_track`]: crate::input::ffmpeg_with_track
+    pub index: usize,
+    /// The language of this track, if tagged.
+    pub language: Option<String>,
+    /// The title of this track, if tagged.
+    pub title: Option<String>,
+    /// The number of audio channels in this track.
+    pub channels: Option<u8>,
+    /// Whether the container marks this track as the default.
+    pub default: bool,
+}
+
+/// Image data embedded within an audio file, such as album art.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Artwork {
+    /// MIME type of the image, *e.g.*, `"image/jpeg"`.
+    pub mime_type: String,
+    /// Raw image bytes.
+    pub data: Vec<u8>,
+}
+
+/// Handle used by an audio source to announce changes to its [`Metadata`] while
+/// it is playing, such as the current song of an internet radio stream.
+///
+/// Each update replaces the metadata of the [`Input`], and fires
+/// [`TrackEvent::MetadataUpdate`] once that input is playing as a track.
+///
+/// [`Input`]: crate::input::Input
+/// [`TrackEvent::MetadataUpdate`]: crate::events::TrackEvent::MetadataUpdate
+#[derive(Clone, Debug)]
+pub struct MetadataUpdater {
+    pub(crate) tx: Sender<Metadata>,
+}
+
+impl MetadataUpdater {
+    /// Announces new metadata for the source.
+    ///
+    /// Returns `false` if the source has since been dropped.
+    pub fn update(&self, metadata: Metadata) -> bool {
+        self.tx.send(metadata).is_ok()
+    }
+}
+
+/// Function applied to all [`Metadata`] reported by a source, as set by
+/// [`Input::transform_metadata`].
+///
+/// [`Input::transform_metadata`]: crate::input::Input::transform_metadata
+#[derive(Clone)]
+pub(crate) struct MetadataTransform(Arc<dyn Fn(Metadata) -> Metadata + Send + Sync>);
+
+impl MetadataTransform {
+    pub(crate) fn new<F>(f: F) -> Self
+    where
+        F: Fn(Metadata) -> Metadata + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Returns a transform which applies `self`, and then `next`.
+    pub(crate) fn then(self, next: Self) -> Self {
+        Self::new(move |metadata| next.apply(self.apply(metadata)))
+    }
+
+    pub(crate) fn apply(&self, metadata: Metadata) -> Metadata {
+        (self.0)(metadata)
+    }
+}
+
+impl Debug for MetadataTransform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("MetadataTransform")
+    }
 }
 
 impl Metadata {
@@ -71,14 +176,18 @@ impl Metadata {
             .and_then(Value::as_str)
             .map(str::to_string);
 
-        let stream = value
+        let audio_streams: Vec<&Value> = value
             .as_object()
             .and_then(|m| m.get("streams"))
             .and_then(|v| v.as_array())
-            .and_then(|v| {
+            .map(|v| {
                 v.iter()
-                    .find(|line| line.get("codec_type").and_then(Value::as_str) == Some("audio"))
-            });
+                    .filter(|line| line.get("codec_type").and_then(Value::as_str) == Some("audio"))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let stream = audio_streams.first().copied();
 
         let channels = stream
             .and_then(|m| m.get("channels"))
@@ -91,6 +200,12 @@ impl Metadata {
             .and_then(|v| v.parse::<u64>().ok())
             .map(|v| v as u32);
 
+        let audio_tracks = audio_streams
+            .iter()
+            .enumerate()
+            .map(|(index, stream)| AudioTrack::from_ffprobe_stream(index, stream))
+            .collect();
+
         Self {
             track,
             artist,
@@ -101,6 +216,8 @@ impl Metadata {
             duration,
             sample_rate,
 
+            audio_tracks,
+
             ..Default::default()
         }
     }
@@ -146,6 +263,10 @@ impl Metadata {
             .and_then(Value::as_f64)
             .map(Duration::from_secs_f64);
 
+        let is_live = obj
+            .and_then(|m| m.get("is_live"))
+            .and_then(Value::as_bool);
+
         let source_url = obj
             .and_then(|m| m.get("webpage_url"))
             .and_then(Value::as_str)
@@ -169,6 +290,7 @@ impl Metadata {
             channels: Some(2),
             channel,
             duration,
+            is_live,
             sample_rate: Some(SAMPLE_RATE_RAW as u32),
             source_url,
             title,
@@ -178,6 +300,62 @@ impl Metadata {
         }
     }
 
+    /// Extract metadata from the identification and comment headers of an
+    /// Ogg/Opus bytestream.
+    ///
+    /// This reads `TITLE`, `ARTIST`, `ALBUM`, and `DATE` comments, and decodes the
+    /// first `METADATA_BLOCK_PICTURE` into [`artwork`]. Parsing is tolerant:
+    /// any fields found before a missing or malformed header are kept.
+    ///
+    /// [`artwork`]: Metadata::artwork
+    pub fn from_ogg_opus<R: Read>(reader: R) -> Self {
+        let mut packets = OggPackets::new(reader);
+        let mut out = Self::default();
+
+        let head = match packets.next_packet() {
+            Some(head) if head.starts_with(b"OpusHead") && head.len() >= 19 => head,
+            _ => return out,
+        };
+
+        out.channels = Some(head[9]);
+        out.sample_rate = Some(SAMPLE_RATE_RAW as u32);
+
+        let tags = match packets.next_packet() {
+            Some(tags) if tags.starts_with(b"OpusTags") => tags,
+            _ => return out,
+        };
+
+        let mut body = &tags[b"OpusTags".len()..];
+        let _vendor = take_le_prefixed(&mut body);
+        let count = take_u32_le(&mut body).unwrap_or(0);
+
+        for _ in 0..count {
+            let comment = match take_le_prefixed(&mut body) {
+                Some(c) => String::from_utf8_lossy(c),
+                None => break,
+            };
+
+            let (key, value) = match comment.split_once('=') {
+                Some(kv) => kv,
+                None => continue,
+            };
+
+            match key.to_ascii_uppercase().as_str() {
+                "TITLE" if out.track.is_none() => out.track = Some(value.to_string()),
+                "ARTIST" if out.artist.is_none() => out.artist = Some(value.to_string()),
+                "ALBUM" if out.album.is_none() => out.album = Some(value.to_string()),
+                "DATE" if out.date.is_none() => out.date = Some(value.to_string()),
+                "METADATA_BLOCK_PICTURE" if out.artwork.is_none() =>
+                    out.artwork = base64::decode(value)
+                        .ok()
+                        .and_then(|b| parse_flac_picture(&b)),
+                _ => {},
+            }
+        }
+
+        out
+    }
+
     /// Move all fields from a `Metadata` object into a new one.
     pub fn take(&mut self) -> Self {
         Self {
@@ -189,10 +367,222 @@ impl Metadata {
             channel: self.channel.take(),
             start_time: self.start_time.take(),
             duration: self.duration.take(),
+            // Kept, as sources such as `Restartable` consult this when seeking.
+            is_live: self.is_live,
             sample_rate: self.sample_rate.take(),
             source_url: self.source_url.take(),
             title: self.title.take(),
             thumbnail: self.thumbnail.take(),
+            album: self.album.take(),
+            artwork: self.artwork.take(),
+            skipped_bytes: self.skipped_bytes.take(),
+            audio_tracks: mem::take(&mut self.audio_tracks),
+        }
+    }
+
+    /// Fill any missing fields in this `Metadata` object using those of `other`.
+    pub(crate) fn fill_from(&mut self, other: Self) {
+        fn fill<T>(dest: &mut Option<T>, src: Option<T>) {
+            if dest.is_none() {
+                *dest = src;
+            }
+        }
+
+        fill(&mut self.track, other.track);
+        fill(&mut self.artist, other.artist);
+        fill(&mut self.date, other.date);
+        fill(&mut self.channels, other.channels);
+        fill(&mut self.channel, other.channel);
+        fill(&mut self.start_time, other.start_time);
+        fill(&mut self.duration, other.duration);
+        fill(&mut self.is_live, other.is_live);
+        fill(&mut self.sample_rate, other.sample_rate);
+        fill(&mut self.source_url, other.source_url);
+        fill(&mut self.title, other.title);
+        fill(&mut self.thumbnail, other.thumbnail);
+        fill(&mut self.album, other.album);
+        fill(&mut self.artwork, other.artwork);
+        fill(&mut self.skipped_bytes, other.skipped_bytes);
+
+        if self.audio_tracks.is_empty() {
+            self.audio_tracks = other.audio_tracks;
+        }
+    }
+
+    /// Returns the audio track marked as default by the container, or else
+    /// the first audio track.
+    pub fn default_audio_track(&self) -> Option<&AudioTrack> {
+        self.audio_tracks
+            .iter()
+            .find(|t| t.default)
+            .or_else(|| self.audio_tracks.first())
+    }
+
+    /// Sets the artist and track from the title of an ICY (Shoutcast/Icecast)
+    /// stream, which is conventionally of the form `"Artist - Track"`.
+    pub(crate) fn apply_stream_title(&mut self, stream_title: &str) {
+        let mut parts = stream_title.splitn(2, " - ");
+
+        match (parts.next(), parts.next()) {
+            (Some(artist), Some(track)) => {
+                self.artist = Some(artist.trim().to_string());
+                self.track = Some(track.trim().to_string());
+            },
+            _ => {
+                self.artist = None;
+                self.track = Some(stream_title.trim().to_string());
+            },
+        }
+    }
+}
+
+/// Extracts the title of an ICY (Shoutcast/Icecast) stream from a line of `ffmpeg`
+/// output, or from a raw ICY metadata block.
+pub(crate) fn parse_stream_title(line: &str) -> Option<&str> {
+    let idx = line.find("StreamTitle")?;
+    let rest = line[idx + "StreamTitle".len()..].trim_start();
+
+    let title = if let Some(quoted) = rest.strip_prefix("='") {
+        quoted.find("';").map_or(quoted, |end| &quoted[..end])
+    } else {
+        rest.strip_prefix(':')?
+    }
+    .trim();
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(title)
+    }
+}
+
+/// Minimal reader for the leading packets of an Ogg bitstream.
+struct OggPackets<R> {
+    reader: R,
+    segments: Vec<u8>,
+    segment_idx: usize,
+}
+
+impl<R: Read> OggPackets<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            segments: vec![],
+            segment_idx: 0,
+        }
+    }
+
+    /// Reads the next complete packet, or `None` if the stream ends or is malformed.
+    fn next_packet(&mut self) -> Option<Vec<u8>> {
+        let mut packet = vec![];
+
+        loop {
+            if self.segment_idx >= self.segments.len() {
+                self.next_page()?;
+            }
+
+            let lace = self.segments[self.segment_idx] as usize;
+            self.segment_idx += 1;
+
+            let start = packet.len();
+            packet.resize(start + lace, 0);
+            self.reader.read_exact(&mut packet[start..]).ok()?;
+
+            if lace < 255 {
+                return Some(packet);
+            }
+        }
+    }
+
+    fn next_page(&mut self) -> Option<()> {
+        let mut header = [0u8; 27];
+        self.reader.read_exact(&mut header).ok()?;
+
+        if &header[..4] != b"OggS" {
+            return None;
+        }
+
+        self.segments = vec![0u8; header[26] as usize];
+        self.segment_idx = 0;
+
+        self.reader.read_exact(&mut self.segments).ok()
+    }
+}
+
+fn take_u32_le(body: &mut &[u8]) -> Option<u32> {
+    take_bytes(body, 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn take_u32_be(body: &mut &[u8]) -> Option<u32> {
+    take_bytes(body, 4).map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+}
+
+fn take_bytes<'a>(body: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if body.len() < len {
+        return None;
+    }
+
+    let (out, rest) = body.split_at(len);
+    *body = rest;
+
+    Some(out)
+}
+
+fn take_le_prefixed<'a>(body: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = take_u32_le(body)? as usize;
+    take_bytes(body, len)
+}
+
+fn take_be_prefixed<'a>(body: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = take_u32_be(body)? as usize;
+    take_bytes(body, len)
+}
+
+/// Parses a FLAC `METADATA_BLOCK_PICTURE` body.
+fn parse_flac_picture(mut body: &[u8]) -> Option<Artwork> {
+    let body = &mut body;
+
+    let _picture_type = take_u32_be(body)?;
+    let mime_type = String::from_utf8_lossy(take_be_prefixed(body)?).into_owned();
+    let _description = take_be_prefixed(body)?;
+    // Width, height, colour depth, and indexed colour count.
+    let _dimensions = take_bytes(body, 16)?;
+    let data = take_be_prefixed(body)?.to_vec();
+
+    Some(Artwork { mime_type, data })
+}
+
+impl AudioTrack {
+    fn from_ffprobe_stream(index: usize, stream: &Value) -> Self {
+        let tags = stream.get("tags");
+
+        let language = tags
+            .and_then(|m| m.get("language"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        let title = tags
+            .and_then(|m| m.get("title"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        let channels = stream
+            .get("channels")
+            .and_then(Value::as_u64)
+            .map(|v| v as u8);
+
+        let default = stream
+            .get("disposition")
+            .and_then(|m| m.get("default"))
+            .and_then(Value::as_u64)
+            == Some(1);
+
+        Self {
+            index,
+            language,
+            title,
+            channels,
+            default,
         }
     }
 }
@@ -28,36 +28,71 @@
 //! [`Compressed`]: cached::Compressed
 //! [`dca`]: dca()
 
+mod builder;
 pub mod cached;
+mod channel_map;
 mod child;
 pub mod codec;
+mod concat;
 mod container;
 mod dca;
+mod decode;
 pub mod error;
 mod ffmpeg_src;
+mod gain;
+#[cfg(feature = "http")]
+mod http_src;
 mod metadata;
+mod pad;
+mod process_limit;
 pub mod reader;
+mod resync;
 pub mod restartable;
+mod sample_rate;
+mod seek_index;
+mod slice;
+mod streaming;
+mod tone;
+pub mod tts;
 pub mod utils;
+mod wrapped;
 mod ytdl_src;
 
 pub use self::{
+    builder::InputBuilder,
+    channel_map::{remap_channels, ChannelMapper},
     child::*,
     codec::{Codec, CodecType},
+    concat::concat,
     container::{Container, Frame},
     dca::dca,
+    decode::{decode_to, DecodeSink, DecodeSummary},
     ffmpeg_src::*,
-    metadata::Metadata,
+    gain::gain,
+    metadata::{Artwork, AudioTrack, Metadata, MetadataUpdater},
+    pad::pad,
+    process_limit::{active_processes, process_limit, set_process_limit},
     reader::Reader,
     restartable::Restartable,
+    sample_rate::with_sample_rate,
+    slice::slice,
+    streaming::{streaming, StreamingSource},
+    tone::{tone, Tone, Waveform},
     ytdl_src::*,
 };
 
+#[cfg(feature = "http")]
+pub use self::http_src::http_with;
+
 use crate::constants::*;
 use audiopus::coder::GenericCtl;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use cached::OpusCompressor;
 use error::{Error, Result};
+use flume::{Receiver, Sender};
+use metadata::MetadataTransform;
+use seek_index::SeekIndex;
+use wrapped::WrappedHandle;
 use tokio::runtime::Handle;
 
 use std::{
@@ -95,6 +130,13 @@ pub struct Input {
     /// Framing strategy needed to identify frames of compressed audio.
     pub container: Container,
     pos: usize,
+    /// Byte offset into `reader` of the next frame, for framed inputs.
+    frame_offset: u64,
+    seek_index: SeekIndex,
+    metadata_updates: Option<(Sender<Metadata>, Receiver<Metadata>)>,
+    metadata_transform: Option<MetadataTransform>,
+    /// Second handle to `reader`, for sources built from other inputs.
+    wrapped: Option<WrappedHandle>,
 }
 
 impl Input {
@@ -107,6 +149,11 @@ impl Input {
             kind: Codec::FloatPcm,
             container: Container::Raw,
             pos: 0,
+            frame_offset: 0,
+            seek_index: Default::default(),
+            metadata_updates: None,
+            metadata_transform: None,
+            wrapped: None,
         }
     }
 
@@ -118,6 +165,8 @@ impl Input {
         container: Container,
         metadata: Option<Metadata>,
     ) -> Self {
+        let frame_offset = container.input_start() as u64;
+
         Input {
             metadata: metadata.unwrap_or_default().into(),
             stereo,
@@ -125,17 +174,69 @@ impl Input {
             kind,
             container,
             pos: 0,
+            frame_offset,
+            seek_index: Default::default(),
+            metadata_updates: None,
+            metadata_transform: None,
+            wrapped: None,
         }
     }
 
-    /// Returns whether the inner [`Reader`] implements [`Seek`].
+    /// Returns whether the inner [`Reader`] implements [`Seek`], and so whether
+    /// [`seek_time`] may move backwards or jump ahead without reading the
+    /// audio in between.
+    ///
+    /// This can be checked before playback, *e.g.*, to hide scrubbing controls for
+    /// live streams. A [`Restartable`] source which has not yet been opened answers
+    /// from the metadata it was created with, and refines this once opened.
     ///
     /// [`Reader`]: reader::Reader
     /// [`Seek`]: https://doc.rust-lang.org/std/io/trait.Seek.html
+    /// [`seek_time`]: Input::seek_time
+    /// [`Restartable`]: restartable::Restartable
     pub fn is_seekable(&self) -> bool {
         self.reader.is_seekable()
     }
 
+    /// Creates a second input over the same cached audio, from its beginning.
+    ///
+    /// Returns `None` unless this input reads from a [`Memory`] or [`Compressed`] cache.
+    ///
+    /// [`Memory`]: cached::Memory
+    /// [`Compressed`]: cached::Compressed
+    pub(crate) fn fork(&self) -> Option<Input> {
+        let reader = self.reader.fork()?;
+
+        // Forks need their own decoder state.
+        let mut kind = Codec::try_from(CodecType::from(&self.kind)).ok()?;
+        if let (Codec::Opus(new), Codec::Opus(old)) = (&mut kind, &self.kind) {
+            new.allow_passthrough = old.allow_passthrough;
+        }
+
+        Some(Input::new(
+            self.stereo,
+            reader,
+            kind,
+            self.container,
+            Some((*self.metadata).clone()),
+        ))
+    }
+
+    /// Returns how much audio this input has read ahead of its playback position.
+    ///
+    /// This counts raw PCM held by songbird itself: data sitting in a pipe's read buffer,
+    /// or the unread remainder of an in-memory cache. Data held by a child process or by
+    /// the network is not counted. Generated sources, [`Reader::Extension`]s and
+    /// Opus-encoded inputs report zero.
+    ///
+    /// [`Reader::Extension`]: reader::Reader::Extension
+    pub fn buffered(&self) -> Duration {
+        match &self.wrapped {
+            Some(wrapped) => wrapped.lock().buffered(),
+            None => self.reader.buffered(&self.kind, self.stereo),
+        }
+    }
+
     /// Returns whether the read audio signal is stereo (or mono).
     pub fn is_stereo(&self) -> bool {
         self.stereo
@@ -148,6 +249,68 @@ impl Input {
         (&self.kind).into()
     }
 
+    /// Returns a handle which this input's source can use to announce
+    /// new [`Metadata`] while playing.
+    ///
+    /// [`Metadata`]: Metadata
+    pub fn metadata_updater(&mut self) -> MetadataUpdater {
+        let (tx, _rx) = self.metadata_updates.get_or_insert_with(flume::unbounded);
+
+        MetadataUpdater { tx: tx.clone() }
+    }
+
+    /// Rewrites this input's [`Metadata`] using `transform`, such as to set a custom
+    /// title or to strip query parameters from its source URL.
+    ///
+    /// This applies once to the current metadata, and then to each update announced
+    /// by the source while it plays (*e.g.*, the song titles of an internet radio
+    /// stream), before they reach the [`TrackHandle`] or fire events. Call this before
+    /// creating a track from this input. Calling this again applies `transform` after
+    /// any existing transforms.
+    ///
+    /// Updates are transformed on the driver's mixing thread, so `transform` must be
+    /// cheap and must not block.
+    ///
+    /// [`Metadata`]: Metadata
+    /// [`TrackHandle`]: crate::tracks::TrackHandle
+    pub fn transform_metadata<F>(&mut self, transform: F)
+    where
+        F: Fn(Metadata) -> Metadata + Send + Sync + 'static,
+    {
+        let transform = MetadataTransform::new(transform);
+
+        *self.metadata = transform.apply(self.metadata.take());
+
+        self.metadata_transform = Some(match self.metadata_transform.take() {
+            Some(existing) => existing.then(transform),
+            None => transform,
+        });
+    }
+
+    /// Applies the most recent metadata announced through a [`MetadataUpdater`],
+    /// returning it if any updates were received.
+    ///
+    /// Updates announced by the source within a [`Restartable`] are forwarded too.
+    /// If both announce metadata between polls, this input's own update wins.
+    ///
+    /// [`Restartable`]: restartable::Restartable
+    pub(crate) fn poll_metadata(&mut self) -> Option<Metadata> {
+        let own = self
+            .metadata_updates
+            .as_ref()
+            .and_then(|(_tx, rx)| rx.try_iter().last());
+        let inner = self.reader.poll_metadata();
+        let mut latest = own.or(inner)?;
+
+        if let Some(transform) = &self.metadata_transform {
+            latest = transform.apply(latest);
+        }
+
+        *self.metadata = latest.clone();
+
+        Some(latest)
+    }
+
     /// Mixes the output of this stream into a 20ms stereo audio buffer.
     #[inline]
     pub fn mix(&mut self, float_buffer: &mut [f32; STEREO_FRAME_SIZE], volume: f32) -> usize {
@@ -165,6 +328,34 @@ impl Input {
             .map(|a| utils::byte_count_to_timestamp(a as usize, self.stereo))
     }
 
+    /// Recreates a [`Restartable`] source from its beginning, or otherwise rewinds
+    /// a seekable source to its first sample.
+    ///
+    /// [`Restartable`]: restartable::Restartable
+    pub(crate) fn restart(&mut self) -> IoResult<()> {
+        if let (None, Reader::Restartable(r)) = (&self.wrapped, &mut self.reader) {
+            r.restart()?;
+
+            if let Codec::Opus(state) = &mut self.kind {
+                state.current_frame.truncate(0);
+                state.frame_pos = 0;
+                state.should_reset = true;
+            }
+
+            self.pos = 0;
+            self.frame_offset = self.container.input_start() as u64;
+
+            Ok(())
+        } else if self.is_seekable() {
+            Seek::seek(self, SeekFrom::Start(0)).map(|_| ())
+        } else {
+            Err(IoError::new(
+                IoErrorKind::Unsupported,
+                "Input can neither be recreated nor rewound.",
+            ))
+        }
+    }
+
     fn read_inner(&mut self, buffer: &mut [u8], ignore_decode: bool) -> IoResult<usize> {
         // This implementation of Read converts the input stream
         // to floating point output.
@@ -198,10 +389,13 @@ impl Input {
                     while buffer.len() - aud_skipped >= STEREO_FRAME_BYTE_SIZE {
                         decoder_state.should_reset = true;
 
+                        self.seek_index.record(self.pos + aud_skipped, self.frame_offset);
+
                         let frame = self
                             .container
                             .next_frame_length(&mut self.reader, CodecType::Opus)?;
                         self.reader.consume(frame.frame_len);
+                        self.frame_offset += (frame.header_len + frame.frame_len) as u64;
 
                         aud_skipped += STEREO_FRAME_BYTE_SIZE;
                     }
@@ -218,9 +412,13 @@ impl Input {
                                 .expect("Critical failure resetting decoder.");
                             decoder_state.should_reset = false;
                         }
+
+                        self.seek_index.record(self.pos, self.frame_offset);
+
                         let frame = self
                             .container
                             .next_frame_length(&mut self.reader, CodecType::Opus)?;
+                        self.frame_offset += (frame.header_len + frame.frame_len) as u64;
 
                         let mut opus_data_buffer = [0u8; 4000];
 
@@ -311,9 +509,12 @@ impl Input {
             state.current_frame.truncate(0);
 
             // step 2: read new header.
+            self.seek_index.record(self.pos, self.frame_offset);
+
             let frame = self
                 .container
                 .next_frame_length(&mut self.reader, CodecType::Opus)?;
+            self.frame_offset += (frame.header_len + frame.frame_len) as u64;
 
             // step 3: read in bytes.
             self.reader
@@ -330,8 +531,57 @@ impl Input {
         }
     }
 
+    /// Returns a frame recorded in the seek index which is a better starting point
+    /// for reaching `target` than the current position.
+    fn nearest_frame(&self, target: usize) -> Option<(usize, u64)> {
+        if !self.is_seekable() {
+            return None;
+        }
+
+        self.seek_index
+            .nearest(target)
+            .filter(|(entry_pos, _)| *entry_pos > self.pos || target < self.pos)
+    }
+
     pub(crate) fn prep_with_handle(&mut self, handle: Handle) {
-        self.reader.prep_with_handle(handle);
+        match &self.wrapped {
+            Some(wrapped) => wrapped.lock().prep_with_handle(handle),
+            None => self.reader.prep_with_handle(handle),
+        }
+    }
+
+    /// Returns whether this source is preparing its stream, and is producing
+    /// silence in the meantime.
+    pub(crate) fn is_buffering(&self) -> bool {
+        match &self.wrapped {
+            Some(wrapped) => wrapped.lock().is_buffering(),
+            None => self.reader.is_buffering(),
+        }
+    }
+
+    pub(crate) fn make_playable(&mut self) {
+        match &self.wrapped {
+            Some(wrapped) => wrapped.lock().make_playable(),
+            None => self.reader.make_playable(),
+        }
+    }
+
+    /// Returns where the latest seek truly landed, if this differs from the
+    /// requested position and has not already been reported.
+    pub(crate) fn take_seek_landing(&mut self) -> Option<Duration> {
+        match &self.wrapped {
+            Some(wrapped) => wrapped.lock().take_seek_landing(),
+            None => self.reader.take_seek_landing(),
+        }
+    }
+
+    /// Returns diagnostics from a failed child process, if this source is (or
+    /// contains) a process whose output has ended unsuccessfully.
+    pub(crate) fn failure(&self) -> Option<ChildFailure> {
+        match &self.wrapped {
+            Some(wrapped) => wrapped.lock().failure(),
+            None => self.reader.failure(),
+        }
     }
 }
 
@@ -365,6 +615,19 @@ impl Seek for Input {
                 self.pos = outer_dest;
                 outer_dest
             })
+        } else if let Some((entry_pos, entry_offset)) = self.nearest_frame(target) {
+            // jump to the closest known frame, then seek in the remainder.
+            Seek::seek(&mut self.reader, SeekFrom::Start(entry_offset))?;
+
+            if let Codec::Opus(state) = &mut self.kind {
+                state.current_frame.truncate(0);
+                state.frame_pos = 0;
+                state.should_reset = true;
+            }
+
+            self.pos = entry_pos;
+            self.frame_offset = entry_offset;
+            self.cheap_consume(target - entry_pos)
         } else if target > self.pos {
             // seek in the next amount, disabling decoding if need be.
             let shift = target - self.pos;
@@ -377,6 +640,7 @@ impl Seek for Input {
             )?;
 
             self.pos = 0;
+            self.frame_offset = self.container.input_start() as u64;
             self.cheap_consume(target)
         })
         .map(|_| self.pos as u64)
@@ -517,6 +781,418 @@ mod tests {
     use super::*;
     use crate::test_utils::*;
 
+    #[test]
+    fn ogg_opus_comments_parsed() {
+        fn page(packet: &[u8]) -> Vec<u8> {
+            let mut out = b"OggS".to_vec();
+            out.extend_from_slice(&[0u8; 22]);
+
+            let mut lacing = vec![255u8; packet.len() / 255];
+            lacing.push((packet.len() % 255) as u8);
+
+            out.push(lacing.len() as u8);
+            out.extend_from_slice(&lacing);
+            out.extend_from_slice(packet);
+            out
+        }
+
+        let mut head = b"OpusHead".to_vec();
+        head.extend_from_slice(&[1, 2, 0x38, 0x01, 0x80, 0xbb, 0, 0, 0, 0, 0]);
+
+        let picture = "METADATA_BLOCK_PICTURE=\
+            AAAAAwAAAAlpbWFnZS9wbmcAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAASJUE5H";
+        let comments = ["title=Song", "ARTIST=Someone", "ALBUM=Record", picture];
+
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&0u32.to_le_bytes());
+        tags.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+        for comment in comments.iter() {
+            tags.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            tags.extend_from_slice(comment.as_bytes());
+        }
+
+        let mut stream = page(&head);
+        stream.extend(page(&tags));
+
+        let metadata = Metadata::from_ogg_opus(&stream[..]);
+
+        assert_eq!(metadata.channels, Some(2));
+        // Pre-skip is a decoder delay, not a start timestamp.
+        assert_eq!(metadata.start_time, None);
+        assert_eq!(metadata.track.as_deref(), Some("Song"));
+        assert_eq!(metadata.artist.as_deref(), Some("Someone"));
+        assert_eq!(metadata.album.as_deref(), Some("Record"));
+        assert_eq!(
+            metadata.artwork,
+            Some(Artwork {
+                mime_type: "image/png".into(),
+                data: b"\x89PNG".to_vec(),
+            })
+        );
+
+        // Truncated comment headers keep what was already found.
+        let truncated = Metadata::from_ogg_opus(&stream[..stream.len() - 10]);
+        assert_eq!(truncated.channels, Some(2));
+        assert_eq!(truncated.track, None);
+    }
+
+    #[test]
+    fn ffprobe_lists_audio_tracks() {
+        let value = serde_json::json!({
+            "format": {"duration": "10.0"},
+            "streams": [
+                {"codec_type": "video"},
+                {
+                    "codec_type": "audio",
+                    "channels": 6,
+                    "sample_rate": "48000",
+                    "disposition": {"default": 0},
+                    "tags": {"language": "jpn"}
+                },
+                {
+                    "codec_type": "audio",
+                    "channels": 2,
+                    "disposition": {"default": 1},
+                    "tags": {"language": "eng", "title": "Commentary"}
+                }
+            ]
+        });
+
+        let metadata = Metadata::from_ffprobe_json(&value);
+
+        assert_eq!(metadata.channels, Some(6));
+        assert_eq!(metadata.audio_tracks.len(), 2);
+        assert_eq!(metadata.audio_tracks[0].language.as_deref(), Some("jpn"));
+        assert_eq!(
+            metadata.default_audio_track(),
+            Some(&AudioTrack {
+                index: 1,
+                language: Some("eng".into()),
+                title: Some("Commentary".into()),
+                channels: Some(2),
+                default: true,
+            })
+        );
+
+        assert_eq!(Metadata::default().default_audio_track(), None);
+    }
+
+    #[test]
+    fn metadata_transforms_apply_to_updates() {
+        let mut input = Input::float_pcm(true, Vec::<u8>::new().into());
+        input.metadata.source_url = Some("https://example.com/a?utm_source=x".into());
+
+        input.transform_metadata(|mut m| {
+            m.source_url = m
+                .source_url
+                .map(|url| url.split('?').next().unwrap_or_default().to_string());
+            m
+        });
+        input.transform_metadata(|mut m| {
+            m.title = Some(format!("{} (requested)", m.track.as_deref().unwrap_or("?")));
+            m
+        });
+
+        assert_eq!(input.metadata.source_url.as_deref(), Some("https://example.com/a"));
+        assert_eq!(input.metadata.title.as_deref(), Some("? (requested)"));
+
+        let updater = input.metadata_updater();
+        assert!(updater.update(Metadata {
+            track: Some("Song".into()),
+            source_url: Some("https://example.com/b?utm_source=x".into()),
+            ..Default::default()
+        }));
+
+        let latest = input.poll_metadata().unwrap();
+        assert_eq!(latest.source_url.as_deref(), Some("https://example.com/b"));
+        assert_eq!(latest.title.as_deref(), Some("Song (requested)"));
+        assert_eq!(*input.metadata, latest);
+    }
+
+    #[tokio::test]
+    async fn tts_backends_play_through_driver() {
+        use crate::driver::Driver;
+        use async_trait::async_trait;
+        use tts::{TtsBackend, VoiceParams};
+
+        struct SineSpeech;
+
+        #[async_trait]
+        impl TtsBackend for SineSpeech {
+            async fn synthesize(&self, text: &str, voice: &VoiceParams) -> Result<Input> {
+                assert_eq!(voice, &VoiceParams::default());
+                let audio = make_sine(text.len() * MONO_FRAME_SIZE, false);
+                Ok(Input::float_pcm(false, Reader::from_memory(audio)))
+            }
+        }
+
+        let mut driver = Driver::new(Default::default());
+        let handle = driver.speak("hello", &SineSpeech).await.unwrap();
+        assert!(handle.is_seekable());
+
+        let dyn_backend: Box<dyn TtsBackend> = Box::new(SineSpeech);
+        assert!(driver.speak("hi", &*dyn_backend).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn process_limit_queues_sources() {
+        use std::time::Duration;
+        use tokio::time::timeout;
+
+        set_process_limit(Some(2));
+        assert_eq!(process_limit(), Some(2));
+
+        let chain = process_limit::acquire(2).await;
+        assert_eq!(active_processes(), 2);
+
+        // Waiting sources can be cancelled without taking a slot.
+        let waited = timeout(Duration::from_millis(20), process_limit::acquire(1)).await;
+        assert!(waited.is_err());
+        assert_eq!(active_processes(), 2);
+
+        let waiter = tokio::spawn(process_limit::acquire(1));
+        tokio::task::yield_now().await;
+        drop(chain);
+
+        let single = timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        assert_eq!(active_processes(), 1);
+        drop(single);
+
+        // Oversized chains run alone.
+        let oversized = process_limit::acquire(3).await;
+        assert_eq!(active_processes(), 3);
+        drop(oversized);
+
+        set_process_limit(None);
+        assert_eq!(active_processes(), 0);
+    }
+
+    #[test]
+    fn tones_generate_seekable_pcm() {
+        use byteorder::ByteOrder;
+
+        let mut input = tone(1000.0, Some(Duration::from_millis(20)), Waveform::Sine, 0.5);
+        assert!(input.is_seekable());
+        assert!(input.stereo);
+
+        let mut pcm = vec![];
+        input.read_to_end(&mut pcm).unwrap();
+        assert_eq!(pcm.len(), STEREO_FRAME_BYTE_SIZE);
+
+        let samples: Vec<f32> = pcm.chunks_exact(4).map(LittleEndian::read_f32).collect();
+        let peak = samples.iter().fold(0f32, |acc, s| acc.max(s.abs()));
+        assert!((peak - 0.5).abs() < 1e-3);
+        // 48 samples per cycle: a quarter cycle in lies the first peak.
+        assert!((samples[2 * 12] - 0.5).abs() < 1e-3);
+
+        // Seeks replay the same signal, even for noise.
+        let mut noise = tone(0.0, None, Waveform::Noise, 1.0);
+        let mut first = [0u8; 64];
+        let mut again = [0u8; 64];
+        noise.read_exact(&mut first[..]).unwrap();
+        noise.seek(SeekFrom::Start(0)).unwrap();
+        noise.read_exact(&mut again[..3]).unwrap();
+        noise.read_exact(&mut again[3..]).unwrap();
+        assert_eq!(first, again);
+        assert!(first.chunks_exact(4).all(|s| LittleEndian::read_f32(s).abs() <= 1.0));
+    }
+
+    #[test]
+    fn icy_stream_titles_update_metadata() {
+        use metadata::parse_stream_title;
+
+        let update = "[http @ 0x5581] Metadata update for StreamTitle: Someone - Song";
+        assert_eq!(parse_stream_title(update), Some("Someone - Song"));
+        assert_eq!(parse_stream_title("      StreamTitle     : Song"), Some("Song"));
+        assert_eq!(
+            parse_stream_title("StreamTitle='Someone - Song';StreamUrl='';"),
+            Some("Someone - Song")
+        );
+        assert_eq!(parse_stream_title("StreamTitle='';"), None);
+        assert_eq!(parse_stream_title("Stream #0:0: Audio: mp3"), None);
+
+        let mut input = Input::float_pcm(true, Vec::<u8>::new().into());
+        assert_eq!(input.poll_metadata(), None);
+
+        let updater = input.metadata_updater();
+        let mut metadata = Metadata::default();
+        metadata.apply_stream_title("Someone - Song");
+        assert!(updater.update(metadata));
+
+        let latest = input.poll_metadata().unwrap();
+        assert_eq!(latest.artist.as_deref(), Some("Someone"));
+        assert_eq!(latest.track.as_deref(), Some("Song"));
+        assert_eq!(input.metadata.track.as_deref(), Some("Song"));
+        assert_eq!(input.poll_metadata(), None);
+
+        drop(input);
+        assert!(!updater.update(Metadata::default()));
+    }
+
+    #[test]
+    fn channel_counts_select_playback_layout() {
+        assert!(!utils::is_stereo_layout(Some(1)));
+        assert!(utils::is_stereo_layout(Some(2)));
+        assert!(utils::is_stereo_layout(Some(6)));
+        assert!(!utils::is_stereo_layout(None));
+
+        // Mono sources are upmixed into both output channels.
+        let data = make_sine(MONO_FRAME_SIZE, false);
+        let mut input = Input::new(false, data.into(), Codec::FloatPcm, Container::Raw, None);
+        let mut out = [0f32; STEREO_FRAME_SIZE];
+
+        assert_eq!(input.mix(&mut out, 1.0), STEREO_FRAME_BYTE_SIZE);
+        assert!(out.chunks_exact(2).all(|frame| frame[0] == frame[1]));
+        assert!(out.iter().any(|&sample| sample != 0.0));
+
+        // Stereo sources are mixed as-is.
+        let data = make_sine(MONO_FRAME_SIZE, true);
+        let mut input = Input::new(true, data.into(), Codec::FloatPcm, Container::Raw, None);
+        let mut out = [0f32; STEREO_FRAME_SIZE];
+
+        assert_eq!(input.mix(&mut out, 1.0), STEREO_FRAME_BYTE_SIZE);
+    }
+
+    #[test]
+    fn concat_plays_and_seeks_across_inputs() {
+        let intro = make_sine(10 * MONO_FRAME_SIZE, true);
+        let song = make_sine(20 * MONO_FRAME_SIZE, true);
+        let make = |data: &Vec<u8>| {
+            Input::new(true, data.clone().into(), Codec::FloatPcm, Container::Raw, None)
+        };
+
+        let mut input = concat(vec![make(&intro), make(&song)]);
+
+        let mut out_vec = vec![];
+        input.read_to_end(&mut out_vec).unwrap();
+        assert_eq!(out_vec, [&intro[..], &song[..]].concat());
+
+        // Seek backwards into the first input, then read across the boundary.
+        let offset = intro.len() - STEREO_FRAME_BYTE_SIZE;
+        input.seek(SeekFrom::Start(offset as u64)).unwrap();
+        let mut out_vec = vec![0u8; 2 * STEREO_FRAME_BYTE_SIZE];
+        input.read_exact(&mut out_vec).unwrap();
+        assert_eq!(out_vec[..STEREO_FRAME_BYTE_SIZE], intro[offset..]);
+        assert_eq!(
+            out_vec[STEREO_FRAME_BYTE_SIZE..],
+            song[..STEREO_FRAME_BYTE_SIZE]
+        );
+    }
+
+    #[test]
+    fn buffered_counts_unread_cached_audio() {
+        let song = make_sine(10 * MONO_FRAME_SIZE, true);
+        let plain = Input::float_pcm(true, song.clone().into());
+        assert_eq!(plain.buffered(), Duration::default());
+
+        let cached = cached::Memory::new(Input::float_pcm(true, song.into())).unwrap();
+        let mut input: Input = cached.try_into().unwrap();
+        input.read_to_end(&mut vec![]).unwrap();
+        input.seek(SeekFrom::Start(0)).unwrap();
+        assert_eq!(input.buffered(), Duration::from_millis(200));
+
+        let mut frame = vec![0u8; STEREO_FRAME_BYTE_SIZE];
+        input.read_exact(&mut frame).unwrap();
+        assert_eq!(input.buffered(), Duration::from_millis(180));
+    }
+
+    #[test]
+    fn pad_surrounds_input_with_silence() {
+        let song = make_sine(10 * MONO_FRAME_SIZE, true);
+        let metadata = Metadata {
+            duration: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let song_input = Input::new(
+            true,
+            song.clone().into(),
+            Codec::FloatPcm,
+            Container::Raw,
+            Some(metadata),
+        );
+
+        let mut input = pad(
+            song_input,
+            Duration::from_millis(20),
+            Duration::from_millis(40),
+        );
+        assert_eq!(input.metadata.duration, Some(Duration::from_millis(260)));
+
+        let silence = vec![0u8; STEREO_FRAME_BYTE_SIZE];
+        let mut out_vec = vec![];
+        input.read_to_end(&mut out_vec).unwrap();
+        assert_eq!(
+            out_vec,
+            [&silence[..], &song[..], &silence[..], &silence[..]].concat()
+        );
+
+        // Seeks are measured from the start of the leading pad.
+        input.seek(SeekFrom::Start(STEREO_FRAME_BYTE_SIZE as u64)).unwrap();
+        let mut out_vec = vec![0u8; STEREO_FRAME_BYTE_SIZE];
+        input.read_exact(&mut out_vec).unwrap();
+        assert_eq!(out_vec, song[..STEREO_FRAME_BYTE_SIZE]);
+    }
+
+    #[test]
+    fn gain_scales_signal_energy() {
+        use byteorder::ByteOrder;
+
+        fn energy(data: &[u8]) -> f32 {
+            data.chunks_exact(4)
+                .map(|s| LittleEndian::read_f32(s).powi(2))
+                .sum()
+        }
+
+        let data = make_sine(10 * MONO_FRAME_SIZE, true);
+        let make = || {
+            Input::new(true, data.clone().into(), Codec::FloatPcm, Container::Raw, None)
+        };
+
+        // -6dB roughly halves amplitude, quartering energy.
+        let mut out_vec = vec![];
+        gain(make(), -6.0, true).read_to_end(&mut out_vec).unwrap();
+        assert_eq!(out_vec.len(), data.len());
+        let ratio = energy(&out_vec) / energy(&data);
+        assert!((ratio - 0.251).abs() < 0.001, "energy ratio {}", ratio);
+
+        // Gains compose, and boosts can be left to clip or clamped.
+        let mut out_vec = vec![];
+        let mut input = gain(gain(make(), -6.0, false), 20.0, false);
+        input.read_to_end(&mut out_vec).unwrap();
+        let ratio = energy(&out_vec) / energy(&data);
+        assert!((ratio - 25.1).abs() < 0.1, "energy ratio {}", ratio);
+
+        let mut out_vec = vec![];
+        gain(make(), 20.0, true).read_to_end(&mut out_vec).unwrap();
+        assert!(out_vec
+            .chunks_exact(4)
+            .all(|s| LittleEndian::read_f32(s).abs() <= 1.0));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn http_auth_failures_are_distinct() {
+        use reqwest::StatusCode;
+
+        assert!(http_src::status_error(StatusCode::OK).is_none());
+        assert!(matches!(
+            http_src::status_error(StatusCode::FORBIDDEN),
+            Some(Error::Unauthorized(403))
+        ));
+        assert!(matches!(
+            http_src::status_error(StatusCode::UNAUTHORIZED),
+            Some(Error::Unauthorized(401))
+        ));
+        assert!(matches!(
+            http_src::status_error(StatusCode::NOT_FOUND),
+            Some(Error::NotFound)
+        ));
+        assert!(matches!(
+            http_src::status_error(StatusCode::BAD_GATEWAY),
+            Some(Error::HttpStatus(502))
+        ));
+    }
+
     #[test]
     fn float_pcm_input_unchanged_mono() {
         let data = make_sine(50 * MONO_FRAME_SIZE, false);
@@ -592,4 +1268,47 @@ mod tests {
             assert!(diff.abs() < f32::EPSILON);
         }
     }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn stream_start_found_past_junk() {
+        assert_eq!(resync::find_stream_start(b"OggS\0\x02"), Some(0));
+        assert_eq!(resync::find_stream_start(b"<html>oops</html>ID3\x04"), Some(17));
+        assert_eq!(resync::find_stream_start(b"\0\0\0\0\0\0\0\x20ftypisom"), Some(4));
+        assert_eq!(resync::find_stream_start(b"no audio here"), None);
+
+        // MPEG audio is only recognised by two consecutive valid frames
+        // (MPEG-1 layer III, 128kbps at 44.1kHz, which are 417 bytes long).
+        let mut mp3 = b"junk".to_vec();
+        for _ in 0..2 {
+            mp3.extend_from_slice(b"\xff\xfb\x90\x00");
+            mp3.resize(mp3.len() + 413, 0);
+        }
+        mp3.extend_from_slice(b"\xff\xfb\x90\x00\0\0\0");
+        assert_eq!(resync::find_stream_start(&mp3), Some(4));
+        assert_eq!(resync::find_stream_start(&mp3[..420]), None);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn frame_syncs_in_audio_are_not_stream_starts() {
+        // A valid-looking frame header within audio data is not followed by another...
+        let mut pcm = vec![0u8; 64];
+        pcm.extend_from_slice(b"\xff\xe2\x10\x00\x12\x34\x56");
+        pcm.resize(4096, 0x11);
+        assert!(!resync::is_stream_start(&pcm[64..]));
+        assert_eq!(resync::find_stream_start(&pcm), None);
+
+        // ...and recognised containers are never searched at all.
+        let mut aiff = b"FORM\0\0\x10\0AIFF".to_vec();
+        aiff.extend_from_slice(&pcm);
+        assert!(resync::is_stream_start(&aiff));
+    }
+
+    #[test]
+    fn dca_start_found_past_junk() {
+        let mut dca = vec![0u8; 10];
+        dca.extend_from_slice(b"DCA1");
+        assert_eq!(resync::find_dca_start(&dca), Some(10));
+    }
 }
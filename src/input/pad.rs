@@ -0,0 +1,110 @@
+use super::{concat, Codec, Container, Input, Reader};
+use crate::constants::*;
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Seek, SeekFrom},
+    mem,
+    time::Duration,
+};
+use symphonia_core::io::MediaSource;
+
+/// Surrounds an [`Input`] with silence, playing `before` of silence, then
+/// `input`, then `after` of silence.
+///
+/// This is a convenient way to space out clips in a queue, or to time a stinger
+/// against other audio, as the result is a single source: its duration and
+/// position include both padded regions, and seeks may land anywhere within them.
+///
+/// The result outputs floating-point PCM with the same channel count as `input`,
+/// and supports seeking if `input` does. Its metadata is taken from `input`, with
+/// the duration extended by `before` and `after` (if known). Padded inputs may be
+/// passed to [`concat`] like any other.
+///
+/// [`Input`]: Input
+/// [`concat`]: concat()
+pub fn pad(mut input: Input, before: Duration, after: Duration) -> Input {
+    let stereo = input.stereo;
+    let mut metadata = input.metadata.take();
+    metadata.duration = metadata.duration.map(|d| before + d + after);
+
+    let mut parts = Vec::with_capacity(3);
+    if before > Duration::default() {
+        parts.push(silence(before, stereo));
+    }
+    parts.push(input);
+    if after > Duration::default() {
+        parts.push(silence(after, stereo));
+    }
+
+    let mut out = concat(parts);
+    metadata.channels = out.metadata.channels;
+    *out.metadata = metadata;
+
+    out
+}
+
+fn silence(duration: Duration, stereo: bool) -> Input {
+    let channels = if stereo { 2 } else { 1 };
+    let frames = (duration.as_secs_f64() * SAMPLE_RATE_RAW as f64) as u64;
+
+    Input::new(
+        stereo,
+        Reader::Extension(Box::new(Silence {
+            len: frames * channels * mem::size_of::<f32>() as u64,
+            pos: 0,
+        })),
+        Codec::FloatPcm,
+        Container::Raw,
+        None,
+    )
+}
+
+/// A fixed length of floating-point silence.
+#[derive(Clone, Debug)]
+struct Silence {
+    /// Length of the silence, in bytes.
+    len: u64,
+    /// Byte position within the silence.
+    pos: u64,
+}
+
+impl Read for Silence {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        let count = (self.len.saturating_sub(self.pos)).min(buffer.len() as u64) as usize;
+        buffer[..count].fill(0);
+        self.pos += count as u64;
+
+        Ok(count)
+    }
+}
+
+impl Seek for Silence {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(n) => (n, 0),
+            SeekFrom::Current(d) => (self.pos, d),
+            SeekFrom::End(d) => (self.len, d),
+        };
+
+        let target = if delta >= 0 {
+            base.checked_add(delta as u64)
+        } else {
+            base.checked_sub(delta.unsigned_abs())
+        };
+
+        self.pos = target.ok_or_else(|| {
+            IoError::new(IoErrorKind::InvalidInput, "Seek before start of silence.")
+        })?;
+
+        Ok(self.pos)
+    }
+}
+
+impl MediaSource for Silence {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.len)
+    }
+}
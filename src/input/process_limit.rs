@@ -0,0 +1,99 @@
+//! Limits on the number of external processes (`ffmpeg`, `youtube-dl`, ...)
+//! which songbird runs at once, shared by all drivers.
+
+use flume::Sender;
+use parking_lot::{const_mutex, Mutex};
+
+struct LimitState {
+    active: usize,
+    limit: Option<usize>,
+    waiters: Vec<Sender<()>>,
+}
+
+static STATE: Mutex<LimitState> = const_mutex(LimitState {
+    active: 0,
+    limit: None,
+    waiters: Vec::new(),
+});
+
+/// Sets the maximum number of external processes which songbird may run at once.
+///
+/// Sources which would exceed this wait to start until enough processes have exited,
+/// rather than spawning more and exhausting the host's process IDs or file descriptors.
+/// A source which runs a chain of several processes (*e.g.*, `youtube-dl` into `ffmpeg`)
+/// counts each process, and waits for the whole chain to fit. A chain larger than the
+/// limit may start once no other processes are running.
+///
+/// Processes are counted for as long as the [`Input`] using them is alive. Short-lived
+/// processes used to probe metadata are also counted while they run.
+///
+/// A source waiting for a slot may be cancelled by dropping its future (*e.g.*, with
+/// [`tokio::time::timeout`]); it does not hold a slot until its processes start.
+///
+/// Lowering the limit does not stop running processes. Defaults to `None` (no limit).
+///
+/// [`Input`]: super::Input
+/// [`tokio::time::timeout`]: https://docs.rs/tokio/1/tokio/time/fn.timeout.html
+pub fn set_process_limit(limit: Option<usize>) {
+    let mut state = STATE.lock();
+    state.limit = limit;
+    wake_all(&mut state);
+}
+
+/// Returns the maximum number of external processes which songbird may run at once.
+pub fn process_limit() -> Option<usize> {
+    STATE.lock().limit
+}
+
+/// Returns the number of external processes currently run by songbird's sources.
+pub fn active_processes() -> usize {
+    STATE.lock().active
+}
+
+/// Reservation for external processes under the [process limit], released on drop.
+///
+/// [process limit]: set_process_limit
+#[derive(Debug)]
+pub(crate) struct ProcessSlot {
+    count: usize,
+}
+
+impl Drop for ProcessSlot {
+    fn drop(&mut self) {
+        let mut state = STATE.lock();
+        state.active -= self.count;
+        wake_all(&mut state);
+    }
+}
+
+/// Waits until `count` more processes may be started.
+pub(crate) async fn acquire(count: usize) -> ProcessSlot {
+    loop {
+        let rx = {
+            let mut state = STATE.lock();
+
+            let fits = match state.limit {
+                Some(limit) => state.active == 0 || state.active + count <= limit,
+                None => true,
+            };
+
+            if fits {
+                state.active += count;
+                return ProcessSlot { count };
+            }
+
+            let (tx, rx) = flume::bounded(1);
+            state.waiters.push(tx);
+            rx
+        };
+
+        // Any change to the limit or active count wakes all waiters to re-check.
+        let _ = rx.recv_async().await;
+    }
+}
+
+fn wake_all(state: &mut LimitState) {
+    for waiter in state.waiters.drain(..) {
+        let _ = waiter.try_send(());
+    }
+}
@@ -14,7 +14,9 @@ use std::{
         Seek,
         SeekFrom,
     },
+    mem,
     result::Result as StdResult,
+    time::Duration,
 };
 use streamcatcher::{Catcher, TxCatcher};
 pub use symphonia_core::io::MediaSource;
@@ -59,12 +61,28 @@ impl Reader {
     pub fn is_seekable(&self) -> bool {
         use Reader::*;
         match self {
-            Restartable(_) | Compressed(_) | Memory(_) => true,
+            Restartable(r) => r.is_seekable(),
+            Compressed(_) | Memory(_) => true,
             Extension(source) => source.is_seekable(),
             _ => false,
         }
     }
 
+    /// Creates a second reader over the same cached data, from its beginning.
+    ///
+    /// Returns `None` for all but [`Memory`] and [`Compressed`] sources.
+    ///
+    /// [`Memory`]: Reader::Memory
+    /// [`Compressed`]: Reader::Compressed
+    pub(crate) fn fork(&self) -> Option<Self> {
+        use Reader::*;
+        match self {
+            Memory(m) => Some(Memory(m.new_handle())),
+            Compressed(c) => Some(Compressed(c.new_handle())),
+            _ => None,
+        }
+    }
+
     /// A source contained in a local file.
     pub fn from_file(file: File) -> Self {
         Self::Extension(Box::new(file))
@@ -75,6 +93,16 @@ impl Reader {
         Self::Extension(Box::new(Cursor::new(buf)))
     }
 
+    /// Returns diagnostics from a failed child process, if this source is a
+    /// process whose output has ended unsuccessfully.
+    pub fn failure(&self) -> Option<ChildFailure> {
+        use Reader::*;
+        match self {
+            Pipe(p) => p.get_ref().failure().cloned(),
+            _ => None,
+        }
+    }
+
     #[allow(clippy::single_match)]
     pub(crate) fn prep_with_handle(&mut self, handle: Handle) {
         use Reader::*;
@@ -84,6 +112,56 @@ impl Reader {
         }
     }
 
+    /// Returns where the latest seek truly landed, if this differs from the
+    /// requested position and has not already been reported.
+    pub(crate) fn take_seek_landing(&mut self) -> Option<Duration> {
+        use Reader::*;
+        match self {
+            Restartable(r) => r.take_landed(),
+            _ => None,
+        }
+    }
+
+    /// Returns the most recent metadata announced by an input held within
+    /// this source, if any.
+    pub(crate) fn poll_metadata(&mut self) -> Option<Metadata> {
+        use Reader::*;
+        match self {
+            Restartable(r) => r.poll_metadata(),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this source is preparing its stream, and is producing
+    /// silence in the meantime.
+    pub(crate) fn is_buffering(&self) -> bool {
+        use Reader::*;
+        match self {
+            Restartable(r) => r.is_buffering(),
+            _ => false,
+        }
+    }
+
+    /// Returns how much audio of type `kind` this source holds ahead of its
+    /// current read position.
+    pub(crate) fn buffered(&self, kind: &Codec, stereo: bool) -> Duration {
+        use Reader::*;
+        let bytes = match self {
+            Pipe(p) => p.buffer().len(),
+            Memory(m) => m.len().saturating_sub(m.pos()),
+            Restartable(r) => return r.buffered(),
+            _ => 0,
+        };
+
+        let sample_len = match kind {
+            Codec::FloatPcm => mem::size_of::<f32>(),
+            Codec::Pcm => mem::size_of::<i16>(),
+            Codec::Opus(_) => return Duration::default(),
+        };
+
+        utils::sample_count_to_timestamp(bytes / sample_len, stereo)
+    }
+
     #[allow(clippy::single_match)]
     pub(crate) fn make_playable(&mut self) {
         use Reader::*;
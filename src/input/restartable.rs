@@ -19,15 +19,25 @@ use std::{
     result::Result as StdResult,
     time::Duration,
 };
+use tokio::task::JoinHandle;
 
 type Recreator = Box<dyn Restart + Send + 'static>;
-type RecreateChannel = Receiver<Result<(Box<Input>, Recreator)>>;
+type RecreateChannel = Receiver<Result<(Box<Input>, Recreator, Option<Duration>)>>;
 
 // Use options here to make "take" more doable from a mut ref.
 enum LazyProgress {
     Dead(Box<Metadata>, Option<Recreator>, Codec, Container),
     Live(Box<Input>, Option<Recreator>),
-    Working(Codec, Container, bool, RecreateChannel),
+    Working(Codec, Container, bool, RecreateChannel, JoinHandle<()>),
+}
+
+impl Drop for LazyProgress {
+    fn drop(&mut self) {
+        // A restart which never completes would otherwise outlive its track.
+        if let LazyProgress::Working(_, _, _, _, task) = self {
+            task.abort();
+        }
+    }
 }
 
 impl Debug for LazyProgress {
@@ -42,7 +52,7 @@ impl Debug for LazyProgress {
                 .finish(),
             LazyProgress::Live(input, _) =>
                 f.debug_tuple("Live").field(input).field(&"<fn>").finish(),
-            LazyProgress::Working(codec, container, stereo, chan) => f
+            LazyProgress::Working(codec, container, stereo, chan, _) => f
                 .debug_tuple("Working")
                 .field(codec)
                 .field(container)
@@ -75,6 +85,9 @@ pub struct Restartable {
     async_handle: Option<Handle>,
     position: usize,
     source: LazyProgress,
+    /// Position at which the latest restarted source truly began, if this
+    /// differs from the position requested and has not yet been collected.
+    landed: Option<Duration>,
 }
 
 impl Restartable {
@@ -93,6 +106,7 @@ impl Restartable {
                 .map(move |(meta, kind, codec)| Self {
                     async_handle: None,
                     position: 0,
+                    landed: None,
                     source: LazyProgress::Dead(
                         meta.unwrap_or_default().into(),
                         Some(Box::new(recreator)),
@@ -104,17 +118,39 @@ impl Restartable {
             recreator.call_restart(None).await.map(move |source| Self {
                 async_handle: None,
                 position: 0,
+                landed: None,
                 source: LazyProgress::Live(source.into(), Some(Box::new(recreator))),
             })
         }
     }
 
     /// Create a new restartable ffmpeg source for a local file.
+    ///
+    /// Seeks are made using [`SeekMode::Fast`]; ffmpeg's input seeking already
+    /// lands on the requested time when transcoding.
     pub async fn ffmpeg<P: AsRef<OsStr> + Send + Clone + Sync + 'static>(
         path: P,
         lazy: bool,
     ) -> Result<Self> {
-        Self::new(FfmpegRestarter { path }, lazy).await
+        Self::ffmpeg_with_seek_mode(path, lazy, SeekMode::Fast).await
+    }
+
+    /// Create a new restartable ffmpeg source for a local file, choosing
+    /// whether seeks favour speed or accuracy.
+    pub async fn ffmpeg_with_seek_mode<P: AsRef<OsStr> + Send + Clone + Sync + 'static>(
+        path: P,
+        lazy: bool,
+        seek_mode: SeekMode,
+    ) -> Result<Self> {
+        Self::new(
+            FfmpegRestarter {
+                path,
+                seek_mode,
+                landed: None,
+            },
+            lazy,
+        )
+        .await
     }
 
     /// Create a new restartable ytdl source.
@@ -140,6 +176,101 @@ impl Restartable {
         self.async_handle = Some(handle);
     }
 
+    pub(crate) fn is_buffering(&self) -> bool {
+        !matches!(self.source, LazyProgress::Live(_, _))
+    }
+
+    /// Returns whether this source can be seeked.
+    ///
+    /// Any source can be recreated at a new position, unless its metadata marks
+    /// it as a live stream. This is refined once the source has been opened.
+    pub fn is_seekable(&self) -> bool {
+        let metadata = match &self.source {
+            LazyProgress::Dead(meta, ..) => meta,
+            LazyProgress::Live(input, _) => &input.metadata,
+            LazyProgress::Working(..) => return true,
+        };
+
+        metadata.is_live != Some(true)
+    }
+
+    pub(crate) fn buffered(&self) -> Duration {
+        match &self.source {
+            LazyProgress::Live(input, _) => input.buffered(),
+            _ => Duration::default(),
+        }
+    }
+
+    /// Returns the most recent metadata announced by the live source, if any.
+    ///
+    /// Updates made by a source which has since been recreated are lost.
+    pub(crate) fn poll_metadata(&mut self) -> Option<Metadata> {
+        match &mut self.source {
+            LazyProgress::Live(input, _) => input.poll_metadata(),
+            _ => None,
+        }
+    }
+
+    /// Returns where the most recent seek truly landed, if this differed from
+    /// the requested position.
+    ///
+    /// This is only reported once, after the restarted source has begun playing.
+    pub(crate) fn take_landed(&mut self) -> Option<Duration> {
+        self.landed.take()
+    }
+
+    /// Recreates this source from its beginning, even if it has not yet advanced.
+    ///
+    /// Unlike a seek to the start, the recreator is asked for a fresh source,
+    /// rather than one which begins at a given time.
+    pub(crate) fn restart(&mut self) -> IoResult<()> {
+        if !self.is_seekable() {
+            return Err(IoError::new(
+                IoErrorKind::Unsupported,
+                "Live streams cannot be restarted.",
+            ));
+        }
+
+        let handle = match self.async_handle.clone() {
+            Some(handle) => handle,
+            None =>
+                return Err(IoError::new(
+                    IoErrorKind::Interrupted,
+                    "Cannot safely restart until provided an async context handle.",
+                )),
+        };
+
+        use LazyProgress::*;
+        let (rec, stereo, kind, container) = match &mut self.source {
+            Dead(meta, rec, kind, container) => (
+                rec.take(),
+                utils::is_stereo_layout(meta.channels),
+                kind.clone(),
+                *container,
+            ),
+            Live(input, rec) => (rec.take(), input.stereo, input.kind.clone(), input.container),
+            Working(..) => {
+                return Err(IoError::new(
+                    IoErrorKind::Interrupted,
+                    "Previous seek in progress.",
+                ));
+            },
+        };
+
+        let rec = rec.ok_or_else(|| {
+            IoError::new(
+                IoErrorKind::UnexpectedEof,
+                "Illegal state: taken recreator was observed.",
+            )
+        })?;
+
+        self.source = regenerate_channel(rec, None, stereo, kind, container, Some(handle))?;
+        self.position = 0;
+        self.landed = None;
+
+        Ok(())
+    }
+
     pub(crate) fn make_playable(&mut self) {
         if matches!(self.source, LazyProgress::Dead(_, _, _, _)) {
             // This read triggers creation of a source, and is guaranteed not to modify any internals.
@@ -165,6 +296,17 @@ pub trait Restart {
     /// should occupy few resources when not live BUT have as much information as
     /// possible made available at creation.
     async fn lazy_init(&mut self) -> Result<(Option<Metadata>, Codec, Container)>;
+
+    /// Returns the position at which the source created by the last call to
+    /// [`call_restart`] truly begins, if this differs from the time requested.
+    ///
+    /// Sources which can only seek approximately should report this, so that
+    /// track positions remain correct.
+    ///
+    /// [`call_restart`]: Restart::call_restart
+    fn take_landed(&mut self) -> Option<Duration> {
+        None
+    }
 }
 
 struct FfmpegRestarter<P>
@@ -172,6 +314,8 @@ where
     P: AsRef<OsStr> + Send + Sync,
 {
     path: P,
+    seek_mode: SeekMode,
+    landed: Option<Duration>,
 }
 
 #[async_trait]
@@ -180,30 +324,16 @@ where
     P: AsRef<OsStr> + Send + Sync,
 {
     async fn call_restart(&mut self, time: Option<Duration>) -> Result<Input> {
+        self.landed = None;
+
         if let Some(time) = time {
-            let is_stereo = is_stereo(self.path.as_ref())
-                .await
-                .unwrap_or_else(|_e| (false, Default::default()));
-            let stereo_val = if is_stereo.0 { "2" } else { "1" };
+            let (input, landed) = ffmpeg_seeked(self.path.as_ref(), time, self.seek_mode).await?;
 
-            let ts = format!("{:.3}", time.as_secs_f64());
-            _ffmpeg_optioned(
-                self.path.as_ref(),
-                &["-ss", &ts],
-                &[
-                    "-f",
-                    "s16le",
-                    "-ac",
-                    stereo_val,
-                    "-ar",
-                    "48000",
-                    "-acodec",
-                    "pcm_f32le",
-                    "-",
-                ],
-                Some(is_stereo),
-            )
-            .await
+            if landed != time {
+                self.landed = Some(landed);
+            }
+
+            Ok(input)
         } else {
             ffmpeg(self.path.as_ref()).await
         }
@@ -214,6 +344,10 @@ where
             .await
             .map(|(_stereo, metadata)| (Some(metadata), Codec::FloatPcm, Container::Raw))
     }
+
+    fn take_landed(&mut self) -> Option<Duration> {
+        self.landed.take()
+    }
 }
 
 struct YtdlRestarter<P>
@@ -249,7 +383,7 @@ impl From<Restartable> for Input {
     fn from(mut src: Restartable) -> Self {
         let (meta, stereo, kind, container) = match &mut src.source {
             LazyProgress::Dead(ref mut m, _rec, kind, container) => {
-                let stereo = m.channels == Some(2);
+                let stereo = utils::is_stereo_layout(m.channels);
                 (Some(m.take()), stereo, kind.clone(), *container)
             },
             LazyProgress::Live(ref mut input, _rec) => (
@@ -259,7 +393,7 @@ impl From<Restartable> for Input {
                 input.container,
             ),
             // This branch should never be taken: this is an emergency measure.
-            LazyProgress::Working(kind, container, stereo, _) =>
+            LazyProgress::Working(kind, container, stereo, _, _) =>
                 (None, *stereo, kind.clone(), *container),
         };
         Input::new(stereo, Reader::Restartable(src), kind, container, meta)
@@ -275,12 +409,12 @@ impl Read for Restartable {
         use LazyProgress::*;
         let (out_val, march_pos, next_source) = match &mut self.source {
             Dead(meta, rec, kind, container) => {
-                let stereo = meta.channels == Some(2);
+                let stereo = utils::is_stereo_layout(meta.channels);
                 let handle = self.async_handle.clone();
                 let new_chan = if let Some(rec) = rec.take() {
                     Some(regenerate_channel(
                         rec,
-                        0,
+                        Some(Duration::default()),
                         stereo,
                         kind.clone(),
                         *container,
@@ -300,11 +434,17 @@ impl Read for Restartable {
                 (Ok(buffer.len()), false, new_chan)
             },
             Live(source, _) => (Read::read(source, buffer), true, None),
-            Working(_, _, _, chan) => {
+            Working(_, _, _, chan, _) => {
                 match chan.try_recv() {
-                    Ok(Ok((mut new_source, recreator))) => {
+                    Ok(Ok((mut new_source, recreator, landed))) => {
                         // Completed!
                         // Do read, then replace inner progress.
+                        if let Some(landed) = landed {
+                            self.position =
+                                utils::timestamp_to_byte_count(landed, new_source.stereo);
+                            self.landed = Some(landed);
+                        }
+
                         let bytes_read = Read::read(&mut new_source, buffer);
 
                         (bytes_read, true, Some(Live(new_source, Some(recreator))))
@@ -353,21 +493,30 @@ impl Seek for Restartable {
     fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
         let _local_pos = self.position as u64;
 
+        if !self.is_seekable() {
+            return Err(IoError::new(
+                IoErrorKind::Unsupported,
+                "Live streams cannot be seeked.",
+            ));
+        }
+
         use SeekFrom::*;
         match pos {
             Start(offset) => {
                 let offset = offset as usize;
                 let handle = self.async_handle.clone();
+                self.landed = None;
 
                 use LazyProgress::*;
                 match &mut self.source {
                     Dead(meta, rec, kind, container) => {
                         // regen at given start point
+                        let stereo = utils::is_stereo_layout(meta.channels);
                         self.source = if let Some(rec) = rec.take() {
                             regenerate_channel(
                                 rec,
-                                offset,
-                                meta.channels == Some(2),
+                                Some(utils::byte_count_to_timestamp(offset, stereo)),
+                                stereo,
                                 kind.clone(),
                                 *container,
                                 handle,
@@ -388,7 +537,7 @@ impl Seek for Restartable {
                             self.source = if let Some(rec) = rec.take() {
                                 regenerate_channel(
                                     rec,
-                                    offset,
+                                    Some(utils::byte_count_to_timestamp(offset, input.stereo)),
                                     input.stereo,
                                     input.kind.clone(),
                                     input.container,
@@ -407,7 +556,7 @@ impl Seek for Restartable {
                             self.position += input.consume(offset - self.position);
                         }
                     },
-                    Working(_, _, _, _) => {
+                    Working(..) => {
                         return Err(IoError::new(
                             IoErrorKind::Interrupted,
                             "Previous seek in progress.",
@@ -428,7 +577,7 @@ impl Seek for Restartable {
 
 fn regenerate_channel(
     mut rec: Recreator,
-    offset: usize,
+    time: Option<Duration>,
     stereo: bool,
     kind: Codec,
     container: Container,
@@ -437,15 +586,15 @@ fn regenerate_channel(
     if let Some(handle) = handle.as_ref() {
         let (tx, rx) = flume::bounded(1);
 
-        handle.spawn(async move {
-            let ret_val = rec
-                .call_restart(Some(utils::byte_count_to_timestamp(offset, stereo)))
-                .await;
+        let task = handle.spawn(async move {
+            let ret_val = rec.call_restart(time).await;
 
-            let _ = tx.send(ret_val.map(Box::new).map(|v| (v, rec)));
+            let landed = rec.take_landed();
+
+            let _ = tx.send(ret_val.map(Box::new).map(|v| (v, rec, landed)));
         });
 
-        Ok(LazyProgress::Working(kind, container, stereo, rx))
+        Ok(LazyProgress::Working(kind, container, stereo, rx, task))
     } else {
         Err(IoError::new(
             IoErrorKind::Interrupted,
@@ -453,3 +602,225 @@ fn regenerate_channel(
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::make_sine;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    /// Lands seeks on the preceding whole second, as a container with sparse
+    /// seek points would.
+    struct CoarseRestarter {
+        landed: Option<Duration>,
+        live: bool,
+    }
+
+    #[async_trait]
+    impl Restart for CoarseRestarter {
+        async fn call_restart(&mut self, time: Option<Duration>) -> Result<Input> {
+            let time = time.unwrap_or_default();
+            let landed = Duration::from_secs(time.as_secs());
+            self.landed = Some(landed).filter(|&t| t != time);
+
+            Ok(Input::float_pcm(true, make_sine(10 * SAMPLE_RATE_RAW, true).into()))
+        }
+
+        async fn lazy_init(&mut self) -> Result<(Option<Metadata>, Codec, Container)> {
+            let metadata = Metadata {
+                is_live: Some(self.live),
+                ..Default::default()
+            };
+
+            Ok((Some(metadata), Codec::FloatPcm, Container::Raw))
+        }
+
+        fn take_landed(&mut self) -> Option<Duration> {
+            self.landed.take()
+        }
+    }
+
+    #[tokio::test]
+    async fn restarts_report_where_seeks_land() {
+        let restarter = CoarseRestarter {
+            landed: None,
+            live: false,
+        };
+        let source = Restartable::new(restarter, true).await.unwrap();
+        let mut input = Input::from(source);
+        input.prep_with_handle(Handle::current());
+
+        let target = Duration::from_millis(2_500);
+        assert_eq!(input.seek_time(target), Some(target));
+
+        let mut buffer = [0f32; STEREO_FRAME_SIZE];
+        while input.is_buffering() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            input.mix(&mut buffer, 1.0);
+        }
+
+        let landed = input.take_seek_landing().expect("Seek landing reported.");
+        assert!(target - landed <= Duration::from_secs(1));
+        assert_eq!(input.take_seek_landing(), None);
+    }
+
+    #[tokio::test]
+    async fn wrapped_restarts_report_where_seeks_land() {
+        let restarter = CoarseRestarter {
+            landed: None,
+            live: false,
+        };
+        let source = Restartable::new(restarter, true).await.unwrap();
+        let mut input = crate::input::gain(Input::from(source), 0.0, false);
+        input.prep_with_handle(Handle::current());
+
+        // The lazy source within is prepared and polled through its wrapper.
+        let target = Duration::from_millis(2_500);
+        assert_eq!(input.seek_time(target), Some(target));
+        assert!(input.is_buffering());
+
+        let mut buffer = [0f32; STEREO_FRAME_SIZE];
+        while input.is_buffering() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            input.mix(&mut buffer, 1.0);
+        }
+
+        let landed = input.take_seek_landing().expect("Seek landing reported.");
+        assert!(target - landed <= Duration::from_secs(1));
+        assert_eq!(input.take_seek_landing(), None);
+    }
+
+    #[tokio::test]
+    async fn concatenated_restarts_report_where_seeks_land() {
+        let restarter = CoarseRestarter {
+            landed: None,
+            live: false,
+        };
+        let source = Restartable::new(restarter, true).await.unwrap();
+        let mut input = crate::input::concat(vec![Input::from(source)]);
+        input.prep_with_handle(Handle::current());
+
+        let target = Duration::from_millis(2_500);
+        assert_eq!(input.seek_time(target), Some(target));
+
+        let mut buffer = [0f32; STEREO_FRAME_SIZE];
+        while input.is_buffering() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            input.mix(&mut buffer, 1.0);
+        }
+
+        let landed = input.take_seek_landing().expect("Seek landing reported.");
+        assert!(target - landed <= Duration::from_secs(1));
+        assert_eq!(input.take_seek_landing(), None);
+    }
+
+    /// Hands out an updater for each source it creates, as an ffmpeg source
+    /// following a radio stream's titles would.
+    struct AnnouncingRestarter(Arc<Mutex<Option<MetadataUpdater>>>);
+
+    #[async_trait]
+    impl Restart for AnnouncingRestarter {
+        async fn call_restart(&mut self, _time: Option<Duration>) -> Result<Input> {
+            let mut input = Input::float_pcm(true, make_sine(10 * SAMPLE_RATE_RAW, true).into());
+            *self.0.lock() = Some(input.metadata_updater());
+
+            Ok(input)
+        }
+
+        async fn lazy_init(&mut self) -> Result<(Option<Metadata>, Codec, Container)> {
+            Ok((None, Codec::FloatPcm, Container::Raw))
+        }
+    }
+
+    #[tokio::test]
+    async fn restartable_sources_forward_metadata_updates() {
+        let updater = Arc::new(Mutex::new(None));
+        let source = Restartable::new(AnnouncingRestarter(updater.clone()), false)
+            .await
+            .unwrap();
+        let mut input = Input::from(source);
+        input.transform_metadata(|mut m| {
+            m.title = m.track.clone();
+            m
+        });
+
+        assert_eq!(input.poll_metadata(), None);
+
+        let updater = updater.lock().take().expect("Source created.");
+        assert!(updater.update(Metadata {
+            track: Some("Now Playing".into()),
+            ..Default::default()
+        }));
+
+        let latest = input.poll_metadata().expect("Update forwarded.");
+        assert_eq!(latest.title.as_deref(), Some("Now Playing"));
+        assert_eq!(input.metadata.track.as_deref(), Some("Now Playing"));
+        assert_eq!(input.poll_metadata(), None);
+    }
+
+    /// Records the time at which each source was requested.
+    struct RecordingRestarter(Arc<Mutex<Vec<Option<Duration>>>>);
+
+    #[async_trait]
+    impl Restart for RecordingRestarter {
+        async fn call_restart(&mut self, time: Option<Duration>) -> Result<Input> {
+            self.0.lock().push(time);
+
+            Ok(Input::float_pcm(true, make_sine(10 * SAMPLE_RATE_RAW, true).into()))
+        }
+
+        async fn lazy_init(&mut self) -> Result<(Option<Metadata>, Codec, Container)> {
+            Ok((None, Codec::FloatPcm, Container::Raw))
+        }
+    }
+
+    #[tokio::test]
+    async fn tracks_restart_by_recreating_their_source() {
+        use crate::tracks::{self, TrackError};
+
+        let calls = Arc::new(Mutex::new(vec![]));
+        let source = Restartable::new(RecordingRestarter(calls.clone()), false)
+            .await
+            .unwrap();
+        let (mut track, _handle) = tracks::create_player(source.into());
+        track.source.prep_with_handle(Handle::current());
+
+        // Even a source which has not moved is recreated, rather than sought.
+        track.restart().unwrap();
+        assert!(track.source.is_buffering());
+        assert_eq!(track.restart(), Err(TrackError::RestartFailed));
+
+        let mut buffer = [0f32; STEREO_FRAME_SIZE];
+        while track.source.is_buffering() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            track.source.mix(&mut buffer, 1.0);
+        }
+        assert_eq!(*calls.lock(), [None, None]);
+        assert_eq!(track.position, Duration::default());
+
+        // Live streams can be neither recreated nor rewound.
+        let restarter = CoarseRestarter {
+            landed: None,
+            live: true,
+        };
+        let source = Restartable::new(restarter, true).await.unwrap();
+        let (mut track, _handle) = tracks::create_player(source.into());
+        track.source.prep_with_handle(Handle::current());
+        assert_eq!(track.restart(), Err(TrackError::RestartUnsupported));
+    }
+
+    #[tokio::test]
+    async fn live_streams_refuse_seeks() {
+        let restarter = CoarseRestarter {
+            landed: None,
+            live: true,
+        };
+        let source = Restartable::new(restarter, true).await.unwrap();
+        let mut input = Input::from(source);
+        input.prep_with_handle(Handle::current());
+
+        assert!(!input.is_seekable());
+        assert_eq!(input.seek_time(Duration::from_secs(2)), None);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::constants::*;
+
+/// Signatures which begin a recognised audio container or stream.
+#[cfg(feature = "http")]
+const SIGNATURES: &[&[u8]] = &[
+    b"OggS",
+    b"fLaC",
+    b"ID3",
+    b"RIFF",
+    b"FORM",
+    b"caff",
+    b"DCA1",
+    // EBML, for WebM and Matroska.
+    &[0x1a, 0x45, 0xdf, 0xa3],
+];
+
+/// Number of bytes needed to check whether a stream begins with a recognised header.
+#[cfg(feature = "http")]
+pub(crate) const PROBE_LEN: usize = 12;
+
+/// Returns whether `buf` begins with a recognised container signature, or with
+/// two consecutive MPEG audio or ADTS frames.
+#[cfg(feature = "http")]
+pub(crate) fn is_stream_start(buf: &[u8]) -> bool {
+    SIGNATURES.iter().any(|sig| buf.starts_with(sig))
+        // MP4 and friends lead with a box size, then `ftyp`.
+        || buf.get(4..8) == Some(&b"ftyp"[..])
+        || frame_len(buf)
+            .and_then(|len| buf.get(len..))
+            .map(|next| frame_len(next).is_some())
+            .unwrap_or(false)
+}
+
+/// Returns the offset of the first recognised stream start (as judged by
+/// [`is_stream_start`]) in `buf`, looking no further than [`RESYNC_WINDOW`] bytes in.
+///
+/// [`RESYNC_WINDOW`]: crate::constants::RESYNC_WINDOW
+#[cfg(feature = "http")]
+pub(crate) fn find_stream_start(buf: &[u8]) -> Option<usize> {
+    (0..buf.len().min(RESYNC_WINDOW)).find(|&i| is_stream_start(&buf[i..]))
+}
+
+/// Returns the length of the MPEG audio or ADTS frame whose header begins `buf`.
+///
+/// A frame sync alone is a poor signal: `0xff` followed by three set bits is common
+/// in PCM and other audio, so each field of the header must also be valid.
+#[cfg(feature = "http")]
+fn frame_len(buf: &[u8]) -> Option<usize> {
+    const MPEG1_KBPS: [[u16; 15]; 3] = [
+        [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+        [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+        [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+    ];
+    const MPEG2_KBPS: [[u16; 15]; 2] = [
+        [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    ];
+    const MPEG1_HZ: [u32; 3] = [44_100, 48_000, 32_000];
+
+    let header = buf.get(..7)?;
+    if header[0] != 0xff || header[1] & 0xe0 != 0xe0 {
+        return None;
+    }
+
+    let version = (header[1] >> 3) & 0b11;
+    let layer = (header[1] >> 1) & 0b11;
+
+    if layer == 0 {
+        // ADTS, which has a 12-bit sync and an explicit frame length.
+        let rate_index = (header[2] >> 2) & 0b1111;
+        let len = (usize::from(header[3] & 0b11) << 11)
+            | (usize::from(header[4]) << 3)
+            | usize::from(header[5] >> 5);
+
+        return if header[1] & 0xf0 == 0xf0 && rate_index < 13 && len >= 7 {
+            Some(len)
+        } else {
+            None
+        };
+    }
+
+    let bitrate_index = usize::from(header[2] >> 4);
+    let rate_index = usize::from((header[2] >> 2) & 0b11);
+    let padding = usize::from((header[2] >> 1) & 1);
+
+    if version == 0b01 || bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+        return None;
+    }
+
+    // Layers are numbered 3 (I) down to 1 (III) in the header.
+    let (kbps, hz) = if version == 0b11 {
+        (MPEG1_KBPS[usize::from(3 - layer)], MPEG1_HZ[rate_index])
+    } else {
+        let divisor = if version == 0b10 { 2 } else { 4 };
+        (
+            MPEG2_KBPS[usize::from(layer != 3)],
+            MPEG1_HZ[rate_index] / divisor,
+        )
+    };
+    let bitrate = u32::from(kbps[bitrate_index]) * 1000;
+
+    let len = match layer {
+        3 => (12 * bitrate / hz) as usize * 4 + padding * 4,
+        1 if version != 0b11 => (72 * bitrate / hz) as usize + padding,
+        _ => (144 * bitrate / hz) as usize + padding,
+    };
+
+    Some(len)
+}
+
+/// Returns the offset of the first DCA1 header in `buf`, looking no further
+/// than [`RESYNC_WINDOW`] bytes in.
+///
+/// [`RESYNC_WINDOW`]: crate::constants::RESYNC_WINDOW
+pub(crate) fn find_dca_start(buf: &[u8]) -> Option<usize> {
+    let window = &buf[..buf.len().min(RESYNC_WINDOW)];
+
+    window.windows(4).position(|w| w == b"DCA1")
+}
@@ -0,0 +1,283 @@
+use super::{wrapped::Wrapped, ChildFailure, Input};
+use crate::constants::*;
+use byteorder::{ByteOrder, LittleEndian};
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Seek, SeekFrom},
+    mem,
+    time::Duration,
+};
+use tokio::runtime::Handle;
+
+/// Overrides the sample rate assumed for an [`Input`], for sources which are
+/// mis-tagged or otherwise report the wrong rate.
+///
+/// Every [`Input`] is played as though it were sampled at 48kHz. If a source's
+/// audio was really sampled at `hz` (*e.g.*, raw PCM from a file whose header lies,
+/// which plays back too fast or too slow), this converts it to 48kHz from the
+/// correct rate. This does not change how audio is resampled, and sources which
+/// already report their rate correctly should not be wrapped.
+///
+/// The result outputs floating-point PCM with the same channel count as `input`,
+/// and supports seeking if `input` does. Its metadata is taken from `input`, with
+/// the sample rate set to `hz` and the duration (if known) scaled to match.
+///
+/// # Panics
+/// Panics if `hz` is zero.
+///
+/// [`Input`]: Input
+pub fn with_sample_rate(mut input: Input, hz: u32) -> Input {
+    assert!(hz > 0, "Sample rate must be greater than zero.");
+
+    let stereo = input.stereo;
+    let mut metadata = input.metadata.take();
+    metadata.sample_rate = Some(hz);
+    metadata.duration = metadata
+        .duration
+        .map(|d| d.mul_f64(SAMPLE_RATE_RAW as f64 / f64::from(hz)));
+
+    Input::wrapping(stereo, Resample::new(input, hz), metadata)
+}
+
+const SAMPLE_LEN: usize = mem::size_of::<f32>();
+
+/// Reader state for an [`Input`] whose sample rate has been overridden, as
+/// created by [`with_sample_rate`].
+///
+/// This outputs floating-point PCM at 48kHz, using linear interpolation.
+///
+/// [`Input`]: Input
+/// [`with_sample_rate`]: with_sample_rate
+#[derive(Debug)]
+struct Resample {
+    input: Box<Input>,
+    hz: u32,
+    channels: usize,
+    /// Source frames read from `input`, ahead of conversion.
+    window: Vec<f32>,
+    /// Position of the next output frame within `window`, in source frames.
+    phase: f64,
+    /// Whether `input` has no more audio to read.
+    ended: bool,
+    /// Converted bytes not yet returned to the caller.
+    pending: Vec<u8>,
+    pending_pos: usize,
+    /// Byte position within the output.
+    pos: u64,
+}
+
+impl Resample {
+    fn new(input: Input, hz: u32) -> Self {
+        let channels = if input.stereo { 2 } else { 1 };
+
+        Self {
+            input: Box::new(input),
+            hz,
+            channels,
+            window: vec![],
+            phase: 0.0,
+            ended: false,
+            pending: vec![],
+            pending_pos: 0,
+            pos: 0,
+        }
+    }
+
+    /// Source frames advanced per output frame.
+    fn step(&self) -> f64 {
+        f64::from(self.hz) / SAMPLE_RATE_RAW as f64
+    }
+
+    fn frame_len(&self) -> usize {
+        self.channels * SAMPLE_LEN
+    }
+
+    /// Reads whole source frames onto the end of the window, until it holds
+    /// at least `frames` or the source ends.
+    fn fill_window(&mut self, frames: usize) -> IoResult<()> {
+        let frame_len = self.frame_len();
+        let mut raw = vec![0u8; MONO_FRAME_SIZE * frame_len];
+
+        while !self.ended && self.window.len() < frames * self.channels {
+            let mut len = 0;
+            while len < raw.len() {
+                match self.input.read(&mut raw[len..]) {
+                    Ok(0) => {
+                        self.ended = true;
+                        break;
+                    },
+                    Ok(n) => len += n,
+                    Err(e) if e.kind() == IoErrorKind::Interrupted => {},
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let start = self.window.len();
+            let samples = (len / frame_len) * self.channels;
+            self.window.resize(start + samples, 0.0);
+            LittleEndian::read_f32_into(&raw[..samples * SAMPLE_LEN], &mut self.window[start..]);
+        }
+
+        Ok(())
+    }
+
+    /// Converts the next block of output frames, returning `false` at the end
+    /// of the source.
+    fn fill(&mut self) -> IoResult<bool> {
+        let step = self.step();
+        let channels = self.channels;
+
+        // Drop source frames which no later output frame can reach.
+        let spent = (self.phase as usize).min(self.window.len() / channels);
+        self.window.drain(..spent * channels);
+        self.phase -= spent as f64;
+
+        let needed = (self.phase + step * MONO_FRAME_SIZE as f64) as usize + 2;
+        self.fill_window(needed)?;
+
+        let available = self.window.len() / channels;
+        let mut out = Vec::with_capacity(MONO_FRAME_SIZE * channels);
+        for _ in 0..MONO_FRAME_SIZE {
+            let idx = self.phase as usize;
+            if idx >= available {
+                break;
+            }
+
+            let next = (idx + 1).min(available - 1);
+            let frac = (self.phase - idx as f64) as f32;
+            for c in 0..channels {
+                let a = self.window[idx * channels + c];
+                let b = self.window[next * channels + c];
+                out.push(a + (b - a) * frac);
+            }
+
+            self.phase += step;
+        }
+
+        self.pending.resize(out.len() * SAMPLE_LEN, 0);
+        self.pending_pos = 0;
+        LittleEndian::write_f32_into(&out, &mut self.pending);
+
+        Ok(!out.is_empty())
+    }
+}
+
+impl Wrapped for Resample {
+    fn is_seekable(&self) -> bool {
+        self.input.is_seekable()
+    }
+
+    fn prep_with_handle(&mut self, handle: Handle) {
+        self.input.prep_with_handle(handle);
+    }
+
+    fn is_buffering(&self) -> bool {
+        self.input.is_buffering()
+    }
+
+    fn make_playable(&mut self) {
+        self.input.make_playable();
+    }
+
+    fn take_seek_landing(&mut self) -> Option<Duration> {
+        self.input
+            .take_seek_landing()
+            .map(|landed| landed.mul_f64(SAMPLE_RATE_RAW as f64 / f64::from(self.hz)))
+    }
+
+    fn failure(&self) -> Option<ChildFailure> {
+        self.input.failure()
+    }
+}
+
+impl Read for Resample {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        if self.pending_pos >= self.pending.len() && !self.fill()? {
+            return Ok(0);
+        }
+
+        let len = buffer.len().min(self.pending.len() - self.pending_pos);
+        buffer[..len].copy_from_slice(&self.pending[self.pending_pos..self.pending_pos + len]);
+        self.pending_pos += len;
+        self.pos += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl Seek for Resample {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => n,
+            SeekFrom::Current(0) => return Ok(self.pos),
+            _ =>
+                return Err(IoError::new(
+                    IoErrorKind::Unsupported,
+                    "Resampled sources only support seeking from their start.",
+                )),
+        };
+
+        let frame_len = self.frame_len() as u64;
+        let frame = target / frame_len;
+        let source_pos = frame as f64 * self.step();
+        let source_frame = source_pos as u64;
+
+        Seek::seek(&mut *self.input, SeekFrom::Start(source_frame * frame_len))?;
+
+        self.window.clear();
+        self.phase = source_pos - source_frame as f64;
+        self.ended = false;
+        self.pending.clear();
+        self.pending_pos = 0;
+        self.pos = frame * frame_len;
+
+        // Skip into the middle of a frame if asked.
+        let offset = (target - self.pos) as usize;
+        if offset > 0 && self.fill()? {
+            self.pending_pos = offset.min(self.pending.len());
+            self.pos += self.pending_pos as u64;
+        }
+
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn overridden_rate_restores_playback_speed() {
+        // One second of a stereo ramp, sampled at 24kHz but not tagged as such.
+        let hz = 24_000;
+        let mut raw = vec![0u8; hz * 2 * SAMPLE_LEN];
+        for (i, frame) in raw.chunks_exact_mut(2 * SAMPLE_LEN).enumerate() {
+            let val = i as f32 / hz as f32;
+            LittleEndian::write_f32_into(&[val, -val], frame);
+        }
+
+        let mut input = with_sample_rate(Input::float_pcm(true, raw.into()), hz as u32);
+        assert_eq!(input.metadata.sample_rate, Some(hz as u32));
+
+        let mut out = vec![];
+        input.read_to_end(&mut out).unwrap();
+
+        // Played at the right speed, the ramp lasts one second and rises at
+        // the same rate as it was recorded.
+        let frames = out.len() / (2 * SAMPLE_LEN);
+        assert!((frames as isize - SAMPLE_RATE_RAW as isize).abs() <= 2);
+        for (i, frame) in out.chunks_exact(2 * SAMPLE_LEN).enumerate().take(hz) {
+            let expected = i as f32 / SAMPLE_RATE_RAW as f32;
+            assert!((LittleEndian::read_f32(frame) - expected).abs() < 1e-4);
+            assert!((LittleEndian::read_f32(&frame[SAMPLE_LEN..]) + expected).abs() < 1e-4);
+        }
+
+        assert_eq!(
+            input.seek_time(Duration::from_millis(500)),
+            Some(Duration::from_millis(500))
+        );
+        let mut frame = [0u8; 2 * SAMPLE_LEN];
+        input.read_exact(&mut frame).unwrap();
+        assert!((LittleEndian::read_f32(&frame) - 0.5).abs() < 1e-4);
+    }
+}
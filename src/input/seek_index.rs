@@ -0,0 +1,65 @@
+use crate::constants::*;
+
+/// Maximum number of entries held by a [`SeekIndex`].
+const MAX_ENTRIES: usize = 1024;
+
+/// Sparse record of where frames begin within a framed input, built up
+/// as the input is read.
+///
+/// This allows seeks to jump to a nearby frame, rather than reading forward
+/// from the start of the input. Entries are spread further apart as the index
+/// grows, keeping its size bounded.
+#[derive(Clone, Debug)]
+pub(crate) struct SeekIndex {
+    /// Number of frames between recorded entries.
+    stride: usize,
+    /// Pairs of output byte position and input byte offset, in ascending order.
+    entries: Vec<(usize, u64)>,
+}
+
+impl Default for SeekIndex {
+    fn default() -> Self {
+        Self {
+            stride: AUDIO_FRAME_RATE,
+            entries: vec![],
+        }
+    }
+}
+
+impl SeekIndex {
+    /// Notes that the frame beginning at output position `pos` starts
+    /// at byte `offset` of the input.
+    pub(crate) fn record(&mut self, pos: usize, offset: u64) {
+        let spacing = self.stride * STEREO_FRAME_BYTE_SIZE;
+        let due = match self.entries.last() {
+            Some((last, _)) => pos >= last + spacing,
+            None => true,
+        };
+
+        if !due {
+            return;
+        }
+
+        self.entries.push((pos, offset));
+
+        if self.entries.len() > MAX_ENTRIES {
+            // Thin out the index, keeping every other entry.
+            let mut keep = false;
+            self.entries.retain(|_| {
+                keep = !keep;
+                keep
+            });
+            self.stride *= 2;
+        }
+    }
+
+    /// Returns the last known frame beginning at or before output position `target`.
+    pub(crate) fn nearest(&self, target: usize) -> Option<(usize, u64)> {
+        let idx = match self.entries.binary_search_by_key(&target, |(pos, _)| *pos) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        };
+
+        idx.checked_sub(1).map(|idx| self.entries[idx])
+    }
+}
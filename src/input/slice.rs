@@ -0,0 +1,206 @@
+use super::{
+    error::{Error, Result},
+    utils,
+    wrapped::Wrapped,
+    ChildFailure,
+    Input,
+};
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Seek, SeekFrom},
+    time::Duration,
+};
+use tokio::runtime::Handle;
+
+/// Plays only the region of an [`Input`] between `start` and `end`.
+///
+/// This suits sprite-sheet style assets, where many short clips (*e.g.*, sound
+/// effects) are stored in a single file: each clip can be played by slicing the
+/// same source at different times. The result starts from `start`, ends at `end`
+/// (or the end of `input`, if sooner), and treats this region as the whole
+/// source: positions are measured from `start`, and seeks are clamped to the
+/// slice.
+///
+/// The result outputs floating-point PCM with the same channel count as `input`,
+/// and supports seeking. Its metadata is taken from `input`, with the duration
+/// replaced by that of the slice.
+///
+/// Returns [`Error::Unseekable`] if `input` does not support seeking.
+///
+/// [`Input`]: Input
+/// [`Error::Unseekable`]: Error::Unseekable
+pub fn slice(mut input: Input, start: Duration, end: Duration) -> Result<Input> {
+    if !input.is_seekable() {
+        return Err(Error::Unseekable);
+    }
+
+    let stereo = input.stereo;
+    let mut metadata = input.metadata.take();
+
+    let end = match metadata.duration {
+        Some(duration) => end.min(duration),
+        None => end,
+    };
+    metadata.duration = Some(end.saturating_sub(start));
+
+    Ok(Input::wrapping(stereo, Slice::new(input, start, end), metadata))
+}
+
+/// Reader state for a time range of an [`Input`], as created by [`slice`].
+///
+/// This outputs floating-point PCM at 48kHz.
+///
+/// [`Input`]: Input
+/// [`slice`]: slice()
+#[derive(Debug)]
+struct Slice {
+    input: Box<Input>,
+    /// Byte offset of the slice within the contained input.
+    start: u64,
+    /// Length of the slice, in bytes.
+    len: u64,
+    /// Byte position within the slice.
+    pos: u64,
+    /// Whether the contained input has been moved to `start + pos`.
+    placed: bool,
+}
+
+impl Slice {
+    fn new(input: Input, start: Duration, end: Duration) -> Self {
+        let stereo = input.stereo;
+        let start = utils::timestamp_to_byte_count(start, stereo) as u64;
+        let end = utils::timestamp_to_byte_count(end, stereo) as u64;
+
+        Self {
+            input: Box::new(input),
+            start,
+            len: end.saturating_sub(start),
+            pos: 0,
+            placed: false,
+        }
+    }
+
+    /// Moves the contained input to the current position, if it is not already there.
+    ///
+    /// This is deferred until first use, as lazy sources cannot seek before they
+    /// are prepared.
+    fn place(&mut self) -> IoResult<()> {
+        if !self.placed {
+            let inner = Seek::seek(&mut *self.input, SeekFrom::Start(self.start + self.pos))?;
+            self.pos = inner.saturating_sub(self.start).min(self.len);
+            self.placed = true;
+        }
+
+        Ok(())
+    }
+}
+
+impl Wrapped for Slice {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn prep_with_handle(&mut self, handle: Handle) {
+        self.input.prep_with_handle(handle);
+    }
+
+    fn is_buffering(&self) -> bool {
+        self.input.is_buffering()
+    }
+
+    fn make_playable(&mut self) {
+        self.input.make_playable();
+    }
+
+    fn buffered(&self) -> Duration {
+        self.input.buffered()
+    }
+
+    fn take_seek_landing(&mut self) -> Option<Duration> {
+        let start = utils::byte_count_to_timestamp(self.start as usize, self.input.stereo);
+
+        self.input
+            .take_seek_landing()
+            .map(|landed| landed.saturating_sub(start))
+    }
+
+    fn failure(&self) -> Option<ChildFailure> {
+        self.input.failure()
+    }
+}
+
+impl Read for Slice {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        self.place()?;
+
+        let remaining = (self.len - self.pos).min(buffer.len() as u64) as usize;
+        let read = self.input.read(&mut buffer[..remaining])?;
+        self.pos += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl Seek for Slice {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => n,
+            SeekFrom::Current(0) => return Ok(self.pos),
+            _ =>
+                return Err(IoError::new(
+                    IoErrorKind::Unsupported,
+                    "Sliced sources only support seeking from their start.",
+                )),
+        };
+
+        self.pos = target.min(self.len);
+        self.placed = false;
+        self.place()?;
+
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constants::*, input::Reader, test_utils::make_sine};
+    use symphonia_core::io::ReadOnlySource;
+
+    #[test]
+    fn slices_end_on_time_and_clamp_seeks() {
+        let source = || Input::float_pcm(true, make_sine(5 * SAMPLE_RATE_RAW, true).into());
+        let mut clip = slice(source(), Duration::from_secs(1), Duration::from_secs(2)).unwrap();
+        assert_eq!(clip.metadata.duration, Some(Duration::from_secs(1)));
+
+        let mut whole = source();
+        whole.seek_time(Duration::from_secs(1)).unwrap();
+        let mut expected = vec![];
+        whole
+            .take(utils::timestamp_to_byte_count(Duration::from_secs(1), true) as u64)
+            .read_to_end(&mut expected)
+            .unwrap();
+
+        let mut played = vec![];
+        clip.read_to_end(&mut played).unwrap();
+        assert_eq!(played, expected);
+
+        // Seeks past the end land at the end, rather than leaving the slice.
+        assert_eq!(
+            clip.seek_time(Duration::from_secs(3)),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(clip.read(&mut [0u8; 64]).unwrap(), 0);
+
+        assert_eq!(
+            clip.seek_time(Duration::from_millis(500)),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(clip.read(&mut [0u8; 64]).unwrap(), 64);
+
+        let stream = Reader::Extension(Box::new(ReadOnlySource::new(std::io::empty())));
+        assert!(matches!(
+            slice(Input::float_pcm(true, stream), Duration::default(), Duration::from_secs(1)),
+            Err(Error::Unseekable)
+        ));
+    }
+}
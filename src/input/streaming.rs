@@ -0,0 +1,392 @@
+use super::{error::Result, Codec, CodecType, Container, Input, Metadata, Reader};
+use crate::constants::SILENT_FRAME;
+use async_trait::async_trait;
+use flume::{Receiver, Sender, TryRecvError};
+use std::{
+    convert::TryFrom,
+    io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Seek, SeekFrom},
+    mem,
+};
+use symphonia_core::io::MediaSource;
+
+/// Number of chunks fetched ahead of playback.
+const CHUNK_BACKLOG: usize = 32;
+
+/// An async source of framed audio, such as a gRPC or WebSocket stream from a
+/// cloud speech service.
+///
+/// Play one using [`streaming`], which pulls chunks in the background and
+/// declares how they should be decoded.
+///
+/// [`streaming`]: streaming
+#[async_trait]
+pub trait StreamingSource: Send {
+    /// Fetches the next chunk of audio, or `None` once the stream has ended.
+    ///
+    /// PCM streams may split their samples across chunks in any way. For Opus
+    /// streams, each chunk must hold exactly one Opus packet.
+    async fn next_chunk(&mut self) -> IoResult<Option<Vec<u8>>>;
+
+    /// Decides what to do after [`next_chunk`] fails with `error`.
+    ///
+    /// Sources may reconnect or back off here (*e.g.*, by reopening a dropped
+    /// WebSocket) and return `Ok(())`, after which reading resumes from
+    /// [`next_chunk`]. Returning an error ends the stream, passing that error to
+    /// the track. `attempt` counts consecutive failures, starting from `1`, and
+    /// resets after each successful chunk.
+    ///
+    /// By default, every error ends the stream.
+    ///
+    /// [`next_chunk`]: StreamingSource::next_chunk
+    async fn recover(&mut self, error: IoError, attempt: u32) -> IoResult<()> {
+        let _ = attempt;
+        Err(error)
+    }
+}
+
+/// Creates an [`Input`] which plays audio pulled from `source`.
+///
+/// `codec` declares how each chunk is encoded: PCM must be interleaved and
+/// little-endian at 48kHz, with two channels if `stereo` is set. Chunks are fetched
+/// by a background task, so this must be called from within a Tokio runtime. Silence
+/// is played whenever the source falls behind, and playback ends once it returns
+/// `None` or fails to [recover] from an error.
+///
+/// This source is not seek-compatible.
+///
+/// [`Input`]: Input
+/// [recover]: StreamingSource::recover
+pub fn streaming<S>(source: S, codec: CodecType, stereo: bool) -> Result<Input>
+where
+    S: StreamingSource + 'static,
+{
+    let (tx, rx) = flume::bounded(CHUNK_BACKLOG);
+    tokio::spawn(pump(source, tx));
+
+    let framed = matches!(codec, CodecType::Opus);
+    let container = if framed {
+        Container::Dca { first_frame: 0 }
+    } else {
+        Container::Raw
+    };
+    let align = match codec {
+        CodecType::Opus => 1,
+        CodecType::Pcm => mem::size_of::<i16>() * if stereo { 2 } else { 1 },
+        CodecType::FloatPcm => mem::size_of::<f32>() * if stereo { 2 } else { 1 },
+    };
+
+    Ok(Input::new(
+        stereo,
+        Reader::Extension(Box::new(StreamReader::new(rx, framed, align))),
+        Codec::try_from(codec)?,
+        container,
+        Some(Metadata {
+            channels: Some(if stereo { 2 } else { 1 }),
+            ..Default::default()
+        }),
+    ))
+}
+
+/// Forwards chunks from `source` until it ends or fails, or playback stops.
+async fn pump<S: StreamingSource>(mut source: S, tx: Sender<IoResult<Vec<u8>>>) {
+    let mut attempt = 0;
+
+    loop {
+        let chunk = match source.next_chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                attempt += 1;
+                if let Err(e) = source.recover(e, attempt).await {
+                    let _ = tx.send_async(Err(e)).await;
+                    break;
+                }
+
+                continue;
+            },
+        };
+
+        attempt = 0;
+        if tx.send_async(Ok(chunk)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Outcome of fetching the next chunk from [`pump`].
+enum Fetch {
+    Loaded,
+    Stalled,
+    Ended,
+}
+
+/// Reader over the chunks forwarded by [`pump`], which plays silence rather than
+/// blocking the mixer whenever no chunk is ready.
+struct StreamReader {
+    rx: Receiver<IoResult<Vec<u8>>>,
+    /// Whether each chunk is an Opus packet, needing a length prefix.
+    framed: bool,
+    /// Length of one sample across all channels, in bytes.
+    align: usize,
+    chunk: Vec<u8>,
+    chunk_pos: usize,
+    /// Start of a sample split across chunks, held back until the rest arrives.
+    partial: Vec<u8>,
+    /// Error which ended the stream, deferred until `partial` has been read.
+    error: Option<IoError>,
+}
+
+impl StreamReader {
+    fn new(rx: Receiver<IoResult<Vec<u8>>>, framed: bool, align: usize) -> Self {
+        Self {
+            rx,
+            framed,
+            align,
+            chunk: vec![],
+            chunk_pos: 0,
+            partial: vec![],
+            error: None,
+        }
+    }
+
+    /// Loads the next non-empty chunk, if one has arrived.
+    fn next_chunk(&mut self) -> IoResult<Fetch> {
+        loop {
+            if let Some(e) = self.error.take() {
+                return Err(e);
+            }
+
+            let chunk = match self.rx.try_recv() {
+                Ok(Ok(chunk)) => {
+                    let mut chunk = if self.partial.is_empty() {
+                        chunk
+                    } else {
+                        let mut joined = mem::take(&mut self.partial);
+                        joined.extend_from_slice(&chunk);
+                        joined
+                    };
+                    let whole_len = chunk.len() - chunk.len() % self.align;
+                    self.partial = chunk.split_off(whole_len);
+                    chunk
+                },
+                Ok(Err(e)) if self.partial.is_empty() => return Err(e),
+                Ok(Err(e)) => {
+                    self.error = Some(e);
+                    mem::take(&mut self.partial)
+                },
+                Err(TryRecvError::Empty) => return Ok(Fetch::Stalled),
+                Err(TryRecvError::Disconnected) if self.partial.is_empty() =>
+                    return Ok(Fetch::Ended),
+                Err(TryRecvError::Disconnected) => mem::take(&mut self.partial),
+            };
+
+            if chunk.is_empty() {
+                continue;
+            }
+
+            self.load(&chunk)?;
+
+            return Ok(Fetch::Loaded);
+        }
+    }
+
+    fn load(&mut self, chunk: &[u8]) -> IoResult<()> {
+        self.chunk.clear();
+        self.chunk_pos = 0;
+
+        if self.framed {
+            let len = i16::try_from(chunk.len()).map_err(|_| {
+                IoError::new(IoErrorKind::InvalidData, "Opus packet is too large.")
+            })?;
+            self.chunk.reserve(mem::size_of::<i16>() + chunk.len());
+            self.chunk.extend_from_slice(&len.to_le_bytes());
+        }
+        self.chunk.extend_from_slice(chunk);
+
+        Ok(())
+    }
+}
+
+impl Read for StreamReader {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        if self.chunk_pos >= self.chunk.len() {
+            match self.next_chunk()? {
+                Fetch::Loaded => {},
+                Fetch::Ended => return Ok(0),
+                Fetch::Stalled if self.framed => self.load(&SILENT_FRAME)?,
+                Fetch::Stalled => {
+                    let len = if buffer.len() >= self.align {
+                        buffer.len() - buffer.len() % self.align
+                    } else {
+                        buffer.len()
+                    };
+                    buffer[..len].fill(0);
+                    return Ok(len);
+                },
+            }
+        }
+
+        let len = buffer.len().min(self.chunk.len() - self.chunk_pos);
+        buffer[..len].copy_from_slice(&self.chunk[self.chunk_pos..self.chunk_pos + len]);
+        self.chunk_pos += len;
+
+        Ok(len)
+    }
+}
+
+impl Seek for StreamReader {
+    fn seek(&mut self, _pos: SeekFrom) -> IoResult<u64> {
+        Err(IoError::new(
+            IoErrorKind::Unsupported,
+            "Streaming sources do not support seeking.",
+        ))
+    }
+}
+
+impl MediaSource for StreamReader {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Yields its chunks in order, failing once before each listed index.
+    struct Flaky {
+        chunks: VecDeque<Vec<u8>>,
+        fail_before: Vec<usize>,
+        sent: usize,
+        recoveries: u32,
+    }
+
+    #[async_trait]
+    impl StreamingSource for Flaky {
+        async fn next_chunk(&mut self) -> IoResult<Option<Vec<u8>>> {
+            if let Some(i) = self.fail_before.iter().position(|i| *i == self.sent) {
+                self.fail_before.remove(i);
+                return Err(IoError::new(IoErrorKind::ConnectionReset, "dropped"));
+            }
+
+            self.sent += 1;
+            Ok(self.chunks.pop_front())
+        }
+
+        async fn recover(&mut self, error: IoError, attempt: u32) -> IoResult<()> {
+            self.recoveries += 1;
+            if self.recoveries > 1 {
+                Err(error)
+            } else {
+                assert_eq!(attempt, 1);
+                Ok(())
+            }
+        }
+    }
+
+    /// Reads `input` to its end, dropping any silence played while it waited.
+    fn read_all(mut input: Input) -> (Vec<u8>, Option<IoError>) {
+        let mut out = vec![];
+        let err = input.reader.read_to_end(&mut out).err();
+
+        let out = if matches!(input.kind, Codec::Pcm) {
+            out.into_iter().filter(|b| *b != 0).collect()
+        } else {
+            let mut packets = vec![];
+            let mut rest = &out[..];
+            while rest.len() >= 2 {
+                let len = 2 + i16::from_le_bytes([rest[0], rest[1]]) as usize;
+                if rest[2..len] != SILENT_FRAME {
+                    packets.extend_from_slice(&rest[..len]);
+                }
+                rest = &rest[len..];
+            }
+            packets
+        };
+
+        (out, err)
+    }
+
+    /// Forwards whatever chunks the test sends it.
+    struct Stalling(flume::Receiver<Vec<u8>>);
+
+    #[async_trait]
+    impl StreamingSource for Stalling {
+        async fn next_chunk(&mut self) -> IoResult<Option<Vec<u8>>> {
+            Ok(self.0.recv_async().await.ok())
+        }
+    }
+
+    async fn let_pump_run() {
+        for _ in 0..8 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn stalled_stream_plays_silence_without_blocking() {
+        let (tx, rx) = flume::unbounded();
+        let mut input = streaming(Stalling(rx), CodecType::Pcm, true).unwrap();
+        let mut buffer = [0xff; 6];
+
+        // Nothing has arrived: whole samples of silence are returned at once.
+        assert_eq!(input.reader.read(&mut buffer).unwrap(), 4);
+        assert_eq!(buffer[..4], [0; 4]);
+
+        // A sample split across chunks is held back until its remainder arrives.
+        tx.send(vec![1, 2, 3, 4, 5, 6]).unwrap();
+        let_pump_run().await;
+        assert_eq!(input.reader.read(&mut buffer).unwrap(), 4);
+        assert_eq!(buffer[..4], [1, 2, 3, 4]);
+        assert_eq!(input.reader.read(&mut buffer).unwrap(), 4);
+        assert_eq!(buffer[..4], [0; 4]);
+
+        tx.send(vec![7, 8]).unwrap();
+        let_pump_run().await;
+        assert_eq!(input.reader.read(&mut buffer).unwrap(), 4);
+        assert_eq!(buffer[..4], [5, 6, 7, 8]);
+
+        // Only the end of the stream reads as zero bytes.
+        drop(tx);
+        let_pump_run().await;
+        assert_eq!(input.reader.read(&mut buffer).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn stream_recovers_then_ends_on_repeated_failure() {
+        let source = |fail_before| Flaky {
+            chunks: vec![vec![1, 2, 3], vec![], vec![4, 5]].into(),
+            fail_before,
+            sent: 0,
+            recoveries: 0,
+        };
+
+        // The first drop is recovered from, and every byte still arrives.
+        let input = streaming(source(vec![1]), CodecType::Pcm, false).unwrap();
+        let (out, err) = tokio::task::spawn_blocking(move || read_all(input))
+            .await
+            .unwrap();
+        assert_eq!(out, vec![1, 2, 3, 4, 5]);
+        assert!(err.is_none());
+
+        // A second drop ends the stream with its error.
+        let input = streaming(source(vec![1, 2]), CodecType::Pcm, false).unwrap();
+        let (out, err) = tokio::task::spawn_blocking(move || read_all(input))
+            .await
+            .unwrap();
+        assert_eq!(out, vec![1, 2, 3]);
+        assert_eq!(err.unwrap().kind(), IoErrorKind::ConnectionReset);
+
+        // Opus packets are framed for the decoder.
+        let input = streaming(source(vec![]), CodecType::Opus, true).unwrap();
+        let (out, _) = tokio::task::spawn_blocking(move || read_all(input))
+            .await
+            .unwrap();
+        assert_eq!(out, vec![3, 0, 1, 2, 3, 2, 0, 4, 5]);
+    }
+}
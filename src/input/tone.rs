@@ -0,0 +1,192 @@
+use super::{Codec, Container, Input, Metadata, Reader};
+use crate::constants::*;
+use byteorder::{ByteOrder, LittleEndian};
+use std::{
+    f64::consts::PI,
+    io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Seek, SeekFrom},
+    mem,
+    time::Duration,
+};
+use symphonia_core::io::MediaSource;
+
+/// Shape of the signal generated by [`tone`].
+///
+/// [`tone`]: tone()
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Waveform {
+    /// A pure sine wave.
+    Sine,
+    /// A square wave, alternating between full positive and negative amplitude.
+    Square,
+    /// White noise. The frequency of the tone is ignored.
+    Noise,
+}
+
+/// Generates a test tone at `frequency_hz`, as 48kHz stereo floating-point PCM.
+///
+/// The tone lasts for `duration`, or plays forever if this is `None`. Samples are
+/// scaled by `amplitude`, where `1.0` is full scale: values around `0.25` make for
+/// a comfortable level alongside other audio.
+///
+/// This is useful for checking that audio reaches a call without any external files
+/// or processes. Samples are computed as they are read, so the tone is cheap to play
+/// as a normal source, and supports seeking.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use songbird::input::{tone, Waveform};
+/// use std::time::Duration;
+///
+/// let a440 = tone(440.0, Some(Duration::from_secs(2)), Waveform::Sine, 0.25);
+/// ```
+pub fn tone(
+    frequency_hz: f32,
+    duration: Option<Duration>,
+    waveform: Waveform,
+    amplitude: f32,
+) -> Input {
+    let tone = Tone::new(frequency_hz, duration, waveform, amplitude);
+
+    let metadata = Metadata {
+        channels: Some(2),
+        duration,
+        sample_rate: Some(SAMPLE_RATE_RAW as u32),
+        ..Default::default()
+    };
+
+    Input::new(
+        true,
+        Reader::Extension(Box::new(tone)),
+        Codec::FloatPcm,
+        Container::Raw,
+        Some(metadata),
+    )
+}
+
+const FRAME_LEN: u64 = 2 * mem::size_of::<f32>() as u64;
+
+/// Reader state for a generated test tone, as created by [`tone`].
+///
+/// [`tone`]: tone()
+#[derive(Clone, Debug)]
+pub struct Tone {
+    /// Fraction of a cycle advanced per sample.
+    step: f64,
+    waveform: Waveform,
+    amplitude: f32,
+    /// Length of the tone in stereo sample frames, if finite.
+    len: Option<u64>,
+    /// Byte position within the output.
+    pos: u64,
+}
+
+impl Tone {
+    fn new(
+        frequency_hz: f32,
+        duration: Option<Duration>,
+        waveform: Waveform,
+        amplitude: f32,
+    ) -> Self {
+        Self {
+            step: f64::from(frequency_hz) / SAMPLE_RATE_RAW as f64,
+            waveform,
+            amplitude,
+            len: duration.map(|d| (d.as_secs_f64() * SAMPLE_RATE_RAW as f64) as u64),
+            pos: 0,
+        }
+    }
+
+    fn sample(&self, frame: u64) -> f32 {
+        let val = match self.waveform {
+            Waveform::Sine => (2.0 * PI * (frame as f64 * self.step).fract()).sin() as f32,
+            Waveform::Square =>
+                if (frame as f64 * self.step).fract() < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                },
+            // Noise is derived from the frame index, so that seeks replay the same signal.
+            Waveform::Noise => {
+                let mut x = frame.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                x ^= x >> 31;
+
+                (x >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+            },
+        };
+
+        val * self.amplitude
+    }
+}
+
+impl Read for Tone {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        let mut written = 0;
+
+        while written < buffer.len() {
+            let frame = self.pos / FRAME_LEN;
+            if matches!(self.len, Some(len) if frame >= len) {
+                break;
+            }
+
+            let sample = self.sample(frame);
+            let mut bytes = [0u8; FRAME_LEN as usize];
+            LittleEndian::write_f32(&mut bytes[..4], sample);
+            LittleEndian::write_f32(&mut bytes[4..], sample);
+
+            // Buffers need not align with sample boundaries.
+            let offset = (self.pos % FRAME_LEN) as usize;
+            let count = (bytes.len() - offset).min(buffer.len() - written);
+            buffer[written..written + count].copy_from_slice(&bytes[offset..offset + count]);
+
+            written += count;
+            self.pos += count as u64;
+        }
+
+        Ok(written)
+    }
+}
+
+impl Seek for Tone {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::Current(d) => offset_by(self.pos, d),
+            SeekFrom::End(d) => match self.byte_len() {
+                Some(len) => offset_by(len, d),
+                None =>
+                    return Err(IoError::new(
+                        IoErrorKind::Unsupported,
+                        "Endless tones cannot seek from their end.",
+                    )),
+            },
+        };
+
+        self.pos = target.ok_or_else(|| {
+            IoError::new(IoErrorKind::InvalidInput, "Seek before start of tone.")
+        })?;
+
+        Ok(self.pos)
+    }
+}
+
+fn offset_by(base: u64, delta: i64) -> Option<u64> {
+    if delta >= 0 {
+        base.checked_add(delta as u64)
+    } else {
+        base.checked_sub(delta.unsigned_abs())
+    }
+}
+
+impl MediaSource for Tone {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.len.map(|len| len * FRAME_LEN)
+    }
+}
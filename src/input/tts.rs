@@ -0,0 +1,85 @@
+//! Glue for playing text-to-speech through external synthesis engines.
+//!
+//! Songbird does not ship a speech engine. Instead, bots implement [`TtsBackend`]
+//! for their engine of choice (*e.g.*, espeak, piper, or a cloud API), and pass
+//! it to [`Driver::speak`] to synthesize and play text as a normal track.
+//!
+//! ## Streaming vs. buffered synthesis
+//! A backend may return an [`Input`] which reads audio as the engine produces it,
+//! such as the stdout of a speech process wrapped via [`ChildContainer`]. Playback
+//! then begins almost immediately, but may stutter if the engine cannot keep pace
+//! with realtime, and the resulting track cannot seek.
+//!
+//! Alternatively, a backend can wait for the full utterance and return it held in
+//! memory (*e.g.*, via [`Reader::from_memory`]). This delays the start of speech by
+//! the whole synthesis time, but plays back smoothly and supports seeking and looping.
+//! This usually suits short responses, or engines which only offer whole-file output.
+//!
+//! [`Driver::speak`]: crate::driver::Driver::speak
+//! [`Input`]: Input
+//! [`ChildContainer`]: super::ChildContainer
+//! [`Reader::from_memory`]: super::Reader::from_memory
+
+use super::{error::Result, Input};
+use async_trait::async_trait;
+
+/// Voice settings requested from a [`TtsBackend`].
+///
+/// Backends should ignore any settings their engine does not support.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct VoiceParams {
+    /// Engine-specific name of the voice to use.
+    ///
+    /// Defaults to `None`, using the engine's default voice.
+    pub voice: Option<String>,
+    /// Language of the text, as a BCP 47 tag such as `"en-GB"`.
+    ///
+    /// Defaults to `None`, leaving the engine to choose.
+    pub language: Option<String>,
+    /// Speaking rate, relative to the voice's normal speed.
+    ///
+    /// Defaults to `1.0`.
+    pub rate: f32,
+}
+
+impl Default for VoiceParams {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            language: None,
+            rate: 1.0,
+        }
+    }
+}
+
+impl VoiceParams {
+    /// Sets the name of the voice to use.
+    pub fn voice(mut self, voice: Option<String>) -> Self {
+        self.voice = voice;
+        self
+    }
+
+    /// Sets the language of the text.
+    pub fn language(mut self, language: Option<String>) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Sets the relative speaking rate.
+    pub fn rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+}
+
+/// Speech engine used to convert text into an audio source.
+///
+/// See the [module docs] for advice on streaming and buffered output.
+///
+/// [module docs]: self
+#[async_trait]
+pub trait TtsBackend: Send + Sync {
+    /// Converts `text` into speech, spoken using `voice`.
+    async fn synthesize(&self, text: &str, voice: &VoiceParams) -> Result<Input>;
+}
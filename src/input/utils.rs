@@ -3,6 +3,28 @@
 use crate::constants::*;
 use audiopus::{coder::Decoder, Channels, Result as OpusResult, SampleRate};
 use std::{mem, time::Duration};
+use tracing::debug;
+
+/// Decides whether a source with the given channel count should be played as stereo.
+///
+/// Mono sources are played as mono, and are upmixed to both output channels
+/// by the mixer. Sources with two or more channels are played as stereo:
+/// any further channels must be downmixed before playback (i.e., by ffmpeg, or
+/// a [`ChannelMapper`]).
+///
+/// If the channel count is unknown, this defaults to mono, as songbird always has.
+///
+/// [`ChannelMapper`]: super::ChannelMapper
+pub fn is_stereo_layout(channels: Option<u8>) -> bool {
+    match channels {
+        Some(1) => false,
+        Some(n) if n >= 2 => true,
+        other => {
+            debug!("Unknown channel count {:?}: defaulting to mono.", other);
+            false
+        },
+    }
+}
 
 /// Calculates the sample position in a FloatPCM stream from a timestamp.
 pub fn timestamp_to_sample_count(timestamp: Duration, stereo: bool) -> usize {
@@ -0,0 +1,89 @@
+use super::{ChildFailure, Codec, Container, Input, Metadata, Reader};
+use parking_lot::Mutex;
+use std::{
+    fmt::Debug,
+    io::{Read, Result as IoResult, Seek, SeekFrom},
+    sync::Arc,
+    time::Duration,
+};
+use symphonia_core::io::MediaSource;
+use tokio::runtime::Handle;
+
+/// A source built from other [`Input`]s, such as by [`gain`] or [`concat`].
+///
+/// These play through a [`Reader::Extension`], which hides the inputs within from
+/// the driver. Their [`Input`] keeps a second handle to the same source, so that any
+/// lazy inputs inside can still be prepared, warmed up, and inspected.
+///
+/// [`gain`]: super::gain()
+/// [`concat`]: super::concat
+/// [`Reader::Extension`]: Reader::Extension
+pub(crate) trait Wrapped: Read + Seek + Debug + Send {
+    fn is_seekable(&self) -> bool;
+
+    fn prep_with_handle(&mut self, handle: Handle);
+
+    fn is_buffering(&self) -> bool;
+
+    fn make_playable(&mut self);
+
+    fn buffered(&self) -> Duration {
+        Duration::default()
+    }
+
+    fn take_seek_landing(&mut self) -> Option<Duration> {
+        None
+    }
+
+    fn failure(&self) -> Option<ChildFailure> {
+        None
+    }
+}
+
+pub(crate) type WrappedHandle = Arc<Mutex<dyn Wrapped>>;
+
+impl Input {
+    /// Creates a floating-point PCM input which plays `source`.
+    pub(crate) fn wrapping<T>(stereo: bool, source: T, metadata: Metadata) -> Self
+    where
+        T: Wrapped + 'static,
+    {
+        let shared = Arc::new(Mutex::new(source));
+
+        let mut input = Input::new(
+            stereo,
+            Reader::Extension(Box::new(Shared(shared.clone()))),
+            Codec::FloatPcm,
+            Container::Raw,
+            Some(metadata),
+        );
+        input.wrapped = Some(shared);
+
+        input
+    }
+}
+
+/// The half of a [`Wrapped`] source held by its [`Reader`].
+struct Shared<T>(Arc<Mutex<T>>);
+
+impl<T: Wrapped> Read for Shared<T> {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        self.0.lock().read(buffer)
+    }
+}
+
+impl<T: Wrapped> Seek for Shared<T> {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        self.0.lock().seek(pos)
+    }
+}
+
+impl<T: Wrapped> MediaSource for Shared<T> {
+    fn is_seekable(&self) -> bool {
+        self.0.lock().is_seekable()
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
@@ -1,6 +1,7 @@
 use super::{
-    children_to_reader,
+    child::limited_children_to_reader,
     error::{Error, Result},
+    process_limit,
     Codec,
     Container,
     Input,
@@ -14,7 +15,7 @@ use std::{
 use tokio::{process::Command as TokioCommand, task};
 use tracing::trace;
 
-const YOUTUBE_DL_COMMAND: &str = if cfg!(feature = "youtube-dlc") {
+pub(crate) const YOUTUBE_DL_COMMAND: &str = if cfg!(feature = "youtube-dlc") {
     "youtube-dlc"
 } else if cfg!(feature = "yt-dlp") {
     "yt-dlp"
@@ -63,6 +64,8 @@ pub(crate) async fn _ytdl(uri: &str, pre_args: &[&str]) -> Result<Input> {
         "-",
     ];
 
+    let slot = process_limit::acquire(2).await;
+
     let mut youtube_dl = Command::new(YOUTUBE_DL_COMMAND)
         .args(&ytdl_args)
         .stdin(Stdio::null())
@@ -103,7 +106,7 @@ pub(crate) async fn _ytdl(uri: &str, pre_args: &[&str]) -> Result<Input> {
         .arg("-")
         .args(&ffmpeg_args)
         .stdin(taken_stdout)
-        .stderr(Stdio::null())
+        .stderr(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn()?;
 
@@ -113,7 +116,7 @@ pub(crate) async fn _ytdl(uri: &str, pre_args: &[&str]) -> Result<Input> {
 
     Ok(Input::new(
         true,
-        children_to_reader::<f32>(vec![youtube_dl, ffmpeg]),
+        limited_children_to_reader::<f32>(vec![youtube_dl, ffmpeg], slot),
         Codec::FloatPcm,
         Container::Raw,
         Some(metadata),
@@ -137,9 +140,12 @@ pub(crate) async fn _ytdl_metadata(uri: &str) -> Result<Metadata> {
         "-",
     ];
 
+    let _slot = process_limit::acquire(1).await;
+
     let youtube_dl_output = TokioCommand::new(YOUTUBE_DL_COMMAND)
         .args(&ytdl_args)
         .stdin(Stdio::null())
+        .kill_on_drop(true)
         .output()
         .await?;
 
@@ -40,6 +40,10 @@
 #[cfg(all(feature = "youtube-dlc", feature = "yt-dlp"))]
 compile_error!("feature \"youtube-dlc\" and feature \"yt-dlp\" cannot be enabled at the same time");
 
+#[cfg(feature = "driver-core")]
+mod broadcast;
+#[cfg(feature = "driver")]
+mod capabilities;
 mod config;
 pub mod constants;
 #[cfg(feature = "driver-core")]
@@ -78,6 +82,7 @@ use utils as test_utils;
 
 #[cfg(feature = "driver-core")]
 pub use crate::{
+    broadcast::Broadcast,
     driver::Driver,
     events::{CoreEvent, Event, EventContext, EventHandler, TrackEvent},
     input::{ffmpeg, ytdl},
@@ -90,5 +95,7 @@ pub use crate::{handler::*, manager::*};
 #[cfg(feature = "serenity")]
 pub use crate::serenity::*;
 
+#[cfg(feature = "driver")]
+pub use capabilities::{capabilities, Capabilities};
 pub use config::Config;
 pub use info::ConnectionInfo;
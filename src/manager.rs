@@ -21,9 +21,17 @@ use serenity::{
         voice::VoiceState,
     },
 };
+#[cfg(feature = "driver-core")]
+use crate::{driver::retry::Retry, events::CoreEvent, handler::QueueLeaver};
 use std::sync::Arc;
+#[cfg(feature = "driver-core")]
+use std::{sync::Weak, time::Duration};
+#[cfg(feature = "driver-core")]
+use tokio::runtime::Handle;
 use tokio::sync::Mutex;
-use tracing::debug;
+use tracing::{debug, warn};
+#[cfg(feature = "driver-core")]
+use tracing::error;
 #[cfg(feature = "twilight")]
 use twilight_gateway::Cluster;
 #[cfg(feature = "twilight")]
@@ -142,6 +150,25 @@ impl Songbird {
             .map(|mapref| Arc::clone(&mapref))
     }
 
+    /// Returns an iterator over all [`Call`]s currently held by this manager,
+    /// alongside their guild IDs.
+    ///
+    /// This iterates over a snapshot of the manager's state taken when called:
+    /// calls added or removed afterwards are not reflected. As no internal locks
+    /// are held while iterating, it is safe to call other methods on this
+    /// manager (such as [`leave`] or [`remove`]) from within the loop.
+    ///
+    /// [`Call`]: Call
+    /// [`leave`]: Songbird::leave
+    /// [`remove`]: Songbird::remove
+    pub fn iter(&self) -> impl Iterator<Item = (GuildId, Arc<Mutex<Call>>)> {
+        self.calls
+            .iter()
+            .map(|mapref| (*mapref.key(), Arc::clone(mapref.value())))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
     /// Retrieves a [`Call`] for the given guild, creating a new one if
     /// none is found.
     ///
@@ -168,25 +195,85 @@ impl Songbird {
                         .get_shard(shard)
                         .expect("Failed to get shard handle: shard_count incorrect?");
 
-                    let call = Call::from_config(
-                        guild_id,
-                        shard_handle,
-                        info.user_id,
-                        self.config.read().clone().unwrap_or_default(),
-                    );
+                    #[cfg_attr(not(feature = "driver-core"), allow(unused_mut))]
+                    let mut config = self.default_config();
+
+                    #[cfg(feature = "driver-core")]
+                    if config.tracing_span.is_none() {
+                        config.tracing_span = Some(tracing::info_span!(
+                            parent: None,
+                            "call",
+                            guild_id = guild_id.0,
+                            channel_id = tracing::field::Empty,
+                        ));
+                    }
 
-                    Arc::new(Mutex::new(call))
+                    // Calls may be made outside of a runtime if `spawn_on` is set.
+                    #[cfg(feature = "driver-core")]
+                    let watchdog = config.watchdog.and_then(|interval| {
+                        let runtime = config
+                            .spawn_on
+                            .clone()
+                            .or_else(|| Handle::try_current().ok());
+
+                        if runtime.is_none() {
+                            warn!(
+                                "No runtime for the watchdog of guild {}: skipping.",
+                                guild_id.0
+                            );
+                        }
+
+                        runtime.map(|runtime| (runtime, interval, config.watchdog_retry))
+                    });
+
+                    let call = Call::from_config(guild_id, shard_handle, info.user_id, config);
+
+                    #[cfg(feature = "driver-core")]
+                    let call = Arc::new_cyclic(|weak| {
+                        let mut call = call;
+                        call.add_global_event(
+                            CoreEvent::QueueEmpty.into(),
+                            QueueLeaver(weak.clone()),
+                        );
+                        Mutex::new(call)
+                    });
+                    #[cfg(not(feature = "driver-core"))]
+                    let call = Arc::new(Mutex::new(call));
+
+                    #[cfg(feature = "driver-core")]
+                    if let Some((runtime, interval, retry)) = watchdog {
+                        runtime.spawn(watchdog_task(
+                            guild_id,
+                            Arc::downgrade(&call),
+                            interval,
+                            retry,
+                        ));
+                    }
+
+                    call
                 })
                 .clone()
         })
     }
 
+    /// Returns a copy of the default configuration inherited by every new [`Call`].
+    ///
+    /// [`Call`]: Call
+    pub fn default_config(&self) -> Config {
+        self.config.read().clone().unwrap_or_default()
+    }
+
     /// Sets a shared configuration for all drivers created from this
     /// manager.
     ///
     /// Changes made here will apply to new Call and Driver instances only.
+    /// Calls which already exist keep their current settings, and any call may
+    /// override the default afterwards via [`Driver::set_config`]: per-call settings
+    /// always take precedence, and are never reset by later changes here.
     ///
     /// Requires the `"driver"` feature.
+    ///
+    /// [`Driver::set_config`]: crate::driver::Driver::set_config
     pub fn set_config(&self, new_config: Config) {
         let mut config = self.config.write();
         *config = Some(new_config);
@@ -368,6 +455,7 @@ impl Songbird {
                     if let Some(endpoint) = &v.endpoint {
                         handler.update_server(endpoint.clone(), v.token.clone());
                     }
+                    refresh_stage(&mut handler).await;
                 }
             },
             TwilightEvent::VoiceStateUpdate(v) => {
@@ -380,6 +468,7 @@ impl Songbird {
                 if let Some(call) = call {
                     let mut handler = call.lock().await;
                     handler.update_state(v.0.session_id.clone(), v.0.channel_id);
+                    refresh_stage(&mut handler).await;
                 }
             },
             _ => {},
@@ -423,6 +512,7 @@ impl VoiceGatewayManager for Songbird {
             if let Some(endpoint) = endpoint {
                 handler.update_server(endpoint.clone(), token.to_string());
             }
+            refresh_stage(&mut handler).await;
         }
     }
 
@@ -434,11 +524,115 @@ impl VoiceGatewayManager for Songbird {
         if let Some(call) = self.get(guild_id) {
             let mut handler = call.lock().await;
             handler.update_state(voice_state.session_id.clone(), voice_state.channel_id);
+            refresh_stage(&mut handler).await;
         }
     }
 }
 
+/// Resends a call's Stage speaker state if it has just (re)joined a channel.
+#[cfg(any(feature = "serenity", feature = "twilight"))]
+async fn refresh_stage(handler: &mut Call) {
+    if let Err(e) = handler.refresh_stage_state().await {
+        warn!("Failed to apply Stage speaker state: {}", e);
+    }
+}
+
+/// Periodically checks that a call's driver tasks are running, restarting them
+/// with backoff until the call is removed or the retry limit is reached, when
+/// the call is told to drop its connection.
+#[cfg(feature = "driver-core")]
+async fn watchdog_task(
+    guild_id: GuildId,
+    call: Weak<Mutex<Call>>,
+    interval: Duration,
+    retry: Retry,
+) {
+    let mut attempts = 0;
+    let mut last_wait = None;
+    let mut wait = interval;
+
+    loop {
+        tokio::time::sleep(wait).await;
+
+        let call = match call.upgrade() {
+            Some(call) => call,
+            None => break,
+        };
+
+        let check = call.lock().await.check_tasks();
+        // A driver which is busy connecting may not reply in time, but is not dead.
+        let alive = tokio::time::timeout(interval, check)
+            .await
+            .unwrap_or(Some(true));
+
+        if alive == Some(true) {
+            attempts = 0;
+            last_wait = None;
+            wait = interval;
+            continue;
+        }
+
+        if !retry.attempts_remain(attempts) {
+            error!(
+                "Call in guild {} keeps failing after {} restarts: giving up.",
+                guild_id.0, attempts
+            );
+            call.lock().await.signal_tasks_failed();
+            break;
+        }
+
+        warn!("Call in guild {} has failed tasks: restarting.", guild_id.0);
+        call.lock().await.restart_tasks();
+
+        attempts += 1;
+        wait = retry.strategy.retry_in(last_wait);
+        last_wait = Some(wait);
+    }
+}
+
 #[inline]
 fn shard_id(guild_id: u64, shard_count: u64) -> u64 {
     (guild_id >> 22) % shard_count
 }
+
+#[cfg(all(test, feature = "serenity", feature = "driver-core"))]
+mod tests {
+    use super::*;
+    use crate::driver::DecodeMode;
+
+    #[tokio::test]
+    async fn config_only_applies_to_new_calls() {
+        let manager = Songbird::serenity();
+        manager.initialise_client_data(1, 1);
+
+        manager.set_config(Config::default().decode_mode(DecodeMode::Pass));
+        let old = manager.get_or_insert(1);
+
+        manager.set_config(Config::default().decode_mode(DecodeMode::Decrypt));
+        let new = manager.get_or_insert(2);
+
+        assert_eq!(old.lock().await.config().decode_mode, DecodeMode::Pass);
+        assert_eq!(new.lock().await.config().decode_mode, DecodeMode::Decrypt);
+        assert_eq!(manager.default_config().decode_mode, DecodeMode::Decrypt);
+    }
+
+    #[tokio::test]
+    async fn calls_can_be_removed_while_iterating() {
+        let manager = Songbird::serenity();
+        manager.initialise_client_data(1, 1);
+        for id in 1..=3 {
+            manager.get_or_insert(id);
+        }
+
+        let mut seen = vec![];
+        for (guild_id, call) in manager.iter() {
+            assert!(Arc::ptr_eq(&call, &manager.get(guild_id).unwrap()));
+            manager.remove(guild_id).await.unwrap();
+            seen.push(guild_id.0);
+        }
+
+        seen.sort_unstable();
+        assert_eq!(seen, vec![1, 2, 3]);
+        assert_eq!(manager.iter().count(), 0);
+    }
+}
@@ -230,6 +230,36 @@ pub trait VoiceUpdate {
     ) -> JoinResult<()>;
 }
 
+/// Trait for sending a bot's speaker state in a Stage channel to Discord.
+///
+/// Unlike mute and deafen state, Discord does not accept these changes over the
+/// gateway: implementors must call the HTTP API's *Modify Current User Voice State*
+/// endpoint (`PATCH /guilds/{guild.id}/voice-states/@me`) with their library's HTTP
+/// client, setting `suppress` and `request_to_speak_timestamp` to match.
+///
+/// Becoming a speaker by unsuppressing requires the bot to hold the `MUTE_MEMBERS`
+/// permission in the Stage channel, while requesting to speak requires the
+/// `REQUEST_TO_SPEAK` permission. In either case, the bot must already have joined
+/// the channel.
+///
+/// Register an implementation using [`Call::set_stage_handler`].
+///
+/// [`Call::set_stage_handler`]: crate::Call::set_stage_handler
+#[async_trait]
+pub trait StageUpdate {
+    /// Send a Stage speaker state update for the current user.
+    ///
+    /// `request_to_speak` should be sent as the current time if `true`,
+    /// and as `null` otherwise.
+    async fn update_stage_state(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        suppress: bool,
+        request_to_speak: bool,
+    ) -> JoinResult<()>;
+}
+
 #[cfg(feature = "serenity")]
 /// Handle to an individual shard designed to buffer unsent messages while
 /// a reconnect/rebalance is ongoing.
@@ -1,7 +1,7 @@
 use super::*;
-use crate::events::EventData;
+use crate::events::{EventData, EventInfo};
 use flume::Sender;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// A request from external code using a [`TrackHandle`] to modify
 /// or act upon an [`Track`] object.
@@ -14,24 +14,54 @@ pub enum TrackCommand {
     Play,
     /// Set the track's play_mode to pause.
     Pause,
+    /// Pause the track until the given instant, then play it.
+    PlayAt(Instant),
     /// Stop the target track. This cannot be undone.
     Stop,
+    /// Stop the target track after one trailing frame of silence.
+    /// This cannot be undone.
+    SoftStop,
+    /// Skip the target track, ending it early.
+    /// This cannot be undone.
+    Skip,
     /// Set the track's volume.
     Volume(f32),
+    /// Set or clear the track's ducking sidechain.
+    Ducking(Option<DuckConfig>),
+    /// Set the bands of the track's equalizer.
+    Equalizer(Vec<EqBand>),
+    /// Set or clear the track's noise gate.
+    NoiseGate(Option<GateConfig>),
+    /// Set the track's pitch shift, in semitones.
+    Pitch(f32),
+    /// Set or clear the track's silence policy.
+    SilencePolicy(Option<SilencePolicy>),
+    /// Apply several changes to the track's settings at once.
+    Update(TrackUpdate),
     /// Seek to the given duration.
     ///
     /// On unsupported input types, this can be fatal.
     Seek(Duration),
+    /// Return to the start of the track, and resume playback.
+    Restart(Sender<TrackResult<()>>),
     /// Register an event on this track.
     AddEvent(EventData),
     /// Run some closure on this track, with direct access to the core object.
     Do(Box<dyn FnOnce(&mut Track) + Send + Sync + 'static>),
     /// Request a copy of this track's state.
     Request(Sender<TrackState>),
+    /// Request a copy of this track's playback statistics.
+    RequestStats(Sender<PlaybackStats>),
+    /// Request how much audio this track's input has read ahead.
+    RequestBufferLevel(Sender<Duration>),
     /// Change the loop count/strategy of this track.
     Loop(LoopState),
     /// Prompts a track's input to become live and usable, if it is not already.
     MakePlayable,
+    /// Request descriptions of all events registered on this track.
+    ListEvents(Sender<Vec<EventInfo>>),
+    /// Start a second track playing the same audio, from the current position.
+    Fork(Sender<TrackResult<TrackHandle>>),
 }
 
 impl std::fmt::Debug for TrackCommand {
@@ -43,14 +73,28 @@ impl std::fmt::Debug for TrackCommand {
             match self {
                 Play => "Play".to_string(),
                 Pause => "Pause".to_string(),
+                PlayAt(at) => format!("PlayAt({:?})", at),
                 Stop => "Stop".to_string(),
+                SoftStop => "SoftStop".to_string(),
+                Skip => "Skip".to_string(),
                 Volume(vol) => format!("Volume({})", vol),
+                Ducking(d) => format!("Ducking({:?})", d),
+                Equalizer(bands) => format!("Equalizer({:?})", bands),
+                NoiseGate(gate) => format!("NoiseGate({:?})", gate),
+                Pitch(semitones) => format!("Pitch({})", semitones),
+                SilencePolicy(policy) => format!("SilencePolicy({:?})", policy),
+                Update(update) => format!("Update({:?})", update),
                 Seek(d) => format!("Seek({:?})", d),
+                Restart(tx) => format!("Restart({:?})", tx),
                 AddEvent(evt) => format!("AddEvent({:?})", evt),
                 Do(_f) => "Do([function])".to_string(),
                 Request(tx) => format!("Request({:?})", tx),
+                RequestStats(tx) => format!("RequestStats({:?})", tx),
+                RequestBufferLevel(tx) => format!("RequestBufferLevel({:?})", tx),
                 Loop(loops) => format!("Loop({:?})", loops),
                 MakePlayable => "MakePlayable".to_string(),
+                ListEvents(tx) => format!("ListEvents({:?})", tx),
+                Fork(tx) => format!("Fork({:?})", tx),
             }
         )
     }
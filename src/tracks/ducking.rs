@@ -0,0 +1,141 @@
+use crate::constants::*;
+use std::time::Duration;
+
+/// Sidechain settings for a track which attenuates ("ducks") all other
+/// tracks while it is playing.
+///
+/// This is typically used to lower music beneath announcements or TTS.
+/// While any ducking track is playing, every non-ducking track has its volume
+/// scaled towards `gain` over the `attack` period. Once all ducking tracks
+/// have ended or paused, other tracks return to full volume over the
+/// `release` period.
+///
+/// If several ducking tracks play at once, the lowest `gain` is used.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DuckConfig {
+    /// Gain applied to all other tracks while this track plays.
+    ///
+    /// This is clamped to lie in `0.0..=1.0`.
+    ///
+    /// *Defaults to `0.25`.*
+    pub gain: f32,
+    /// Time taken to fade other tracks down to `gain`.
+    ///
+    /// *Defaults to 100ms.*
+    pub attack: Duration,
+    /// Time taken to restore other tracks to full volume.
+    ///
+    /// *Defaults to 500ms.*
+    pub release: Duration,
+}
+
+impl Default for DuckConfig {
+    fn default() -> Self {
+        Self {
+            gain: 0.25,
+            attack: Duration::from_millis(100),
+            release: Duration::from_millis(500),
+        }
+    }
+}
+
+impl DuckConfig {
+    fn safe_gain(&self) -> f32 {
+        self.gain.clamp(0.0, 1.0)
+    }
+}
+
+/// Mixer-side state for smoothly applying [`DuckConfig`]s.
+#[derive(Clone, Debug)]
+pub(crate) struct Ducker {
+    gain: f32,
+    last: Option<DuckConfig>,
+}
+
+impl Default for Ducker {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            last: None,
+        }
+    }
+}
+
+impl Ducker {
+    /// Moves the gain applied to non-ducking tracks one frame closer to
+    /// that required by `active` (the strongest playing ducker, if any),
+    /// returning the new gain.
+    pub(crate) fn step(&mut self, active: Option<DuckConfig>) -> f32 {
+        if active.is_some() {
+            self.last = active;
+        }
+
+        let cfg = match self.last {
+            Some(cfg) => cfg,
+            None => return self.gain,
+        };
+
+        let target = active.map(|c| c.safe_gain()).unwrap_or(1.0);
+        let ramp = if target < self.gain {
+            cfg.attack
+        } else {
+            cfg.release
+        };
+
+        let span = (1.0 - cfg.safe_gain()).max(f32::EPSILON);
+        let step = if ramp.as_secs_f32() > 0.0 {
+            span * TIMESTEP_LENGTH.as_secs_f32() / ramp.as_secs_f32()
+        } else {
+            f32::INFINITY
+        };
+
+        self.gain = if target < self.gain {
+            (self.gain - step).max(target)
+        } else {
+            (self.gain + step).min(target)
+        };
+
+        if active.is_none() && (self.gain - 1.0).abs() < f32::EPSILON {
+            self.last = None;
+        }
+
+        self.gain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ducked_tracks_return_to_full_volume() {
+        let cfg = DuckConfig {
+            gain: 0.2,
+            attack: Duration::from_millis(60),
+            release: Duration::from_millis(100),
+        };
+        let mut ducker = Ducker::default();
+
+        // Attack takes 3 frames, and should ramp down rather than jump.
+        let first = ducker.step(Some(cfg));
+        assert!(first < 1.0 && first > cfg.gain);
+        for _ in 0..2 {
+            ducker.step(Some(cfg));
+        }
+        assert!((ducker.gain - cfg.gain).abs() < 1e-4);
+
+        // Hold while the announcement continues.
+        for _ in 0..10 {
+            assert!((ducker.step(Some(cfg)) - cfg.gain).abs() < 1e-4);
+        }
+
+        // Release takes 5 frames once the announcement ends.
+        let first = ducker.step(None);
+        assert!(first > cfg.gain && first < 1.0);
+        for _ in 0..4 {
+            ducker.step(None);
+        }
+        assert!((ducker.gain - 1.0).abs() < 1e-4);
+        assert_eq!(ducker.step(None), 1.0);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::constants::*;
+use std::f32::consts::PI;
+
+/// A single peaking filter within a track's equalizer.
+///
+/// Frequencies near `freq` are boosted or cut by `gain_db`, while `q` controls
+/// the width of the affected band: higher values give narrower bands. Typical
+/// values of `q` range from `0.5` (broad tone shaping) to around `5.0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EqBand {
+    /// Centre frequency of this band, in Hz.
+    ///
+    /// This is clamped to lie between `20.0` and `20_000.0`.
+    pub freq: f32,
+    /// Gain applied at `freq`, in decibels.
+    pub gain_db: f32,
+    /// Quality factor of the band.
+    ///
+    /// Non-positive values are treated as `0.1`.
+    pub q: f32,
+}
+
+impl EqBand {
+    /// Creates a band boosting or cutting `freq` by `gain_db`, with quality factor `q`.
+    pub fn new(freq: f32, gain_db: f32, q: f32) -> Self {
+        Self { freq, gain_db, q }
+    }
+}
+
+/// Coefficients and per-channel history of a biquad filter, following the
+/// peaking EQ design of the Audio EQ Cookbook.
+#[derive(Clone, Debug)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    /// Last two inputs and outputs for each channel.
+    history: [[f32; 4]; 2],
+}
+
+impl Biquad {
+    fn peaking(band: EqBand) -> Self {
+        let freq = band.freq.clamp(20.0, 20_000.0);
+        let q = if band.q > 0.0 { band.q } else { 0.1 };
+
+        let a = 10f32.powf(band.gain_db / 40.0);
+        let w0 = 2.0 * PI * freq / SAMPLE_RATE_RAW as f32;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos_w0 = w0.cos();
+        let a0 = 1.0 + alpha / a;
+
+        Self {
+            b0: (1.0 + alpha * a) / a0,
+            b1: (-2.0 * cos_w0) / a0,
+            b2: (1.0 - alpha * a) / a0,
+            a1: (-2.0 * cos_w0) / a0,
+            a2: (1.0 - alpha / a) / a0,
+            history: [[0.0; 4]; 2],
+        }
+    }
+
+    fn process(&mut self, sample: f32, channel: usize) -> f32 {
+        let [x1, x2, y1, y2] = self.history[channel];
+        let y = self.b0 * sample + self.b1 * x1 + self.b2 * x2 - self.a1 * y1 - self.a2 * y2;
+        self.history[channel] = [sample, x1, y, y1];
+
+        y
+    }
+}
+
+/// Mixer-side state for a track's multi-band equalizer.
+#[derive(Clone, Debug)]
+pub(crate) struct Equalizer {
+    bands: Vec<EqBand>,
+    filters: Vec<Biquad>,
+}
+
+impl Equalizer {
+    /// Creates an equalizer applying each of `bands` in turn, keeping at most
+    /// [`MAX_EQ_BANDS`] bands.
+    ///
+    /// Returns `None` if `bands` is empty.
+    ///
+    /// [`MAX_EQ_BANDS`]: crate::constants::MAX_EQ_BANDS
+    pub(crate) fn new(mut bands: Vec<EqBand>) -> Option<Self> {
+        bands.truncate(MAX_EQ_BANDS);
+        if bands.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            filters: bands.iter().copied().map(Biquad::peaking).collect(),
+            bands,
+        })
+    }
+
+    pub(crate) fn bands(&self) -> &[EqBand] {
+        &self.bands
+    }
+
+    /// Passes interleaved stereo `samples` through this equalizer, in place.
+    pub(crate) fn process(&mut self, samples: &mut [f32]) {
+        filter_all(&mut self.filters, samples);
+    }
+}
+
+/// Passes interleaved stereo `samples` through each filter in turn, in place.
+fn filter_all(filters: &mut [Biquad], samples: &mut [f32]) {
+    for frame in samples.chunks_exact_mut(2) {
+        for (channel, sample) in frame.iter_mut().enumerate() {
+            for filter in filters.iter_mut() {
+                *sample = filter.process(*sample, channel);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn energy_after_eq(freq: f32, eq: &mut Equalizer) -> f32 {
+        let mut samples: Vec<f32> = (0..SAMPLE_RATE_RAW)
+            .flat_map(|i| {
+                let s = (2.0 * PI * freq * i as f32 / SAMPLE_RATE_RAW as f32).sin();
+                [s, s]
+            })
+            .collect();
+        filter_all(&mut eq.filters, &mut samples);
+
+        // Skip the filter's initial transient.
+        samples[SAMPLE_RATE_RAW..].iter().map(|s| s * s).sum()
+    }
+
+    #[test]
+    fn boosted_band_raises_energy_at_its_frequency() {
+        let eq = |band| Equalizer::new(vec![band]).unwrap();
+        let flat = |freq| energy_after_eq(freq, &mut eq(EqBand::new(1000.0, 0.0, 1.0)));
+        let boosted = |freq| energy_after_eq(freq, &mut eq(EqBand::new(100.0, 6.0, 1.0)));
+
+        // +6dB doubles amplitude, so roughly quadruples energy.
+        let ratio = boosted(100.0) / flat(100.0);
+        assert!((3.5..4.5).contains(&ratio), "ratio was {}", ratio);
+
+        // Distant frequencies are left alone.
+        let ratio = boosted(8000.0) / flat(8000.0);
+        assert!((0.95..1.05).contains(&ratio), "ratio was {}", ratio);
+
+        assert!(Equalizer::new(vec![]).is_none());
+    }
+}
@@ -1,9 +1,13 @@
+use crate::input::ChildFailure;
 use std::{error::Error, fmt};
 
 /// Errors associated with control and manipulation of tracks.
 ///
 /// Unless otherwise stated, these don't invalidate an existing track,
-/// but do advise on valid operations and commands.
+/// but do advise on valid operations and commands. Errors which ended a
+/// track are reported by [`EndReason::Error`].
+///
+/// [`EndReason::Error`]: super::EndReason::Error
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[non_exhaustive]
 pub enum TrackError {
@@ -17,6 +21,37 @@ pub enum TrackError {
     ///
     /// [`Input`]: crate::input::Input
     SeekUnsupported,
+    /// The track's underlying [`Input`] can neither be recreated nor rewound,
+    /// so cannot be restarted.
+    ///
+    /// [`Input`]: crate::input::Input
+    RestartUnsupported,
+    /// The track's underlying [`Input`] could not be recreated, such as while an
+    /// earlier seek or restart is still under way.
+    ///
+    /// [`Input`]: crate::input::Input
+    RestartFailed,
+    /// The track's underlying [`Input`] has no known duration, so positions
+    /// relative to its length cannot be computed.
+    ///
+    /// [`Input`]: crate::input::Input
+    DurationUnknown,
+    /// The track's underlying [`Input`] is not cached, so a second track cannot
+    /// play the same audio.
+    ///
+    /// [`Input`]: crate::input::Input
+    ForkUnsupported,
+    /// The track's source process exited unsuccessfully, ending the track.
+    ///
+    /// Details are available via [`TrackHandle::failure`].
+    ///
+    /// [`TrackHandle::failure`]: super::TrackHandle::failure
+    SourceFailed,
+    /// The track's source produced no audio within [`Config::input_timeout`],
+    /// ending the track.
+    ///
+    /// [`Config::input_timeout`]: crate::Config::input_timeout
+    TimedOut,
 }
 
 impl fmt::Display for TrackError {
@@ -28,12 +63,47 @@ impl fmt::Display for TrackError {
                 write!(f, "given event listener can't be fired on a track")
             },
             TrackError::SeekUnsupported => write!(f, "track did not support seeking"),
+            TrackError::RestartUnsupported => write!(f, "track did not support restarting"),
+            TrackError::RestartFailed => write!(f, "track's source could not be restarted"),
+            TrackError::DurationUnknown => write!(f, "track had no known duration"),
+            TrackError::ForkUnsupported => write!(f, "track did not support forking"),
+            TrackError::SourceFailed => write!(f, "track's source failed"),
+            TrackError::TimedOut => write!(f, "track's source produced no audio in time"),
         }
     }
 }
 
 impl Error for TrackError {}
 
+/// Errors returned when a source could not begin playing, as reported by
+/// [`Driver::try_play`].
+///
+/// [`Driver::try_play`]: crate::driver::Driver::try_play
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PlayError {
+    /// The source's process exited unsuccessfully before producing any audio.
+    Failed(ChildFailure),
+    /// The source produced no audio in time, and has been stopped.
+    TimedOut,
+    /// The track ended, was stopped, or was removed from the driver before
+    /// producing any audio.
+    Ended,
+}
+
+impl fmt::Display for PlayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to start track: ")?;
+        match self {
+            PlayError::Failed(failure) => write!(f, "source process {}", failure.status),
+            PlayError::TimedOut => write!(f, "source produced no audio in time"),
+            PlayError::Ended => write!(f, "track ended before producing audio"),
+        }
+    }
+}
+
+impl Error for PlayError {}
+
 /// Alias for most calls to a [`TrackHandle`].
 ///
 /// [`TrackHandle`]: super::TrackHandle
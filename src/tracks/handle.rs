@@ -1,10 +1,19 @@
 use super::*;
 use crate::{
-    events::{Event, EventData, EventHandler},
-    input::Metadata,
+    events::{Event, EventData, EventHandler, EventInfo},
+    input::{ChildFailure, Metadata},
 };
 use flume::Sender;
-use std::{fmt, sync::Arc, time::Duration};
+use parking_lot::Mutex;
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 use tokio::sync::RwLock;
 use typemap_rev::TypeMap;
 use uuid::Uuid;
@@ -27,10 +36,13 @@ pub struct TrackHandle {
 
 struct InnerHandle {
     command_channel: Sender<TrackCommand>,
-    seekable: bool,
+    seekable: AtomicBool,
     uuid: Uuid,
     metadata: Box<Metadata>,
     typemap: RwLock<TypeMap>,
+    failure: Mutex<Option<ChildFailure>>,
+    metadata_update: Mutex<Option<Metadata>>,
+    timed_out: AtomicBool,
 }
 
 impl fmt::Debug for InnerHandle {
@@ -41,6 +53,9 @@ impl fmt::Debug for InnerHandle {
             .field("uuid", &self.uuid)
             .field("metadata", &self.metadata)
             .field("typemap", &"<LOCK>")
+            .field("failure", &self.failure)
+            .field("metadata_update", &self.metadata_update)
+            .field("timed_out", &self.timed_out)
             .finish()
     }
 }
@@ -58,10 +73,13 @@ impl TrackHandle {
     ) -> Self {
         let inner = Arc::new(InnerHandle {
             command_channel,
-            seekable,
+            seekable: AtomicBool::new(seekable),
             uuid,
             metadata,
             typemap: RwLock::new(TypeMap::new()),
+            failure: Mutex::new(None),
+            metadata_update: Mutex::new(None),
+            timed_out: AtomicBool::new(false),
         });
 
         Self { inner }
@@ -77,6 +95,17 @@ impl TrackHandle {
         self.send(TrackCommand::Pause)
     }
 
+    /// Pauses an audio track until `at`, when it resumes playing.
+    ///
+    /// This allows several tracks, including those played by other bots, to begin
+    /// in step with one another. If `at` has already passed, the track plays
+    /// immediately. See [`Track::set_start_at`] for details.
+    ///
+    /// [`Track::set_start_at`]: Track::set_start_at
+    pub fn play_at(&self, at: Instant) -> TrackResult<()> {
+        self.send(TrackCommand::PlayAt(at))
+    }
+
     /// Stops an audio track.
     ///
     /// This is *final*, and will cause the audio context to fire
@@ -87,11 +116,158 @@ impl TrackHandle {
         self.send(TrackCommand::Stop)
     }
 
+    /// Skips an audio track, ending it early.
+    ///
+    /// Like [`stop`], this is *final*, and will cause the audio context to fire
+    /// a [`TrackEvent::End`] event. The track's end reason is then reported as
+    /// [`EndReason::Skipped`] rather than [`EndReason::Stopped`], so that handlers
+    /// can tell the two apart.
+    ///
+    /// [`stop`]: TrackHandle::stop
+    /// [`TrackEvent::End`]: crate::events::TrackEvent::End
+    /// [`EndReason::Skipped`]: super::EndReason::Skipped
+    /// [`EndReason::Stopped`]: super::EndReason::Stopped
+    pub fn skip(&self) -> TrackResult<()> {
+        self.send(TrackCommand::Skip)
+    }
+
+    /// Stops an audio track after it has finished its current frame and sent
+    /// one trailing frame of silence.
+    ///
+    /// This produces a cleaner end than [`stop`], without the cost of a full fade.
+    /// Like [`stop`], this is *final*, and will cause the audio context to fire
+    /// a [`TrackEvent::End`] event once the trailing frame has been sent.
+    ///
+    /// [`stop`]: TrackHandle::stop
+    /// [`TrackEvent::End`]: crate::events::TrackEvent::End
+    pub fn soft_stop(&self) -> TrackResult<()> {
+        self.send(TrackCommand::SoftStop)
+    }
+
     /// Sets the volume of an audio track.
     pub fn set_volume(&self, volume: f32) -> TrackResult<()> {
         self.send(TrackCommand::Volume(volume))
     }
 
+    /// Makes this track duck (attenuate) all other tracks while it plays.
+    ///
+    /// Other tracks fade down and back up according to the attack and
+    /// release times in `ducking`.
+    pub fn set_ducking(&self, ducking: DuckConfig) -> TrackResult<()> {
+        self.send(TrackCommand::Ducking(Some(ducking)))
+    }
+
+    /// Stops this track from ducking other tracks.
+    pub fn clear_ducking(&self) -> TrackResult<()> {
+        self.send(TrackCommand::Ducking(None))
+    }
+
+    /// Applies a multi-band equalizer to this track, such as to boost bass
+    /// or cut treble.
+    ///
+    /// Each band is a peaking filter, applied one after another. At most
+    /// [`MAX_EQ_BANDS`] bands are used, and an empty list disables equalization.
+    ///
+    /// [`MAX_EQ_BANDS`]: crate::constants::MAX_EQ_BANDS
+    pub fn set_equalizer(&self, bands: Vec<EqBand>) -> TrackResult<()> {
+        self.send(TrackCommand::Equalizer(bands))
+    }
+
+    /// Applies a noise gate to this track, muting its audio whenever it falls
+    /// below the gate's threshold.
+    ///
+    /// This suits noisy sources, such as relayed microphones, and disables
+    /// Opus passthrough.
+    pub fn set_noise_gate(&self, gate: GateConfig) -> TrackResult<()> {
+        self.send(TrackCommand::NoiseGate(Some(gate)))
+    }
+
+    /// Removes this track's noise gate.
+    pub fn clear_noise_gate(&self) -> TrackResult<()> {
+        self.send(TrackCommand::NoiseGate(None))
+    }
+
+    /// Watches this track for silence, taking the policy's action once its audio
+    /// has stayed below a threshold for long enough.
+    ///
+    /// [`TrackEvent::Silent`] and [`TrackEvent::Audible`] fire as the track falls
+    /// silent and recovers. This suits streams which may go quiet for long periods,
+    /// and disables Opus passthrough.
+    ///
+    /// [`TrackEvent::Silent`]: crate::events::TrackEvent::Silent
+    /// [`TrackEvent::Audible`]: crate::events::TrackEvent::Audible
+    pub fn set_silence_policy(&self, policy: SilencePolicy) -> TrackResult<()> {
+        self.send(TrackCommand::SilencePolicy(Some(policy)))
+    }
+
+    /// Stops watching this track for silence.
+    ///
+    /// A track left idle by its previous policy rejoins the mix.
+    pub fn clear_silence_policy(&self) -> TrackResult<()> {
+        self.send(TrackCommand::SilencePolicy(None))
+    }
+
+    /// Shifts the pitch of this track by `semitones`, without changing its speed
+    /// (*e.g.*, for "chipmunk" or "deep voice" effects).
+    ///
+    /// Positive values raise the pitch, and `12.0` is one octave. Shifts are
+    /// clamped to [`MAX_PITCH_SEMITONES`], and `0.0` disables pitch shifting.
+    /// This disables Opus passthrough. Enable the `"pitch-vocoder"` feature for
+    /// cleaner, but more expensive, shifting.
+    ///
+    /// [`MAX_PITCH_SEMITONES`]: crate::constants::MAX_PITCH_SEMITONES
+    pub fn set_pitch(&self, semitones: f32) -> TrackResult<()> {
+        self.send(TrackCommand::Pitch(semitones))
+    }
+
+    /// Applies several changes to this track's settings at once, such as a new
+    /// volume alongside a new equalizer.
+    ///
+    /// All changes take effect together, before the next frame of this track is
+    /// mixed. If the update changes the loop count and the underlying [`Input`] does
+    /// not support seeking, then this fails with [`TrackError::SeekUnsupported`]
+    /// and nothing is changed.
+    ///
+    /// [`Input`]: crate::input::Input
+    /// [`TrackError::SeekUnsupported`]: TrackError::SeekUnsupported
+    pub fn apply(&self, update: TrackUpdate) -> TrackResult<()> {
+        if update.loops.is_some() && !self.is_seekable() {
+            return Err(TrackError::SeekUnsupported);
+        }
+
+        self.send(TrackCommand::Update(update))
+    }
+
+    /// Returns diagnostics describing why this track's source failed, if it
+    /// ended due to its source process exiting unsuccessfully.
+    ///
+    /// This is available once [`TrackEvent::Error`] has fired, and remains
+    /// available after the track has been removed from the driver.
+    ///
+    /// [`TrackEvent::Error`]: crate::events::TrackEvent::Error
+    pub fn failure(&self) -> Option<ChildFailure> {
+        self.inner.failure.lock().clone()
+    }
+
+    pub(crate) fn set_failure(&self, failure: ChildFailure) {
+        *self.inner.failure.lock() = Some(failure);
+    }
+
+    /// Returns whether this track was ended because its source produced no
+    /// audio within [`Config::input_timeout`].
+    ///
+    /// [`TrackEvent::Error`] fires when this occurs.
+    ///
+    /// [`Config::input_timeout`]: crate::Config::input_timeout
+    /// [`TrackEvent::Error`]: crate::events::TrackEvent::Error
+    pub fn timed_out(&self) -> bool {
+        self.inner.timed_out.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_timed_out(&self) {
+        self.inner.timed_out.store(true, Ordering::Release);
+    }
+
     /// Ready a track for playing if it is lazily initialised.
     ///
     /// Currently, only [`Restartable`] sources support lazy setup.
@@ -107,10 +283,19 @@ impl TrackHandle {
     /// If this returns `false`, all calls to [`seek_time`] will fail, and the track is
     /// incapable of looping.
     ///
+    /// This matches [`Input::is_seekable`], and is kept up to date while the track
+    /// plays, as lazily opened sources may only learn that they are live streams
+    /// once started.
+    ///
     /// [`seek_time`]: TrackHandle::seek_time
     /// [`Input`]: crate::input::Input
+    /// [`Input::is_seekable`]: crate::input::Input::is_seekable
     pub fn is_seekable(&self) -> bool {
-        self.inner.seekable
+        self.inner.seekable.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_seekable(&self, seekable: bool) {
+        self.inner.seekable.store(seekable, Ordering::Release);
     }
 
     /// Seeks along the track to the specified position.
@@ -128,6 +313,67 @@ impl TrackHandle {
         }
     }
 
+    /// Seeks to the given fraction of the way through the track, where `0.0`
+    /// is its start and `1.0` its end.
+    ///
+    /// `fraction` is clamped to lie between `0.0` and `1.0`, and positions are
+    /// computed from the track's [`Metadata::duration`]. Calls fail with
+    /// [`TrackError::DurationUnknown`] if no duration is known, as for live streams,
+    /// and with [`TrackError::SeekUnsupported`] as in [`seek_time`].
+    ///
+    /// [`Metadata::duration`]: crate::input::Metadata::duration
+    /// [`TrackError::DurationUnknown`]: TrackError::DurationUnknown
+    /// [`TrackError::SeekUnsupported`]: TrackError::SeekUnsupported
+    /// [`seek_time`]: TrackHandle::seek_time
+    pub fn seek_fraction(&self, fraction: f32) -> TrackResult<()> {
+        if !self.is_seekable() {
+            return Err(TrackError::SeekUnsupported);
+        }
+
+        let duration = self
+            .inner
+            .metadata_update
+            .lock()
+            .as_ref()
+            .unwrap_or(&self.inner.metadata)
+            .duration
+            .ok_or(TrackError::DurationUnknown)?;
+
+        let fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+
+        self.seek_time(duration.mul_f32(fraction))
+    }
+
+    /// Restarts the track from its beginning, and resumes playback.
+    ///
+    /// Unlike seeking, this fires [`TrackEvent::Play`] even if the track was already
+    /// playing. See [`Track::restart`] for which sources can be restarted: all others
+    /// fail with [`TrackError::RestartUnsupported`].
+    ///
+    /// The restart is requested immediately, and the returned future resolves once
+    /// the audio context has acted upon it.
+    ///
+    /// [`TrackEvent::Play`]: crate::events::TrackEvent::Play
+    /// [`Track::restart`]: Track::restart
+    /// [`TrackError::RestartUnsupported`]: TrackError::RestartUnsupported
+    pub fn restart(&self) -> impl Future<Output = TrackResult<()>> {
+        let (tx, rx) = flume::bounded(1);
+        let sent = if self.is_seekable() {
+            self.send(TrackCommand::Restart(tx))
+        } else {
+            Err(TrackError::RestartUnsupported)
+        };
+
+        async move {
+            sent?;
+            rx.recv_async().await.map_err(|_| TrackError::Finished)?
+        }
+    }
+
     /// Attach an event handler to an audio track. These will receive [`EventContext::Track`].
     ///
     /// Events which can only be fired by the global context return [`TrackError::InvalidTrackEvent`]
@@ -166,6 +412,62 @@ impl TrackHandle {
         rx.recv_async().await.map_err(|_| TrackError::Finished)
     }
 
+    /// Request cumulative playback statistics from the audio context.
+    pub async fn stats(&self) -> TrackResult<PlaybackStats> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(TrackCommand::RequestStats(tx))?;
+
+        rx.recv_async().await.map_err(|_| TrackError::Finished)
+    }
+
+    /// Request how much audio this track's input has buffered ahead of playback.
+    ///
+    /// In-memory caches report their unread remainder, and generated and Opus-encoded
+    /// sources report zero: see [`Input::buffered`]. Child processes, such as `ffmpeg`,
+    /// report only the audio songbird has read from their pipe and not yet played. This
+    /// says nothing of data held by the process or the network, so a low figure alone
+    /// does not mean that such a source is struggling to keep up.
+    ///
+    /// [`Input::buffered`]: crate::input::Input::buffered
+    pub async fn buffer_level(&self) -> TrackResult<Duration> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(TrackCommand::RequestBufferLevel(tx))?;
+
+        rx.recv_async().await.map_err(|_| TrackError::Finished)
+    }
+
+    /// Starts a second, independent track playing the same audio as this one,
+    /// from its current position.
+    ///
+    /// The new track begins with this track's volume, but none of its effects, loops
+    /// or events. Each can then be controlled separately through its own handle,
+    /// *e.g.*, to layer a detuned copy using [`set_pitch`].
+    ///
+    /// Only cached sources, such as [`Memory`] and [`Compressed`], can be replayed
+    /// in this way: all others fail with [`TrackError::ForkUnsupported`].
+    ///
+    /// [`set_pitch`]: TrackHandle::set_pitch
+    /// [`Memory`]: crate::input::cached::Memory
+    /// [`Compressed`]: crate::input::cached::Compressed
+    /// [`TrackError::ForkUnsupported`]: TrackError::ForkUnsupported
+    pub async fn fork(&self) -> TrackResult<TrackHandle> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(TrackCommand::Fork(tx))?;
+
+        rx.recv_async().await.map_err(|_| TrackError::Finished)?
+    }
+
+    /// Request descriptions of all events currently registered on this track.
+    ///
+    /// This is intended for debugging and inspection, and does not expose
+    /// any event handlers.
+    pub async fn list_events(&self) -> TrackResult<Vec<EventInfo>> {
+        let (tx, rx) = flume::bounded(1);
+        self.send(TrackCommand::ListEvents(tx))?;
+
+        rx.recv_async().await.map_err(|_| TrackError::Finished)
+    }
+
     /// Set an audio track to loop indefinitely.
     ///
     /// If the underlying [`Input`] does not support seeking,
@@ -220,13 +522,33 @@ impl TrackHandle {
     ///
     /// Metadata is cloned from the inner [`Input`] at
     /// the time a track/handle is created, and is effectively
-    /// read-only from then on.
+    /// read-only from then on. Use [`current_metadata`] to see
+    /// any later updates announced by a live source.
     ///
     /// [`Input`]: crate::input::Input
+    /// [`current_metadata`]: TrackHandle::current_metadata
     pub fn metadata(&self) -> &Metadata {
         &self.inner.metadata
     }
 
+    /// Returns the most recent metadata announced by this track's source,
+    /// or the metadata it was created with if there have been no updates.
+    ///
+    /// Updates also fire [`TrackEvent::MetadataUpdate`].
+    ///
+    /// [`TrackEvent::MetadataUpdate`]: crate::events::TrackEvent::MetadataUpdate
+    pub fn current_metadata(&self) -> Metadata {
+        self.inner
+            .metadata_update
+            .lock()
+            .clone()
+            .unwrap_or_else(|| (*self.inner.metadata).clone())
+    }
+
+    pub(crate) fn set_metadata(&self, metadata: Metadata) {
+        *self.inner.metadata_update.lock() = Some(metadata);
+    }
+
     /// Allows access to this track's attached TypeMap.
     ///
     /// TypeMaps allow additional, user-defined data shared by all handles
@@ -251,3 +573,40 @@ impl TrackHandle {
             .map_err(|_e| TrackError::Finished)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Codec, Container, Input};
+
+    #[test]
+    fn seek_fractions_are_scaled_by_duration() {
+        let seek_to = |duration: Option<Duration>, fraction: f32| {
+            let metadata = Metadata {
+                duration,
+                ..Default::default()
+            };
+            let input = Input::new(
+                true,
+                vec![0u8; 4096].into(),
+                Codec::FloatPcm,
+                Container::Raw,
+                Some(metadata),
+            );
+            let (track, handle) = create_player(input);
+
+            handle.seek_fraction(fraction).map(|_| {
+                match track.commands.try_recv() {
+                    Ok(TrackCommand::Seek(time)) => time,
+                    _ => panic!("Expected a seek command."),
+                }
+            })
+        };
+
+        let duration = Some(Duration::from_secs(100));
+        assert_eq!(seek_to(duration, 0.25), Ok(Duration::from_secs(25)));
+        assert_eq!(seek_to(duration, 2.0), Ok(Duration::from_secs(100)));
+        assert_eq!(seek_to(duration, f32::NAN), Ok(Duration::ZERO));
+        assert_eq!(seek_to(None, 0.5), Err(TrackError::DurationUnknown));
+    }
+}
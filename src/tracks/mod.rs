@@ -15,18 +15,51 @@
 //! [`create_player`]: fn.create_player.html
 
 mod command;
+mod ducking;
+mod equalizer;
 mod error;
 mod handle;
 mod looping;
 mod mode;
+mod noise_gate;
+mod pitch;
 mod queue;
+mod silence;
 mod state;
-
-pub use self::{command::*, error::*, handle::*, looping::*, mode::*, queue::*, state::*};
+mod stats;
+mod update;
+
+pub use self::{
+    command::*,
+    ducking::DuckConfig,
+    equalizer::EqBand,
+    error::*,
+    handle::*,
+    looping::*,
+    mode::*,
+    noise_gate::GateConfig,
+    queue::*,
+    silence::{SilenceAction, SilencePolicy},
+    state::*,
+    stats::PlaybackStats,
+    update::TrackUpdate,
+};
+
+pub(crate) use self::{
+    ducking::Ducker,
+    equalizer::Equalizer,
+    noise_gate::NoiseGate,
+    pitch::PitchShifter,
+    silence::SilenceDetector,
+};
 
 use crate::{constants::*, driver::tasks::message::*, events::EventStore, input::Input};
-use flume::{Receiver, TryRecvError};
-use std::time::Duration;
+use flume::{Receiver, Sender, TryRecvError};
+use std::{
+    io::ErrorKind as IoErrorKind,
+    time::{Duration, Instant},
+};
+use tracing::warn;
 use uuid::Uuid;
 
 /// Control object for audio playback.
@@ -115,6 +148,69 @@ pub struct Track {
 
     /// Unique identifier for this track.
     pub(crate) uuid: Uuid,
+
+    /// Progress through a soft stop, if one has been requested.
+    pub(crate) soft_stop: Option<SoftStop>,
+
+    /// Attenuation applied to all other tracks while this track is playing.
+    pub(crate) ducking: Option<DuckConfig>,
+
+    /// Multi-band equalizer applied to this track's audio, if any.
+    pub(crate) equalizer: Option<Equalizer>,
+
+    /// Noise gate applied to this track's audio, if any.
+    pub(crate) noise_gate: Option<NoiseGate>,
+
+    /// Pitch shift applied to this track's audio, if any.
+    pub(crate) pitch: Option<PitchShifter>,
+
+    /// Detects when this track's audio has fallen silent, if enabled.
+    pub(crate) silence: Option<SilenceDetector>,
+
+    /// Working space for applying the equalizer and noise gate, allocated on first use.
+    pub(crate) effect_buffer: Option<Box<[f32; STEREO_FRAME_SIZE]>>,
+
+    /// The gain most recently applied to this track by the mixer.
+    pub(crate) effective_volume: f32,
+
+    /// Position to seek to before the first frame of this track is played.
+    pub(crate) start_offset: Option<Duration>,
+
+    /// When this paused track is due to begin playing, if scheduled.
+    pub(crate) start_at: Option<Instant>,
+
+    /// Longest time for which this track may play before it is ended.
+    pub(crate) max_duration: Option<Duration>,
+
+    /// Why this track finished, once it has stopped or ended.
+    pub(crate) end_reason: Option<EndReason>,
+
+    /// Number of consecutive frames which exceeded the mixer's processing budget.
+    pub(crate) budget_overruns: usize,
+
+    /// Cumulative playback statistics.
+    pub(crate) stats: PlaybackStats,
+
+    /// Whether the input was buffering during the last played frame.
+    pub(crate) buffering: bool,
+
+    /// Whether this track produced audio for the packet now being prepared.
+    pub(crate) contributed: bool,
+
+    /// Notified once this track first produces audio, if anyone is waiting.
+    pub(crate) started: Option<Sender<()>>,
+
+    /// Whether [`TrackEvent::Ready`] has fired since this track was created or
+    /// last restarted.
+    ///
+    /// [`TrackEvent::Ready`]: crate::events::TrackEvent::Ready
+    pub(crate) ready: bool,
+
+    /// Latest seek requested, waiting to be rate-limited.
+    pub(crate) pending_seek: Option<Duration>,
+
+    /// When a seek was last carried out.
+    pub(crate) last_seek: Option<Instant>,
 }
 
 impl Track {
@@ -138,6 +234,26 @@ impl Track {
             handle,
             loops: LoopState::Finite(0),
             uuid,
+            soft_stop: None,
+            ducking: None,
+            equalizer: None,
+            noise_gate: None,
+            silence: None,
+            pitch: None,
+            effect_buffer: None,
+            effective_volume: 1.0,
+            start_offset: None,
+            start_at: None,
+            max_duration: None,
+            end_reason: None,
+            budget_overruns: 0,
+            stats: Default::default(),
+            buffering: false,
+            contributed: false,
+            started: None,
+            ready: false,
+            pending_seek: None,
+            last_seek: None,
         }
     }
 
@@ -156,16 +272,201 @@ impl Track {
     /// This will cause the audio track to be removed, with any relevant events triggered.
     /// Stopped/ended tracks cannot be restarted.
     pub fn stop(&mut self) -> &mut Self {
-        self.set_playing(PlayMode::Stop)
+        self.end(EndReason::Stopped)
+    }
+
+    /// Stops a track once it has mixed its current frame and contributed one
+    /// trailing frame of silence to the mixer.
+    ///
+    /// This avoids the audible click which an abrupt [`stop`] may cause,
+    /// without the cost of a full fade. Paused tracks are stopped immediately.
+    ///
+    /// [`stop`]: Track::stop
+    pub fn soft_stop(&mut self) -> &mut Self {
+        if self.playing == PlayMode::Play {
+            self.soft_stop.get_or_insert(SoftStop::CurrentFrame);
+            self
+        } else {
+            self.stop()
+        }
+    }
+
+    /// Sets whether this track should duck all other tracks while it plays,
+    /// in a manner that allows method chaining.
+    ///
+    /// Passing `None` stops this track from ducking others.
+    pub fn set_ducking(&mut self, ducking: Option<DuckConfig>) -> &mut Self {
+        self.ducking = ducking;
+        self
+    }
+
+    /// Returns the ducking settings applied by this track, if any.
+    pub fn ducking(&self) -> Option<DuckConfig> {
+        self.ducking
+    }
+
+    /// Sets the bands of this track's equalizer, in a manner that allows
+    /// method chaining.
+    ///
+    /// Bands are applied one after another, and at most [`MAX_EQ_BANDS`] are kept.
+    /// Passing an empty list disables equalization. Equalized tracks cannot use
+    /// Opus passthrough.
+    ///
+    /// [`MAX_EQ_BANDS`]: crate::constants::MAX_EQ_BANDS
+    pub fn set_equalizer(&mut self, bands: Vec<EqBand>) -> &mut Self {
+        self.equalizer = Equalizer::new(bands);
+        self
+    }
+
+    /// Returns the bands of this track's equalizer.
+    pub fn equalizer(&self) -> &[EqBand] {
+        self.equalizer.as_ref().map(Equalizer::bands).unwrap_or(&[])
+    }
+
+    /// Sets the noise gate applied to this track, in a manner that allows
+    /// method chaining.
+    ///
+    /// Passing `None` disables the gate. Gated tracks cannot use Opus passthrough.
+    pub fn set_noise_gate(&mut self, gate: Option<GateConfig>) -> &mut Self {
+        self.noise_gate = match (self.noise_gate.take(), gate) {
+            (Some(mut current), Some(config)) => {
+                current.configure(config);
+                Some(current)
+            },
+            (_, config) => config.map(NoiseGate::new),
+        };
+        self
+    }
+
+    /// Returns the settings of this track's noise gate, if any.
+    pub fn noise_gate(&self) -> Option<GateConfig> {
+        self.noise_gate.as_ref().map(NoiseGate::config)
+    }
+
+    /// Shifts the pitch of this track by `semitones` without changing its speed,
+    /// in a manner that allows method chaining.
+    ///
+    /// Positive values raise the pitch, and `12.0` is one octave. Shifts are
+    /// clamped to [`MAX_PITCH_SEMITONES`], and `0.0` disables pitch shifting.
+    /// Pitch-shifted tracks cannot use Opus passthrough.
+    ///
+    /// [`MAX_PITCH_SEMITONES`]: crate::constants::MAX_PITCH_SEMITONES
+    pub fn set_pitch(&mut self, semitones: f32) -> &mut Self {
+        if (semitones - self.pitch()).abs() > f32::EPSILON {
+            self.pitch = PitchShifter::new(semitones);
+        }
+        self
+    }
+
+    /// Returns the pitch shift applied to this track, in semitones.
+    pub fn pitch(&self) -> f32 {
+        self.pitch.as_ref().map(PitchShifter::semitones).unwrap_or(0.0)
+    }
+
+    /// Sets the policy used to detect when this track has fallen silent, in a
+    /// manner that allows method chaining.
+    ///
+    /// Passing `None` disables silence detection. Tracks with a silence policy
+    /// cannot use Opus passthrough.
+    pub fn set_silence_policy(&mut self, policy: Option<SilencePolicy>) -> &mut Self {
+        self.silence = policy.map(SilenceDetector::new);
+        self
+    }
+
+    /// Returns this track's silence policy, if any.
+    pub fn silence_policy(&self) -> Option<SilencePolicy> {
+        self.silence.as_ref().map(SilenceDetector::policy)
+    }
+
+    /// Returns whether this track is being left out of the mix, having fallen
+    /// silent under a [`SilenceAction::Idle`] policy.
+    pub(crate) fn is_idle(&self) -> bool {
+        matches!(&self.silence, Some(silence) if silence.is_idle())
+    }
+
+    /// Returns whether this track's audio must pass through its pitch shifter,
+    /// equalizer, noise gate, or silence detector before mixing.
+    pub(crate) fn has_effects(&self) -> bool {
+        self.pitch.is_some()
+            || self.equalizer.is_some()
+            || self.noise_gate.is_some()
+            || self.silence.is_some()
+    }
+
+    /// Mixes the next frame of this track into `mix_buffer` at `volume`, after
+    /// passing it through its pitch shifter, equalizer, and noise gate.
+    ///
+    /// Frames are measured by the silence detector after these effects, and are
+    /// left out of the mix while the track is idle.
+    ///
+    /// Returns the number of samples read, as [`Input::mix`].
+    ///
+    /// [`Input::mix`]: crate::input::Input::mix
+    pub(crate) fn mix_effects(
+        &mut self,
+        mix_buffer: &mut [f32; STEREO_FRAME_SIZE],
+        volume: f32,
+    ) -> usize {
+        let scratch = self
+            .effect_buffer
+            .get_or_insert_with(|| Box::new([0.0; STEREO_FRAME_SIZE]));
+        scratch.fill(0.0);
+
+        // Volume is applied last, so that the gate threshold follows the source.
+        let len = self.source.mix(scratch, 1.0);
+
+        // The whole frame is processed, so that each band's tail rings out
+        // smoothly once the input ends.
+        if let Some(pitch) = self.pitch.as_mut() {
+            pitch.process(&mut scratch[..]);
+        }
+        if let Some(eq) = self.equalizer.as_mut() {
+            eq.process(&mut scratch[..]);
+        }
+        if let Some(gate) = self.noise_gate.as_mut() {
+            gate.process(&mut scratch[..]);
+        }
+        if let Some(silence) = self.silence.as_mut() {
+            silence.process(&scratch[..]);
+
+            if silence.is_idle() {
+                return len;
+            }
+        }
+
+        for (out, sample) in mix_buffer.iter_mut().zip(scratch.iter()) {
+            *out += sample * volume;
+        }
+
+        len
     }
 
-    pub(crate) fn end(&mut self) -> &mut Self {
-        self.set_playing(PlayMode::End)
+    /// Ends this track, recording `reason` unless it has already finished.
+    pub(crate) fn end(&mut self, reason: EndReason) -> &mut Self {
+        if !self.playing.is_done() {
+            self.end_reason = Some(reason);
+        }
+        self.set_playing(reason.mode())
+    }
+
+    /// Returns why this track finished, once it has stopped or ended.
+    pub fn end_reason(&self) -> Option<EndReason> {
+        self.end_reason
+    }
+
+    /// Describes this track's current play mode to the event thread, along with
+    /// its end reason once finished.
+    pub(crate) fn mode_change(&self) -> TrackStateChange {
+        match self.end_reason {
+            Some(reason) if self.playing.is_done() => TrackStateChange::Ended(reason),
+            _ => TrackStateChange::Mode(self.playing),
+        }
     }
 
     #[inline]
     fn set_playing(&mut self, new_state: PlayMode) -> &mut Self {
         self.playing = self.playing.change_to(new_state);
+        self.start_at = None;
 
         self
     }
@@ -199,6 +500,32 @@ impl Track {
         self.play_time
     }
 
+    /// Returns cumulative playback statistics for this track.
+    pub fn stats(&self) -> PlaybackStats {
+        self.stats
+    }
+
+    /// Records the outcome of one frame of playback in this track's statistics.
+    pub(crate) fn record_frame(&mut self, len: usize) {
+        let buffering = self.source.is_buffering();
+        self.contributed = !buffering && len > 0;
+
+        if buffering {
+            if !self.buffering && self.stats.frames > 0 {
+                self.stats.underruns += 1;
+            }
+            self.stats.buffering += TIMESTEP_LENGTH;
+        } else if len > 0 {
+            self.stats.frames += 1;
+
+            if let Some(started) = self.started.take() {
+                let _ = started.send(());
+            }
+        }
+
+        self.buffering = buffering;
+    }
+
     /// Set an audio track to loop a set number of times.
     ///
     /// If the underlying [`Input`] does not support seeking,
@@ -215,6 +542,141 @@ impl Track {
         }
     }
 
+    /// Applies several changes to this track's settings at once.
+    ///
+    /// If any change cannot be made, then none are applied. Changing the loop
+    /// count fails with [`TrackError::SeekUnsupported`] if the underlying [`Input`]
+    /// does not support seeking.
+    ///
+    /// [`Input`]: crate::input::Input
+    /// [`TrackError::SeekUnsupported`]: TrackError::SeekUnsupported
+    pub fn apply(&mut self, update: TrackUpdate) -> TrackResult<()> {
+        if update.loops.is_some() && !self.source.is_seekable() {
+            return Err(TrackError::SeekUnsupported);
+        }
+
+        if let Some(volume) = update.volume {
+            self.set_volume(volume);
+        }
+        if let Some(ducking) = update.ducking {
+            self.set_ducking(ducking);
+        }
+        if let Some(bands) = update.equalizer {
+            self.set_equalizer(bands);
+        }
+        if let Some(loops) = update.loops {
+            self.loops = loops;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the position from which this track will begin playing.
+    ///
+    /// The driver seeks to this position before the first frame of audio is
+    /// produced, so playback begins partway in without audibly jumping from the start.
+    /// This avoids racing the driver when calling [`TrackHandle::seek_time`] just after
+    /// the track is played.
+    ///
+    /// If the underlying [`Input`] does not support seeking,
+    /// then all calls will fail with [`TrackError::SeekUnsupported`].
+    ///
+    /// [`TrackHandle::seek_time`]: TrackHandle::seek_time
+    /// [`Input`]: crate::input::Input
+    /// [`TrackError::SeekUnsupported`]: TrackError::SeekUnsupported
+    pub fn set_start_offset(&mut self, offset: Duration) -> TrackResult<()> {
+        if self.source.is_seekable() {
+            self.start_offset = Some(offset);
+            Ok(())
+        } else {
+            Err(TrackError::SeekUnsupported)
+        }
+    }
+
+    /// Returns the position from which this track will begin playing, if set
+    /// and not yet applied.
+    pub fn start_offset(&self) -> Option<Duration> {
+        self.start_offset
+    }
+
+    /// Holds this track paused until `at`, when it begins playing and fires
+    /// [`TrackEvent::Play`].
+    ///
+    /// Playback begins with the first packet sent at or after `at`, so that tracks
+    /// scheduled for the same instant (even across several drivers) start within
+    /// one frame of one another. If `at` has already passed, the track plays
+    /// immediately. Playing, pausing, or stopping the track beforehand cancels
+    /// its scheduled start.
+    ///
+    /// [`TrackEvent::Play`]: crate::events::TrackEvent::Play
+    pub fn set_start_at(&mut self, at: Instant) -> &mut Self {
+        if at <= Instant::now() {
+            self.play()
+        } else {
+            self.pause();
+            self.start_at = Some(at);
+            self
+        }
+    }
+
+    /// Returns when this track is scheduled to begin playing, if it is waiting
+    /// to do so.
+    pub fn start_at(&self) -> Option<Instant> {
+        self.start_at
+    }
+
+    /// Limits how long this track may play, in a manner that allows method chaining.
+    ///
+    /// Once its [play time] reaches `max`, the track ends with
+    /// [`EndReason::MaxDuration`]. Passing `None` removes the limit.
+    ///
+    /// [play time]: Track::play_time
+    pub fn set_max_duration(&mut self, max: Option<Duration>) -> &mut Self {
+        self.max_duration = max;
+        self
+    }
+
+    /// Returns the longest time for which this track may play, if limited.
+    pub fn max_duration(&self) -> Option<Duration> {
+        self.max_duration
+    }
+
+    /// Begins playback if this track's scheduled start falls at or before `now`,
+    /// returning whether it did so.
+    pub(crate) fn poll_start(&mut self, now: Instant) -> bool {
+        match self.start_at {
+            Some(at) if at <= now => {
+                self.play();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Sends a new track playing this one's audio from its current position to the mixer.
+    fn fork(&self, ic: &Interconnect) -> TrackResult<TrackHandle> {
+        let input = self.source.fork().ok_or(TrackError::ForkUnsupported)?;
+        let (mut track, handle) = create_player(input);
+
+        track.set_volume(self.volume);
+        track.set_start_offset(self.position)?;
+
+        ic.mixer
+            .send(MixerMessage::AddTrack(track))
+            .map_err(|_| TrackError::Finished)?;
+
+        Ok(handle)
+    }
+
+    /// Moves to the requested start position, if any, before playback begins.
+    pub(crate) fn apply_start_offset(&mut self) {
+        if let Some(offset) = self.start_offset.take() {
+            if self.seek_time(offset).is_err() {
+                warn!("Track {} failed to seek to start offset {:?}.", self.uuid, offset);
+            }
+        }
+    }
+
     pub(crate) fn do_loop(&mut self) -> bool {
         match self.loops {
             LoopState::Infinite => true,
@@ -235,7 +697,16 @@ impl Track {
     /// Receives and acts upon any commands forwarded by TrackHandles.
     ///
     /// *Used internally*, this should not be exposed to users.
-    pub(crate) fn process_commands(&mut self, index: usize, ic: &Interconnect) {
+    /// Applies all commands sent to this track since the last call.
+    ///
+    /// At most one seek is carried out per `seek_interval`, if given: any seeks made
+    /// in the meantime are merged into the latest.
+    pub(crate) fn process_commands(
+        &mut self,
+        index: usize,
+        ic: &Interconnect,
+        seek_interval: Option<Duration>,
+    ) {
         // Note: disconnection and an empty channel are both valid,
         // and should allow the audio object to keep running as intended.
 
@@ -262,13 +733,34 @@ impl Track {
                                 TrackStateChange::Mode(self.playing),
                             ));
                         },
-                        Stop => {
-                            self.stop();
+                        Fork(tx) => {
+                            let _ = tx.send(self.fork(ic));
+                        },
+                        PlayAt(at) => {
+                            self.set_start_at(at);
                             let _ = ic.events.send(EventMessage::ChangeState(
                                 index,
                                 TrackStateChange::Mode(self.playing),
                             ));
                         },
+                        Stop => {
+                            self.stop();
+                            let _ = ic
+                                .events
+                                .send(EventMessage::ChangeState(index, self.mode_change()));
+                        },
+                        SoftStop =>
+                            if self.soft_stop().playing.is_done() {
+                                let _ = ic
+                                    .events
+                                    .send(EventMessage::ChangeState(index, self.mode_change()));
+                            },
+                        Skip => {
+                            self.end(EndReason::Skipped);
+                            let _ = ic
+                                .events
+                                .send(EventMessage::ChangeState(index, self.mode_change()));
+                        },
                         Volume(vol) => {
                             self.set_volume(vol);
                             let _ = ic.events.send(EventMessage::ChangeState(
@@ -276,13 +768,39 @@ impl Track {
                                 TrackStateChange::Volume(self.volume),
                             ));
                         },
-                        Seek(time) =>
-                            if let Ok(new_time) = self.seek_time(time) {
+                        Ducking(ducking) => {
+                            self.set_ducking(ducking);
+                        },
+                        Equalizer(bands) => {
+                            self.set_equalizer(bands);
+                        },
+                        NoiseGate(gate) => {
+                            self.set_noise_gate(gate);
+                        },
+                        Pitch(semitones) => {
+                            self.set_pitch(semitones);
+                        },
+                        SilencePolicy(policy) => {
+                            self.set_silence_policy(policy);
+                        },
+                        Seek(time) => {
+                            self.pending_seek = Some(time);
+                            self.apply_pending_seek(index, ic, seek_interval);
+                        },
+                        Restart(tx) => {
+                            let result = self.restart();
+
+                            if result.is_ok() {
+                                self.pending_seek = None;
+                                self.stats.seeks += 1;
                                 let _ = ic.events.send(EventMessage::ChangeState(
                                     index,
-                                    TrackStateChange::Position(new_time),
+                                    TrackStateChange::Restarted,
                                 ));
-                            },
+                            }
+
+                            let _ = tx.send(result);
+                        },
                         AddEvent(evt) => {
                             let _ = ic.events.send(EventMessage::AddTrackEvent(index, evt));
                         },
@@ -296,6 +814,19 @@ impl Track {
                         Request(tx) => {
                             let _ = tx.send(self.state());
                         },
+                        RequestStats(tx) => {
+                            let _ = tx.send(self.stats);
+                        },
+                        RequestBufferLevel(tx) => {
+                            let _ = tx.send(self.source.buffered());
+                        },
+                        Update(update) =>
+                            if self.apply(update).is_ok() {
+                                let _ = ic.events.send(EventMessage::ChangeState(
+                                    index,
+                                    TrackStateChange::Total(self.state()),
+                                ));
+                            },
                         Loop(loops) =>
                             if self.set_loops(loops).is_ok() {
                                 let _ = ic.events.send(EventMessage::ChangeState(
@@ -304,6 +835,9 @@ impl Track {
                                 ));
                             },
                         MakePlayable => self.make_playable(),
+                        ListEvents(tx) => {
+                            let _ = ic.events.send(EventMessage::ListTrackEvents(index, tx));
+                        },
                     }
                 },
                 Err(TryRecvError::Disconnected) => {
@@ -315,6 +849,60 @@ impl Track {
                 },
             }
         }
+
+        // Seeks held back by the rate limit are retried on later ticks.
+        self.apply_pending_seek(index, ic, seek_interval);
+
+        if let Some(metadata) = self.source.poll_metadata() {
+            self.handle.set_metadata(metadata);
+            let _ = ic
+                .events
+                .send(EventMessage::ChangeState(index, TrackStateChange::MetadataUpdate));
+        }
+    }
+
+    /// Carries out the latest requested seek, unless another was carried out within
+    /// `seek_interval`.
+    ///
+    /// Seeks are applied in order with other commands where possible, so that a later
+    /// [`TrackHandle::get_info`] observes their new position.
+    fn apply_pending_seek(
+        &mut self,
+        index: usize,
+        ic: &Interconnect,
+        seek_interval: Option<Duration>,
+    ) {
+        let time = match self.pending_seek {
+            Some(time) => time,
+            None => return,
+        };
+
+        let allowed = match (seek_interval, self.last_seek) {
+            (Some(interval), Some(last)) => last.elapsed() >= interval,
+            _ => true,
+        };
+
+        if allowed {
+            self.pending_seek = None;
+            self.last_seek = Some(Instant::now());
+
+            if let Ok(new_time) = self.seek_time(time) {
+                self.stats.seeks += 1;
+                let _ = ic.events.send(EventMessage::ChangeState(
+                    index,
+                    TrackStateChange::Position(new_time),
+                ));
+            }
+        }
+    }
+
+    /// Returns `true` the first time this track's source has audio ready to
+    /// play, since it was created or last restarted.
+    pub(crate) fn poll_ready(&mut self) -> bool {
+        let newly_ready = !self.ready && !self.source.is_buffering();
+        self.ready |= newly_ready;
+
+        newly_ready
     }
 
     /// Ready a track for playing if it is lazily initialised.
@@ -324,7 +912,7 @@ impl Track {
     ///
     /// [`Restartable`]: crate::input::restartable::Restartable
     pub fn make_playable(&mut self) {
-        self.source.reader.make_playable();
+        self.source.make_playable();
     }
 
     /// Creates a read-only copy of the audio track's state.
@@ -337,12 +925,43 @@ impl Track {
         TrackState {
             playing: self.playing,
             volume: self.volume,
+            effective_volume: self.effective_volume,
             position: self.position,
             play_time: self.play_time,
             loops: self.loops,
+            end_reason: self.end_reason,
         }
     }
 
+    /// Restarts this track from its beginning, and resumes playback.
+    ///
+    /// [`Restartable`] sources which have begun playing are recreated from scratch,
+    /// while other seekable sources (such as [`Compressed`] and [`Memory`]) rewind
+    /// to their first sample. Sources which are neither, such as plain `ffmpeg`
+    /// processes, fail with [`TrackError::RestartUnsupported`].
+    ///
+    /// [`Restartable`]: crate::input::restartable::Restartable
+    /// [`Compressed`]: crate::input::cached::Compressed
+    /// [`Memory`]: crate::input::cached::Memory
+    /// [`TrackError::RestartUnsupported`]: TrackError::RestartUnsupported
+    ///
+    /// Sources which are still being recreated by an earlier seek or restart fail
+    /// with [`TrackError::RestartFailed`].
+    ///
+    /// [`TrackError::RestartFailed`]: TrackError::RestartFailed
+    pub fn restart(&mut self) -> TrackResult<()> {
+        self.source.restart().map_err(|e| match e.kind() {
+            IoErrorKind::Unsupported | IoErrorKind::InvalidInput =>
+                TrackError::RestartUnsupported,
+            _ => TrackError::RestartFailed,
+        })?;
+        self.position = Duration::default();
+        self.play();
+        self.ready = false;
+
+        Ok(())
+    }
+
     /// Seek to a specific point in the track.
     ///
     /// If the underlying [`Input`] does not support seeking,
@@ -1,3 +1,4 @@
+use super::TrackError;
 use crate::events::TrackEvent;
 
 /// Playback status of a track.
@@ -42,6 +43,56 @@ impl PlayMode {
     }
 }
 
+/// Why a track finished playing, as reported by [`TrackState::end_reason`].
+///
+/// [`TrackState::end_reason`]: super::TrackState::end_reason
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum EndReason {
+    /// The track played to the end of its source, including any loops.
+    Completed,
+    /// The track was stopped, such as by [`TrackHandle::stop`], by
+    /// [`TrackHandle::soft_stop`], or by stopping its queue.
+    ///
+    /// [`TrackHandle::stop`]: super::TrackHandle::stop
+    /// [`TrackHandle::soft_stop`]: super::TrackHandle::soft_stop
+    Stopped,
+    /// The track was skipped by [`TrackHandle::skip`], by a [`TrackQueue`], or by
+    /// its [`SilencePolicy`].
+    ///
+    /// [`TrackHandle::skip`]: super::TrackHandle::skip
+    /// [`TrackQueue`]: super::TrackQueue
+    /// [`SilencePolicy`]: super::SilencePolicy
+    Skipped,
+    /// The track's source failed, as described by the given error.
+    Error(TrackError),
+    /// The track reached the limit set by [`Track::set_max_duration`].
+    ///
+    /// [`Track::set_max_duration`]: super::Track::set_max_duration
+    MaxDuration,
+}
+
+impl EndReason {
+    /// Returns the play mode of a track which ended for this reason.
+    pub(crate) fn mode(self) -> PlayMode {
+        match self {
+            EndReason::Stopped => PlayMode::Stop,
+            _ => PlayMode::End,
+        }
+    }
+}
+
+/// How far a track has progressed through a [`Track::soft_stop`].
+///
+/// [`Track::soft_stop`]: super::Track::soft_stop
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum SoftStop {
+    /// The track mixes its current frame as normal.
+    CurrentFrame,
+    /// The track sends one frame of silence, then stops.
+    TrailingFrame,
+}
+
 impl Default for PlayMode {
     fn default() -> Self {
         PlayMode::Play
@@ -0,0 +1,159 @@
+use crate::constants::*;
+use std::time::Duration;
+
+/// Settings for a noise gate, which silences a track whenever its level falls
+/// below a threshold.
+///
+/// This suppresses background hum or hiss in noisy sources, such as relayed
+/// microphones. The gate opens over the `attack` period once the track's level
+/// reaches `threshold_db`, stays open for at least `hold` after the level next
+/// falls below it, and then closes over the `release` period.
+///
+/// Levels are measured from each stereo frame's peak sample, before the track's
+/// volume is applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GateConfig {
+    /// Level at which the gate opens, in decibels relative to full scale.
+    ///
+    /// *Defaults to `-40.0`.*
+    pub threshold_db: f32,
+    /// Time taken for the gate to fully open.
+    ///
+    /// *Defaults to 5ms.*
+    pub attack: Duration,
+    /// Time for which the gate stays open once the level drops below the threshold.
+    ///
+    /// This also stops the gate chattering on low frequencies, whose waveforms
+    /// spend longer near zero, so should exceed their period.
+    ///
+    /// *Defaults to 100ms.*
+    pub hold: Duration,
+    /// Time taken for the gate to fully close.
+    ///
+    /// *Defaults to 150ms.*
+    pub release: Duration,
+}
+
+impl Default for GateConfig {
+    fn default() -> Self {
+        Self {
+            threshold_db: -40.0,
+            attack: Duration::from_millis(5),
+            hold: Duration::from_millis(100),
+            release: Duration::from_millis(150),
+        }
+    }
+}
+
+/// Converts a duration into a whole number of stereo frames at 48kHz.
+fn frames(duration: Duration) -> usize {
+    (duration.as_secs_f64() * SAMPLE_RATE_RAW as f64) as usize
+}
+
+/// Mixer-side state for a track's [`GateConfig`].
+#[derive(Clone, Debug)]
+pub(crate) struct NoiseGate {
+    config: GateConfig,
+    threshold: f32,
+    attack_step: f32,
+    release_step: f32,
+    hold_frames: usize,
+    /// Gain currently applied, from `0.0` (closed) to `1.0` (open).
+    gain: f32,
+    /// Frames spent below the threshold since the gate was last triggered.
+    held: usize,
+}
+
+impl NoiseGate {
+    /// Creates a gate which starts closed.
+    pub(crate) fn new(config: GateConfig) -> Self {
+        let mut out = Self {
+            config,
+            threshold: 0.0,
+            attack_step: 1.0,
+            release_step: 1.0,
+            hold_frames: 0,
+            gain: 0.0,
+            held: 0,
+        };
+        out.configure(config);
+
+        out
+    }
+
+    /// Changes the gate's settings, without resetting whether it is open.
+    pub(crate) fn configure(&mut self, config: GateConfig) {
+        self.config = config;
+        self.threshold = 10f32.powf(config.threshold_db / 20.0);
+        self.attack_step = 1.0 / frames(config.attack).max(1) as f32;
+        self.release_step = 1.0 / frames(config.release).max(1) as f32;
+        self.hold_frames = frames(config.hold);
+        self.held = self.held.min(self.hold_frames);
+    }
+
+    pub(crate) fn config(&self) -> GateConfig {
+        self.config
+    }
+
+    /// Gates interleaved stereo `samples` in place.
+    pub(crate) fn process(&mut self, samples: &mut [f32]) {
+        for frame in samples.chunks_exact_mut(2) {
+            let level = frame.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+
+            if level >= self.threshold {
+                self.held = 0;
+                self.gain = (self.gain + self.attack_step).min(1.0);
+            } else if self.held < self.hold_frames {
+                self.held += 1;
+            } else {
+                self.gain = (self.gain - self.release_step).max(0.0);
+            }
+
+            for sample in frame.iter_mut() {
+                *sample *= self.gain;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant(level: f32, len: Duration) -> Vec<f32> {
+        vec![level; 2 * frames(len)]
+    }
+
+    #[test]
+    fn gate_mutes_quiet_audio_and_follows_envelope() {
+        let config = GateConfig {
+            threshold_db: -20.0,
+            attack: Duration::from_millis(10),
+            hold: Duration::from_millis(20),
+            release: Duration::from_millis(10),
+        };
+        let mut gate = NoiseGate::new(config);
+
+        // -40dB hiss never opens the gate.
+        let mut hiss = constant(0.01, Duration::from_millis(100));
+        gate.process(&mut hiss);
+        assert!(hiss.iter().all(|s| *s == 0.0));
+
+        // Loud audio fades in over the attack, then passes through untouched.
+        let mut loud = constant(0.5, Duration::from_millis(50));
+        gate.process(&mut loud);
+        let attack = 2 * frames(config.attack);
+        assert!(loud[0] > 0.0 && loud[0] < 0.01);
+        assert!(loud[attack / 2] > 0.2 && loud[attack / 2] < 0.3);
+        assert!(loud[attack..].iter().all(|s| *s == 0.5));
+
+        // Quiet audio is held open, then fades out over the release.
+        let mut tail = constant(0.01, Duration::from_millis(50));
+        gate.process(&mut tail);
+        let hold = 2 * frames(config.hold);
+        let release = 2 * frames(config.release);
+        assert!(tail[..hold].iter().all(|s| *s == 0.01));
+        assert!(tail[hold + release / 2] > 0.0 && tail[hold + release / 2] < 0.01);
+        assert!(tail[hold + release..].iter().all(|s| *s == 0.0));
+    }
+}
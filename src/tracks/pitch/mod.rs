@@ -0,0 +1,176 @@
+#[cfg(feature = "pitch-vocoder")]
+mod vocoder;
+
+use crate::constants::*;
+#[cfg(not(feature = "pitch-vocoder"))]
+use std::f32::consts::PI;
+
+/// Length of the delay line read by the default shifter, in stereo frames (~43ms).
+///
+/// Longer windows smear transients, while shorter windows add audible flutter.
+#[cfg(not(feature = "pitch-vocoder"))]
+const GRAIN_FRAMES: usize = 2048;
+
+/// Mixer-side state for shifting a track's pitch, without changing its tempo.
+///
+/// By default, this reads from a short delay line using two crossfaded taps,
+/// which move at the shifted speed ("rotating tape heads"). This is cheap, but
+/// can sound rough or metallic on sustained tones. The `"pitch-vocoder"` feature
+/// replaces this with a phase vocoder, which sounds cleaner at a higher CPU cost
+/// and around 32ms of added latency.
+#[derive(Clone, Debug)]
+pub(crate) struct PitchShifter {
+    semitones: f32,
+    #[cfg(not(feature = "pitch-vocoder"))]
+    inner: Granular,
+    #[cfg(feature = "pitch-vocoder")]
+    inner: [vocoder::Vocoder; 2],
+}
+
+impl PitchShifter {
+    /// Creates a shifter moving audio by `semitones`, clamped to lie within
+    /// [`MAX_PITCH_SEMITONES`] of the original pitch.
+    ///
+    /// Returns `None` if no shift is needed.
+    ///
+    /// [`MAX_PITCH_SEMITONES`]: crate::constants::MAX_PITCH_SEMITONES
+    pub(crate) fn new(semitones: f32) -> Option<Self> {
+        let semitones = semitones.clamp(-MAX_PITCH_SEMITONES, MAX_PITCH_SEMITONES);
+        if semitones.abs() < f32::EPSILON || semitones.is_nan() {
+            return None;
+        }
+
+        let ratio = 2f32.powf(semitones / 12.0);
+
+        Some(Self {
+            semitones,
+            #[cfg(not(feature = "pitch-vocoder"))]
+            inner: Granular::new(ratio),
+            #[cfg(feature = "pitch-vocoder")]
+            inner: [vocoder::Vocoder::new(ratio), vocoder::Vocoder::new(ratio)],
+        })
+    }
+
+    pub(crate) fn semitones(&self) -> f32 {
+        self.semitones
+    }
+
+    /// Shifts interleaved stereo `samples` in place.
+    pub(crate) fn process(&mut self, samples: &mut [f32]) {
+        #[cfg(not(feature = "pitch-vocoder"))]
+        self.inner.process(samples);
+
+        #[cfg(feature = "pitch-vocoder")]
+        for frame in samples.chunks_exact_mut(2) {
+            for (sample, vocoder) in frame.iter_mut().zip(self.inner.iter_mut()) {
+                *sample = vocoder.process(*sample);
+            }
+        }
+    }
+}
+
+/// Delay-line pitch shifter, crossfading between two read taps.
+#[cfg(not(feature = "pitch-vocoder"))]
+#[derive(Clone, Debug)]
+struct Granular {
+    ratio: f32,
+    buffer: Vec<[f32; 2]>,
+    write: usize,
+    /// Position of the first tap within the delay line, from `0.0` to `1.0`.
+    phase: f32,
+}
+
+#[cfg(not(feature = "pitch-vocoder"))]
+impl Granular {
+    fn new(ratio: f32) -> Self {
+        Self {
+            ratio,
+            buffer: vec![[0.0; 2]; GRAIN_FRAMES + 2],
+            write: 0,
+            phase: 0.0,
+        }
+    }
+
+    /// Reads a stereo frame `delay` frames behind the write head.
+    fn tap(&self, delay: f32) -> [f32; 2] {
+        let len = self.buffer.len();
+        let whole = delay as usize;
+        let frac = delay - whole as f32;
+
+        let a = self.buffer[(self.write + len - whole) % len];
+        let b = self.buffer[(self.write + len - whole - 1) % len];
+
+        [a[0] + (b[0] - a[0]) * frac, a[1] + (b[1] - a[1]) * frac]
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        // Each tap's delay changes by this much per frame, so that it reads
+        // through the input at `ratio` times the normal speed.
+        let step = (1.0 - self.ratio) / GRAIN_FRAMES as f32;
+
+        for frame in samples.chunks_exact_mut(2) {
+            self.buffer[self.write] = [frame[0], frame[1]];
+
+            let mut out = [0.0; 2];
+            for offset in [0.0, 0.5] {
+                // Taps are silent as they wrap, and their gains always sum to one.
+                let phase = (self.phase + offset).fract();
+                let gain = (PI * phase).sin().powi(2);
+                let tap = self.tap(phase * GRAIN_FRAMES as f32);
+
+                out[0] += tap[0] * gain;
+                out[1] += tap[1] * gain;
+            }
+            frame.copy_from_slice(&out);
+
+            self.phase = (self.phase + step).rem_euclid(1.0);
+            self.write = (self.write + 1) % self.buffer.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn zero_crossings(samples: &[f32]) -> usize {
+        samples
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+            .count()
+    }
+
+    #[test]
+    fn octave_up_doubles_frequency_at_same_length() {
+        let freq = 440.0;
+        let len = 2 * SAMPLE_RATE_RAW;
+        let mut samples: Vec<f32> = (0..len)
+            .flat_map(|i| {
+                let s = (2.0 * PI * freq * i as f32 / SAMPLE_RATE_RAW as f32).sin();
+                [s, s]
+            })
+            .collect();
+
+        let mut shifter = PitchShifter::new(12.0).unwrap();
+        for chunk in samples.chunks_mut(STEREO_FRAME_SIZE) {
+            shifter.process(chunk);
+        }
+        assert_eq!(samples.len(), 2 * len);
+
+        // Measure over the second second, once the shifter has settled.
+        let left: Vec<f32> = samples[len..].iter().step_by(2).copied().collect();
+        let shifted = zero_crossings(&left) as f32 / 2.0;
+        assert!(
+            (shifted - 2.0 * freq).abs() < 0.1 * freq,
+            "measured {}Hz",
+            shifted
+        );
+
+        assert!(PitchShifter::new(0.0).is_none());
+        assert_eq!(
+            PitchShifter::new(100.0).unwrap().semitones(),
+            MAX_PITCH_SEMITONES
+        );
+    }
+}
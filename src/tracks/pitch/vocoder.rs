@@ -0,0 +1,172 @@
+use crate::constants::*;
+use std::f64::consts::PI;
+
+/// Length of each analysis frame, in samples.
+const FFT_SIZE: usize = 2048;
+/// Number of overlapping frames covering each sample.
+const OVERSAMPLE: usize = 4;
+const HOP: usize = FFT_SIZE / OVERSAMPLE;
+const BINS: usize = FFT_SIZE / 2 + 1;
+/// Delay between a sample entering the vocoder and its shifted output.
+const LATENCY: usize = FFT_SIZE - HOP;
+
+/// Phase vocoder for a single channel.
+///
+/// Each hop, the last [`FFT_SIZE`] samples are analysed to find the true frequency
+/// of each bin. These are moved to the bins at `ratio` times their frequency, and
+/// resynthesised by overlap-add.
+#[derive(Clone, Debug)]
+pub(super) struct Vocoder {
+    ratio: f64,
+    window: Vec<f64>,
+    input: Vec<f64>,
+    output: Vec<f64>,
+    accum: Vec<f64>,
+    last_phase: Vec<f64>,
+    sum_phase: Vec<f64>,
+    /// Write position within `input`.
+    pos: usize,
+    re: Vec<f64>,
+    im: Vec<f64>,
+    magn: Vec<f64>,
+    freq: Vec<f64>,
+}
+
+impl Vocoder {
+    pub(super) fn new(ratio: f32) -> Self {
+        Self {
+            ratio: f64::from(ratio),
+            window: (0..FFT_SIZE)
+                .map(|k| 0.5 - 0.5 * (2.0 * PI * k as f64 / FFT_SIZE as f64).cos())
+                .collect(),
+            input: vec![0.0; FFT_SIZE],
+            output: vec![0.0; HOP],
+            accum: vec![0.0; 2 * FFT_SIZE],
+            last_phase: vec![0.0; BINS],
+            sum_phase: vec![0.0; BINS],
+            pos: LATENCY,
+            re: vec![0.0; FFT_SIZE],
+            im: vec![0.0; FFT_SIZE],
+            magn: vec![0.0; BINS],
+            freq: vec![0.0; BINS],
+        }
+    }
+
+    /// Feeds one sample in, returning the output sample [`LATENCY`] samples behind it.
+    pub(super) fn process(&mut self, sample: f32) -> f32 {
+        self.input[self.pos] = f64::from(sample);
+        let out = self.output[self.pos - LATENCY];
+        self.pos += 1;
+
+        if self.pos >= FFT_SIZE {
+            self.pos = LATENCY;
+            self.shift_frame();
+        }
+
+        out as f32
+    }
+
+    fn shift_frame(&mut self) {
+        let bin_freq = SAMPLE_RATE_RAW as f64 / FFT_SIZE as f64;
+        let expected = 2.0 * PI * HOP as f64 / FFT_SIZE as f64;
+
+        for k in 0..FFT_SIZE {
+            self.re[k] = self.input[k] * self.window[k];
+            self.im[k] = 0.0;
+        }
+        fft(&mut self.re, &mut self.im, false);
+
+        // Analysis: estimate the true frequency in each bin from its phase advance.
+        self.magn.iter_mut().for_each(|m| *m = 0.0);
+        self.freq.iter_mut().for_each(|f| *f = 0.0);
+        for k in 0..BINS {
+            let magn = 2.0 * self.re[k].hypot(self.im[k]);
+            let phase = self.im[k].atan2(self.re[k]);
+
+            let mut delta = phase - self.last_phase[k] - k as f64 * expected;
+            self.last_phase[k] = phase;
+            delta -= 2.0 * PI * (delta / (2.0 * PI)).round();
+
+            let true_freq = (k as f64 + delta * OVERSAMPLE as f64 / (2.0 * PI)) * bin_freq;
+
+            // Move the bin's energy to its shifted frequency.
+            let target = (k as f64 * self.ratio) as usize;
+            if target < BINS {
+                self.magn[target] += magn;
+                self.freq[target] = true_freq * self.ratio;
+            }
+        }
+
+        // Synthesis: advance each bin's phase to match its new frequency.
+        for k in 0..FFT_SIZE {
+            if k < BINS {
+                let deviation = self.freq[k] / bin_freq - k as f64;
+                self.sum_phase[k] += k as f64 * expected + 2.0 * PI * deviation / OVERSAMPLE as f64;
+
+                let (sin, cos) = self.sum_phase[k].sin_cos();
+                self.re[k] = self.magn[k] * cos;
+                self.im[k] = self.magn[k] * sin;
+            } else {
+                self.re[k] = 0.0;
+                self.im[k] = 0.0;
+            }
+        }
+        fft(&mut self.re, &mut self.im, true);
+
+        let scale = 2.0 / ((FFT_SIZE / 2) * OVERSAMPLE) as f64;
+        for k in 0..FFT_SIZE {
+            self.accum[k] += self.window[k] * self.re[k] * scale;
+        }
+
+        self.output.copy_from_slice(&self.accum[..HOP]);
+        self.accum.copy_within(HOP..HOP + FFT_SIZE, 0);
+        self.input.copy_within(HOP.., 0);
+    }
+}
+
+/// In-place radix-2 FFT, without normalisation.
+fn fft(re: &mut [f64], im: &mut [f64], inverse: bool) {
+    let n = re.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let (w_im, w_re) = (sign * 2.0 * PI / len as f64).sin_cos();
+
+        for start in (0..n).step_by(len) {
+            let (mut c_re, mut c_im) = (1.0, 0.0);
+
+            for a in start..start + len / 2 {
+                let b = a + len / 2;
+                let t_re = re[b] * c_re - im[b] * c_im;
+                let t_im = re[b] * c_im + im[b] * c_re;
+
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+
+                let next_re = c_re * w_re - c_im * w_im;
+                c_im = c_re * w_im + c_im * w_re;
+                c_re = next_re;
+            }
+        }
+
+        len <<= 1;
+    }
+}
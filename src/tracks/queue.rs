@@ -1,12 +1,20 @@
 use crate::{
-    driver::Driver,
+    driver::{tasks::message::CoreMessage, Driver},
     events::{Event, EventContext, EventData, EventHandler, TrackEvent},
-    input::Input,
-    tracks::{self, Track, TrackHandle, TrackResult},
+    input::{error::Result as InputResult, Input},
+    tracks::{self, LoopState, Track, TrackHandle, TrackResult},
 };
 use async_trait::async_trait;
+use flume::Sender;
 use parking_lot::Mutex;
-use std::{collections::VecDeque, ops::Deref, sync::Arc, time::Duration};
+use std::{
+    collections::VecDeque,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    ops::Deref,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{runtime::Handle, task::JoinHandle};
 use tracing::{info, warn};
 
 /// A simple queue for several audio sources, designed to
@@ -82,6 +90,104 @@ impl Queued {
     }
 }
 
+/// Estimated playback times for the tracks in a [`TrackQueue`].
+///
+/// Estimates are based on the duration reported in each track's [`Metadata`],
+/// and do not account for looping. Tracks without a known duration
+/// (*e.g.*, live streams) make all later estimates unknown.
+///
+/// [`TrackQueue`]: TrackQueue
+/// [`Metadata`]: crate::input::Metadata
+#[derive(Clone, Debug)]
+pub struct QueueTimings {
+    /// Each queued track, alongside the estimated time until it begins playing.
+    ///
+    /// This is `None` if any earlier track has an unknown duration.
+    pub tracks: Vec<(TrackHandle, Option<Duration>)>,
+    /// Estimated time until every queued track has finished playing.
+    ///
+    /// This is `None` if any track has an unknown duration.
+    pub remaining: Option<Duration>,
+}
+
+/// Creates a fresh [`Input`] each time it is needed, such as for a queue's
+/// [fallback audio].
+///
+/// [`Input`]: Input
+/// [fallback audio]: EmptyBehavior::Fallback
+#[async_trait]
+pub trait LazyInput: Send + Sync {
+    /// Creates a new source.
+    async fn create(&self) -> InputResult<Input>;
+}
+
+/// What a [`TrackQueue`] should do once its last track has ended.
+///
+/// [`TrackQueue`]: TrackQueue
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum EmptyBehavior {
+    /// Stay connected, playing nothing.
+    Stay,
+    /// Leave the call if no new track has been added after the given time.
+    ///
+    /// Calls made by [`Songbird`] leave as in [`Call::leave`], updating their
+    /// gateway voice state. Otherwise, this disconnects the driver as in
+    /// [`Driver::leave`], and bots using a standalone [`Call`] should listen for
+    /// [`CoreEvent::QueueEmpty`] to leave the channel themselves.
+    ///
+    /// Removing all global events from a [`Call`] made by [`Songbird`] also
+    /// removes its handler for this.
+    ///
+    /// [`Songbird`]: crate::Songbird
+    /// [`Call::leave`]: crate::Call::leave
+    /// [`Driver::leave`]: Driver::leave
+    /// [`Call`]: crate::Call
+    /// [`CoreEvent::QueueEmpty`]: crate::events::CoreEvent::QueueEmpty
+    LeaveAfter(Duration),
+    /// Play a source created on demand (*e.g.*, an idle music stream) until a
+    /// new track is added.
+    ///
+    /// Fallback sources loop if they can be seeked. They are not part of the
+    /// queue, and are stopped as soon as a track is added.
+    Fallback(Arc<dyn LazyInput>),
+}
+
+// `#[default]` on enum variants needs a newer compiler than our MSRV.
+#[allow(clippy::derivable_impls)]
+impl Default for EmptyBehavior {
+    fn default() -> Self {
+        Self::Stay
+    }
+}
+
+impl Debug for EmptyBehavior {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            EmptyBehavior::Stay => write!(f, "Stay"),
+            EmptyBehavior::LeaveAfter(timeout) => write!(f, "LeaveAfter({:?})", timeout),
+            EmptyBehavior::Fallback(_) => write!(f, "Fallback"),
+        }
+    }
+}
+
+/// The [`EmptyBehavior`] carried out by a [`TrackQueue`], reported by
+/// [`CoreEvent::QueueEmpty`].
+///
+/// [`EmptyBehavior`]: EmptyBehavior
+/// [`TrackQueue`]: TrackQueue
+/// [`CoreEvent::QueueEmpty`]: crate::events::CoreEvent::QueueEmpty
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum EmptyAction {
+    /// The queue ran out of tracks, and the driver stayed connected.
+    Stay,
+    /// The queue stayed empty for the configured time, and the driver left the call.
+    Leave,
+    /// The queue ran out of tracks, and fallback audio began to play.
+    Fallback,
+}
+
 #[derive(Debug, Default)]
 /// Inner portion of a [`TrackQueue`].
 ///
@@ -91,6 +197,15 @@ impl Queued {
 /// [`TrackQueue`]: TrackQueue
 struct TrackQueueCore {
     tracks: VecDeque<Queued>,
+    empty_behavior: EmptyBehavior,
+    /// The driver most recently given a track, which carries out `empty_behavior`.
+    driver: Option<Sender<CoreMessage>>,
+    /// A scheduled leave, or fallback audio being created.
+    pending: Option<JoinHandle<()>>,
+    fallback: Option<TrackHandle>,
+    skip_debounce: Option<Duration>,
+    /// A skip waiting for its debounce window to pass without further skips.
+    pending_skip: Option<JoinHandle<()>>,
 }
 
 struct QueueHandler {
@@ -133,6 +248,10 @@ impl EventHandler for QueueHandler {
             }
         }
 
+        if inner.tracks.is_empty() {
+            inner.on_empty(&self.remote_lock);
+        }
+
         None
     }
 }
@@ -157,11 +276,25 @@ impl EventHandler for SongPreloader {
 impl TrackQueue {
     /// Create a new, empty, track queue.
     pub fn new() -> Self {
-        Self {
-            inner: Arc::new(Mutex::new(TrackQueueCore {
-                tracks: VecDeque::new(),
-            })),
-        }
+        Self::default()
+    }
+
+    /// Sets the driver which carries out this queue's [`EmptyBehavior`].
+    ///
+    /// [`EmptyBehavior`]: EmptyBehavior
+    pub(crate) fn set_driver(&self, driver: Sender<CoreMessage>) {
+        self.inner.lock().driver = Some(driver);
+    }
+
+    /// Sets what this queue does once its last track ends.
+    ///
+    /// This applies from the next time the queue empties, and defaults to
+    /// [`EmptyBehavior::Stay`]. Whichever behavior is chosen, adding a track
+    /// stops any fallback audio and cancels a pending leave.
+    ///
+    /// [`EmptyBehavior::Stay`]: EmptyBehavior::Stay
+    pub fn set_empty_behavior(&self, behavior: EmptyBehavior) {
+        self.inner.lock().empty_behavior = behavior;
     }
 
     /// Adds an audio source to the queue, to be played in the channel managed by `handler`.
@@ -181,6 +314,7 @@ impl TrackQueue {
     /// [`create_player`]: super::create_player
     pub fn add(&self, mut track: Track, handler: &mut Driver) {
         self.add_raw(&mut track);
+        self.set_driver(handler.core_sender());
         handler.play(track);
     }
 
@@ -192,6 +326,8 @@ impl TrackQueue {
 
         let track_handle = track.handle.clone();
 
+        inner.cancel_empty();
+
         if !inner.tracks.is_empty() {
             track.pause();
         }
@@ -225,6 +361,21 @@ impl TrackQueue {
         inner.tracks.push_back(Queued(track_handle));
     }
 
+    /// Reattaches the handlers which advance this queue to each of its tracks,
+    /// after the driver's event processor (and every track event) was lost.
+    pub(crate) fn reattach_handlers(&self) {
+        let inner = self.inner.lock();
+
+        for track in inner.tracks.iter() {
+            let _ = track.add_event(
+                Event::Track(TrackEvent::End),
+                QueueHandler {
+                    remote_lock: self.inner.clone(),
+                },
+            );
+        }
+    }
+
     /// Returns a handle to the currently playing track.
     pub fn current(&self) -> Option<TrackHandle> {
         let inner = self.inner.lock();
@@ -293,6 +444,12 @@ impl TrackQueue {
     pub fn stop(&self) {
         let mut inner = self.inner.lock();
 
+        inner.cancel_empty();
+
+        if let Some(pending) = inner.pending_skip.take() {
+            pending.abort();
+        }
+
         for track in inner.tracks.drain(..) {
             // Errors when removing tracks don't really make
             // a difference: an error just implies it's already gone.
@@ -301,10 +458,57 @@ impl TrackQueue {
     }
 
     /// Skip to the next track in the queue, if it exists.
+    ///
+    /// The current track ends with [`EndReason::Skipped`].
+    ///
+    /// If a [skip debounce] is set, the skip is instead carried out once the window
+    /// has passed without another skip, merging any made in the meantime into it.
+    /// This returns `Ok(())` straight away, and any error from the deferred skip
+    /// is discarded.
+    ///
+    /// [`EndReason::Skipped`]: super::EndReason::Skipped
+    /// [skip debounce]: TrackQueue::set_skip_debounce
     pub fn skip(&self) -> TrackResult<()> {
-        let inner = self.inner.lock();
+        let mut inner = self.inner.lock();
+
+        // Skips can only be deferred from within a runtime.
+        let (window, runtime) = match (inner.skip_debounce, Handle::try_current()) {
+            (Some(window), Ok(runtime)) => (window, runtime),
+            _ => return inner.stop_current(),
+        };
+
+        if let Some(pending) = inner.pending_skip.take() {
+            pending.abort();
+        }
+
+        let remote_lock = self.inner.clone();
+        inner.pending_skip = Some(runtime.spawn(async move {
+            tokio::time::sleep(window).await;
+
+            let mut inner = remote_lock.lock();
+            inner.pending_skip = None;
+            let _ = inner.stop_current();
+        }));
+
+        Ok(())
+    }
 
-        inner.stop_current()
+    /// Sets a window within which repeated calls to [`skip`] are merged into one.
+    ///
+    /// Skips made in quick succession (*e.g.*, by many users at once) then advance
+    /// the queue only once: each skip is held back until `window` has passed
+    /// without another, and only the last is carried out. Sources for any tracks
+    /// which would otherwise have been skipped past are never started, rather than
+    /// started and cancelled. [`stop`] discards any held-back skip.
+    ///
+    /// Windows of 100–300ms are usually enough to absorb a burst. Defaults to
+    /// `None`, or the driver's [`Config::command_debounce`] for its built-in queue.
+    ///
+    /// [`skip`]: TrackQueue::skip
+    /// [`stop`]: TrackQueue::stop
+    /// [`Config::command_debounce`]: crate::Config::command_debounce
+    pub fn set_skip_debounce(&self, window: Option<Duration>) {
+        self.inner.lock().skip_debounce = window;
     }
 
     /// Returns a list of currently queued tracks.
@@ -319,15 +523,293 @@ impl TrackQueue {
 
         inner.tracks.iter().map(|q| q.handle()).collect()
     }
+
+    /// Estimates when each queued track will begin playing, and how long
+    /// remains until the queue is finished.
+    ///
+    /// This is computed on demand from a snapshot of the queue. The current
+    /// track's playback position is requested from the driver, as in
+    /// [`TrackHandle::get_info`].
+    ///
+    /// [`TrackHandle::get_info`]: TrackHandle::get_info
+    pub async fn timings(&self) -> QueueTimings {
+        let handles = self.current_queue();
+
+        let position = match handles.first() {
+            Some(handle) => handle
+                .get_info()
+                .await
+                .map(|state| state.position)
+                .unwrap_or_default(),
+            None => Duration::default(),
+        };
+
+        let mut elapsed = Some(Duration::default());
+        let mut tracks = Vec::with_capacity(handles.len());
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            let start = elapsed;
+            let length = handle.metadata().duration.map(|duration| {
+                if i == 0 {
+                    duration.checked_sub(position).unwrap_or_default()
+                } else {
+                    duration
+                }
+            });
+
+            elapsed = elapsed.and_then(|t| Some(t + length?));
+            tracks.push((handle, start));
+        }
+
+        QueueTimings {
+            tracks,
+            remaining: elapsed,
+        }
+    }
+
+    /// Estimates how long remains until every queued track has finished playing.
+    ///
+    /// See [`timings`] for details.
+    ///
+    /// [`timings`]: TrackQueue::timings
+    pub async fn remaining_duration(&self) -> Option<Duration> {
+        self.timings().await.remaining
+    }
 }
 
 impl TrackQueueCore {
+    /// Carries out the queue's [`EmptyBehavior`] once its last track has ended.
+    ///
+    /// [`EmptyBehavior`]: EmptyBehavior
+    fn on_empty(&mut self, remote_lock: &Arc<Mutex<TrackQueueCore>>) {
+        let driver = match &self.driver {
+            Some(driver) => driver.clone(),
+            None => return,
+        };
+
+        self.cancel_empty();
+
+        match &self.empty_behavior {
+            EmptyBehavior::Stay => {
+                let _ = driver.send(CoreMessage::QueueEmpty(EmptyAction::Stay));
+            },
+            EmptyBehavior::LeaveAfter(timeout) => {
+                let timeout = *timeout;
+                let remote_lock = remote_lock.clone();
+
+                self.pending = Some(tokio::spawn(async move {
+                    tokio::time::sleep(timeout).await;
+
+                    // Holding the lock prevents a racing `add` from being left behind.
+                    let inner = remote_lock.lock();
+                    if inner.tracks.is_empty() {
+                        info!("Queue empty for {:?}: leaving.", timeout);
+                        let _ = driver.send(CoreMessage::QueueEmpty(EmptyAction::Leave));
+                        let _ = driver.send(CoreMessage::Disconnect);
+                    }
+                }));
+            },
+            EmptyBehavior::Fallback(source) => {
+                let source = source.clone();
+                let remote_lock = remote_lock.clone();
+
+                self.pending = Some(tokio::spawn(async move {
+                    let input = match source.create().await {
+                        Ok(input) => input,
+                        Err(e) => {
+                            warn!("Failed to create queue fallback: {:?}", e);
+                            return;
+                        },
+                    };
+
+                    let (mut track, handle) = tracks::create_player(input);
+                    let _ = track.set_loops(LoopState::Infinite);
+
+                    let mut inner = remote_lock.lock();
+                    if inner.tracks.is_empty() {
+                        inner.fallback = Some(handle);
+                        let _ = driver.send(CoreMessage::QueueEmpty(EmptyAction::Fallback));
+                        let _ = driver.send(CoreMessage::AddTrack(track));
+                    }
+                }));
+            },
+        }
+    }
+
+    /// Cancels any pending leave, and stops fallback audio.
+    fn cancel_empty(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.abort();
+        }
+
+        if let Some(fallback) = self.fallback.take() {
+            let _ = fallback.stop();
+        }
+    }
+
     /// Skip to the next track in the queue, if it exists.
     fn stop_current(&self) -> TrackResult<()> {
         if let Some(handle) = self.tracks.front() {
-            handle.stop()
+            handle.skip()
         } else {
             Ok(())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Codec, Container, Metadata};
+
+    fn track_input(secs: Option<u64>) -> Input {
+        let metadata = Metadata {
+            duration: secs.map(Duration::from_secs),
+            ..Default::default()
+        };
+
+        Input::new(
+            true,
+            Vec::<u8>::new().into(),
+            Codec::FloatPcm,
+            Container::Raw,
+            Some(metadata),
+        )
+    }
+
+    fn track_lasting(secs: Option<u64>) -> Track {
+        tracks::create_player(track_input(secs)).0
+    }
+
+    #[tokio::test]
+    async fn timings_stop_at_unknown_durations() {
+        let queue = TrackQueue::new();
+
+        // Tracks are dropped (rather than played), so the head reports no progress.
+        for secs in [Some(30), Some(45), None, Some(10)] {
+            queue.add_raw(&mut track_lasting(secs));
+        }
+
+        let timings = queue.timings().await;
+        let starts: Vec<_> = timings.tracks.iter().map(|(_, start)| *start).collect();
+
+        assert_eq!(
+            starts,
+            vec![
+                Some(Duration::default()),
+                Some(Duration::from_secs(30)),
+                Some(Duration::from_secs(75)),
+                None,
+            ]
+        );
+        assert_eq!(timings.remaining, None);
+
+        queue.modify_queue(|tracks| tracks.truncate(2));
+        assert_eq!(queue.remaining_duration().await, Some(Duration::from_secs(75)));
+    }
+
+    #[tokio::test]
+    async fn enqueuing_cancels_pending_leave() {
+        tokio::time::pause();
+
+        let queue = TrackQueue::new();
+        let (tx, rx) = flume::unbounded();
+        queue.set_driver(tx);
+        queue.set_empty_behavior(EmptyBehavior::LeaveAfter(Duration::from_millis(50)));
+
+        let drain = |queue: &TrackQueue| queue.inner.lock().on_empty(&queue.inner);
+
+        drain(&queue);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(CoreMessage::QueueEmpty(EmptyAction::Leave))
+        ));
+        assert!(matches!(rx.try_recv(), Ok(CoreMessage::Disconnect)));
+
+        drain(&queue);
+        queue.add_raw(&mut track_lasting(Some(30)));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn empty_queues_can_stay() {
+        let queue = TrackQueue::new();
+        let (tx, rx) = flume::unbounded();
+        queue.set_driver(tx);
+
+        queue.inner.lock().on_empty(&queue.inner);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(CoreMessage::QueueEmpty(EmptyAction::Stay))
+        ));
+        assert!(rx.try_recv().is_err());
+    }
+
+    struct Silence;
+
+    #[async_trait]
+    impl LazyInput for Silence {
+        async fn create(&self) -> InputResult<Input> {
+            Ok(track_input(None))
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback_plays_until_a_track_is_added() {
+        let queue = TrackQueue::new();
+        let (tx, rx) = flume::unbounded();
+        queue.set_driver(tx);
+        queue.set_empty_behavior(EmptyBehavior::Fallback(Arc::new(Silence)));
+
+        queue.inner.lock().on_empty(&queue.inner);
+        assert!(matches!(
+            rx.recv_async().await,
+            Ok(CoreMessage::QueueEmpty(EmptyAction::Fallback))
+        ));
+        let fallback = match rx.recv_async().await {
+            Ok(CoreMessage::AddTrack(track)) => track,
+            _ => panic!("Fallback track was not played."),
+        };
+        assert_eq!(fallback.loops, LoopState::Infinite);
+
+        queue.add_raw(&mut track_lasting(Some(30)));
+        assert!(matches!(
+            fallback.commands.try_recv(),
+            Ok(tracks::TrackCommand::Stop)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rapid_skips_advance_once() {
+        let queue = TrackQueue::new();
+        queue.set_skip_debounce(Some(Duration::from_millis(50)));
+
+        let mut tracks = [track_lasting(Some(30)), track_lasting(Some(30))];
+        for track in tracks.iter_mut() {
+            queue.add_raw(track);
+        }
+
+        let skips = |track: &Track| {
+            track
+                .commands
+                .try_iter()
+                .filter(|cmd| matches!(cmd, tracks::TrackCommand::Skip))
+                .count()
+        };
+
+        // Nothing happens while the burst continues...
+        tokio::time::pause();
+        for _ in 0..5 {
+            queue.skip().unwrap();
+            tokio::time::advance(Duration::from_millis(20)).await;
+        }
+        assert_eq!(skips(&tracks[0]), 0);
+
+        // ...and the last skip acts once the window has passed after it.
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(skips(&tracks[0]), 1);
+        assert_eq!(skips(&tracks[1]), 0);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::constants::*;
+use std::time::Duration;
+
+/// What happens to a track once it falls silent, under its [`SilencePolicy`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SilenceAction {
+    /// Leaves the track out of the mix until its audio returns.
+    ///
+    /// The track keeps reading its source, so that it can tell when to resume.
+    /// While every playing track is idle, the driver stops encoding and sending audio.
+    Idle,
+    /// Ends the track, as though its source had finished.
+    ///
+    /// Queued tracks move on to their next entry.
+    Skip,
+}
+
+/// Settings for detecting when a track has fallen silent, such as a stream which
+/// has gone quiet or a poorly-produced source with a long silent tail.
+///
+/// A track falls silent once its level has stayed below `threshold_db` for
+/// `window`, when it fires [`TrackEvent::Silent`] and `action` is taken. Idle
+/// tracks fire [`TrackEvent::Audible`] on the first frame to reach the threshold.
+///
+/// Levels are measured from each frame's peak sample, after any other effects
+/// but before the track's volume is applied.
+///
+/// [`TrackEvent::Silent`]: crate::events::TrackEvent::Silent
+/// [`TrackEvent::Audible`]: crate::events::TrackEvent::Audible
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SilencePolicy {
+    /// Level below which audio counts as silence, in decibels relative to full scale.
+    ///
+    /// *Defaults to `-60.0`.*
+    pub threshold_db: f32,
+    /// Time for which a track must stay below the threshold to fall silent.
+    ///
+    /// *Defaults to 5s.*
+    pub window: Duration,
+    /// What to do with a track once it falls silent.
+    ///
+    /// *Defaults to [`SilenceAction::Idle`].*
+    pub action: SilenceAction,
+}
+
+impl Default for SilencePolicy {
+    fn default() -> Self {
+        Self {
+            threshold_db: -60.0,
+            window: Duration::from_secs(5),
+            action: SilenceAction::Idle,
+        }
+    }
+}
+
+/// Mixer-side state for a track's [`SilencePolicy`].
+#[derive(Clone, Debug)]
+pub(crate) struct SilenceDetector {
+    policy: SilencePolicy,
+    threshold: f32,
+    window_frames: usize,
+    /// Consecutive frames spent below the threshold.
+    quiet_frames: usize,
+    silent: bool,
+    /// Transition made by the latest frame, waiting to be reported.
+    change: Option<bool>,
+}
+
+impl SilenceDetector {
+    pub(crate) fn new(policy: SilencePolicy) -> Self {
+        Self {
+            policy,
+            threshold: 10f32.powf(policy.threshold_db / 20.0),
+            window_frames: (policy.window.as_millis() / FRAME_LEN_MS as u128).max(1) as usize,
+            quiet_frames: 0,
+            silent: false,
+            change: None,
+        }
+    }
+
+    pub(crate) fn policy(&self) -> SilencePolicy {
+        self.policy
+    }
+
+    /// Returns whether the track should be left out of the mix.
+    pub(crate) fn is_idle(&self) -> bool {
+        self.silent && self.policy.action == SilenceAction::Idle
+    }
+
+    /// Measures one frame of interleaved stereo `samples`.
+    pub(crate) fn process(&mut self, samples: &[f32]) {
+        let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+
+        if peak >= self.threshold {
+            self.quiet_frames = 0;
+            if self.silent {
+                self.silent = false;
+                self.change = Some(false);
+            }
+        } else {
+            self.quiet_frames = self.quiet_frames.saturating_add(1);
+            if !self.silent && self.quiet_frames >= self.window_frames {
+                self.silent = true;
+                self.change = Some(true);
+            }
+        }
+    }
+
+    /// Returns `Some(true)` if the track has just fallen silent, or `Some(false)`
+    /// if it has just become audible again.
+    pub(crate) fn take_change(&mut self) -> Option<bool> {
+        self.change.take()
+    }
+}
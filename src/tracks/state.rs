@@ -9,8 +9,9 @@ use super::*;
 pub struct TrackState {
     /// Play status (e.g., active, paused, stopped) of this track.
     pub playing: PlayMode,
-    /// Current volume of this track.
+    /// Current volume of this track, as set by the user.
     pub volume: f32,
+    pub(crate) effective_volume: f32,
     /// Current playback position in the source.
     ///
     /// This is altered by loops and seeks, and represents this track's
@@ -20,9 +21,31 @@ pub struct TrackState {
     pub play_time: Duration,
     /// Remaining loops on this track.
     pub loops: LoopState,
+    pub(crate) end_reason: Option<EndReason>,
 }
 
 impl TrackState {
+    /// Returns the gain actually applied to this track by the mixer.
+    ///
+    /// This combines [`volume`] with any attenuation from other tracks, such as
+    /// [ducking], and the driver's [master gain].
+    ///
+    /// [`volume`]: TrackState::volume
+    /// [ducking]: DuckConfig
+    /// [master gain]: crate::driver::Driver::set_master_gain
+    pub fn effective_volume(&self) -> f32 {
+        self.effective_volume
+    }
+
+    /// Returns why this track finished, once it has stopped or ended.
+    ///
+    /// This is set by the time [`TrackEvent::End`] fires.
+    ///
+    /// [`TrackEvent::End`]: crate::events::TrackEvent::End
+    pub fn end_reason(&self) -> Option<EndReason> {
+        self.end_reason
+    }
+
     pub(crate) fn step_frame(&mut self) {
         self.position += TIMESTEP_LENGTH;
         self.play_time += TIMESTEP_LENGTH;
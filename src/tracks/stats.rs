@@ -0,0 +1,36 @@
+use super::*;
+
+/// Cumulative playback statistics for a [`Track`], retrieved remotely
+/// via [`TrackHandle::stats`].
+///
+/// These are maintained for the lifetime of the track, and are not reset
+/// by seeks or loops.
+///
+/// [`Track`]: Track
+/// [`TrackHandle::stats`]: TrackHandle::stats
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct PlaybackStats {
+    /// Number of 20ms frames of audio produced by this track.
+    pub frames: u64,
+    /// Encrypted payload bytes sent over UDP in packets carrying this track's audio.
+    ///
+    /// Packets mixing several tracks count towards each of them. Frames which are
+    /// not sent (e.g., while muted) are not counted.
+    pub bytes: u64,
+    /// Number of times this track's input stalled to buffer after playback
+    /// had begun (e.g., while a [`Restartable`] source recreated its stream).
+    ///
+    /// [`Restartable`]: crate::input::restartable::Restartable
+    pub underruns: u64,
+    /// Total time spent waiting for this track's input to buffer, including
+    /// before playback began.
+    pub buffering: Duration,
+    /// Number of successful seeks requested through a [`TrackHandle`],
+    /// including restarts.
+    ///
+    /// [`TrackHandle`]: TrackHandle
+    pub seeks: u64,
+    /// Number of times this track has looped.
+    pub loops: u64,
+}
@@ -0,0 +1,108 @@
+use super::{DuckConfig, EqBand, LoopState};
+
+/// A set of changes to apply to a track all at once, via [`TrackHandle::apply`]
+/// or [`Track::apply`].
+///
+/// Fields left as `None` are unchanged, so an update is best built from its
+/// [`Default`] using the setters below. Every change takes effect before the
+/// track's next frame is mixed, so a new volume is never heard without, *e.g.*,
+/// the equalizer meant to go along with it. This also needs only one message to
+/// reach the mixer, rather than one per setting.
+///
+/// [`TrackHandle::apply`]: super::TrackHandle::apply
+/// [`Track::apply`]: super::Track::apply
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct TrackUpdate {
+    /// The track's new volume.
+    pub volume: Option<f32>,
+    /// The track's new ducking sidechain.
+    ///
+    /// `Some(None)` stops this track from ducking others.
+    pub ducking: Option<Option<DuckConfig>>,
+    /// The track's new equalizer bands.
+    ///
+    /// An empty list disables equalization.
+    pub equalizer: Option<Vec<EqBand>>,
+    /// The track's new loop count.
+    ///
+    /// Setting this requires a seekable input, as for [`TrackHandle::loop_for`].
+    ///
+    /// [`TrackHandle::loop_for`]: super::TrackHandle::loop_for
+    pub loops: Option<LoopState>,
+}
+
+impl TrackUpdate {
+    /// Sets the track's new volume.
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume = Some(volume);
+        self
+    }
+
+    /// Sets the track's new ducking sidechain.
+    pub fn ducking(mut self, ducking: Option<DuckConfig>) -> Self {
+        self.ducking = Some(ducking);
+        self
+    }
+
+    /// Sets the track's new equalizer bands.
+    pub fn equalizer(mut self, equalizer: Vec<EqBand>) -> Self {
+        self.equalizer = Some(equalizer);
+        self
+    }
+
+    /// Sets the track's new loop count.
+    pub fn loops(mut self, loops: LoopState) -> Self {
+        self.loops = Some(loops);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        driver::tasks::message::*,
+        input::Input,
+        tracks::{self, TrackStateChange},
+    };
+
+    #[test]
+    fn track_updates_apply_in_one_step() {
+        let (mix_tx, _mix_rx) = flume::unbounded();
+        let (core_tx, _core_rx) = flume::unbounded();
+        let (event_tx, event_rx) = flume::unbounded();
+        let ic = Interconnect {
+            core: core_tx,
+            events: event_tx,
+            mixer: mix_tx,
+        };
+
+        let input = Input::float_pcm(true, vec![0u8; 4096].into());
+        let (mut track, handle) = tracks::create_player(input);
+
+        let bands = vec![EqBand::new(100.0, 6.0, 1.0)];
+        handle
+            .apply(
+                TrackUpdate::default()
+                    .volume(0.5)
+                    .equalizer(bands.clone())
+                    .loops(LoopState::Finite(2)),
+            )
+            .unwrap();
+        track.process_commands(0, &ic, None);
+
+        assert_eq!(track.volume(), 0.5);
+        assert_eq!(track.equalizer(), &bands[..]);
+        assert_eq!(track.loops, LoopState::Finite(2));
+        assert!(track.ducking().is_none());
+
+        // Listeners learn of every change at once.
+        let changes = event_rx.drain().collect::<Vec<_>>();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(
+            changes[0],
+            EventMessage::ChangeState(0, TrackStateChange::Total(_))
+        ));
+    }
+}
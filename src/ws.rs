@@ -1,21 +1,36 @@
-use crate::model::Event;
+use crate::model::{payload::HeartbeatAck, Event};
 
 use async_trait::async_trait;
 use async_tungstenite::{
     self as tungstenite,
     tokio::ConnectStream,
-    tungstenite::{error::Error as TungsteniteError, protocol::CloseFrame, Message},
+    tungstenite::{
+        error::Error as TungsteniteError,
+        protocol::{frame::coding::CloseCode, CloseFrame},
+        Message,
+    },
     WebSocketStream,
 };
 use futures::{SinkExt, StreamExt, TryStreamExt};
-use serde_json::Error as JsonError;
-use tokio::time::{timeout, Duration};
+use serde::{Deserialize, Serialize};
+use serde_json::{value::RawValue, Error as JsonError};
+use tokio::{
+    net::TcpStream,
+    time::{timeout, Duration},
+};
 use tracing::instrument;
 
 pub type WsStream = WebSocketStream<ConnectStream>;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// A received gateway event, alongside its sequence number
+/// (if sent by the gateway version in use).
+///
+/// The sequence number is kept even if the event itself could not be
+/// understood (*e.g.*, for new opcodes), so that it is still acknowledged.
+pub type SequencedEvent = (std::result::Result<Event, JsonError>, Option<u64>);
+
 #[derive(Debug)]
 pub enum Error {
     Json(JsonError),
@@ -61,18 +76,18 @@ use url::Url;
 
 #[async_trait]
 pub trait ReceiverExt {
-    async fn recv_json(&mut self) -> Result<Option<Event>>;
-    async fn recv_json_no_timeout(&mut self) -> Result<Option<Event>>;
+    async fn recv_json(&mut self) -> Result<Option<SequencedEvent>>;
+    async fn recv_json_no_timeout(&mut self) -> Result<Option<SequencedEvent>>;
 }
 
 #[async_trait]
 pub trait SenderExt {
-    async fn send_json(&mut self, value: &Event) -> Result<()>;
+    async fn send_json<T: Serialize + Sync>(&mut self, value: &T) -> Result<()>;
 }
 
 #[async_trait]
 impl ReceiverExt for WsStream {
-    async fn recv_json(&mut self) -> Result<Option<Event>> {
+    async fn recv_json(&mut self) -> Result<Option<SequencedEvent>> {
         const TIMEOUT: Duration = Duration::from_millis(500);
 
         let ws_message = match timeout(TIMEOUT, self.next()).await {
@@ -84,14 +99,14 @@ impl ReceiverExt for WsStream {
         convert_ws_message(ws_message)
     }
 
-    async fn recv_json_no_timeout(&mut self) -> Result<Option<Event>> {
+    async fn recv_json_no_timeout(&mut self) -> Result<Option<SequencedEvent>> {
         convert_ws_message(self.try_next().await?)
     }
 }
 
 #[async_trait]
 impl SenderExt for SplitSink<WsStream, Message> {
-    async fn send_json(&mut self, value: &Event) -> Result<()> {
+    async fn send_json<T: Serialize + Sync>(&mut self, value: &T) -> Result<()> {
         Ok(serde_json::to_string(value)
             .map(Message::Text)
             .map_err(Error::from)
@@ -102,7 +117,7 @@ impl SenderExt for SplitSink<WsStream, Message> {
 
 #[async_trait]
 impl SenderExt for WsStream {
-    async fn send_json(&mut self, value: &Event) -> Result<()> {
+    async fn send_json<T: Serialize + Sync>(&mut self, value: &T) -> Result<()> {
         Ok(serde_json::to_string(value)
             .map(Message::Text)
             .map_err(Error::from)
@@ -112,9 +127,9 @@ impl SenderExt for WsStream {
 }
 
 #[inline]
-pub(crate) fn convert_ws_message(message: Option<Message>) -> Result<Option<Event>> {
+pub(crate) fn convert_ws_message(message: Option<Message>) -> Result<Option<SequencedEvent>> {
     Ok(match message {
-        Some(Message::Text(payload)) => serde_json::from_str(&payload).map(Some)?,
+        Some(Message::Text(payload)) => parse_event(&payload).map(Some)?,
         Some(Message::Binary(bytes)) => {
             return Err(Error::UnexpectedBinaryMessage(bytes));
         },
@@ -126,6 +141,74 @@ pub(crate) fn convert_ws_message(message: Option<Message>) -> Result<Option<Even
     })
 }
 
+/// Sends a close frame with the given `code`, then waits up to `wait` for the
+/// server to acknowledge it before the connection is dropped.
+///
+/// Returns whether the server completed the closing handshake in time.
+pub(crate) async fn close_gracefully(
+    stream: &mut WsStream,
+    code: CloseCode,
+    wait: Duration,
+) -> Result<bool> {
+    stream
+        .close(Some(CloseFrame {
+            code,
+            reason: "".into(),
+        }))
+        .await?;
+
+    let ack = async {
+        // Anything sent before the server's own close frame is discarded.
+        while let Some(Ok(msg)) = stream.next().await {
+            if msg.is_close() {
+                break;
+            }
+        }
+    };
+
+    Ok(timeout(wait, ack).await.is_ok())
+}
+
+/// The fields of a gateway message needed before it is parsed as an [`Event`].
+#[derive(Deserialize)]
+struct Envelope<'a> {
+    op: Option<u8>,
+    #[serde(borrow)]
+    d: Option<&'a RawValue>,
+    seq: Option<u64>,
+}
+
+/// A heartbeat ACK nonce, as wrapped by gateway v8.
+#[derive(Deserialize)]
+struct WrappedNonce<'a> {
+    #[serde(borrow)]
+    t: &'a RawValue,
+}
+
+/// Parses a gateway event, stripping out any sequence number.
+///
+/// Gateway v8 adds a top-level `seq` field, and wraps heartbeat ACK nonces
+/// in an object. Both are normalised here into the form expected by the
+/// voice model.
+///
+/// Only a payload which is not valid JSON is an error: an event which cannot
+/// be understood is returned alongside its sequence number.
+fn parse_event(payload: &str) -> std::result::Result<SequencedEvent, JsonError> {
+    let envelope: Envelope<'_> = serde_json::from_str(payload)?;
+
+    let wrapped_nonce = match (envelope.op, envelope.d) {
+        (Some(6), Some(d)) => serde_json::from_str::<WrappedNonce<'_>>(d.get()).ok(),
+        _ => None,
+    };
+
+    let event = match wrapped_nonce {
+        Some(nonce) => serde_json::from_str::<HeartbeatAck>(nonce.t.get()).map(Event::from),
+        None => serde_json::from_str(payload),
+    };
+
+    Ok((event, envelope.seq))
+}
+
 /// An error that occured while connecting over rustls
 #[derive(Debug)]
 #[non_exhaustive]
@@ -167,9 +250,10 @@ impl StdError for RustlsError {
 
 #[cfg(all(feature = "rustls-marker", not(feature = "native-marker")))]
 #[instrument]
-pub(crate) async fn create_rustls_client(url: Url) -> Result<WsStream> {
-    let (stream, _) = tungstenite::tokio::connect_async_with_config::<Url>(
+pub(crate) async fn create_rustls_client(url: Url, tcp: TcpStream) -> Result<WsStream> {
+    let (stream, _) = tungstenite::tokio::client_async_tls_with_config::<Url, _>(
         url,
+        tcp,
         Some(tungstenite::tungstenite::protocol::WebSocketConfig {
             max_message_size: None,
             max_frame_size: None,
@@ -185,9 +269,10 @@ pub(crate) async fn create_rustls_client(url: Url) -> Result<WsStream> {
 
 #[cfg(feature = "native-marker")]
 #[instrument]
-pub(crate) async fn create_native_tls_client(url: Url) -> Result<WsStream> {
-    let (stream, _) = tungstenite::tokio::connect_async_with_config::<Url>(
+pub(crate) async fn create_native_tls_client(url: Url, tcp: TcpStream) -> Result<WsStream> {
+    let (stream, _) = tungstenite::tokio::client_async_tls_with_config::<Url, _>(
         url,
+        tcp,
         Some(tungstenite::tungstenite::protocol::WebSocketConfig {
             max_message_size: None,
             max_frame_size: None,
@@ -199,3 +284,63 @@ pub(crate) async fn create_native_tls_client(url: Url) -> Result<WsStream> {
 
     Ok(stream)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    async fn server(respond: bool) -> (String, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let task = tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = tungstenite::tokio::accept_async(tcp).await.unwrap();
+
+            if respond {
+                // Reading the close frame queues tungstenite's reply.
+                while let Some(Ok(_)) = ws.next().await {}
+            } else {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        });
+
+        (url, task)
+    }
+
+    #[test]
+    fn unknown_events_keep_their_sequence_number() {
+        let (event, seq) = parse_event(r#"{"op": 255, "d": {}, "seq": 10}"#).unwrap();
+        assert!(event.is_err());
+        assert_eq!(seq, Some(10));
+
+        let (event, seq) = parse_event(r#"{"op": 6, "d": {"t": 123}, "seq": 11}"#).unwrap();
+        assert!(matches!(event, Ok(Event::HeartbeatAck(HeartbeatAck { nonce: 123 }))));
+        assert_eq!(seq, Some(11));
+
+        let (event, seq) = parse_event(r#"{"op": 6, "d": 124}"#).unwrap();
+        assert!(matches!(event, Ok(Event::HeartbeatAck(HeartbeatAck { nonce: 124 }))));
+        assert_eq!(seq, None);
+    }
+
+    #[tokio::test]
+    async fn close_waits_for_server_acknowledgement() {
+        let (url, task) = server(true).await;
+        let (mut client, _) = tungstenite::tokio::connect_async(url).await.unwrap();
+        let acked = close_gracefully(&mut client, CloseCode::Normal, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(acked);
+        task.await.unwrap();
+
+        // Silent servers can't hold up a close for longer than the limit.
+        let (url, task) = server(false).await;
+        let (mut client, _) = tungstenite::tokio::connect_async(url).await.unwrap();
+        let acked = close_gracefully(&mut client, CloseCode::Normal, Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert!(!acked);
+        task.abort();
+    }
+}